@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::backlinks::find_backlinks;
+use crate::error::OnyxError;
+use crate::search::search_vault;
+use crate::vault_handle::{OpenVaults, VaultHandle};
+
+/// Request bodies larger than this are refused with `413`.
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+/// A file written through the API, reported so open windows can refresh.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FileChange {
+    pub vault: String,
+    pub path: String,
+    pub kind: FileChangeKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileChangeKind {
+    Created,
+    Modified,
+}
+
+/// Called after every write the API makes.
+pub type ChangeListener = Arc<dyn Fn(&FileChange) + Send + Sync>;
+
+/// A running API server; dropping it stops the server.
+pub struct ApiServer {
+    server: Arc<Server>,
+    port: u16,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ApiServer {
+    /// Binds `127.0.0.1:port` (0 picks a free port) and serves the vaults in `vaults` to clients
+    /// presenting `token`.
+    pub fn start(
+        port: u16,
+        token: String,
+        vaults: OpenVaults,
+        on_change: ChangeListener,
+    ) -> Result<Self, OnyxError> {
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::AddrInUse, e.to_string()))?;
+        let server = Arc::new(server);
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map_or(port, |address| address.port());
+        let api = Api {
+            token,
+            vaults,
+            on_change,
+        };
+        let incoming = Arc::clone(&server);
+        let thread = std::thread::spawn(move || {
+            for request in incoming.incoming_requests() {
+                api.serve(request);
+            }
+        });
+        info!("Local API listening on 127.0.0.1:{port}");
+        Ok(Self {
+            server,
+            port,
+            thread: Some(thread),
+        })
+    }
+
+    /// The port actually bound.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        info!("Local API on port {} stopped", self.port);
+    }
+}
+
+/// An HTTP error: status code plus message.
+#[derive(Debug, PartialEq)]
+struct ApiError(u16, String);
+
+impl From<OnyxError> for ApiError {
+    fn from(error: OnyxError) -> Self {
+        let status = match &error {
+            OnyxError::Io(io) if io.kind() == std::io::ErrorKind::NotFound => 404,
+            OnyxError::Io(io) if io.kind() == std::io::ErrorKind::InvalidData => 400,
+            OnyxError::OutsideVault(_) => 400,
+            _ => 500,
+        };
+        Self(status, error.to_string())
+    }
+}
+
+fn bad_request(message: impl Into<String>) -> ApiError {
+    ApiError(400, message.into())
+}
+
+struct Api {
+    token: String,
+    vaults: OpenVaults,
+    on_change: ChangeListener,
+}
+
+impl Api {
+    fn serve(&self, mut request: Request) {
+        let authorization = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Authorization"))
+            .map(|header| header.value.as_str().to_string());
+        let method = request.method().clone();
+        let url = request.url().to_string();
+        let declared = request.body_length();
+        let result = self.answer(
+            &method,
+            &url,
+            authorization.as_deref(),
+            request.as_reader(),
+            declared,
+        );
+        let (status, body) = match result {
+            Ok(value) => (200, value),
+            Err(ApiError(status, message)) => (status, json!({ "error": message })),
+        };
+        let content_type =
+            Header::from_bytes("Content-Type", "application/json").expect("static header is valid");
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to answer API request: {e}");
+        }
+    }
+
+    /// Accepts only requests carrying the server's bearer token.
+    fn authorize(&self, authorization: Option<&str>) -> Result<(), ApiError> {
+        let presented = authorization.and_then(|value| value.strip_prefix("Bearer "));
+        if !presented.is_some_and(|token| tokens_match(token, &self.token)) {
+            return Err(ApiError(401, "Missing or invalid bearer token".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Checks the token, then reads the body and routes the request. The token comes first so
+    /// unauthenticated callers can't make the server buffer a body.
+    fn answer(
+        &self,
+        method: &Method,
+        url: &str,
+        authorization: Option<&str>,
+        body: impl Read,
+        declared: Option<usize>,
+    ) -> Result<Value, ApiError> {
+        self.authorize(authorization)?;
+        let body = read_body(body, declared, MAX_BODY_BYTES)?;
+        self.handle(method, url, &body)
+    }
+
+    /// Routes one authorized request; everything except the body transport is handled here.
+    fn handle(&self, method: &Method, url: &str, body: &str) -> Result<Value, ApiError> {
+        let (route, params) = parse_url(url);
+        let mut vaults = self
+            .vaults
+            .lock()
+            .map_err(|e| ApiError(500, e.to_string()))?;
+        if (method, route.as_str()) == (&Method::Get, "/vaults") {
+            let list: Vec<Value> = vaults
+                .values()
+                .map(|handle| {
+                    json!({
+                        "name": handle.config().name,
+                        "root": handle.root().to_string_lossy(),
+                    })
+                })
+                .collect();
+            return Ok(Value::Array(list));
+        }
+        let handle = select_vault(&mut vaults, params.get("vault").map(String::as_str))?;
+        let path = || {
+            params
+                .get("path")
+                .map(String::as_str)
+                .ok_or_else(|| bad_request("Missing ?path="))
+        };
+        match (method, route.as_str()) {
+            (Method::Get, "/files") => Ok(json!(handle.list_files())),
+            (Method::Get, "/file") => Ok(json!({ "content": handle.read_file(path()?)? })),
+            (Method::Put, "/file") => self.write(handle, path()?, body, false),
+            (Method::Post, "/append") => self.write(handle, path()?, body, true),
+            // Searches and backlinks scan the whole vault, so they run without holding the lock
+            // the app's own commands wait on.
+            (Method::Get, "/search") => {
+                let query = params.get("q").ok_or_else(|| bad_request("Missing ?q="))?;
+                let limit = match params.get("limit") {
+                    Some(limit) => limit.parse().map_err(|_| bad_request("Invalid ?limit="))?,
+                    None => 50,
+                };
+                let root = handle.root().to_path_buf();
+                drop(vaults);
+                Ok(json!(search_vault(&root, query, limit)?))
+            }
+            (Method::Get, "/tags") => Ok(json!(handle.tags()?)),
+            (Method::Get, "/backlinks") => {
+                let target = handle.resolve(path()?)?;
+                let root = handle.root().to_path_buf();
+                drop(vaults);
+                Ok(json!(find_backlinks(&root, &target)?))
+            }
+            _ => Err(ApiError(404, format!("No route for {method} {route}"))),
+        }
+    }
+
+    fn write(
+        &self,
+        handle: &mut VaultHandle,
+        relative: &str,
+        body: &str,
+        append: bool,
+    ) -> Result<Value, ApiError> {
+        let existed = handle.resolve(relative)?.exists();
+        let path = if append {
+            handle.append_to_file(relative, body)?
+        } else {
+            handle.write_file(relative, body)?
+        };
+        let change = FileChange {
+            vault: handle.root().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            kind: if existed {
+                FileChangeKind::Modified
+            } else {
+                FileChangeKind::Created
+            },
+        };
+        (self.on_change)(&change);
+        Ok(json!(change))
+    }
+}
+
+/// Reads a UTF-8 request body of at most `limit` bytes. Larger bodies are refused with `413`,
+/// up front when `Content-Length` declares them and otherwise once the limit is passed.
+fn read_body(reader: impl Read, declared: Option<usize>, limit: usize) -> Result<String, ApiError> {
+    let too_large = || ApiError(413, format!("Request body is larger than {limit} bytes"));
+    if declared.is_some_and(|length| length > limit) {
+        return Err(too_large());
+    }
+    let mut body = String::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_string(&mut body)
+        .map_err(|e| bad_request(format!("Unreadable body: {e}")))?;
+    if body.len() > limit {
+        return Err(too_large());
+    }
+    Ok(body)
+}
+
+/// Compares tokens without exiting early on the first differing byte.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Splits a request URL into its path and decoded query parameters.
+fn parse_url(url: &str) -> (String, HashMap<String, String>) {
+    let (route, query) = url.split_once('?').unwrap_or((url, ""));
+    let decode = |value: &str| {
+        let value = value.replace('+', " ");
+        urlencoding::decode(&value)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or(value)
+    };
+    let params = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect();
+    (route.trim_end_matches('/').to_string(), params)
+}
+
+/// Picks the vault named by `?vault=` (its root path or name), or the only open vault.
+fn select_vault<'a>(
+    vaults: &'a mut HashMap<PathBuf, VaultHandle>,
+    wanted: Option<&str>,
+) -> Result<&'a mut VaultHandle, ApiError> {
+    match wanted {
+        Some(wanted) => vaults
+            .values_mut()
+            .find(|handle| handle.root() == Path::new(wanted) || handle.config().name == wanted)
+            .ok_or_else(|| ApiError(404, format!("No open vault {wanted}"))),
+        None if vaults.len() == 1 => Ok(vaults.values_mut().next().expect("one vault")),
+        None if vaults.is_empty() => Err(ApiError(404, "No vault is open".to_string())),
+        None => Err(bad_request("Several vaults are open; pass ?vault=")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    const TOKEN: &str = "secret";
+
+    fn api(root: &std::path::Path) -> (Api, Arc<Mutex<Vec<FileChange>>>) {
+        let vaults: OpenVaults = Arc::default();
+        vaults
+            .lock()
+            .unwrap()
+            .insert(root.to_path_buf(), VaultHandle::open(root).unwrap());
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&changes);
+        let api = Api {
+            token: TOKEN.to_string(),
+            vaults,
+            on_change: Arc::new(move |change| recorded.lock().unwrap().push(change.clone())),
+        };
+        (api, changes)
+    }
+
+    fn call(api: &Api, method: Method, url: &str, body: &str) -> Result<Value, ApiError> {
+        api.handle(&method, url, body)
+    }
+
+    #[test]
+    fn requests_without_the_token_are_refused() {
+        let dir = TempDir::new().unwrap();
+        let (api, _) = api(dir.path());
+
+        assert_eq!(api.authorize(None).unwrap_err().0, 401);
+        assert_eq!(api.authorize(Some("Bearer nope")).unwrap_err().0, 401);
+        assert_eq!(api.authorize(Some("Bearer secret")), Ok(()));
+    }
+
+    #[test]
+    fn unauthorized_requests_are_refused_before_the_body_is_read() {
+        let dir = TempDir::new().unwrap();
+        let (api, _) = api(dir.path());
+        let oversized = Some(MAX_BODY_BYTES + 1);
+
+        let anonymous = api.answer(&Method::Put, "/file?path=a.md", None, &b""[..], oversized);
+        let signed = api.answer(
+            &Method::Put,
+            "/file?path=a.md",
+            Some("Bearer secret"),
+            &b""[..],
+            oversized,
+        );
+
+        assert_eq!(anonymous.unwrap_err().0, 401);
+        assert_eq!(signed.unwrap_err().0, 413);
+    }
+
+    #[test]
+    fn writes_and_appends_report_changes() {
+        let dir = TempDir::new().unwrap();
+        let (api, changes) = api(dir.path());
+
+        call(
+            &api,
+            Method::Put,
+            "/file?path=Inbox%2FToday.md",
+            "# Today #daily",
+        )
+        .unwrap();
+        call(
+            &api,
+            Method::Post,
+            "/append?path=Inbox/Today.md",
+            "- call Sam",
+        )
+        .unwrap();
+
+        let read = call(&api, Method::Get, "/file?path=Inbox/Today.md", "").unwrap();
+        assert_eq!(read["content"], "# Today #daily\n- call Sam");
+        let kinds: Vec<FileChangeKind> = changes.lock().unwrap().iter().map(|c| c.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![FileChangeKind::Created, FileChangeKind::Modified]
+        );
+        assert_eq!(
+            call(&api, Method::Get, "/tags", "").unwrap(),
+            json!(["daily"])
+        );
+        assert_eq!(
+            call(&api, Method::Get, "/files", "").unwrap(),
+            json!(["Inbox/Today.md"])
+        );
+    }
+
+    #[test]
+    fn paths_outside_the_vault_and_unknown_routes_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let (api, _) = api(dir.path());
+
+        let escape = call(&api, Method::Put, "/file?path=../evil.md", "x");
+        let missing = call(&api, Method::Get, "/file?path=ghost.md", "");
+        let unknown = call(&api, Method::Delete, "/file?path=a.md", "");
+
+        assert_eq!(escape.unwrap_err().0, 400);
+        assert_eq!(missing.unwrap_err().0, 404);
+        assert_eq!(unknown.unwrap_err().0, 404);
+        assert!(!dir.path().parent().unwrap().join("evil.md").exists());
+    }
+
+    #[test]
+    fn oversized_bodies_are_refused_not_truncated() {
+        let body = "x".repeat(11);
+
+        assert_eq!(read_body(body.as_bytes(), None, 10).unwrap_err().0, 413);
+        assert_eq!(read_body(body.as_bytes(), Some(11), 10).unwrap_err().0, 413);
+        assert_eq!(
+            read_body(&body.as_bytes()[..10], None, 10).unwrap(),
+            "x".repeat(10)
+        );
+        assert_eq!(read_body(&[0xff][..], None, 10).unwrap_err().0, 400);
+    }
+
+    #[test]
+    fn serves_over_http_on_localhost() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join("Note.md"), "hello world").unwrap();
+        let (api, _) = api(dir.path());
+        let server = ApiServer::start(0, TOKEN.to_string(), api.vaults, api.on_change).unwrap();
+
+        let mut stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+        write!(
+            stream,
+            "GET /search?q=hello HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {TOKEN}\r\nConnection: close\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("hello world"));
+    }
+}
@@ -0,0 +1,200 @@
+use std::path::{Path, PathBuf};
+
+use log::info;
+use md5::{Digest, Md5};
+
+use crate::error::OnyxError;
+use crate::export_html::is_image;
+use crate::importer::{relative_link, sanitize_file_name};
+use crate::link_resolver::normalize_path;
+use crate::vault_config::{AttachmentsFolder, VaultConfig};
+
+/// An attachment written for a note (or an identical one that was reused).
+#[derive(Debug, PartialEq)]
+pub struct SavedAttachment {
+    pub path: PathBuf,
+    /// Markdown to insert into the note: an image embed for images, a plain link otherwise.
+    pub link: String,
+    pub reused: bool,
+}
+
+/// The folder that receives attachments for `note_path` under the vault's policy.
+pub fn attachments_dir(
+    vault_root: &Path,
+    policy: &AttachmentsFolder,
+    note_path: &Path,
+) -> Result<PathBuf, OnyxError> {
+    let root = normalize_path(vault_root);
+    let note_path = normalize_path(note_path);
+    if !note_path.starts_with(&root) {
+        return Err(OnyxError::OutsideVault(note_path));
+    }
+    let dir = match policy {
+        AttachmentsFolder::VaultRoot => root.clone(),
+        AttachmentsFolder::Folder(folder) => root.join(folder.trim_matches('/')),
+        AttachmentsFolder::NextToNote(folder) => note_path
+            .parent()
+            .unwrap_or(&root)
+            .join(folder.trim_matches('/')),
+    };
+    let dir = normalize_path(&dir);
+    if dir.starts_with(&root) {
+        Ok(dir)
+    } else {
+        Err(OnyxError::OutsideVault(dir))
+    }
+}
+
+/// Stores `bytes` as an attachment of `note_path`, numbering the name if it is taken
+/// (`image 1.png`), or reusing an identical file when the vault de-duplicates content.
+pub fn save_attachment(
+    vault_root: &Path,
+    config: &VaultConfig,
+    note_path: &Path,
+    file_name: &str,
+    bytes: &[u8],
+) -> Result<SavedAttachment, OnyxError> {
+    let dir = attachments_dir(vault_root, &config.attachments_folder, note_path)?;
+    let existing = if config.deduplicate_attachments {
+        find_identical(&dir, bytes)?
+    } else {
+        None
+    };
+    let (path, reused) = match existing {
+        Some(path) => (path, true),
+        None => {
+            std::fs::create_dir_all(&dir)?;
+            let path = free_path(&dir, &sanitize_file_name(file_name));
+            std::fs::write(&path, bytes)?;
+            info!("Saved attachment {}", path.display());
+            (path, false)
+        }
+    };
+    let link = attachment_link(vault_root, note_path, &path);
+    Ok(SavedAttachment { path, link, reused })
+}
+
+/// `name` inside `dir`, with ` 1`, ` 2`… appended to the stem until no file exists there.
+fn free_path(dir: &Path, name: &str) -> PathBuf {
+    let name = Path::new(name);
+    let stem = name
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = name
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let mut candidate = dir.join(name);
+    let mut counter = 1;
+    while candidate.exists() {
+        candidate = dir.join(format!("{stem} {counter}{extension}"));
+        counter += 1;
+    }
+    candidate
+}
+
+/// A file in `dir` whose MD5 matches `bytes`; only files of the same size are hashed.
+fn find_identical(dir: &Path, bytes: &[u8]) -> Result<Option<PathBuf>, OnyxError> {
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    let digest = Md5::digest(bytes);
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let same_size = path
+            .metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.len() == bytes.len() as u64);
+        if same_size && Md5::digest(std::fs::read(&path)?) == digest {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// A link from the note to the attachment, relative to the note's folder.
+fn attachment_link(vault_root: &Path, note_path: &Path, attachment: &Path) -> String {
+    let root = normalize_path(vault_root);
+    let note = normalize_path(note_path);
+    let relative_note = note.strip_prefix(&root).unwrap_or(&note);
+    let relative_attachment = attachment.strip_prefix(&root).unwrap_or(attachment);
+    let destination = relative_link(relative_note, relative_attachment);
+    let name = attachment
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if is_image(attachment) {
+        format!("![{name}]({destination})")
+    } else {
+        format!("[{name}]({destination})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(policy: AttachmentsFolder, deduplicate: bool) -> VaultConfig {
+        VaultConfig {
+            attachments_folder: policy,
+            deduplicate_attachments: deduplicate,
+            ..VaultConfig::default()
+        }
+    }
+
+    #[test]
+    fn saves_into_fixed_folder_and_numbers_clashes() {
+        let temp = TempDir::new().unwrap();
+        let note = temp.path().join("Notes/Day.md");
+        let config = config(AttachmentsFolder::Folder("Files".to_string()), false);
+
+        let first = save_attachment(temp.path(), &config, &note, "my image.png", b"a").unwrap();
+        let second = save_attachment(temp.path(), &config, &note, "my image.png", b"b").unwrap();
+
+        assert_eq!(first.link, "![my image.png](../Files/my%20image.png)");
+        assert_eq!(second.path, temp.path().join("Files/my image 1.png"));
+        assert_eq!(std::fs::read(&second.path).unwrap(), b"b");
+    }
+
+    #[test]
+    fn next_to_note_policy_uses_note_folder() {
+        let temp = TempDir::new().unwrap();
+        let note = temp.path().join("Notes/Day.md");
+        let config = config(AttachmentsFolder::NextToNote("assets".to_string()), false);
+
+        let saved = save_attachment(temp.path(), &config, &note, "report.pdf", b"%PDF").unwrap();
+
+        assert_eq!(saved.path, temp.path().join("Notes/assets/report.pdf"));
+        assert_eq!(saved.link, "[report.pdf](assets/report.pdf)");
+    }
+
+    #[test]
+    fn identical_content_is_reused_when_enabled() {
+        let temp = TempDir::new().unwrap();
+        let note = temp.path().join("Day.md");
+        let config = config(AttachmentsFolder::VaultRoot, true);
+        save_attachment(temp.path(), &config, &note, "a.png", b"same").unwrap();
+
+        let again = save_attachment(temp.path(), &config, &note, "b.png", b"same").unwrap();
+
+        assert!(again.reused);
+        assert_eq!(again.link, "![a.png](a.png)");
+        assert!(!temp.path().join("b.png").exists());
+    }
+
+    #[test]
+    fn folders_outside_the_vault_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        let policy = AttachmentsFolder::Folder("../elsewhere".to_string());
+
+        let result = attachments_dir(&vault, &policy, &vault.join("Day.md"));
+
+        assert!(matches!(result, Err(OnyxError::OutsideVault(_))));
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::canvas::{is_canvas, read_canvas};
+use crate::error::OnyxError;
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::vault_health::{references, resolve, vault_files};
+
+/// A note or canvas that links to, embeds or places the target file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Backlink {
+    pub source: String,
+    /// 1-based line of the link, for notes.
+    pub line: Option<usize>,
+    /// Id of the file node, for canvases.
+    pub node: Option<String>,
+    /// The linking line of a note, or the canvas node's file path.
+    pub context: String,
+}
+
+/// Every reference to `target` across the vault's notes and canvases, in path order.
+pub fn find_backlinks(vault_root: &Path, target: &Path) -> Result<Vec<Backlink>, OnyxError> {
+    let target = normalize_path(target);
+    let files = vault_files(vault_root);
+    let resolver = LinkResolver::from_paths(vault_root, files.iter().cloned());
+    let mut backlinks = Vec::new();
+    for source in &files {
+        let source_string = source.to_string_lossy().to_string();
+        if is_markdown(source) {
+            if normalize_path(source) == target {
+                continue;
+            }
+            let content = std::fs::read_to_string(source)?;
+            for reference in references(&content) {
+                let resolved =
+                    resolve(&resolver, source, &reference).map(|path| normalize_path(&path));
+                if resolved.as_ref() != Some(&target) {
+                    continue;
+                }
+                let line = content[..reference.offset].matches('\n').count();
+                backlinks.push(Backlink {
+                    source: source_string.clone(),
+                    line: Some(line + 1),
+                    node: None,
+                    context: content
+                        .lines()
+                        .nth(line)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                });
+            }
+        } else if is_canvas(source) {
+            let canvas = match read_canvas(source) {
+                Ok(canvas) => canvas,
+                Err(e) => {
+                    warn!("Skipping canvas {} in backlinks: {e}", source.display());
+                    continue;
+                }
+            };
+            for (node, file) in canvas.file_references() {
+                if normalize_path(&vault_root.join(file)) == target {
+                    backlinks.push(Backlink {
+                        source: source_string.clone(),
+                        line: None,
+                        node: Some(node.to_string()),
+                        context: file.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    Ok(backlinks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_note_links_and_canvas_file_nodes() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("Projects")).unwrap();
+        std::fs::write(root.join("Projects/Plan.md"), "# Plan\n[[Plan]] self\n").unwrap();
+        std::fs::write(root.join("Home.md"), "# Home\n\nSee [[Plan]] soon\n").unwrap();
+        std::fs::write(
+            root.join("Board.canvas"),
+            r#"{"nodes":[{"id":"n1","type":"file","x":0,"y":0,"width":10,"height":10,"file":"Projects/Plan.md"}]}"#,
+        )
+        .unwrap();
+
+        let backlinks = find_backlinks(root, &root.join("Projects/Plan.md")).unwrap();
+
+        assert_eq!(
+            backlinks,
+            vec![
+                Backlink {
+                    source: root.join("Board.canvas").to_string_lossy().to_string(),
+                    line: None,
+                    node: Some("n1".to_string()),
+                    context: "Projects/Plan.md".to_string(),
+                },
+                Backlink {
+                    source: root.join("Home.md").to_string_lossy().to_string(),
+                    line: Some(3),
+                    node: None,
+                    context: "See [[Plan]] soon".to_string(),
+                },
+            ]
+        );
+    }
+}
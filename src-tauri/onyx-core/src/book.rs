@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::export_html::{collect_notes, BrokenLink};
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::markdown::{heading_slug, split_link_target};
+use crate::markdown_ast::{inline_text, parse_document, Block, Inline};
+
+/// Which notes become chapters of an exported document, and in what order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ChapterSource {
+    /// Notes in the given order; folders expand to their notes sorted by path.
+    Notes(Vec<String>),
+    Folder(String),
+    /// The notes an index note links to, in the order the links appear.
+    OutgoingLinks(String),
+}
+
+/// Metadata for DOCX and EPUB exports.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookOptions {
+    pub title: Option<String>,
+    pub author: Option<String>,
+}
+
+/// Summary of a DOCX or EPUB export, returned to the frontend.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BookExportReport {
+    pub path: String,
+    /// Chapter notes in output order.
+    pub chapters: Vec<String>,
+    /// Links to notes that are not part of the export, rendered as plain text.
+    pub broken_links: Vec<BrokenLink>,
+    /// Image specifiers that could not be resolved or embedded.
+    pub missing_images: Vec<String>,
+}
+
+/// A note parsed for export; `blocks` always starts with a level-one heading.
+pub struct Chapter {
+    pub path: PathBuf,
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+/// Where an internal or external link points once the notes are bundled into one document.
+#[derive(Debug, PartialEq)]
+pub enum BookLink {
+    Chapter {
+        index: usize,
+        heading: Option<String>,
+    },
+    External(String),
+    Unresolved,
+}
+
+/// An ordered set of chapters plus what is needed to resolve links and images between them.
+pub struct Book {
+    pub title: String,
+    pub author: Option<String>,
+    pub chapters: Vec<Chapter>,
+    chapter_index: HashMap<PathBuf, usize>,
+    resolver: LinkResolver,
+}
+
+impl Book {
+    pub fn load(
+        vault_root: &Path,
+        source: &ChapterSource,
+        options: &BookOptions,
+    ) -> Result<Self, OnyxError> {
+        let resolver = LinkResolver::build(vault_root);
+        let (paths, default_title) = chapter_paths(vault_root, source, &resolver)?;
+        if paths.is_empty() {
+            return Err(OnyxError::NothingToExport);
+        }
+
+        let mut chapters = Vec::new();
+        let mut chapter_index = HashMap::new();
+        for path in paths {
+            let content = std::fs::read_to_string(&path)?;
+            let stem = file_stem(&path);
+            let mut blocks = parse_document(&content);
+            let title = match blocks.first() {
+                Some(Block::Heading {
+                    level: 1, content, ..
+                }) => inline_text(content),
+                _ => {
+                    blocks.insert(
+                        0,
+                        Block::Heading {
+                            level: 1,
+                            id: Some(heading_slug(&stem)),
+                            content: vec![Inline::Text(stem.clone())],
+                        },
+                    );
+                    stem
+                }
+            };
+            chapter_index.insert(normalize_path(&path), chapters.len());
+            chapters.push(Chapter {
+                path,
+                title,
+                blocks,
+            });
+        }
+
+        let title = options
+            .title
+            .clone()
+            .unwrap_or_else(|| match default_title {
+                Some(title) => title,
+                None => chapters[0].title.clone(),
+            });
+        Ok(Self {
+            title,
+            author: options.author.clone(),
+            chapters,
+            chapter_index,
+            resolver,
+        })
+    }
+
+    /// Resolves a link found in chapter `from` to another chapter, a URL, or nothing.
+    pub fn link_target(&self, from: usize, dest: &str, wikilink: bool) -> BookLink {
+        if !wikilink && (dest.contains("://") || dest.starts_with("mailto:")) {
+            return BookLink::External(dest.to_string());
+        }
+        let (target, fragment) = split_link_target(dest);
+        let heading = fragment.map(heading_slug);
+        if target.is_empty() {
+            return BookLink::Chapter {
+                index: from,
+                heading,
+            };
+        }
+        let note = if wikilink {
+            match self.resolver.resolve_note(target) {
+                Some(note) => note.to_path_buf(),
+                None => return BookLink::Unresolved,
+            }
+        } else {
+            let decoded = urlencoding::decode(target)
+                .map(|decoded| decoded.into_owned())
+                .unwrap_or_else(|_| target.to_string());
+            let Some(parent) = self.chapters[from].path.parent() else {
+                return BookLink::Unresolved;
+            };
+            parent.join(decoded)
+        };
+        match self.chapter_of(&note) {
+            Some(index) => BookLink::Chapter { index, heading },
+            None => BookLink::Unresolved,
+        }
+    }
+
+    /// Absolute path of an image referenced from chapter `from`.
+    pub fn image_path(&self, from: usize, dest: &str) -> Option<PathBuf> {
+        self.resolver.resolve_asset(&self.chapters[from].path, dest)
+    }
+
+    /// Broken link entry for the report.
+    pub fn broken_link(&self, from: usize, dest: &str) -> BrokenLink {
+        BrokenLink {
+            source: self.chapters[from].path.to_string_lossy().to_string(),
+            target: dest.to_string(),
+        }
+    }
+
+    fn chapter_of(&self, note: &Path) -> Option<usize> {
+        let normalized = normalize_path(note);
+        self.chapter_index.get(&normalized).copied().or_else(|| {
+            let canonical = note.canonicalize().ok()?;
+            self.chapter_index
+                .iter()
+                .find(|(path, _)| path.canonicalize().ok().as_ref() == Some(&canonical))
+                .map(|(_, index)| *index)
+        })
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Untitled")
+        .to_string()
+}
+
+/// Expands a chapter source into note paths plus a title suggested by the source itself.
+fn chapter_paths(
+    vault_root: &Path,
+    source: &ChapterSource,
+    resolver: &LinkResolver,
+) -> Result<(Vec<PathBuf>, Option<String>), OnyxError> {
+    let mut paths = Vec::new();
+    let title = match source {
+        ChapterSource::Notes(notes) => {
+            for note in notes {
+                paths.extend(collect_notes(&[PathBuf::from(note)]));
+            }
+            None
+        }
+        ChapterSource::Folder(folder) => {
+            let folder = PathBuf::from(folder);
+            paths = collect_notes(std::slice::from_ref(&folder));
+            folder
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .or_else(|| Some(file_stem(vault_root)))
+        }
+        ChapterSource::OutgoingLinks(index) => {
+            let index = PathBuf::from(index);
+            let content = std::fs::read_to_string(&index)?;
+            let mut targets = Vec::new();
+            collect_link_targets(&parse_document(&content), &mut targets);
+            let parent = index.parent().unwrap_or(vault_root);
+            for (dest, wikilink) in targets {
+                let (target, _) = split_link_target(&dest);
+                if target.is_empty() {
+                    continue;
+                }
+                let note = if wikilink {
+                    resolver.resolve_note(target).map(Path::to_path_buf)
+                } else {
+                    let decoded = urlencoding::decode(target)
+                        .map(|decoded| decoded.into_owned())
+                        .unwrap_or_else(|_| target.to_string());
+                    Some(normalize_path(&parent.join(decoded)))
+                        .filter(|path| is_markdown(path) && path.is_file())
+                };
+                if let Some(note) = note {
+                    paths.push(note);
+                }
+            }
+            paths.retain(|path| normalize_path(path) != normalize_path(&index));
+            Some(file_stem(&index))
+        }
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    paths.retain(|path| seen.insert(normalize_path(path)));
+    Ok((paths, title))
+}
+
+/// Link (and note embed) destinations in document order, flagged by whether they are wikilinks.
+fn collect_link_targets(blocks: &[Block], targets: &mut Vec<(String, bool)>) {
+    for block in blocks {
+        match block {
+            Block::Heading { content, .. } | Block::Paragraph(content) => {
+                collect_inline_targets(content, targets);
+            }
+            Block::List { items, .. } => {
+                for item in items {
+                    collect_link_targets(&item.blocks, targets);
+                }
+            }
+            Block::Quote(blocks) | Block::Footnote { blocks, .. } => {
+                collect_link_targets(blocks, targets);
+            }
+            Block::Table { header, rows } => {
+                for cell in header.iter().chain(rows.iter().flatten()) {
+                    collect_inline_targets(cell, targets);
+                }
+            }
+            Block::Code { .. } | Block::Rule => {}
+        }
+    }
+}
+
+fn collect_inline_targets(inlines: &[Inline], targets: &mut Vec<(String, bool)>) {
+    for inline in inlines {
+        match inline {
+            Inline::Link {
+                dest,
+                wikilink,
+                content,
+            } => {
+                targets.push((dest.clone(), *wikilink));
+                collect_inline_targets(content, targets);
+            }
+            Inline::Image { dest, .. } if is_markdown(Path::new(dest)) || !dest.contains('.') => {
+                targets.push((dest.clone(), true));
+            }
+            Inline::Strong(children)
+            | Inline::Emphasis(children)
+            | Inline::Strikethrough(children) => {
+                collect_inline_targets(children, targets);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An image ready to embed: PNG, JPEG or GIF bytes plus pixel dimensions.
+pub struct EmbeddedImage {
+    pub data: Vec<u8>,
+    pub extension: &'static str,
+    pub media_type: &'static str,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl EmbeddedImage {
+    /// Reads an image, re-encoding formats that word processors and e-readers may not support as PNG.
+    pub fn load(path: &Path) -> Result<Self, OnyxError> {
+        let data = std::fs::read(path)?;
+        let format = image::guess_format(&data)?;
+        let decoded = image::load_from_memory_with_format(&data, format)?;
+        let (width, height) = (decoded.width(), decoded.height());
+        let (extension, media_type) = match format {
+            ImageFormat::Png => ("png", "image/png"),
+            ImageFormat::Jpeg => ("jpeg", "image/jpeg"),
+            ImageFormat::Gif => ("gif", "image/gif"),
+            _ => {
+                let mut png = Vec::new();
+                decoded.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+                return Ok(Self {
+                    data: png,
+                    extension: "png",
+                    media_type: "image/png",
+                    width,
+                    height,
+                });
+            }
+        };
+        Ok(Self {
+            data,
+            extension,
+            media_type,
+            width,
+            height,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("book")).unwrap();
+        std::fs::write(
+            root.join("Index.md"),
+            "# Contents\n\n1. [[Second]]\n2. [First](book/First.md)\n3. [[Missing]]\n4. [[Second]]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("book/First.md"),
+            "# Opening\n\nSee [[Second#Part Two]].",
+        )
+        .unwrap();
+        std::fs::write(root.join("book/Second.md"), "Body\n\n## Part Two\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn outgoing_links_become_chapters_in_link_order() {
+        let vault = setup_vault();
+        let index = vault.path().join("Index.md");
+        let source = ChapterSource::OutgoingLinks(index.to_string_lossy().to_string());
+
+        let book = Book::load(vault.path(), &source, &BookOptions::default()).unwrap();
+
+        assert_eq!(book.title, "Index");
+        let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Second", "Opening"]);
+    }
+
+    #[test]
+    fn folder_chapters_get_a_title_heading() {
+        let vault = setup_vault();
+        let folder = vault.path().join("book");
+        let source = ChapterSource::Folder(folder.to_string_lossy().to_string());
+
+        let book = Book::load(vault.path(), &source, &BookOptions::default()).unwrap();
+
+        assert_eq!(book.title, "book");
+        assert!(matches!(
+            &book.chapters[1].blocks[0],
+            Block::Heading { level: 1, content, .. } if inline_text(content) == "Second"
+        ));
+    }
+
+    #[test]
+    fn empty_selections_have_nothing_to_export() {
+        let vault = setup_vault();
+        std::fs::create_dir_all(vault.path().join("empty")).unwrap();
+        let folder = vault.path().join("empty");
+        let source = ChapterSource::Folder(folder.to_string_lossy().to_string());
+
+        let result = Book::load(vault.path(), &source, &BookOptions::default());
+
+        assert!(matches!(result, Err(OnyxError::NothingToExport)));
+    }
+
+    #[test]
+    fn links_resolve_to_chapters_and_headings() {
+        let vault = setup_vault();
+        let folder = vault.path().join("book");
+        let source = ChapterSource::Folder(folder.to_string_lossy().to_string());
+        let book = Book::load(vault.path(), &source, &BookOptions::default()).unwrap();
+
+        assert_eq!(
+            book.link_target(0, "Second#Part Two", true),
+            BookLink::Chapter {
+                index: 1,
+                heading: Some("part-two".to_string())
+            }
+        );
+        assert_eq!(book.link_target(0, "Index", true), BookLink::Unresolved);
+        assert_eq!(
+            book.link_target(0, "https://example.com", false),
+            BookLink::External("https://example.com".to_string())
+        );
+    }
+}
@@ -0,0 +1,500 @@
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::OnyxError;
+
+/// Edits made closer together than this are undone as one step.
+const UNDO_GROUP: Duration = Duration::from_millis(750);
+
+/// A set of changes to a document in the JSON form of CodeMirror's `ChangeSet.toJSON()`: a
+/// list of sections where a number keeps that many code units and `[delete, ...lines]` replaces
+/// `delete` code units with the lines joined by `\n`. Lengths count UTF-16 code units, as
+/// positions do in the editor.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Value>", into = "Vec<Value>")]
+pub struct ChangeSet {
+    sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Section {
+    Retain(usize),
+    Replace { delete: usize, insert: String },
+}
+
+impl ChangeSet {
+    /// Appends a run of `len` unchanged code units.
+    pub fn retain(mut self, len: usize) -> Self {
+        if len > 0 {
+            self.sections.push(Section::Retain(len));
+        }
+        self
+    }
+
+    /// Appends a replacement of `delete` code units by `insert`.
+    pub fn replace(mut self, delete: usize, insert: &str) -> Self {
+        if delete > 0 || !insert.is_empty() {
+            self.sections.push(Section::Replace {
+                delete,
+                insert: insert.to_string(),
+            });
+        }
+        self
+    }
+
+    /// Length of the document the changes apply to, in UTF-16 code units.
+    pub fn doc_len(&self) -> usize {
+        self.sections
+            .iter()
+            .map(|section| match section {
+                Section::Retain(len) => *len,
+                Section::Replace { delete, .. } => *delete,
+            })
+            .sum()
+    }
+
+    /// Whether applying the changes leaves the document as it was.
+    pub fn is_identity(&self) -> bool {
+        self.sections
+            .iter()
+            .all(|section| matches!(section, Section::Retain(_)))
+    }
+}
+
+impl TryFrom<Vec<Value>> for ChangeSet {
+    type Error = String;
+
+    fn try_from(parts: Vec<Value>) -> Result<Self, String> {
+        let mut changes = ChangeSet::default();
+        for part in parts {
+            changes = match part {
+                Value::Array(items) => {
+                    let mut items = items.into_iter();
+                    let delete = items
+                        .next()
+                        .and_then(|delete| delete.as_u64())
+                        .ok_or("a replacement must start with the deleted length")?;
+                    let lines = items
+                        .map(|line| match line {
+                            Value::String(line) => Ok(line),
+                            other => Err(format!("inserted lines must be strings, found {other}")),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    changes.replace(delete as usize, &lines.join("\n"))
+                }
+                other => {
+                    let len = other.as_u64().ok_or_else(|| {
+                        format!("expected a length or a replacement, found {other}")
+                    })?;
+                    changes.retain(len as usize)
+                }
+            };
+        }
+        Ok(changes)
+    }
+}
+
+impl From<ChangeSet> for Vec<Value> {
+    fn from(changes: ChangeSet) -> Self {
+        changes
+            .sections
+            .into_iter()
+            .map(|section| match section {
+                Section::Retain(len) => Value::from(len),
+                Section::Replace { delete, insert } => {
+                    let mut parts = vec![Value::from(delete)];
+                    if !insert.is_empty() {
+                        parts.extend(insert.split('\n').map(Value::from));
+                    }
+                    Value::Array(parts)
+                }
+            })
+            .collect()
+    }
+}
+
+/// The lines an edit touched: `old_line_count` lines from `start_line` (0-based) were replaced
+/// by `new_line_count` lines. Both texts hold whole lines, line breaks included, so indexes can
+/// update from them without rescanning the document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineDelta {
+    pub start_line: usize,
+    pub old_line_count: usize,
+    pub new_line_count: usize,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+impl LineDelta {
+    /// Whether the edit changed nothing.
+    pub fn is_empty(&self) -> bool {
+        self.old_line_count == 0 && self.new_line_count == 0
+    }
+}
+
+/// The change sets an undo or redo applied, in order, each with the lines it touched.
+pub type Replayed = Vec<(ChangeSet, LineDelta)>;
+
+/// One applied change set together with the change set that reverts it.
+#[derive(Debug, Clone)]
+struct Step {
+    changes: ChangeSet,
+    inverse: ChangeSet,
+}
+
+/// An open document stored as a rope, edited through change sets and keeping its own undo
+/// history. Line breaks are normalised to `\n`, as the editor does.
+#[derive(Debug, Default)]
+pub struct Buffer {
+    rope: Rope,
+    /// Groups of steps, most recent last; each group is undone as one.
+    undo: Vec<Vec<Step>>,
+    redo: Vec<Vec<Step>>,
+    last_edit: Option<Instant>,
+    /// Bumped by every edit, undo and redo.
+    version: u64,
+    saved_version: u64,
+}
+
+impl Buffer {
+    /// A clean buffer holding `text`.
+    pub fn new(text: &str) -> Self {
+        let rope = if text.contains('\r') {
+            Rope::from_str(&text.replace("\r\n", "\n").replace('\r', "\n"))
+        } else {
+            Rope::from_str(text)
+        };
+        Self {
+            rope,
+            ..Self::default()
+        }
+    }
+
+    /// Reads a buffer from a UTF-8 file.
+    pub fn open(path: &Path) -> Result<Self, OnyxError> {
+        Ok(Self::new(&std::fs::read_to_string(path)?))
+    }
+
+    /// Writes the buffer to `path` and marks it saved.
+    pub fn save(&mut self, path: &Path) -> Result<(), OnyxError> {
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        self.rope.write_to(&mut writer)?;
+        writer.flush()?;
+        self.saved_version = self.version;
+        Ok(())
+    }
+
+    /// Whether the buffer changed since it was opened or last saved.
+    pub fn is_dirty(&self) -> bool {
+        self.version != self.saved_version
+    }
+
+    /// Marks the buffer as holding changes that aren't on disk yet.
+    pub fn mark_dirty(&mut self) {
+        self.version += 1;
+    }
+
+    /// Applies `changes`, recording them for undo, and returns the lines they touched. Fails
+    /// without changing anything when the change set was made for a document of another length.
+    pub fn apply_edits(&mut self, changes: &ChangeSet) -> Result<LineDelta, OnyxError> {
+        let (inverse, delta) = self.apply(changes)?;
+        if changes.is_identity() {
+            return Ok(delta);
+        }
+        let now = Instant::now();
+        let step = Step {
+            changes: changes.clone(),
+            inverse,
+        };
+        let grouped = self
+            .last_edit
+            .is_some_and(|last| now.duration_since(last) < UNDO_GROUP);
+        match self.undo.last_mut() {
+            Some(group) if grouped => group.push(step),
+            _ => self.undo.push(vec![step]),
+        }
+        self.last_edit = Some(now);
+        self.redo.clear();
+        Ok(delta)
+    }
+
+    /// Ends the current undo group, so the next edit is undone on its own.
+    pub fn checkpoint(&mut self) {
+        self.last_edit = None;
+    }
+
+    /// Reverts the most recent group of edits, returning the change sets applied (in order)
+    /// with the lines each touched. Empty when there is nothing to undo.
+    pub fn undo(&mut self) -> Result<Replayed, OnyxError> {
+        let Some(group) = self.undo.pop() else {
+            return Ok(Vec::new());
+        };
+        let mut applied = Vec::with_capacity(group.len());
+        for step in group.iter().rev() {
+            let (_, delta) = self.apply(&step.inverse)?;
+            applied.push((step.inverse.clone(), delta));
+        }
+        self.redo.push(group);
+        self.last_edit = None;
+        Ok(applied)
+    }
+
+    /// Re-applies the most recently undone group of edits; the counterpart of [`Buffer::undo`].
+    pub fn redo(&mut self) -> Result<Replayed, OnyxError> {
+        let Some(group) = self.redo.pop() else {
+            return Ok(Vec::new());
+        };
+        let mut applied = Vec::with_capacity(group.len());
+        for step in &group {
+            let (_, delta) = self.apply(&step.changes)?;
+            applied.push((step.changes.clone(), delta));
+        }
+        self.undo.push(group);
+        self.last_edit = None;
+        Ok(applied)
+    }
+
+    /// Number of characters.
+    pub fn len_chars(&self) -> usize {
+        self.rope.len_chars()
+    }
+
+    /// Number of lines; a trailing line break starts an empty last line.
+    pub fn len_lines(&self) -> usize {
+        self.rope.len_lines()
+    }
+
+    /// Length in UTF-16 code units, the unit of editor positions.
+    pub fn len_utf16(&self) -> usize {
+        self.rope.len_utf16_cu()
+    }
+
+    /// The text of 0-based line `line_idx`, without its line break.
+    pub fn line(&self, line_idx: usize) -> Option<String> {
+        let line = self.rope.get_line(line_idx)?.to_string();
+        Some(match line.strip_suffix('\n') {
+            Some(stripped) => stripped.to_string(),
+            None => line,
+        })
+    }
+
+    /// Character index where 0-based line `line_idx` starts.
+    pub fn line_to_char(&self, line_idx: usize) -> Option<usize> {
+        self.rope.try_line_to_char(line_idx).ok()
+    }
+
+    /// 0-based line containing character `char_idx`.
+    pub fn char_to_line(&self, char_idx: usize) -> Option<usize> {
+        self.rope.try_char_to_line(char_idx).ok()
+    }
+
+    /// Editor position (UTF-16 code units) of character `char_idx`.
+    pub fn char_to_utf16(&self, char_idx: usize) -> Option<usize> {
+        self.rope.try_char_to_utf16_cu(char_idx).ok()
+    }
+
+    /// Character index of editor position `utf16_idx`.
+    pub fn utf16_to_char(&self, utf16_idx: usize) -> Option<usize> {
+        self.rope.try_utf16_cu_to_char(utf16_idx).ok()
+    }
+
+    /// Applies `changes` without touching the history; returns their inverse and the lines
+    /// they touched.
+    fn apply(&mut self, changes: &ChangeSet) -> Result<(ChangeSet, LineDelta), OnyxError> {
+        let old_len = self.rope.len_utf16_cu();
+        if changes.doc_len() != old_len {
+            return Err(OnyxError::Edit(format!(
+                "changes are for a document of {} code units but the buffer has {old_len}",
+                changes.doc_len()
+            )));
+        }
+        // The span of the old document that is replaced, in code units.
+        let mut span: Option<(usize, usize)> = None;
+        let mut position = 0;
+        for section in &changes.sections {
+            match section {
+                Section::Retain(len) => position += len,
+                Section::Replace { delete, .. } => {
+                    span = Some((span.map_or(position, |(start, _)| start), position + delete));
+                    position += delete;
+                }
+            }
+        }
+        let Some((start, old_end)) = span else {
+            return Ok((changes.clone(), LineDelta::default()));
+        };
+        let start_line = self.rope.char_to_line(self.rope.utf16_cu_to_char(start));
+        let old_end_line = self.rope.char_to_line(self.rope.utf16_cu_to_char(old_end));
+        let old_text = self.line_span(start_line, old_end_line);
+
+        let mut inverse = ChangeSet::default();
+        let mut position = 0;
+        for section in &changes.sections {
+            match section {
+                Section::Retain(len) => {
+                    position += len;
+                    inverse = inverse.retain(*len);
+                }
+                Section::Replace { delete, insert } => {
+                    let from = self.rope.utf16_cu_to_char(position);
+                    let to = self.rope.utf16_cu_to_char(position + delete);
+                    let removed = self.rope.slice(from..to).to_string();
+                    self.rope.remove(from..to);
+                    self.rope.insert(from, insert);
+                    let inserted = insert.encode_utf16().count();
+                    position += inserted;
+                    inverse = inverse.replace(inserted, &removed);
+                }
+            }
+        }
+        self.version += 1;
+
+        // Everything after the span moved by the change in length.
+        let new_end = old_end + self.rope.len_utf16_cu() - old_len;
+        let new_end_line = self.rope.char_to_line(self.rope.utf16_cu_to_char(new_end));
+        let delta = LineDelta {
+            start_line,
+            old_line_count: old_end_line - start_line + 1,
+            new_line_count: new_end_line - start_line + 1,
+            old_text,
+            new_text: self.line_span(start_line, new_end_line),
+        };
+        Ok((inverse, delta))
+    }
+
+    /// Lines `first..=last` with their line breaks.
+    fn line_span(&self, first: usize, last: usize) -> String {
+        let from = self.rope.line_to_char(first);
+        let to = if last + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(last + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        self.rope.slice(from..to).to_string()
+    }
+}
+
+impl fmt::Display for Buffer {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.rope.chunks() {
+            formatter.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn change_sets_round_trip_codemirror_json() {
+        let json = r#"[6,[5,"there","friend"],3,[2]]"#;
+        let changes: ChangeSet = serde_json::from_str(json).unwrap();
+        assert_eq!(changes.doc_len(), 16);
+        assert_eq!(
+            changes,
+            ChangeSet::default()
+                .retain(6)
+                .replace(5, "there\nfriend")
+                .retain(3)
+                .replace(2, "")
+        );
+        assert_eq!(serde_json::to_string(&changes).unwrap(), json);
+        assert!(serde_json::from_str::<ChangeSet>(r#"[1,["x"]]"#).is_err());
+    }
+
+    #[test]
+    fn edits_use_editor_positions_and_report_touched_lines() {
+        let mut buffer = Buffer::new("# Title\r\nsmile 😀 here\nlast");
+        assert_eq!(buffer.len_lines(), 3);
+        // The emoji is two code units in the editor but one character in the rope.
+        let position = "# Title\nsmile 😀 ".encode_utf16().count();
+        assert_eq!(buffer.utf16_to_char(position), Some(16));
+        assert_eq!(buffer.char_to_utf16(16), Some(position));
+
+        let changes = ChangeSet::default()
+            .retain(position)
+            .replace(4, "there\nand")
+            .retain(5);
+        let delta = buffer.apply_edits(&changes).unwrap();
+        assert_eq!(buffer.to_string(), "# Title\nsmile 😀 there\nand\nlast");
+        assert_eq!(
+            delta,
+            LineDelta {
+                start_line: 1,
+                old_line_count: 1,
+                new_line_count: 2,
+                old_text: "smile 😀 here\n".to_string(),
+                new_text: "smile 😀 there\nand\n".to_string(),
+            }
+        );
+        assert_eq!(buffer.line(2).as_deref(), Some("and"));
+        assert_eq!(buffer.line_to_char(3), Some(buffer.len_chars() - 4));
+        assert_eq!(buffer.char_to_line(0), Some(0));
+        assert_eq!(buffer.line(4), None);
+
+        let stale = ChangeSet::default().retain(3);
+        assert!(matches!(
+            buffer.apply_edits(&stale),
+            Err(OnyxError::Edit(_))
+        ));
+        assert_eq!(buffer.to_string(), "# Title\nsmile 😀 there\nand\nlast");
+    }
+
+    #[test]
+    fn undo_and_redo_replay_grouped_edits() {
+        let mut buffer = Buffer::new("abc");
+        buffer
+            .apply_edits(&ChangeSet::default().retain(3).replace(0, "d"))
+            .unwrap();
+        buffer
+            .apply_edits(&ChangeSet::default().retain(4).replace(0, "e"))
+            .unwrap();
+        buffer.checkpoint();
+        buffer
+            .apply_edits(&ChangeSet::default().replace(1, "A").retain(4))
+            .unwrap();
+        assert_eq!(buffer.to_string(), "Abcde");
+
+        let undone = buffer.undo().unwrap();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(undone[0].0, ChangeSet::default().replace(1, "a").retain(4));
+        assert_eq!(buffer.to_string(), "abcde");
+        assert_eq!(buffer.undo().unwrap().len(), 2);
+        assert_eq!(buffer.to_string(), "abc");
+        assert!(buffer.undo().unwrap().is_empty());
+
+        buffer.redo().unwrap();
+        assert_eq!(buffer.to_string(), "abcde");
+        buffer
+            .apply_edits(&ChangeSet::default().retain(5).replace(0, "!"))
+            .unwrap();
+        assert!(buffer.redo().unwrap().is_empty());
+        assert_eq!(buffer.to_string(), "abcde!");
+    }
+
+    #[test]
+    fn saving_writes_the_rope_and_clears_dirty() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("note.md");
+        std::fs::write(&path, "one\n").unwrap();
+
+        let mut buffer = Buffer::open(&path).unwrap();
+        assert!(!buffer.is_dirty());
+        buffer
+            .apply_edits(&ChangeSet::default().retain(4).replace(0, "two\n"))
+            .unwrap();
+        assert!(buffer.is_dirty());
+        buffer.save(&path).unwrap();
+        assert!(!buffer.is_dirty());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\ntwo\n");
+    }
+}
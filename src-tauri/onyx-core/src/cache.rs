@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use md5::{Digest, Md5};
+
+use crate::error::OnyxError;
+use crate::link_resolver::normalize_path;
+
+/// Derived data (thumbnails, extracted text) kept inside the vault, relative to its root.
+pub const CACHE_DIR: &str = ".onyx/cache";
+
+/// Path of the cache entry for `source` in `.onyx/cache/<kind>/`. The name hashes the file's
+/// vault-relative path and modification time, so editing the file invalidates the entry.
+pub fn cache_entry(
+    vault_root: &Path,
+    kind: &str,
+    source: &Path,
+    suffix: &str,
+) -> Result<PathBuf, OnyxError> {
+    let root = normalize_path(vault_root);
+    let source = normalize_path(source);
+    let Ok(relative) = source.strip_prefix(&root) else {
+        return Err(OnyxError::OutsideVault(source));
+    };
+    let modified = std::fs::metadata(&source)?
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let mut hasher = Md5::new();
+    hasher.update(relative.to_string_lossy().as_bytes());
+    hasher.update(modified.to_le_bytes());
+    let name = format!("{:x}{suffix}", hasher.finalize());
+    Ok(root.join(CACHE_DIR).join(kind).join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn entries_change_when_the_file_changes() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("a.png");
+        std::fs::write(&path, "one").unwrap();
+        let before = cache_entry(temp.path(), "thumbs", &path, ".png").unwrap();
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(60))
+            .unwrap();
+        let after = cache_entry(temp.path(), "thumbs", &path, ".png").unwrap();
+
+        assert_ne!(before, after);
+        assert!(after.starts_with(temp.path().join(CACHE_DIR).join("thumbs")));
+    }
+
+    #[test]
+    fn sources_outside_the_vault_are_rejected() {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        let outside = temp.path().join("b.png");
+        std::fs::write(&outside, "x").unwrap();
+
+        let result = cache_entry(&vault, "thumbs", &outside, ".png");
+
+        assert!(matches!(result, Err(OnyxError::OutsideVault(_))));
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::link_resolver::normalize_path;
+
+/// Extension of JSON Canvas files.
+pub const CANVAS_EXTENSION: &str = "canvas";
+
+/// A JSON Canvas 1.0 document: nodes in z-order (first is bottom-most) plus the edges between them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Canvas {
+    #[serde(default)]
+    pub nodes: Vec<CanvasNode>,
+    #[serde(default)]
+    pub edges: Vec<CanvasEdge>,
+}
+
+/// A node's id, position and size in canvas pixels, plus its type-specific fields.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CanvasNode {
+    pub id: String,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(flatten)]
+    pub kind: CanvasNodeKind,
+}
+
+/// The four node types of the spec, tagged by the `type` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CanvasNodeKind {
+    /// Markdown text.
+    Text { text: String },
+    /// A vault file, by vault-relative path; `subpath` is a `#heading` or `#^block`.
+    File {
+        file: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subpath: Option<String>,
+    },
+    /// A web page.
+    Link { url: String },
+    /// A labelled area that visually contains other nodes.
+    Group {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        label: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        background: Option<String>,
+        #[serde(
+            default,
+            rename = "backgroundStyle",
+            skip_serializing_if = "Option::is_none"
+        )]
+        background_style: Option<BackgroundStyle>,
+    },
+}
+
+/// How a group's background image is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundStyle {
+    Cover,
+    Ratio,
+    Repeat,
+}
+
+/// A connection between two nodes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasEdge {
+    pub id: String,
+    pub from_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_side: Option<Side>,
+    /// Defaults to `none` when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_end: Option<EdgeEnd>,
+    pub to_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_side: Option<Side>,
+    /// Defaults to `arrow` when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_end: Option<EdgeEnd>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// The side of a node an edge attaches to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+/// The shape drawn at one end of an edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeEnd {
+    None,
+    Arrow,
+}
+
+/// Whether the path is a JSON Canvas file.
+pub fn is_canvas(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(CANVAS_EXTENSION))
+}
+
+/// A preset color (`"1"`–`"6"`) or a `#RGB` / `#RRGGBB` hex color.
+fn is_valid_color(color: &str) -> bool {
+    if matches!(color, "1" | "2" | "3" | "4" | "5" | "6") {
+        return true;
+    }
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl Canvas {
+    /// Checks what serde can't: unique ids, edges between existing nodes, positive sizes,
+    /// non-empty file paths and well-formed colors.
+    pub fn validate(&self) -> Result<(), OnyxError> {
+        let invalid = |message: String| Err(OnyxError::InvalidCanvas(message));
+        let mut ids = HashSet::new();
+        for node in &self.nodes {
+            if node.id.is_empty() || !ids.insert(node.id.as_str()) {
+                return invalid(format!("duplicate or empty node id '{}'", node.id));
+            }
+            if node.width <= 0 || node.height <= 0 {
+                return invalid(format!("node '{}' must have a positive size", node.id));
+            }
+            if let CanvasNodeKind::File { file, .. } = &node.kind {
+                if file.trim().is_empty() {
+                    return invalid(format!("file node '{}' has no file", node.id));
+                }
+            }
+        }
+        let node_ids = ids.clone();
+        for edge in &self.edges {
+            if edge.id.is_empty() || !ids.insert(edge.id.as_str()) {
+                return invalid(format!("duplicate or empty edge id '{}'", edge.id));
+            }
+            for end in [&edge.from_node, &edge.to_node] {
+                if !node_ids.contains(end.as_str()) {
+                    return invalid(format!("edge '{}' points at unknown node '{end}'", edge.id));
+                }
+            }
+        }
+        let colors = self
+            .nodes
+            .iter()
+            .map(|node| (&node.id, &node.color))
+            .chain(self.edges.iter().map(|edge| (&edge.id, &edge.color)));
+        for (id, color) in colors {
+            if let Some(color) = color.as_deref().filter(|color| !is_valid_color(color)) {
+                return invalid(format!("'{id}' has an invalid color '{color}'"));
+            }
+        }
+        Ok(())
+    }
+
+    /// File nodes as `(node id, vault-relative path)` pairs.
+    pub fn file_references(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.nodes.iter().filter_map(|node| match &node.kind {
+            CanvasNodeKind::File { file, .. } => Some((node.id.as_str(), file.as_str())),
+            _ => None,
+        })
+    }
+}
+
+/// Reads and validates a `.canvas` file.
+pub fn read_canvas(path: &Path) -> Result<Canvas, OnyxError> {
+    let canvas: Canvas = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    canvas.validate()?;
+    Ok(canvas)
+}
+
+/// Validates `canvas` and writes it to `path`; nothing is written if validation fails.
+pub fn write_canvas(path: &Path, canvas: &Canvas) -> Result<(), OnyxError> {
+    canvas.validate()?;
+    std::fs::write(path, serde_json::to_string_pretty(canvas)?)?;
+    Ok(())
+}
+
+/// Creates an empty canvas at `path`, adding the `.canvas` extension when missing.
+/// Refuses to overwrite an existing file.
+pub fn create_canvas(path: &Path) -> Result<PathBuf, OnyxError> {
+    let path = if is_canvas(path) {
+        path.to_path_buf()
+    } else {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".{CANVAS_EXTENSION}"));
+        PathBuf::from(name)
+    };
+    if path.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        )
+        .into());
+    }
+    write_canvas(&path, &Canvas::default())?;
+    info!("Created canvas {}", path.display());
+    Ok(path)
+}
+
+/// Every non-hidden `.canvas` file in the vault, sorted.
+pub fn vault_canvases(vault_root: &Path) -> Vec<PathBuf> {
+    let mut canvases: Vec<PathBuf> = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_canvas(entry.path()))
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    canvases.sort();
+    canvases
+}
+
+fn vault_relative(vault_root: &Path, path: &Path) -> Option<String> {
+    let relative = normalize_path(path);
+    let relative = relative.strip_prefix(normalize_path(vault_root)).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Points canvas file nodes at `new_path` after a file or folder moved from `old_path`, and
+/// returns the canvases that changed. The JSON is edited in place so fields this model doesn't
+/// know about survive.
+pub fn rewrite_file_references(
+    vault_root: &Path,
+    old_path: &Path,
+    new_path: &Path,
+) -> Result<Vec<PathBuf>, OnyxError> {
+    let (Some(old), Some(new)) = (
+        vault_relative(vault_root, old_path),
+        vault_relative(vault_root, new_path),
+    ) else {
+        return Ok(Vec::new());
+    };
+    let mut changed = Vec::new();
+    for canvas_path in vault_canvases(vault_root) {
+        let contents = std::fs::read_to_string(&canvas_path)?;
+        let mut document: serde_json::Value = match serde_json::from_str(&contents) {
+            Ok(document) => document,
+            Err(e) => {
+                warn!("Skipping unreadable canvas {}: {e}", canvas_path.display());
+                continue;
+            }
+        };
+        let Some(nodes) = document
+            .get_mut("nodes")
+            .and_then(serde_json::Value::as_array_mut)
+        else {
+            continue;
+        };
+        let mut rewritten = false;
+        for node in nodes {
+            if node.get("type").and_then(serde_json::Value::as_str) != Some("file") {
+                continue;
+            }
+            let Some(file) = node.get("file").and_then(serde_json::Value::as_str) else {
+                continue;
+            };
+            let replacement = if file == old {
+                new.clone()
+            } else if let Some(rest) = file.strip_prefix(&format!("{old}/")) {
+                format!("{new}/{rest}")
+            } else {
+                continue;
+            };
+            node["file"] = serde_json::Value::String(replacement);
+            rewritten = true;
+        }
+        if rewritten {
+            std::fs::write(&canvas_path, serde_json::to_string_pretty(&document)?)?;
+            changed.push(canvas_path);
+        }
+    }
+    if !changed.is_empty() {
+        info!(
+            "Updated {} canvases after {old} moved to {new}",
+            changed.len()
+        );
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SAMPLE: &str = r##"{
+  "nodes": [
+    {"id": "g", "type": "group", "x": 0, "y": 0, "width": 800, "height": 600, "label": "Ideas", "backgroundStyle": "cover"},
+    {"id": "t", "type": "text", "x": 20, "y": 20, "width": 200, "height": 100, "text": "# Hi", "color": "4"},
+    {"id": "f", "type": "file", "x": 300, "y": 20, "width": 200, "height": 100, "file": "Projects/Plan.md", "subpath": "#Goals"},
+    {"id": "l", "type": "link", "x": 20, "y": 300, "width": 200, "height": 100, "url": "https://example.com", "color": "#ff8800"}
+  ],
+  "edges": [
+    {"id": "e", "fromNode": "t", "fromSide": "right", "toNode": "f", "toSide": "left", "toEnd": "arrow", "label": "see"}
+  ]
+}"##;
+
+    #[test]
+    fn reads_every_node_type_and_round_trips() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Board.canvas");
+        std::fs::write(&path, SAMPLE).unwrap();
+
+        let canvas = read_canvas(&path).unwrap();
+
+        assert_eq!(canvas.nodes.len(), 4);
+        assert_eq!(
+            canvas.nodes[2].kind,
+            CanvasNodeKind::File {
+                file: "Projects/Plan.md".to_string(),
+                subpath: Some("#Goals".to_string())
+            }
+        );
+        assert_eq!(canvas.edges[0].from_side, Some(Side::Right));
+        assert_eq!(
+            canvas.file_references().collect::<Vec<_>>(),
+            vec![("f", "Projects/Plan.md")]
+        );
+        write_canvas(&path, &canvas).unwrap();
+        assert_eq!(read_canvas(&path).unwrap(), canvas);
+    }
+
+    #[test]
+    fn invalid_canvases_are_rejected() {
+        let mut canvas: Canvas = serde_json::from_str(SAMPLE).unwrap();
+        canvas.edges[0].to_node = "ghost".to_string();
+        assert!(matches!(
+            canvas.validate(),
+            Err(OnyxError::InvalidCanvas(_))
+        ));
+
+        let mut canvas: Canvas = serde_json::from_str(SAMPLE).unwrap();
+        canvas.nodes[1].color = Some("teal".to_string());
+        assert!(canvas.validate().is_err());
+
+        let mut canvas: Canvas = serde_json::from_str(SAMPLE).unwrap();
+        canvas.nodes[1].id = "g".to_string();
+        assert!(canvas.validate().is_err());
+
+        assert!(
+            serde_json::from_str::<Canvas>(r#"{"nodes":[{"id":"x","type":"shape"}]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn create_canvas_adds_the_extension_and_refuses_to_overwrite() {
+        let temp = TempDir::new().unwrap();
+
+        let path = create_canvas(&temp.path().join("Board")).unwrap();
+
+        assert_eq!(path, temp.path().join("Board.canvas"));
+        assert_eq!(read_canvas(&path).unwrap(), Canvas::default());
+        assert!(create_canvas(&path).is_err());
+    }
+
+    #[test]
+    fn renames_rewrite_file_nodes_and_keep_unknown_fields() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let sample = SAMPLE.replace("\"subpath\"", "\"extra\": 1, \"subpath\"");
+        std::fs::write(root.join("Board.canvas"), sample).unwrap();
+        std::fs::write(root.join("Other.canvas"), r#"{"nodes":[],"edges":[]}"#).unwrap();
+
+        let changed = rewrite_file_references(
+            root,
+            &root.join("Projects/Plan.md"),
+            &root.join("Projects/Roadmap.md"),
+        )
+        .unwrap();
+
+        assert_eq!(changed, vec![root.join("Board.canvas")]);
+        let canvas = read_canvas(&root.join("Board.canvas")).unwrap();
+        assert_eq!(
+            canvas.file_references().collect::<Vec<_>>(),
+            vec![("f", "Projects/Roadmap.md")]
+        );
+        let raw = std::fs::read_to_string(root.join("Board.canvas")).unwrap();
+        assert!(raw.contains("\"extra\": 1"));
+
+        rewrite_file_references(root, &root.join("Projects"), &root.join("Work")).unwrap();
+        let canvas = read_canvas(&root.join("Board.canvas")).unwrap();
+        assert_eq!(
+            canvas.file_references().collect::<Vec<_>>(),
+            vec![("f", "Work/Roadmap.md")]
+        );
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Datelike, Utc};
+
+/// Formats a time as `YYYY-MM-DDThh:mm:ssZ`, the form EPUB requires for `dcterms:modified`.
+pub fn utc_timestamp(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+/// Today's `(year, month, day)` in the local time zone, e.g. for naming daily notes.
+pub fn local_today() -> (i64, i64, i64) {
+    let today = chrono::Local::now().date_naive();
+    (
+        i64::from(today.year()),
+        i64::from(today.month()),
+        i64::from(today.day()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn utc_timestamp_formats_dates() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_792_281_600 + 3_661);
+        assert_eq!(utc_timestamp(time), "2026-10-18T01:01:01Z");
+        assert_eq!(utc_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,435 @@
+use std::path::{Path, PathBuf};
+
+use log::info;
+use serde::Serialize;
+
+use crate::dates::local_today;
+use crate::error::OnyxError;
+use crate::files::find_note_by_stem;
+use crate::global_config::GlobalConfig;
+use crate::vault::find_vault_root;
+use crate::vault_config::{ensure_vault_config, DailyNotesConfig};
+use crate::vault_handle::VaultHandle;
+
+/// The URL scheme Onyx registers with the operating system.
+pub const SCHEME: &str = "onyx";
+
+/// A parsed `onyx://` URL. `vault` is a vault name or path; without it the last active vault
+/// is used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLink {
+    /// `onyx://open?vault=…&file=…&heading=…`
+    Open {
+        vault: Option<String>,
+        file: Option<String>,
+        heading: Option<String>,
+    },
+    /// `onyx://new?vault=…&name=…&content=…`
+    New {
+        vault: Option<String>,
+        name: String,
+        content: String,
+    },
+    /// `onyx://search?vault=…&query=…`
+    Search {
+        vault: Option<String>,
+        query: String,
+    },
+    /// `onyx://daily?vault=…`
+    Daily { vault: Option<String> },
+}
+
+/// What a vault window should show once a link has been followed. `file` is absolute.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OpenRequest {
+    pub file: Option<String>,
+    pub heading: Option<String>,
+    pub search: Option<String>,
+}
+
+/// Whether a launch argument is an `onyx://` URL rather than a path.
+pub fn is_deep_link(arg: &str) -> bool {
+    arg.get(..SCHEME.len() + 3)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(&format!("{SCHEME}://")))
+}
+
+impl DeepLink {
+    /// Parses an `onyx://<action>?<query>` URL; query values are percent-decoded and `+` reads
+    /// as a space.
+    pub fn parse(url: &str) -> Result<Self, OnyxError> {
+        if !is_deep_link(url) {
+            return Err(invalid(format!("'{url}' does not start with {SCHEME}://")));
+        }
+        let rest = &url[SCHEME.len() + 3..];
+        let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let params = query_params(query)?;
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value.clone())
+                .filter(|value| !value.is_empty())
+        };
+        let required = |key: &str| param(key).ok_or_else(|| invalid(format!("missing '{key}'")));
+        let vault = param("vault");
+        match action.trim_end_matches('/').to_lowercase().as_str() {
+            "open" => Ok(Self::Open {
+                vault,
+                file: param("file"),
+                heading: param("heading"),
+            }),
+            "new" => Ok(Self::New {
+                vault,
+                name: required("name")?,
+                content: param("content").unwrap_or_default(),
+            }),
+            "search" => Ok(Self::Search {
+                vault,
+                query: required("query")?,
+            }),
+            "daily" => Ok(Self::Daily { vault }),
+            other => Err(invalid(format!("unknown action '{other}'"))),
+        }
+    }
+
+    /// The vault the link names, if any.
+    pub fn vault(&self) -> Option<&str> {
+        match self {
+            Self::Open { vault, .. }
+            | Self::New { vault, .. }
+            | Self::Search { vault, .. }
+            | Self::Daily { vault } => vault.as_deref(),
+        }
+    }
+}
+
+/// Finds the vault a link refers to: a path inside a vault, or the name (or folder name) of a
+/// known vault. Without one, the last active vault, or the only known vault.
+pub fn resolve_vault(vault: Option<&str>, config: &GlobalConfig) -> Result<PathBuf, OnyxError> {
+    let Some(vault) = vault else {
+        return config
+            .last_active_vault
+            .clone()
+            .or_else(|| match config.vaults.as_slice() {
+                [only] => Some(only.clone()),
+                _ => None,
+            })
+            .ok_or_else(|| invalid("no vault given and no vault was open before".to_string()));
+    };
+    let path = Path::new(vault);
+    if path.is_absolute() {
+        return find_vault_root(path).ok_or_else(|| invalid(format!("{vault} is not a vault")));
+    }
+    config
+        .vaults
+        .iter()
+        .find(|root| {
+            let folder = root.file_name().map(|name| name.to_string_lossy());
+            let named = ensure_vault_config(root).is_ok_and(|config| config.name == vault);
+            named || folder.is_some_and(|folder| folder.eq_ignore_ascii_case(vault))
+        })
+        .cloned()
+        .ok_or_else(|| invalid(format!("no known vault named '{vault}'")))
+}
+
+/// Carries out a link inside its vault: finds the file to open, creating it for `new` and
+/// `daily` links.
+pub fn follow(link: &DeepLink, vault: &mut VaultHandle) -> Result<OpenRequest, OnyxError> {
+    match link {
+        DeepLink::Open { file, heading, .. } => {
+            let file = match file {
+                Some(file) => Some(find_file(vault, file)?),
+                None => None,
+            };
+            Ok(OpenRequest {
+                file: file.map(|file| file.to_string_lossy().to_string()),
+                heading: heading.clone(),
+                search: None,
+            })
+        }
+        DeepLink::New { name, content, .. } => {
+            let folder = vault.config().new_note_folder.clone().unwrap_or_default();
+            let relative = with_markdown_extension(&join_relative(&folder, name));
+            if vault.resolve(&relative)?.exists() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("A file named '{relative}' already exists"),
+                )
+                .into());
+            }
+            let path = vault.write_file(&relative, content)?;
+            info!("Created {} from an onyx:// link", path.display());
+            Ok(open_file(&path))
+        }
+        DeepLink::Search { query, .. } => Ok(OpenRequest {
+            search: Some(query.clone()),
+            ..OpenRequest::default()
+        }),
+        DeepLink::Daily { .. } => {
+            let daily = vault.config().daily_notes.clone();
+            let relative = daily_note_path(daily.as_ref(), local_today());
+            let path = vault.resolve(&relative)?;
+            if !path.exists() {
+                let template = daily
+                    .and_then(|daily| daily.template)
+                    .and_then(|template| vault.read_file(&with_markdown_extension(&template)).ok())
+                    .unwrap_or_default();
+                vault.write_file(&relative, &template)?;
+                info!("Created daily note {}", path.display());
+            }
+            Ok(open_file(&path))
+        }
+    }
+}
+
+/// The vault-relative path of the daily note for `(year, month, day)`.
+pub fn daily_note_path(config: Option<&DailyNotesConfig>, date: (i64, i64, i64)) -> String {
+    let (folder, format) = match config {
+        Some(config) => (
+            config.folder.clone().unwrap_or_default(),
+            config.format.as_str(),
+        ),
+        None => (String::new(), "YYYY-MM-DD"),
+    };
+    with_markdown_extension(&join_relative(&folder, &format_date(format, date)))
+}
+
+/// Formats a date with the Moment.js tokens daily notes use: `YYYY`, `YY`, `MM`, `M`, `DD`
+/// and `D`. Text in `[brackets]` is copied as is, as is anything else.
+pub fn format_date(format: &str, (year, month, day): (i64, i64, i64)) -> String {
+    let mut formatted = String::new();
+    let mut rest = format;
+    while let Some(character) = rest.chars().next() {
+        let (text, length) = if character == '[' {
+            match rest.find(']') {
+                Some(end) => (rest[1..end].to_string(), end + 1),
+                None => (rest[1..].to_string(), rest.len()),
+            }
+        } else if rest.starts_with("YYYY") {
+            (format!("{year:04}"), 4)
+        } else if rest.starts_with("YY") {
+            (format!("{:02}", year % 100), 2)
+        } else if rest.starts_with("MM") {
+            (format!("{month:02}"), 2)
+        } else if rest.starts_with("DD") {
+            (format!("{day:02}"), 2)
+        } else if character == 'M' {
+            (month.to_string(), 1)
+        } else if character == 'D' {
+            (day.to_string(), 1)
+        } else {
+            (character.to_string(), character.len_utf8())
+        };
+        formatted.push_str(&text);
+        rest = &rest[length..];
+    }
+    formatted
+}
+
+/// A file given as a vault-relative path (with or without `.md`) or as a note name.
+fn find_file(vault: &VaultHandle, file: &str) -> Result<PathBuf, OnyxError> {
+    for candidate in [file.to_string(), with_markdown_extension(file)] {
+        let path = vault.resolve(&candidate)?;
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+    let stem = file.strip_suffix(".md").unwrap_or(file);
+    find_note_by_stem(vault.root(), stem)
+        .ok_or_else(|| invalid(format!("no file '{file}' in {}", vault.config().name)))
+}
+
+fn open_file(path: &Path) -> OpenRequest {
+    OpenRequest {
+        file: Some(path.to_string_lossy().to_string()),
+        ..OpenRequest::default()
+    }
+}
+
+fn join_relative(folder: &str, name: &str) -> String {
+    let folder = folder.trim_matches('/');
+    if folder.is_empty() {
+        name.to_string()
+    } else {
+        format!("{folder}/{name}")
+    }
+}
+
+/// `name` with `.md` appended unless it already ends in it; dots elsewhere (`2026.03.07`,
+/// `v1.2 notes`) are part of the name.
+fn with_markdown_extension(name: &str) -> String {
+    if name.to_lowercase().ends_with(".md") {
+        name.to_string()
+    } else {
+        format!("{name}.md")
+    }
+}
+
+fn query_params(query: &str) -> Result<Vec<(String, String)>, OnyxError> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let decode = |text: &str| {
+                urlencoding::decode(&text.replace('+', " "))
+                    .map(|decoded| decoded.into_owned())
+                    .map_err(|e| invalid(format!("'{text}' is not valid UTF-8: {e}")))
+            };
+            Ok((decode(key)?, decode(value)?))
+        })
+        .collect()
+}
+
+fn invalid(message: String) -> OnyxError {
+    OnyxError::DeepLink(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_each_action() {
+        assert_eq!(
+            DeepLink::parse("onyx://open?vault=Work&file=Projects%2FPlan.md&heading=Next+steps")
+                .unwrap(),
+            DeepLink::Open {
+                vault: Some("Work".to_string()),
+                file: Some("Projects/Plan.md".to_string()),
+                heading: Some("Next steps".to_string()),
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("ONYX://new/?name=Idea&content=%23%20Idea%0Abody").unwrap(),
+            DeepLink::New {
+                vault: None,
+                name: "Idea".to_string(),
+                content: "# Idea\nbody".to_string(),
+            }
+        );
+        assert_eq!(
+            DeepLink::parse("onyx://search?query=a%2Bb").unwrap(),
+            DeepLink::Search {
+                vault: None,
+                query: "a+b".to_string(),
+            }
+        );
+        let daily = DeepLink::parse("onyx://daily?vault=%2Fnotes%2FWork").unwrap();
+        assert_eq!(daily.vault(), Some("/notes/Work"));
+    }
+
+    #[test]
+    fn rejects_malformed_links() {
+        for url in [
+            "https://open?file=x",
+            "onyx://delete?file=x",
+            "onyx://new?content=x",
+            "onyx://search?query=",
+            "onyx://open?file=%FF",
+        ] {
+            let error = DeepLink::parse(url).unwrap_err();
+            assert!(matches!(error, OnyxError::DeepLink(_)), "{url}: {error}");
+        }
+        assert!(is_deep_link("onyx://daily"));
+        assert!(!is_deep_link("/Users/me/onyx"));
+    }
+
+    #[test]
+    fn formats_daily_note_names() {
+        let date = (2026, 3, 7);
+        assert_eq!(daily_note_path(None, date), "2026-03-07.md");
+        let config = DailyNotesConfig {
+            folder: Some("Journal/".to_string()),
+            format: "YYYY/MM/[Day] D.M.YY".to_string(),
+            template: None,
+        };
+        assert_eq!(
+            daily_note_path(Some(&config), date),
+            "Journal/2026/03/Day 7.3.26.md"
+        );
+    }
+
+    #[test]
+    fn resolves_vaults_by_name_path_or_last_use() {
+        let dir = TempDir::new().unwrap();
+        let work = dir.path().join("work-notes");
+        let home = dir.path().join("Home");
+        for root in [&work, &home] {
+            ensure_vault_config(root).unwrap();
+        }
+        let mut config = GlobalConfig {
+            vaults: vec![work.clone(), home.clone()],
+            ..GlobalConfig::default()
+        };
+
+        assert_eq!(resolve_vault(Some("work-notes"), &config).unwrap(), work);
+        assert_eq!(resolve_vault(Some("home"), &config).unwrap(), home);
+        let inside = work.join("Projects");
+        assert_eq!(
+            resolve_vault(Some(inside.to_str().unwrap()), &config).unwrap(),
+            work
+        );
+        assert!(resolve_vault(Some("Other"), &config).is_err());
+        assert!(resolve_vault(None, &config).is_err());
+        config.last_active_vault = Some(home.clone());
+        assert_eq!(resolve_vault(None, &config).unwrap(), home);
+    }
+
+    #[test]
+    fn follows_links_inside_the_vault() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("Projects")).unwrap();
+        std::fs::write(dir.path().join("Projects/Plan.md"), "# Plan\n").unwrap();
+        std::fs::write(dir.path().join("Daily.md"), "## Log\n").unwrap();
+        let mut vault = VaultHandle::open(dir.path()).unwrap();
+        let root = vault.root().to_path_buf();
+
+        for file in ["Projects/Plan.md", "Projects/Plan", "plan"] {
+            let link = DeepLink::Open {
+                vault: None,
+                file: Some(file.to_string()),
+                heading: Some("Plan".to_string()),
+            };
+            let request = follow(&link, &mut vault).unwrap();
+            assert_eq!(
+                request.file.as_deref(),
+                root.join("Projects/Plan.md").to_str()
+            );
+            assert_eq!(request.heading.as_deref(), Some("Plan"));
+        }
+        let missing = DeepLink::Open {
+            vault: None,
+            file: Some("../secrets".to_string()),
+            heading: None,
+        };
+        assert!(follow(&missing, &mut vault).is_err());
+
+        let new = DeepLink::New {
+            vault: None,
+            name: "Inbox/Idea".to_string(),
+            content: "#idea".to_string(),
+        };
+        follow(&new, &mut vault).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("Inbox/Idea.md")).unwrap(),
+            "#idea"
+        );
+        assert!(vault.tags().unwrap().contains(&"idea".to_string()));
+        assert!(follow(&new, &mut vault).is_err());
+
+        let mut config = vault.config().clone();
+        config.daily_notes = Some(DailyNotesConfig {
+            folder: Some("Journal".to_string()),
+            format: "YYYY-MM-DD".to_string(),
+            template: Some("Daily".to_string()),
+        });
+        crate::vault_config::save_vault_config(&root, &config).unwrap();
+        let mut vault = VaultHandle::open(&root).unwrap();
+        let request = follow(&DeepLink::Daily { vault: None }, &mut vault).unwrap();
+        let daily = PathBuf::from(request.file.unwrap());
+        assert!(daily.starts_with(root.join("Journal")));
+        assert_eq!(std::fs::read_to_string(daily).unwrap(), "## Log\n");
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt;
+
+/// All fallible operations in the Onyx workspace funnel through this type.
+#[derive(Debug)]
+pub enum OnyxError {
+    Io(std::io::Error),
+    TomlDeserialize(toml::de::Error),
+    TomlSerialize(toml::ser::Error),
+    Json(serde_json::Error),
+    Image(image::ImageError),
+    Zip(zip::result::ZipError),
+    Trash(trash::Error),
+    /// An export whose selection holds no notes.
+    NothingToExport,
+    /// A folder picked for Obsidian import that has no `.obsidian` settings folder.
+    NotObsidianVault(std::path::PathBuf),
+    /// A PDF that could not be parsed.
+    Pdf(String),
+    /// A `.canvas` file that parses but breaks the JSON Canvas rules.
+    InvalidCanvas(String),
+    /// A note query that doesn't parse.
+    Query(String),
+    NoHomeDir,
+    /// A path that resolves outside the vault root.
+    OutsideVault(std::path::PathBuf),
+    /// An `onyx://` URL that can't be followed.
+    DeepLink(String),
+    /// A change set that doesn't fit the buffer it was sent to.
+    Edit(String),
+    /// A checkbox toggle aimed at a 1-based line that holds no task.
+    NotATask(std::path::PathBuf, usize),
+}
+
+impl fmt::Display for OnyxError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(formatter, "IO error: {error}"),
+            Self::TomlDeserialize(error) => write!(formatter, "TOML parse error: {error}"),
+            Self::TomlSerialize(error) => write!(formatter, "TOML serialize error: {error}"),
+            Self::Json(error) => write!(formatter, "JSON error: {error}"),
+            Self::Image(error) => write!(formatter, "Image error: {error}"),
+            Self::Zip(error) => write!(formatter, "ZIP error: {error}"),
+            Self::Trash(error) => write!(formatter, "Trash error: {error}"),
+            Self::NothingToExport => write!(formatter, "no notes to export"),
+            Self::NotObsidianVault(path) => {
+                write!(formatter, "{} is not an Obsidian vault", path.display())
+            }
+            Self::Pdf(message) => write!(formatter, "PDF error: {message}"),
+            Self::InvalidCanvas(message) => write!(formatter, "Invalid canvas: {message}"),
+            Self::Query(message) => write!(formatter, "Query error: {message}"),
+            Self::NoHomeDir => write!(formatter, "could not determine home directory"),
+            Self::OutsideVault(path) => {
+                write!(formatter, "{} is outside the vault", path.display())
+            }
+            Self::DeepLink(message) => write!(formatter, "Invalid onyx:// link: {message}"),
+            Self::Edit(message) => write!(formatter, "Invalid edit: {message}"),
+            Self::NotATask(path, line) => {
+                write!(formatter, "line {line} of {} is not a task", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for OnyxError {}
+
+impl From<std::io::Error> for OnyxError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for OnyxError {
+    fn from(error: toml::de::Error) -> Self {
+        Self::TomlDeserialize(error)
+    }
+}
+
+impl From<toml::ser::Error> for OnyxError {
+    fn from(error: toml::ser::Error) -> Self {
+        Self::TomlSerialize(error)
+    }
+}
+
+impl From<serde_json::Error> for OnyxError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Json(error)
+    }
+}
+
+impl From<image::ImageError> for OnyxError {
+    fn from(error: image::ImageError) -> Self {
+        Self::Image(error)
+    }
+}
+
+impl From<zip::result::ZipError> for OnyxError {
+    fn from(error: zip::result::ZipError) -> Self {
+        Self::Zip(error)
+    }
+}
+
+impl From<trash::Error> for OnyxError {
+    fn from(error: trash::Error) -> Self {
+        Self::Trash(error)
+    }
+}
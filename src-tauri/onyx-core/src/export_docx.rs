@@ -0,0 +1,743 @@
+use std::collections::HashMap;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::book::{Book, BookExportReport, BookLink, BookOptions, ChapterSource, EmbeddedImage};
+use crate::error::OnyxError;
+use crate::markdown::escape_html;
+use crate::markdown_ast::{Block, Inline};
+
+/// Widest an image may be on the page: 6 inches in EMU.
+const MAX_IMAGE_WIDTH_EMU: u64 = 5_486_400;
+const EMU_PER_PIXEL: u64 = 9_525;
+/// Word caps bookmark names at 40 characters.
+const MAX_BOOKMARK_LENGTH: usize = 40;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Default Extension="gif" ContentType="image/gif"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>"#;
+
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:cs="Calibri"/><w:sz w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="300" w:after="100"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:sz w:val="24"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:pPr><w:pBdr><w:left w:val="single" w:sz="18" w:space="8" w:color="A0A0A0"/></w:pBdr><w:ind w:left="360"/></w:pPr><w:rPr><w:i/><w:color w:val="555555"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/><w:spacing w:after="160" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/><w:sz w:val="19"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="60"/><w:contextualSpacing/></w:pPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="1F5FBF"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="InlineCode"><w:name w:val="Inline Code"/><w:rPr><w:rFonts w:ascii="Courier New" w:hAnsi="Courier New" w:cs="Courier New"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:rPr></w:style>
+<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/><w:left w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/><w:right w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="A0A0A0"/></w:tblBorders><w:tblCellMar><w:left w:w="100" w:type="dxa"/><w:right w:w="100" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>
+</w:styles>"#;
+
+/// Bullet glyphs for each nesting level of an unordered list.
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Converts the notes selected by `source` into a single Word document at `dest`.
+pub fn export_book_to_docx(
+    vault_root: &Path,
+    source: &ChapterSource,
+    dest: &Path,
+    options: &BookOptions,
+) -> Result<BookExportReport, OnyxError> {
+    let book = Book::load(vault_root, source, options)?;
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(dest)?;
+    let mut report = write_docx(&book, file)?;
+    report.path = dest.to_string_lossy().to_string();
+    info!(
+        "Exported {} chapters to DOCX at {}",
+        book.chapters.len(),
+        dest.display()
+    );
+    Ok(report)
+}
+
+/// Writes the whole package; the report comes back without `path` filled in.
+pub fn write_docx<W: Write + Seek>(book: &Book, writer: W) -> Result<BookExportReport, OnyxError> {
+    let mut document = DocxWriter::new(book);
+    for index in 0..book.chapters.len() {
+        document.chapter(index);
+    }
+
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default();
+    let add = |zip: &mut ZipWriter<W>, name: &str, bytes: &[u8]| -> Result<(), OnyxError> {
+        zip.start_file(name, options)?;
+        zip.write_all(bytes)?;
+        Ok(())
+    };
+    add(&mut zip, "[Content_Types].xml", CONTENT_TYPES.as_bytes())?;
+    add(&mut zip, "_rels/.rels", PACKAGE_RELS.as_bytes())?;
+    add(
+        &mut zip,
+        "docProps/core.xml",
+        core_properties(book).as_bytes(),
+    )?;
+    add(&mut zip, "word/styles.xml", STYLES.as_bytes())?;
+    add(
+        &mut zip,
+        "word/numbering.xml",
+        document.numbering().as_bytes(),
+    )?;
+    add(
+        &mut zip,
+        "word/_rels/document.xml.rels",
+        document.relationships().as_bytes(),
+    )?;
+    add(
+        &mut zip,
+        "word/document.xml",
+        document.document_xml().as_bytes(),
+    )?;
+    for (name, image) in &document.media {
+        add(&mut zip, &format!("word/media/{name}"), &image.data)?;
+    }
+    zip.finish()?;
+
+    Ok(BookExportReport {
+        path: String::new(),
+        chapters: book
+            .chapters
+            .iter()
+            .map(|chapter| chapter.path.to_string_lossy().to_string())
+            .collect(),
+        broken_links: document.broken_links,
+        missing_images: document.missing_images,
+    })
+}
+
+fn core_properties(book: &Book) -> String {
+    let creator = book
+        .author
+        .as_deref()
+        .map(|author| format!("<dc:creator>{}</dc:creator>", escape_html(author)))
+        .unwrap_or_default();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>{}</dc:title>{creator}</cp:coreProperties>"#,
+        escape_html(&book.title)
+    )
+}
+
+/// Run formatting inherited from enclosing inline nodes.
+#[derive(Clone, Copy, Default)]
+struct RunStyle {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    code: bool,
+    link: bool,
+}
+
+impl RunStyle {
+    fn properties(self) -> String {
+        let mut properties = String::new();
+        if self.link {
+            properties.push_str(r#"<w:rStyle w:val="Hyperlink"/>"#);
+        } else if self.code {
+            properties.push_str(r#"<w:rStyle w:val="InlineCode"/>"#);
+        }
+        if self.bold {
+            properties.push_str("<w:b/>");
+        }
+        if self.italic {
+            properties.push_str("<w:i/>");
+        }
+        if self.strike {
+            properties.push_str("<w:strike/>");
+        }
+        if properties.is_empty() {
+            properties
+        } else {
+            format!("<w:rPr>{properties}</w:rPr>")
+        }
+    }
+}
+
+/// Paragraph context inherited from enclosing blocks.
+#[derive(Clone, Copy, Default)]
+struct BlockContext {
+    quote_depth: usize,
+    /// Numbering instance and level of the list item this paragraph starts, if any.
+    numbering: Option<(usize, usize)>,
+    /// Nesting depth of lists, used to indent continuation paragraphs.
+    list_depth: usize,
+}
+
+/// Accumulates `document.xml` and the parts it references.
+struct DocxWriter<'a> {
+    book: &'a Book,
+    body: String,
+    relationships: Vec<String>,
+    media: Vec<(String, EmbeddedImage)>,
+    media_by_path: HashMap<PathBuf, (String, u32, u32)>,
+    /// Ordered list instances, each with the number it starts at.
+    ordered_lists: Vec<u64>,
+    bookmark_id: usize,
+    drawing_id: usize,
+    broken_links: Vec<crate::export_html::BrokenLink>,
+    missing_images: Vec<String>,
+}
+
+impl<'a> DocxWriter<'a> {
+    fn new(book: &'a Book) -> Self {
+        Self {
+            book,
+            body: String::new(),
+            relationships: vec![
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#.to_string(),
+                r#"<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/>"#.to_string(),
+            ],
+            media: Vec::new(),
+            media_by_path: HashMap::new(),
+            ordered_lists: Vec::new(),
+            bookmark_id: 0,
+            drawing_id: 0,
+            broken_links: Vec::new(),
+            missing_images: Vec::new(),
+        }
+    }
+
+    fn document_xml(&self) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main" xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><w:body>{}<w:p/></w:body></w:document>"#,
+            self.body
+        )
+    }
+
+    fn relationships(&self) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+            self.relationships.join("")
+        )
+    }
+
+    /// Numbering definitions: instance 1 is bullets, every ordered list gets its own instance so it restarts.
+    fn numbering(&self) -> String {
+        let levels = |ordered: bool| {
+            (0..9)
+                .map(|level| {
+                    let (format, text) = if ordered {
+                        ("decimal", format!("%{}.", level + 1))
+                    } else {
+                        ("bullet", BULLETS[level % BULLETS.len()].to_string())
+                    };
+                    let indent = 720 * (level + 1);
+                    format!(
+                        r#"<w:lvl w:ilvl="{level}"><w:start w:val="1"/><w:numFmt w:val="{format}"/><w:lvlText w:val="{text}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{indent}" w:hanging="360"/></w:pPr></w:lvl>"#
+                    )
+                })
+                .collect::<String>()
+        };
+        let mut xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:abstractNum w:abstractNumId="0"><w:multiLevelType w:val="multilevel"/>{}</w:abstractNum><w:abstractNum w:abstractNumId="1"><w:multiLevelType w:val="multilevel"/>{}</w:abstractNum><w:num w:numId="1"><w:abstractNumId w:val="0"/></w:num>"#,
+            levels(false),
+            levels(true)
+        );
+        for (offset, start) in self.ordered_lists.iter().enumerate() {
+            let overrides: String = (0..9)
+                .map(|level| {
+                    let start = if level == 0 { *start } else { 1 };
+                    format!(
+                        r#"<w:lvlOverride w:ilvl="{level}"><w:startOverride w:val="{start}"/></w:lvlOverride>"#
+                    )
+                })
+                .collect();
+            xml.push_str(&format!(
+                r#"<w:num w:numId="{}"><w:abstractNumId w:val="1"/>{overrides}</w:num>"#,
+                offset + 2
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+
+    fn chapter(&mut self, index: usize) {
+        if index > 0 {
+            self.body
+                .push_str(r#"<w:p><w:r><w:br w:type="page"/></w:r></w:p>"#);
+        }
+        let blocks = &self.book.chapters[index].blocks;
+        for (position, block) in blocks.iter().enumerate() {
+            let anchor = (position == 0).then(|| chapter_bookmark(index));
+            self.block(index, block, BlockContext::default(), anchor);
+        }
+    }
+
+    fn block(
+        &mut self,
+        chapter: usize,
+        block: &Block,
+        context: BlockContext,
+        anchor: Option<String>,
+    ) {
+        match block {
+            Block::Heading { level, id, content } => {
+                let mut bookmarks: Vec<String> = anchor.into_iter().collect();
+                bookmarks.extend(id.as_deref().map(|id| heading_bookmark(chapter, id)));
+                let style = format!(r#"<w:pStyle w:val="Heading{}"/>"#, (*level).clamp(1, 6));
+                self.paragraph(chapter, &style, content, &bookmarks);
+            }
+            Block::Paragraph(content) => {
+                let bookmarks: Vec<String> = anchor.into_iter().collect();
+                let properties = paragraph_properties(context);
+                self.paragraph(chapter, &properties, content, &bookmarks);
+            }
+            Block::List { start, items } => {
+                let numbering_id = match start {
+                    Some(start) => {
+                        self.ordered_lists.push(*start);
+                        self.ordered_lists.len() + 1
+                    }
+                    None => 1,
+                };
+                for item in items {
+                    let mut item_context = BlockContext {
+                        numbering: Some((numbering_id, context.list_depth)),
+                        list_depth: context.list_depth + 1,
+                        ..context
+                    };
+                    let marker = item
+                        .checked
+                        .map(|checked| if checked { "☒ " } else { "☐ " });
+                    if item.blocks.is_empty() {
+                        let properties = paragraph_properties(item_context);
+                        let content: Vec<Inline> = marker
+                            .map(|m| Inline::Text(m.to_string()))
+                            .into_iter()
+                            .collect();
+                        self.paragraph(chapter, &properties, &content, &[]);
+                        continue;
+                    }
+                    for (position, child) in item.blocks.iter().enumerate() {
+                        match (position, child, marker) {
+                            (0, Block::Paragraph(content), Some(marker)) => {
+                                let mut content = content.clone();
+                                content.insert(0, Inline::Text(marker.to_string()));
+                                self.block(chapter, &Block::Paragraph(content), item_context, None);
+                            }
+                            _ => self.block(chapter, child, item_context, None),
+                        }
+                        item_context.numbering = None;
+                    }
+                }
+            }
+            Block::Quote(blocks) => {
+                let quote_context = BlockContext {
+                    quote_depth: context.quote_depth + 1,
+                    ..context
+                };
+                for child in blocks {
+                    self.block(chapter, child, quote_context, None);
+                }
+            }
+            Block::Code { text, .. } => {
+                let mut runs = String::new();
+                for (position, line) in text.trim_end_matches('\n').split('\n').enumerate() {
+                    if position > 0 {
+                        runs.push_str("<w:r><w:br/></w:r>");
+                    }
+                    runs.push_str(&text_run(&line.replace('\t', "    "), RunStyle::default()));
+                }
+                let indent = indent_properties(context);
+                self.body.push_str(&format!(
+                    r#"<w:p><w:pPr><w:pStyle w:val="Code"/>{indent}</w:pPr>{runs}</w:p>"#
+                ));
+            }
+            Block::Table { header, rows } => self.table(chapter, header, rows),
+            Block::Footnote { label, blocks } => {
+                for (position, child) in blocks.iter().enumerate() {
+                    match (position, child) {
+                        (0, Block::Paragraph(content)) => {
+                            let mut content = content.clone();
+                            content.insert(0, Inline::Text(format!("[{label}] ")));
+                            self.block(chapter, &Block::Paragraph(content), context, None);
+                        }
+                        _ => self.block(chapter, child, context, None),
+                    }
+                }
+            }
+            Block::Rule => {
+                self.body.push_str(r#"<w:p><w:pPr><w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="A0A0A0"/></w:pBdr></w:pPr></w:p>"#);
+            }
+        }
+    }
+
+    fn paragraph(
+        &mut self,
+        chapter: usize,
+        properties: &str,
+        content: &[Inline],
+        bookmarks: &[String],
+    ) {
+        let mut xml = String::from("<w:p>");
+        if !properties.is_empty() {
+            xml.push_str(&format!("<w:pPr>{properties}</w:pPr>"));
+        }
+        for name in bookmarks {
+            self.bookmark_id += 1;
+            xml.push_str(&format!(
+                r#"<w:bookmarkStart w:id="{0}" w:name="{1}"/><w:bookmarkEnd w:id="{0}"/>"#,
+                self.bookmark_id,
+                escape_html(name)
+            ));
+        }
+        xml.push_str(&self.runs(chapter, content, RunStyle::default()));
+        xml.push_str("</w:p>");
+        self.body.push_str(&xml);
+    }
+
+    fn runs(&mut self, chapter: usize, inlines: &[Inline], style: RunStyle) -> String {
+        let mut xml = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => xml.push_str(&text_run(text, style)),
+                Inline::Code(code) => xml.push_str(&text_run(
+                    code,
+                    RunStyle {
+                        code: true,
+                        ..style
+                    },
+                )),
+                Inline::Strong(children) => {
+                    xml.push_str(&self.runs(
+                        chapter,
+                        children,
+                        RunStyle {
+                            bold: true,
+                            ..style
+                        },
+                    ));
+                }
+                Inline::Emphasis(children) => {
+                    xml.push_str(&self.runs(
+                        chapter,
+                        children,
+                        RunStyle {
+                            italic: true,
+                            ..style
+                        },
+                    ));
+                }
+                Inline::Strikethrough(children) => {
+                    xml.push_str(&self.runs(
+                        chapter,
+                        children,
+                        RunStyle {
+                            strike: true,
+                            ..style
+                        },
+                    ));
+                }
+                Inline::Link {
+                    dest,
+                    wikilink,
+                    content,
+                } => {
+                    let link_style = RunStyle {
+                        link: true,
+                        ..style
+                    };
+                    match self.book.link_target(chapter, dest, *wikilink) {
+                        BookLink::Chapter { index, heading } => {
+                            let anchor = match heading {
+                                Some(heading) => heading_bookmark(index, &heading),
+                                None => chapter_bookmark(index),
+                            };
+                            let runs = self.runs(chapter, content, link_style);
+                            xml.push_str(&format!(
+                                r#"<w:hyperlink w:anchor="{}">{runs}</w:hyperlink>"#,
+                                escape_html(&anchor)
+                            ));
+                        }
+                        BookLink::External(url) => {
+                            let id = format!("rId{}", self.relationships.len() + 1);
+                            self.relationships.push(format!(
+                                r#"<Relationship Id="{id}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="{}" TargetMode="External"/>"#,
+                                escape_html(&url)
+                            ));
+                            let runs = self.runs(chapter, content, link_style);
+                            xml.push_str(&format!(
+                                r#"<w:hyperlink r:id="{id}">{runs}</w:hyperlink>"#
+                            ));
+                        }
+                        BookLink::Unresolved => {
+                            self.broken_links.push(self.book.broken_link(chapter, dest));
+                            xml.push_str(&self.runs(chapter, content, style));
+                        }
+                    }
+                }
+                Inline::Image { dest, alt } => xml.push_str(&self.image(chapter, dest, alt, style)),
+                Inline::FootnoteReference(label) => {
+                    xml.push_str(&format!(
+                        r#"<w:r><w:rPr><w:vertAlign w:val="superscript"/></w:rPr><w:t>[{}]</w:t></w:r>"#,
+                        escape_html(label)
+                    ));
+                }
+                Inline::LineBreak => xml.push_str("<w:r><w:br/></w:r>"),
+            }
+        }
+        xml
+    }
+
+    /// Embeds an image as an inline drawing, falling back to its alt text when it can't be loaded.
+    fn image(&mut self, chapter: usize, dest: &str, alt: &str, style: RunStyle) -> String {
+        let Some(path) = self.book.image_path(chapter, dest) else {
+            if let BookLink::Chapter { index, .. } = self.book.link_target(chapter, dest, true) {
+                // A note embed: link to the chapter instead of transcluding it.
+                let title = &self.book.chapters[index].title;
+                return format!(
+                    r#"<w:hyperlink w:anchor="{}">{}</w:hyperlink>"#,
+                    chapter_bookmark(index),
+                    text_run(
+                        title,
+                        RunStyle {
+                            link: true,
+                            ..style
+                        }
+                    )
+                );
+            }
+            self.missing_images.push(dest.to_string());
+            return text_run(&format!("[{alt}]"), style);
+        };
+        let (name, width, height) = match self.media_by_path.get(&path) {
+            Some(entry) => entry.clone(),
+            None => match EmbeddedImage::load(&path) {
+                Ok(image) => {
+                    let name = format!("image{}.{}", self.media.len() + 1, image.extension);
+                    let entry = (name.clone(), image.width, image.height);
+                    self.media.push((name, image));
+                    self.media_by_path.insert(path.clone(), entry.clone());
+                    entry
+                }
+                Err(error) => {
+                    warn!("Skipping image {} in DOCX export: {error}", path.display());
+                    self.missing_images.push(dest.to_string());
+                    return text_run(&format!("[{alt}]"), style);
+                }
+            },
+        };
+
+        let relationship = format!("rId{}", self.relationships.len() + 1);
+        self.relationships.push(format!(
+            r#"<Relationship Id="{relationship}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/image" Target="media/{name}"/>"#
+        ));
+        let mut cx = u64::from(width.max(1)) * EMU_PER_PIXEL;
+        let mut cy = u64::from(height.max(1)) * EMU_PER_PIXEL;
+        if cx > MAX_IMAGE_WIDTH_EMU {
+            cy = cy * MAX_IMAGE_WIDTH_EMU / cx;
+            cx = MAX_IMAGE_WIDTH_EMU;
+        }
+        self.drawing_id += 1;
+        let id = self.drawing_id;
+        let alt = escape_html(alt);
+        format!(
+            r#"<w:r><w:drawing><wp:inline distT="0" distB="0" distL="0" distR="0"><wp:extent cx="{cx}" cy="{cy}"/><wp:docPr id="{id}" name="Picture {id}" descr="{alt}"/><a:graphic><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic><pic:nvPicPr><pic:cNvPr id="{id}" name="{name}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{relationship}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{cx}" cy="{cy}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#
+        )
+    }
+
+    fn table(&mut self, chapter: usize, header: &[Vec<Inline>], rows: &[Vec<Vec<Inline>>]) {
+        let columns = rows
+            .iter()
+            .map(Vec::len)
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        // Spread the 6.5" text block (in twentieths of a point) evenly across columns.
+        let column_width = 9360 / columns;
+        let grid: String = (0..columns)
+            .map(|_| format!(r#"<w:gridCol w:w="{column_width}"/>"#))
+            .collect();
+        let mut xml = format!(
+            r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="0" w:type="auto"/></w:tblPr><w:tblGrid>{grid}</w:tblGrid>"#
+        );
+        let empty = Vec::new();
+        let all_rows =
+            std::iter::once((true, header)).chain(rows.iter().map(|row| (false, row.as_slice())));
+        for (is_header, row) in all_rows {
+            if is_header && row.is_empty() {
+                continue;
+            }
+            xml.push_str("<w:tr>");
+            if is_header {
+                xml.push_str("<w:trPr><w:tblHeader/></w:trPr>");
+            }
+            for column in 0..columns {
+                let cell = row.get(column).unwrap_or(&empty);
+                let style = RunStyle {
+                    bold: is_header,
+                    ..RunStyle::default()
+                };
+                let runs = self.runs(chapter, cell, style);
+                xml.push_str(&format!(
+                    r#"<w:tc><w:tcPr><w:tcW w:w="{column_width}" w:type="dxa"/></w:tcPr><w:p><w:pPr><w:spacing w:after="0"/></w:pPr>{runs}</w:p></w:tc>"#
+                ));
+            }
+            xml.push_str("</w:tr>");
+        }
+        xml.push_str("</w:tbl>");
+        self.body.push_str(&xml);
+    }
+}
+
+fn paragraph_properties(context: BlockContext) -> String {
+    let mut properties = String::new();
+    if context.quote_depth > 0 {
+        properties.push_str(r#"<w:pStyle w:val="Quote"/>"#);
+    } else if context.list_depth > 0 {
+        properties.push_str(r#"<w:pStyle w:val="ListParagraph"/>"#);
+    }
+    match context.numbering {
+        Some((id, level)) => properties.push_str(&format!(
+            r#"<w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{id}"/></w:numPr>"#
+        )),
+        None => properties.push_str(&indent_properties(context)),
+    }
+    properties
+}
+
+/// Left indent that lines up continuation paragraphs and nested blocks with their list item or quote.
+fn indent_properties(context: BlockContext) -> String {
+    let indent = 720 * context.list_depth + 360 * context.quote_depth;
+    if indent == 0 {
+        String::new()
+    } else {
+        format!(r#"<w:ind w:left="{indent}"/>"#)
+    }
+}
+
+fn text_run(text: &str, style: RunStyle) -> String {
+    format!(
+        r#"<w:r>{}<w:t xml:space="preserve">{}</w:t></w:r>"#,
+        style.properties(),
+        escape_html(text)
+    )
+}
+
+fn chapter_bookmark(index: usize) -> String {
+    format!("_chapter{}", index + 1)
+}
+
+/// Bookmark for a heading; Word only allows letters, digits and underscores.
+fn heading_bookmark(chapter: usize, slug: &str) -> String {
+    let mut name = format!("_c{}_", chapter + 1);
+    name.extend(slug.chars().map(|character| {
+        if character.is_ascii_alphanumeric() {
+            character
+        } else {
+            '_'
+        }
+    }));
+    name.chars().take(MAX_BOOKMARK_LENGTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+    use zip::ZipArchive;
+
+    fn read_part(path: &Path, name: &str) -> String {
+        let mut archive = ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        let mut part = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut part)
+            .unwrap();
+        part
+    }
+
+    fn setup_vault() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let image = image::RgbImage::from_pixel(2000, 1000, image::Rgb([0, 128, 255]));
+        image.save(root.join("wide.png")).unwrap();
+        std::fs::write(
+            root.join("One.md"),
+            "# One\n\n- [ ] task\n  1. nested\n\n| H |\n|---|\n| c |\n\n```\ncode & more\n```\n\n![[wide.png]] ![x](gone.png)\n\nGo to [[Two#Details]] or [web](https://example.com) or [[Nowhere]].\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("Two.md"), "Intro\n\n## Details\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn exports_chapters_with_links_lists_tables_and_images() {
+        let vault = setup_vault();
+        let dest = vault.path().join("out/book.docx");
+        let source = ChapterSource::Notes(vec![
+            vault.path().join("One.md").to_string_lossy().to_string(),
+            vault.path().join("Two.md").to_string_lossy().to_string(),
+        ]);
+
+        let report =
+            export_book_to_docx(vault.path(), &source, &dest, &BookOptions::default()).unwrap();
+
+        assert_eq!(report.chapters.len(), 2);
+        assert_eq!(report.missing_images, vec!["gone.png"]);
+        assert_eq!(report.broken_links.len(), 1);
+        assert_eq!(report.broken_links[0].target, "Nowhere");
+
+        let document = read_part(&dest, "word/document.xml");
+        assert!(document.contains(r#"<w:hyperlink w:anchor="_c2_details">"#));
+        assert!(document.contains(r#"w:name="_c2_details""#));
+        assert!(document.contains(r#"<w:br w:type="page"/>"#));
+        assert!(document.contains(r#"<w:t xml:space="preserve">☐ </w:t>"#));
+        assert!(document.contains(r#"<w:numId w:val="2"/>"#));
+        assert!(document.contains("<w:tblHeader/>"));
+        assert!(document.contains("code &amp; more"));
+        assert!(document.contains(r#"<wp:extent cx="5486400" cy="2743200"/>"#));
+
+        let relationships = read_part(&dest, "word/_rels/document.xml.rels");
+        assert!(relationships.contains(r#"Target="https://example.com" TargetMode="External""#));
+        assert!(relationships.contains(r#"Target="media/image1.png""#));
+        let numbering = read_part(&dest, "word/numbering.xml");
+        assert!(numbering.contains(r#"<w:num w:numId="2"><w:abstractNumId w:val="1"/>"#));
+        assert!(read_part(&dest, "docProps/core.xml").contains("<dc:title>One</dc:title>"));
+    }
+
+    #[test]
+    fn heading_bookmarks_are_word_safe() {
+        assert_eq!(heading_bookmark(0, "über-part"), "_c1__ber_part");
+        assert_eq!(
+            heading_bookmark(9, &"a".repeat(60)).len(),
+            MAX_BOOKMARK_LENGTH
+        );
+    }
+}
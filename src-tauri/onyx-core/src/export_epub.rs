@@ -0,0 +1,572 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use log::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::book::{Book, BookExportReport, BookLink, BookOptions, ChapterSource, EmbeddedImage};
+use crate::dates::utc_timestamp;
+use crate::error::OnyxError;
+use crate::markdown::escape_html;
+use crate::markdown_ast::{Block, Inline};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+/// Deliberately light styling: e-readers apply their own fonts, sizes and night modes.
+const STYLESHEET: &str = "body { line-height: 1.5; }
+h1, h2, h3, h4, h5, h6 { line-height: 1.2; page-break-after: avoid; }
+pre { white-space: pre-wrap; font-size: 0.85em; background: #f2f2f2; padding: 0.5em; }
+code { font-family: monospace; }
+blockquote { margin-left: 1em; padding-left: 0.8em; border-left: 3px solid #a0a0a0; color: #555; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #a0a0a0; padding: 0.2em 0.5em; }
+img { max-width: 100%; }
+li.task { list-style: none; }
+.footnote { font-size: 0.9em; }
+";
+
+/// Converts the notes selected by `source` into an EPUB 3 book at `dest`, one chapter per note.
+pub fn export_book_to_epub(
+    vault_root: &Path,
+    source: &ChapterSource,
+    dest: &Path,
+    options: &BookOptions,
+) -> Result<BookExportReport, OnyxError> {
+    let book = Book::load(vault_root, source, options)?;
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(dest)?;
+    let mut report = write_epub(&book, file)?;
+    report.path = dest.to_string_lossy().to_string();
+    info!(
+        "Exported {} chapters to EPUB at {}",
+        book.chapters.len(),
+        dest.display()
+    );
+    Ok(report)
+}
+
+/// Writes the whole package; the report comes back without `path` filled in.
+pub fn write_epub<W: Write + Seek>(book: &Book, writer: W) -> Result<BookExportReport, OnyxError> {
+    let mut renderer = XhtmlRenderer::new(book);
+    let chapters: Vec<String> = (0..book.chapters.len())
+        .map(|index| renderer.chapter(index))
+        .collect();
+
+    let mut zip = ZipWriter::new(writer);
+    // The mimetype entry must come first and be stored uncompressed.
+    zip.start_file(
+        "mimetype",
+        SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    zip.write_all(b"application/epub+zip")?;
+
+    let options = SimpleFileOptions::default();
+    let add = |zip: &mut ZipWriter<W>, name: &str, bytes: &[u8]| -> Result<(), OnyxError> {
+        zip.start_file(name, options)?;
+        zip.write_all(bytes)?;
+        Ok(())
+    };
+    add(&mut zip, "META-INF/container.xml", CONTAINER.as_bytes())?;
+    add(
+        &mut zip,
+        "OEBPS/content.opf",
+        package_document(book, &renderer.images).as_bytes(),
+    )?;
+    add(&mut zip, "OEBPS/nav.xhtml", navigation(book).as_bytes())?;
+    add(&mut zip, "OEBPS/style.css", STYLESHEET.as_bytes())?;
+    for (index, body) in chapters.iter().enumerate() {
+        let title = &book.chapters[index].title;
+        add(
+            &mut zip,
+            &format!("OEBPS/{}", chapter_file(index)),
+            xhtml_document(title, body).as_bytes(),
+        )?;
+    }
+    for image in &renderer.images {
+        add(&mut zip, &format!("OEBPS/{}", image.href), &image.data)?;
+    }
+    zip.finish()?;
+
+    Ok(BookExportReport {
+        path: String::new(),
+        chapters: book
+            .chapters
+            .iter()
+            .map(|chapter| chapter.path.to_string_lossy().to_string())
+            .collect(),
+        broken_links: renderer.broken_links,
+        missing_images: renderer.missing_images,
+    })
+}
+
+fn chapter_file(index: usize) -> String {
+    format!("chapter-{}.xhtml", index + 1)
+}
+
+fn xhtml_document(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+<title>{}</title>
+<link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+{body}</body>
+</html>
+"#,
+        escape_html(title)
+    )
+}
+
+fn navigation(book: &Book) -> String {
+    let entries: String = book
+        .chapters
+        .iter()
+        .enumerate()
+        .map(|(index, chapter)| {
+            format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                chapter_file(index),
+                escape_html(&chapter.title)
+            )
+        })
+        .collect();
+    let body = format!(
+        "<nav epub:type=\"toc\" id=\"toc\">\n<h1>{}</h1>\n<ol>\n{entries}</ol>\n</nav>\n",
+        escape_html(&book.title)
+    );
+    xhtml_document(&book.title, &body)
+}
+
+fn package_document(book: &Book, images: &[StoredImage]) -> String {
+    let mut hasher = DefaultHasher::new();
+    book.title.hash(&mut hasher);
+    for chapter in &book.chapters {
+        chapter.path.hash(&mut hasher);
+    }
+    let identifier = format!("urn:onyx:{:016x}", hasher.finish());
+    let creator = book
+        .author
+        .as_deref()
+        .map(|author| format!("<dc:creator>{}</dc:creator>\n", escape_html(author)))
+        .unwrap_or_default();
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n",
+    );
+    let mut spine = String::new();
+    for index in 0..book.chapters.len() {
+        manifest.push_str(&format!(
+            "<item id=\"chapter-{0}\" href=\"{1}\" media-type=\"application/xhtml+xml\"/>\n",
+            index + 1,
+            chapter_file(index)
+        ));
+        spine.push_str(&format!("<itemref idref=\"chapter-{}\"/>\n", index + 1));
+    }
+    for (index, image) in images.iter().enumerate() {
+        manifest.push_str(&format!(
+            "<item id=\"image-{}\" href=\"{}\" media-type=\"{}\"/>\n",
+            index + 1,
+            escape_html(&image.href),
+            image.media_type
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+<metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+<dc:identifier id="book-id">{identifier}</dc:identifier>
+<dc:title>{}</dc:title>
+{creator}<dc:language>en</dc:language>
+<meta property="dcterms:modified">{}</meta>
+</metadata>
+<manifest>
+{manifest}</manifest>
+<spine>
+{spine}</spine>
+</package>
+"#,
+        escape_html(&book.title),
+        utc_timestamp(SystemTime::now())
+    )
+}
+
+struct StoredImage {
+    href: String,
+    media_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// Renders chapter blocks to XHTML, collecting images and unresolved links along the way.
+struct XhtmlRenderer<'a> {
+    book: &'a Book,
+    images: Vec<StoredImage>,
+    image_hrefs: HashMap<PathBuf, String>,
+    broken_links: Vec<crate::export_html::BrokenLink>,
+    missing_images: Vec<String>,
+}
+
+impl<'a> XhtmlRenderer<'a> {
+    fn new(book: &'a Book) -> Self {
+        Self {
+            book,
+            images: Vec::new(),
+            image_hrefs: HashMap::new(),
+            broken_links: Vec::new(),
+            missing_images: Vec::new(),
+        }
+    }
+
+    fn chapter(&mut self, index: usize) -> String {
+        let mut html = String::new();
+        for block in &self.book.chapters[index].blocks {
+            self.block(index, block, &mut html);
+        }
+        html
+    }
+
+    fn block(&mut self, chapter: usize, block: &Block, html: &mut String) {
+        match block {
+            Block::Heading { level, id, content } => {
+                let level = (*level).clamp(1, 6);
+                let id = id
+                    .as_deref()
+                    .map(|id| format!(" id=\"{}\"", escape_html(id)))
+                    .unwrap_or_default();
+                let inner = self.inlines(chapter, content);
+                html.push_str(&format!("<h{level}{id}>{inner}</h{level}>\n"));
+            }
+            Block::Paragraph(content) => {
+                let inner = self.inlines(chapter, content);
+                html.push_str(&format!("<p>{inner}</p>\n"));
+            }
+            Block::List { start, items } => {
+                let tag = match start {
+                    Some(1) => "ol".to_string(),
+                    Some(start) => format!("ol start=\"{start}\""),
+                    None => "ul".to_string(),
+                };
+                html.push_str(&format!("<{tag}>\n"));
+                for item in items {
+                    match item.checked {
+                        Some(checked) => {
+                            let mark = if checked { "☒" } else { "☐" };
+                            html.push_str(&format!("<li class=\"task\">{mark} "));
+                        }
+                        None => html.push_str("<li>"),
+                    }
+                    // Tight items read better without a paragraph wrapper around their text.
+                    for (position, child) in item.blocks.iter().enumerate() {
+                        match child {
+                            Block::Paragraph(content) if position == 0 => {
+                                let inner = self.inlines(chapter, content);
+                                html.push_str(&inner);
+                                html.push('\n');
+                            }
+                            _ => self.block(chapter, child, html),
+                        }
+                    }
+                    html.push_str("</li>\n");
+                }
+                html.push_str(if start.is_some() {
+                    "</ol>\n"
+                } else {
+                    "</ul>\n"
+                });
+            }
+            Block::Quote(blocks) => {
+                html.push_str("<blockquote>\n");
+                for child in blocks {
+                    self.block(chapter, child, html);
+                }
+                html.push_str("</blockquote>\n");
+            }
+            Block::Code { language, text } => {
+                let class = language
+                    .as_deref()
+                    .map(|language| format!(" class=\"language-{}\"", escape_html(language)))
+                    .unwrap_or_default();
+                html.push_str(&format!(
+                    "<pre><code{class}>{}</code></pre>\n",
+                    escape_html(text)
+                ));
+            }
+            Block::Table { header, rows } => {
+                html.push_str("<table>\n");
+                if !header.is_empty() {
+                    html.push_str("<thead><tr>");
+                    for cell in header {
+                        let inner = self.inlines(chapter, cell);
+                        html.push_str(&format!("<th>{inner}</th>"));
+                    }
+                    html.push_str("</tr></thead>\n");
+                }
+                html.push_str("<tbody>\n");
+                for row in rows {
+                    html.push_str("<tr>");
+                    for cell in row {
+                        let inner = self.inlines(chapter, cell);
+                        html.push_str(&format!("<td>{inner}</td>"));
+                    }
+                    html.push_str("</tr>\n");
+                }
+                html.push_str("</tbody>\n</table>\n");
+            }
+            Block::Footnote { label, blocks } => {
+                html.push_str(&format!(
+                    "<aside epub:type=\"footnote\" class=\"footnote\" id=\"fn-{}\">\n<p>[{}]</p>\n",
+                    escape_html(&footnote_id(label)),
+                    escape_html(label)
+                ));
+                for child in blocks {
+                    self.block(chapter, child, html);
+                }
+                html.push_str("</aside>\n");
+            }
+            Block::Rule => html.push_str("<hr/>\n"),
+        }
+    }
+
+    fn inlines(&mut self, chapter: usize, inlines: &[Inline]) -> String {
+        let mut html = String::new();
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => html.push_str(&escape_html(text)),
+                Inline::Code(code) => {
+                    html.push_str(&format!("<code>{}</code>", escape_html(code)));
+                }
+                Inline::Strong(children) => {
+                    html.push_str(&format!(
+                        "<strong>{}</strong>",
+                        self.inlines(chapter, children)
+                    ));
+                }
+                Inline::Emphasis(children) => {
+                    html.push_str(&format!("<em>{}</em>", self.inlines(chapter, children)));
+                }
+                Inline::Strikethrough(children) => {
+                    html.push_str(&format!("<del>{}</del>", self.inlines(chapter, children)));
+                }
+                Inline::Link {
+                    dest,
+                    wikilink,
+                    content,
+                } => {
+                    let inner = self.inlines(chapter, content);
+                    match self.book.link_target(chapter, dest, *wikilink) {
+                        BookLink::Chapter { index, heading } => {
+                            let href = chapter_href(index, heading.as_deref());
+                            html.push_str(&format!("<a href=\"{href}\">{inner}</a>"));
+                        }
+                        BookLink::External(url) => {
+                            html.push_str(&format!(
+                                "<a href=\"{}\">{inner}</a>",
+                                escape_html(&url)
+                            ));
+                        }
+                        BookLink::Unresolved => {
+                            self.broken_links.push(self.book.broken_link(chapter, dest));
+                            html.push_str(&inner);
+                        }
+                    }
+                }
+                Inline::Image { dest, alt } => {
+                    let image = self.image(chapter, dest, alt);
+                    html.push_str(&image);
+                }
+                Inline::FootnoteReference(label) => {
+                    html.push_str(&format!(
+                        "<a epub:type=\"noteref\" href=\"#fn-{}\">[{}]</a>",
+                        escape_html(&footnote_id(label)),
+                        escape_html(label)
+                    ));
+                }
+                Inline::LineBreak => html.push_str("<br/>"),
+            }
+        }
+        html
+    }
+
+    /// Embeds an image, linking note embeds to their chapter and falling back to alt text.
+    fn image(&mut self, chapter: usize, dest: &str, alt: &str) -> String {
+        if dest.contains("://") {
+            // Reading systems may not fetch remote resources, so keep the link rather than the image.
+            return format!("<a href=\"{}\">{}</a>", escape_html(dest), escape_html(alt));
+        }
+        let Some(path) = self.book.image_path(chapter, dest) else {
+            if let BookLink::Chapter { index, .. } = self.book.link_target(chapter, dest, true) {
+                return format!(
+                    "<a href=\"{}\">{}</a>",
+                    chapter_file(index),
+                    escape_html(&self.book.chapters[index].title)
+                );
+            }
+            self.missing_images.push(dest.to_string());
+            return format!("[{}]", escape_html(alt));
+        };
+        let href = match self.image_hrefs.get(&path) {
+            Some(href) => href.clone(),
+            None => match self.store_image(&path) {
+                Ok(href) => href,
+                Err(error) => {
+                    warn!("Skipping image {} in EPUB export: {error}", path.display());
+                    self.missing_images.push(dest.to_string());
+                    return format!("[{}]", escape_html(alt));
+                }
+            },
+        };
+        format!(
+            "<img src=\"{}\" alt=\"{}\"/>",
+            escape_html(&href),
+            escape_html(alt)
+        )
+    }
+
+    fn store_image(&mut self, path: &Path) -> Result<String, OnyxError> {
+        let number = self.images.len() + 1;
+        let is_svg = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+        let image = if is_svg {
+            StoredImage {
+                href: format!("images/image{number}.svg"),
+                media_type: "image/svg+xml",
+                data: std::fs::read(path)?,
+            }
+        } else {
+            let embedded = EmbeddedImage::load(path)?;
+            StoredImage {
+                href: format!("images/image{number}.{}", embedded.extension),
+                media_type: embedded.media_type,
+                data: embedded.data,
+            }
+        };
+        let href = image.href.clone();
+        self.images.push(image);
+        self.image_hrefs.insert(path.to_path_buf(), href.clone());
+        Ok(href)
+    }
+}
+
+fn chapter_href(index: usize, heading: Option<&str>) -> String {
+    match heading {
+        Some(heading) => format!("{}#{}", chapter_file(index), escape_html(heading)),
+        None => chapter_file(index),
+    }
+}
+
+/// Footnote labels may contain anything; ids may not.
+fn footnote_id(label: &str) -> String {
+    label
+        .chars()
+        .map(|character| {
+            if character.is_alphanumeric() {
+                character
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+    use zip::ZipArchive;
+
+    fn setup_vault() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("novel")).unwrap();
+        image::RgbImage::from_pixel(3, 3, image::Rgb([10, 20, 30]))
+            .save(root.join("cover.bmp"))
+            .unwrap();
+        std::fs::write(
+            root.join("novel/01 Start.md"),
+            "# Start\n\n![[cover.bmp]]\n\n1. first\n2. second\n\nNext: [[02 End#The End|ending]], [[Elsewhere]].\n\n| a | b |\n|---|---|\n| 1 | 2 |\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("novel/02 End.md"), "## The End\n\nDone <soon>.\n").unwrap();
+        dir
+    }
+
+    fn read_entries(path: &Path) -> Vec<(String, String)> {
+        let mut archive = ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        (0..archive.len())
+            .map(|index| {
+                let mut entry = archive.by_index(index).unwrap();
+                let mut bytes = Vec::new();
+                entry.read_to_end(&mut bytes).unwrap();
+                (
+                    entry.name().to_string(),
+                    String::from_utf8_lossy(&bytes).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn exports_a_folder_as_an_epub_book() {
+        let vault = setup_vault();
+        let dest = vault.path().join("novel.epub");
+        let source =
+            ChapterSource::Folder(vault.path().join("novel").to_string_lossy().to_string());
+        let options = BookOptions {
+            title: None,
+            author: Some("Ada".to_string()),
+        };
+
+        let report = export_book_to_epub(vault.path(), &source, &dest, &options).unwrap();
+
+        assert_eq!(report.chapters.len(), 2);
+        assert_eq!(report.broken_links.len(), 1);
+        assert!(report.missing_images.is_empty());
+
+        let entries = read_entries(&dest);
+        assert_eq!(
+            entries[0],
+            ("mimetype".to_string(), "application/epub+zip".to_string())
+        );
+        let entry = |name: &str| {
+            entries
+                .iter()
+                .find(|(entry, _)| entry == name)
+                .map(|(_, content)| content.clone())
+                .unwrap_or_else(|| panic!("missing {name}"))
+        };
+        let opf = entry("OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>novel</dc:title>"));
+        assert!(opf.contains("<dc:creator>Ada</dc:creator>"));
+        assert!(opf.contains("href=\"images/image1.png\" media-type=\"image/png\""));
+        assert!(opf.contains("<itemref idref=\"chapter-2\"/>"));
+
+        let first = entry("OEBPS/chapter-1.xhtml");
+        assert!(first.contains("<a href=\"chapter-2.xhtml#the-end\">ending</a>"));
+        assert!(first.contains("<img src=\"images/image1.png\" alt=\"cover.bmp\"/>"));
+        assert!(first.contains("<ol>\n<li>first\n</li>"));
+        assert!(first.contains("<th>a</th>"));
+
+        let second = entry("OEBPS/chapter-2.xhtml");
+        assert!(second.contains("<h1 id=\"02-end\">02 End</h1>"));
+        assert!(entry("OEBPS/nav.xhtml").contains("<a href=\"chapter-2.xhtml\">02 End</a>"));
+    }
+}
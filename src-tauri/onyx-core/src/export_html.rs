@@ -0,0 +1,759 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use log::{info, warn};
+use pulldown_cmark::{html, CowStr, Event, LinkType, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::markdown::{escape_html, heading_slug, parse_events, split_link_target};
+
+/// Palette keys and defaults mirrored from `src/theme.ts`; `theme.json` overrides any of them.
+const DEFAULT_PALETTE: &[(&str, &str)] = &[
+    ("background", "#282c33"),
+    ("surface", "#2f343e"),
+    ("surface-hover", "#363c46"),
+    ("surface-active", "#454a56"),
+    ("accent", "#74ade8"),
+    ("text-primary", "#dce0e5"),
+    ("text-secondary", "#a9afbc"),
+    ("code-bg", "#22262e"),
+    ("success", "#4db89a"),
+    ("warning", "#e8c074"),
+    ("danger", "#e87474"),
+];
+
+const BASE_STYLESHEET: &str = r#"
+body { margin: 0; background: var(--onyx-background); color: var(--onyx-text-primary); font-family: -apple-system, BlinkMacSystemFont, "Inter", "Segoe UI", sans-serif; line-height: 1.6; }
+nav { padding: 0.75rem 1.5rem; background: var(--onyx-surface); border-bottom: 1px solid var(--onyx-surface-active); }
+main { max-width: 48rem; margin: 0 auto; padding: 2rem 1.5rem; }
+a { color: var(--onyx-accent); }
+code, pre { background: var(--onyx-code-bg); border-radius: 4px; font-family: "JetBrains Mono", Menlo, monospace; }
+code { padding: 0.1em 0.3em; }
+pre { padding: 1rem; overflow-x: auto; }
+pre code { padding: 0; }
+blockquote { margin: 0; padding-left: 1rem; border-left: 3px solid var(--onyx-surface-active); color: var(--onyx-text-secondary); }
+table { border-collapse: collapse; }
+th, td { border: 1px solid var(--onyx-surface-active); padding: 0.3rem 0.6rem; }
+img { max-width: 100%; }
+.onyx-broken-link { color: var(--onyx-text-secondary); text-decoration: underline dotted; }
+"#;
+
+/// Image extensions that are embedded as `<img>`; other embeds become plain links.
+const IMAGE_EXTENSIONS: &[&str] = &["avif", "bmp", "gif", "jpeg", "jpg", "png", "svg", "webp"];
+
+/// Caller-tunable knobs for an HTML export.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HtmlExportOptions {
+    /// Embed images as base64 data URLs instead of copying them into `assets/`.
+    #[serde(default)]
+    pub inline_images: bool,
+    /// Title of the generated index page; defaults to the vault directory name.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// Summary of what an export wrote, returned to the frontend.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HtmlExportReport {
+    pub index: String,
+    pub pages: Vec<String>,
+    pub assets: Vec<String>,
+    /// Image specifiers (as written in the note) that could not be resolved.
+    pub missing_assets: Vec<String>,
+    /// Links to notes outside the export, rendered as plain text instead of dead links.
+    pub broken_links: Vec<BrokenLink>,
+}
+
+/// A link from an exported note to a note that is missing or was left out of the export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BrokenLink {
+    pub source: String,
+    pub target: String,
+}
+
+/// A note body rendered to HTML along with what its links resolved to.
+pub struct RenderedNote {
+    pub html: String,
+    /// Exported notes this note links to, in document order.
+    pub links: Vec<PathBuf>,
+    /// Link targets (as written) that don't resolve to an exported note.
+    pub broken_links: Vec<String>,
+    pub missing_assets: Vec<String>,
+}
+
+/// Renders the given notes and folders to a navigable HTML folder at `dest`.
+/// Wikilinks between exported notes become relative links; links to anything else are
+/// rendered as plain text so the export never points at pages that don't exist.
+pub fn export_notes_to_html(
+    vault_root: &Path,
+    paths: &[PathBuf],
+    dest: &Path,
+    options: &HtmlExportOptions,
+    theme_json: &str,
+) -> Result<HtmlExportReport, OnyxError> {
+    let notes = collect_notes(paths);
+    let pages: HashMap<PathBuf, String> = notes
+        .iter()
+        .map(|note| (note.clone(), page_path(vault_root, note)))
+        .collect();
+    let resolver = LinkResolver::build(vault_root);
+    let stylesheet = stylesheet(theme_json);
+    let mut assets = AssetSink::new(dest, options.inline_images);
+    let mut report = HtmlExportReport::default();
+
+    std::fs::create_dir_all(dest)?;
+    for note in &notes {
+        let page = &pages[note];
+        let content = std::fs::read_to_string(note)?;
+        let context = PageContext {
+            note,
+            root_prefix: root_prefix(page),
+            pages: &pages,
+            resolver: &resolver,
+        };
+        let rendered = render_note(&content, &context, &mut assets)?;
+        let title = note
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Untitled");
+        let nav = format!("<a href=\"{}index.html\">Index</a>", context.root_prefix);
+        let document = page_document(title, &stylesheet, &nav, &rendered.html);
+        let output = dest.join(page);
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&output, document)?;
+        report.pages.push(output.to_string_lossy().to_string());
+        report.missing_assets.extend(rendered.missing_assets);
+        report
+            .broken_links
+            .extend(rendered.broken_links.into_iter().map(|target| BrokenLink {
+                source: note.to_string_lossy().to_string(),
+                target,
+            }));
+    }
+
+    let title = options.title.clone().unwrap_or_else(|| {
+        vault_root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Onyx export")
+            .to_string()
+    });
+    let index_body = index_listing(&title, pages.values().map(String::as_str));
+    let index_path = dest.join("index.html");
+    std::fs::write(
+        &index_path,
+        page_document(
+            &title,
+            &stylesheet,
+            "<a href=\"index.html\">Index</a>",
+            &index_body,
+        ),
+    )?;
+
+    report.index = index_path.to_string_lossy().to_string();
+    report.assets = assets.written;
+    info!(
+        "Exported {} notes to {}",
+        report.pages.len(),
+        dest.display()
+    );
+    Ok(report)
+}
+
+/// Renders the `:root` custom properties from the user's `theme.json`, falling back to defaults.
+pub fn palette_css(theme_json: &str) -> String {
+    let overrides: serde_json::Map<String, serde_json::Value> = serde_json::from_str(theme_json)
+        .unwrap_or_else(|error| {
+            warn!("Ignoring unreadable theme.json: {error}");
+            serde_json::Map::new()
+        });
+    let variables: String = DEFAULT_PALETTE
+        .iter()
+        .map(|(key, default)| {
+            let value = match overrides.get(*key).and_then(|value| value.as_str()) {
+                Some(value) if is_css_color(value) => value,
+                Some(value) => {
+                    warn!("Ignoring theme color {key}: {value:?} is not a color");
+                    default
+                }
+                None => default,
+            };
+            format!("  --onyx-{key}: {value};\n")
+        })
+        .collect();
+    format!(":root {{\n{variables}}}\n")
+}
+
+/// Whether a theme value looks like a CSS color (`#282c33`, `rgb(40, 44, 51)`, `teal`), so it
+/// can't close the declaration or the `<style>` element it is written into.
+fn is_css_color(value: &str) -> bool {
+    !value.trim().is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "#(),.%/ -".contains(c))
+}
+
+/// The stylesheet embedded in every exported page: the palette followed by the base rules.
+pub fn stylesheet(theme_json: &str) -> String {
+    format!("{}{}", palette_css(theme_json), BASE_STYLESHEET)
+}
+
+/// Expands folders into the notes they contain, skipping hidden directories.
+pub fn collect_notes(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut notes = BTreeSet::new();
+    for path in paths {
+        if path.is_dir() {
+            for entry in walkdir::WalkDir::new(path)
+                .into_iter()
+                .filter_entry(|entry| {
+                    entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+                })
+                .filter_map(|entry| entry.ok())
+            {
+                if entry.file_type().is_file() && is_markdown(entry.path()) {
+                    notes.insert(entry.into_path());
+                }
+            }
+        } else if is_markdown(path) {
+            notes.insert(path.clone());
+        }
+    }
+    notes.into_iter().collect()
+}
+
+/// Output path of a note's page relative to the export root, always `/`-separated.
+pub fn page_path(vault_root: &Path, note: &Path) -> String {
+    let relative = note
+        .strip_prefix(vault_root)
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|_| note.file_name().map(PathBuf::from).unwrap_or_default());
+    relative
+        .with_extension("html")
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// The `../` prefix that leads from a page back to the export root.
+pub fn root_prefix(page: &str) -> String {
+    "../".repeat(page.matches('/').count())
+}
+
+/// Percent-encodes each segment of a `/`-separated relative path.
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn is_external(url: &str) -> bool {
+    url.starts_with('#') || url.contains("://") || url.starts_with("mailto:")
+}
+
+/// Whether the path has an image extension the editor can display.
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Everything needed to render one page: where it lives and which notes are being exported.
+pub struct PageContext<'a> {
+    pub note: &'a Path,
+    pub root_prefix: String,
+    /// Exported notes mapped to their page path relative to the export root.
+    pub pages: &'a HashMap<PathBuf, String>,
+    pub resolver: &'a LinkResolver,
+}
+
+/// Where a link inside a page ends up after export.
+enum LinkTarget {
+    /// An exported page (or a heading on the current page).
+    Page { href: String, note: Option<PathBuf> },
+    /// A note-style link whose target is missing or not part of the export.
+    Broken,
+    /// Not a note link at all; left untouched.
+    Other,
+}
+
+impl PageContext<'_> {
+    /// Maps a link destination to the exported page it points at, keeping any heading fragment.
+    fn link_target(&self, dest_url: &str, link_type: LinkType) -> LinkTarget {
+        let (target, fragment) = split_link_target(dest_url);
+        let fragment = fragment.map(|heading| format!("#{}", heading_slug(heading)));
+        if target.is_empty() {
+            return match fragment {
+                Some(href) => LinkTarget::Page { href, note: None },
+                None => LinkTarget::Other,
+            };
+        }
+        let note = if matches!(link_type, LinkType::WikiLink { .. }) {
+            match self.resolver.resolve_note(target) {
+                Some(note) => note.to_path_buf(),
+                None => return LinkTarget::Broken,
+            }
+        } else {
+            let decoded = urlencoding::decode(target)
+                .map(|decoded| decoded.into_owned())
+                .unwrap_or_else(|_| target.to_string());
+            let Some(parent) = self.note.parent() else {
+                return LinkTarget::Other;
+            };
+            if !is_markdown(Path::new(&decoded)) {
+                return LinkTarget::Other;
+            }
+            normalize_path(&parent.join(decoded))
+        };
+        let Some(page) = self.page_of(&note) else {
+            return LinkTarget::Broken;
+        };
+        LinkTarget::Page {
+            href: format!(
+                "{}{}{}",
+                self.root_prefix,
+                encode_path(page),
+                fragment.unwrap_or_default()
+            ),
+            note: Some(note),
+        }
+    }
+
+    fn page_of(&self, note: &Path) -> Option<&String> {
+        self.pages.get(note).or_else(|| {
+            let canonical = note.canonicalize().ok()?;
+            self.pages
+                .iter()
+                .find(|(path, _)| path.canonicalize().ok().as_ref() == Some(&canonical))
+                .map(|(_, page)| page)
+        })
+    }
+}
+
+/// Copies or inlines the images referenced by exported notes, de-duplicating by source path.
+pub struct AssetSink {
+    dest: PathBuf,
+    inline: bool,
+    copied: HashMap<PathBuf, String>,
+    used_names: HashSet<String>,
+    pub written: Vec<String>,
+}
+
+impl AssetSink {
+    pub fn new(dest: &Path, inline: bool) -> Self {
+        Self {
+            dest: dest.to_path_buf(),
+            inline,
+            copied: HashMap::new(),
+            used_names: HashSet::new(),
+            written: Vec::new(),
+        }
+    }
+
+    /// Returns the `src` to use for `source` from a page whose root prefix is `root_prefix`.
+    pub fn src_for(&mut self, source: &Path, root_prefix: &str) -> Result<String, OnyxError> {
+        if self.inline {
+            let bytes = std::fs::read(source)?;
+            let encoded = general_purpose::STANDARD.encode(bytes);
+            return Ok(format!("data:{};base64,{encoded}", mime_type(source)));
+        }
+        if let Some(relative) = self.copied.get(source) {
+            return Ok(format!("{root_prefix}{}", encode_path(relative)));
+        }
+        let name = self.unique_name(source);
+        let relative = format!("assets/{name}");
+        let output = self.dest.join("assets").join(&name);
+        std::fs::create_dir_all(self.dest.join("assets"))?;
+        std::fs::copy(source, &output)?;
+        self.written.push(output.to_string_lossy().to_string());
+        self.copied.insert(source.to_path_buf(), relative.clone());
+        Ok(format!("{root_prefix}{}", encode_path(&relative)))
+    }
+
+    fn unique_name(&mut self, source: &Path) -> String {
+        let stem = source
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("asset");
+        let extension = source
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| format!(".{ext}"))
+            .unwrap_or_default();
+        let mut name = format!("{stem}{extension}");
+        let mut counter = 1;
+        while !self.used_names.insert(name.to_lowercase()) {
+            name = format!("{stem}-{counter}{extension}");
+            counter += 1;
+        }
+        name
+    }
+}
+
+/// MIME type for an image, matching the mapping used by `read_binary_as_data_url`.
+fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "avif" => "image/avif",
+        _ => "image/png",
+    }
+}
+
+/// Renders a note body, rewriting links and images for the export layout.
+pub fn render_note(
+    content: &str,
+    context: &PageContext<'_>,
+    assets: &mut AssetSink,
+) -> Result<RenderedNote, OnyxError> {
+    let mut rendered = RenderedNote {
+        html: String::new(),
+        links: Vec::new(),
+        broken_links: Vec::new(),
+        missing_assets: Vec::new(),
+    };
+    let mut events = Vec::new();
+    // Each open link or image remembers which closing event it must be paired with.
+    let mut closers: Vec<Event<'_>> = Vec::new();
+    let broken_open = || Event::Html(CowStr::from(r#"<span class="onyx-broken-link">"#));
+    let broken_close = || Event::Html(CowStr::from("</span>"));
+
+    for event in parse_events(content) {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                let is_wikilink = matches!(link_type, LinkType::WikiLink { .. });
+                let target = if is_external(&dest_url) && !is_wikilink {
+                    LinkTarget::Other
+                } else {
+                    context.link_target(&dest_url, link_type)
+                };
+                match target {
+                    LinkTarget::Page { href, note } => {
+                        rendered.links.extend(note);
+                        closers.push(Event::End(TagEnd::Link));
+                        events.push(Event::Start(Tag::Link {
+                            link_type,
+                            dest_url: CowStr::from(href),
+                            title,
+                            id,
+                        }));
+                    }
+                    LinkTarget::Broken => {
+                        rendered.broken_links.push(dest_url.to_string());
+                        closers.push(broken_close());
+                        events.push(broken_open());
+                    }
+                    LinkTarget::Other => {
+                        closers.push(Event::End(TagEnd::Link));
+                        events.push(Event::Start(Tag::Link {
+                            link_type,
+                            dest_url,
+                            title,
+                            id,
+                        }));
+                    }
+                }
+            }
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => {
+                if is_external(&dest_url) || dest_url.starts_with("data:") {
+                    closers.push(Event::End(TagEnd::Image));
+                    events.push(Event::Start(Tag::Image {
+                        link_type,
+                        dest_url,
+                        title,
+                        id,
+                    }));
+                    continue;
+                }
+                if let Some(source) = context
+                    .resolver
+                    .resolve_asset(context.note, &dest_url)
+                    .filter(|source| is_image(source))
+                {
+                    let src = assets.src_for(&source, &context.root_prefix)?;
+                    closers.push(Event::End(TagEnd::Image));
+                    events.push(Event::Start(Tag::Image {
+                        link_type,
+                        dest_url: CowStr::from(src),
+                        title,
+                        id,
+                    }));
+                    continue;
+                }
+                // Non-image embeds (`![[Other note]]`) degrade to a link when possible.
+                let embed = LinkType::WikiLink { has_pothole: false };
+                match context.link_target(&dest_url, embed) {
+                    LinkTarget::Page { href, note } => {
+                        rendered.links.extend(note);
+                        closers.push(Event::End(TagEnd::Link));
+                        events.push(Event::Start(Tag::Link {
+                            link_type: LinkType::Inline,
+                            dest_url: CowStr::from(href),
+                            title,
+                            id,
+                        }));
+                    }
+                    _ => {
+                        if context.resolver.resolve_note(&dest_url).is_some()
+                            || is_markdown(Path::new(dest_url.as_ref()))
+                        {
+                            rendered.broken_links.push(dest_url.to_string());
+                        } else {
+                            rendered.missing_assets.push(dest_url.to_string());
+                        }
+                        closers.push(broken_close());
+                        events.push(broken_open());
+                    }
+                }
+            }
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                events.push(closers.pop().unwrap_or(event));
+            }
+            other => events.push(other),
+        }
+    }
+
+    html::push_html(&mut rendered.html, events.into_iter());
+    Ok(rendered)
+}
+
+/// Wraps a rendered body in a complete HTML document with the stylesheet inlined.
+pub fn page_document(title: &str, stylesheet: &str, nav: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{title}</title>\n<style>\n{stylesheet}</style>\n</head>\n<body>\n\
+         <nav>{nav}</nav>\n\
+         <main>\n{body}</main>\n</body>\n</html>\n",
+        title = escape_html(title),
+    )
+}
+
+/// Builds the index page body: a nested list of every exported page grouped by folder.
+pub fn index_listing<'a>(title: &str, pages: impl Iterator<Item = &'a str>) -> String {
+    let mut sorted: Vec<&str> = pages.collect();
+    sorted.sort_by_key(|page| page.to_lowercase());
+
+    let mut html = format!("<h1>{}</h1>\n<ul>\n", escape_html(title));
+    let mut open_folders: Vec<&str> = Vec::new();
+    for page in sorted {
+        let segments: Vec<&str> = page.split('/').collect();
+        let folders = &segments[..segments.len() - 1];
+        let shared = open_folders
+            .iter()
+            .zip(folders)
+            .take_while(|(open, folder)| open == folder)
+            .count();
+        while open_folders.len() > shared {
+            open_folders.pop();
+            html.push_str("</ul></li>\n");
+        }
+        for folder in &folders[shared..] {
+            html.push_str(&format!("<li>{}\n<ul>\n", escape_html(folder)));
+            open_folders.push(folder);
+        }
+        let name = segments[segments.len() - 1].trim_end_matches(".html");
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            encode_path(page),
+            escape_html(name)
+        ));
+    }
+    for _ in open_folders {
+        html.push_str("</ul></li>\n");
+    }
+    html.push_str("</ul>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("notes/deep")).unwrap();
+        std::fs::create_dir_all(root.join("media")).unwrap();
+        std::fs::write(
+            root.join("notes/Home.md"),
+            "# Home\n\nSee [[Project|the project]] and [[Secret]].\n\n![[photo.png]]\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("notes/deep/Project.md"),
+            "# Project\n\nBack to [[Home#Home]]. ![](../../media/photo.png)\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("Secret.md"), "not exported").unwrap();
+        std::fs::write(root.join("media/photo.png"), "png bytes").unwrap();
+        temp
+    }
+
+    #[test]
+    fn palette_css_merges_overrides_onto_defaults() {
+        let css = palette_css(r##"{"accent":"#ff0000","unknown":"#000"}"##);
+        assert!(css.contains("--onyx-accent: #ff0000;"));
+        assert!(css.contains("--onyx-background: #282c33;"));
+        assert!(!css.contains("unknown"));
+    }
+
+    #[test]
+    fn palette_css_rejects_values_that_are_not_colors() {
+        let css = palette_css(
+            r##"{"accent":"red; } body { display: none","text-primary":"</style><script>","background":"rgb(1, 2, 3)"}"##,
+        );
+        assert!(css.contains("--onyx-accent: #"));
+        assert!(!css.contains("display"));
+        assert!(!css.contains("<"));
+        assert!(css.contains("--onyx-background: rgb(1, 2, 3);"));
+    }
+
+    #[test]
+    fn palette_css_falls_back_on_invalid_json() {
+        let css = palette_css("not json");
+        assert!(css.contains("--onyx-accent: #74ade8;"));
+    }
+
+    #[test]
+    fn export_converts_wikilinks_between_exported_notes() {
+        let temp = setup_vault();
+        let dest = temp.path().join("out");
+        let paths = vec![temp.path().join("notes")];
+
+        let report = export_notes_to_html(
+            temp.path(),
+            &paths,
+            &dest,
+            &HtmlExportOptions::default(),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(report.pages.len(), 2);
+        let home = std::fs::read_to_string(dest.join("notes/Home.html")).unwrap();
+        assert!(home.contains(r#"<a href="../notes/deep/Project.html">the project</a>"#));
+        assert!(home.contains(r#"<span class="onyx-broken-link">Secret</span>"#));
+        let project = std::fs::read_to_string(dest.join("notes/deep/Project.html")).unwrap();
+        assert!(project.contains(r#"href="../../notes/Home.html#home""#));
+    }
+
+    #[test]
+    fn export_copies_images_once_into_assets() {
+        let temp = setup_vault();
+        let dest = temp.path().join("out");
+        let paths = vec![temp.path().join("notes")];
+
+        let report = export_notes_to_html(
+            temp.path(),
+            &paths,
+            &dest,
+            &HtmlExportOptions::default(),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(report.assets.len(), 1);
+        assert!(dest.join("assets/photo.png").exists());
+        let home = std::fs::read_to_string(dest.join("notes/Home.html")).unwrap();
+        assert!(home.contains(r#"src="../assets/photo.png""#));
+        assert!(report.missing_assets.is_empty());
+    }
+
+    #[test]
+    fn export_reports_links_to_unexported_notes() {
+        let temp = setup_vault();
+        let dest = temp.path().join("out");
+
+        let report = export_notes_to_html(
+            temp.path(),
+            &[temp.path().join("notes")],
+            &dest,
+            &HtmlExportOptions::default(),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(report.broken_links.len(), 1);
+        assert_eq!(report.broken_links[0].target, "Secret");
+        assert!(report.broken_links[0].source.ends_with("Home.md"));
+    }
+
+    #[test]
+    fn export_inlines_images_when_requested() {
+        let temp = setup_vault();
+        let dest = temp.path().join("out");
+        let options = HtmlExportOptions {
+            inline_images: true,
+            title: None,
+        };
+
+        export_notes_to_html(
+            temp.path(),
+            &[temp.path().join("notes/Home.md")],
+            &dest,
+            &options,
+            "{}",
+        )
+        .unwrap();
+
+        let home = std::fs::read_to_string(dest.join("notes/Home.html")).unwrap();
+        assert!(home.contains("src=\"data:image/png;base64,"));
+        assert!(!dest.join("assets").exists());
+    }
+
+    #[test]
+    fn export_writes_index_with_nested_folders() {
+        let temp = setup_vault();
+        let dest = temp.path().join("out");
+        let options = HtmlExportOptions {
+            inline_images: false,
+            title: Some("My Notes".into()),
+        };
+
+        export_notes_to_html(
+            temp.path(),
+            &[temp.path().join("notes")],
+            &dest,
+            &options,
+            "{}",
+        )
+        .unwrap();
+
+        let index = std::fs::read_to_string(dest.join("index.html")).unwrap();
+        assert!(index.contains("<h1>My Notes</h1>"));
+        assert!(index.contains(r#"<a href="notes/Home.html">Home</a>"#));
+        assert!(index.contains(r#"<a href="notes/deep/Project.html">Project</a>"#));
+    }
+
+    #[test]
+    fn index_listing_closes_every_folder() {
+        let html = index_listing("T", ["a/b/c.html", "a/d.html", "e.html"].into_iter());
+        assert_eq!(html.matches("<ul>").count(), html.matches("</ul>").count());
+    }
+}
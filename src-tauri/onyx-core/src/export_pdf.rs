@@ -0,0 +1,921 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use image::codecs::jpeg::JpegDecoder;
+use image::{ExtendedColorType, ImageDecoder, ImageFormat};
+use log::{info, warn};
+use pulldown_cmark::{Event, HeadingLevel, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::link_resolver::LinkResolver;
+use crate::markdown::parse_events;
+use crate::pdf_writer::{deflate, pdf_string, PdfDocument, PdfImage, PdfPage, StandardFont};
+
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+const LINE_HEIGHT: f32 = 1.4;
+const TEXT_COLOR: &str = "0.13 0.13 0.13";
+const LINK_COLOR: &str = "0.10 0.35 0.75";
+const MUTED_COLOR: &str = "0.40 0.40 0.40";
+const CODE_BACKGROUND: &str = "0.95 0.95 0.95";
+
+/// Paper sizes offered by the exporter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    Legal,
+}
+
+impl PageSize {
+    /// Portrait width and height in points.
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.28, 841.89),
+            Self::A5 => (419.53, 595.28),
+            Self::Letter => (612.0, 792.0),
+            Self::Legal => (612.0, 1008.0),
+        }
+    }
+}
+
+/// Body font family; headings use the bold face and code always uses Courier.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FontFamily {
+    #[default]
+    Helvetica,
+    Times,
+    Courier,
+}
+
+/// Caller-tunable knobs for a PDF export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfExportOptions {
+    pub page_size: PageSize,
+    pub landscape: bool,
+    pub margin_mm: f32,
+    pub font: FontFamily,
+    /// Body text size in points; headings and code are scaled from it.
+    pub font_size: f32,
+}
+
+impl Default for PdfExportOptions {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            landscape: false,
+            margin_mm: 20.0,
+            font: FontFamily::Helvetica,
+            font_size: 11.0,
+        }
+    }
+}
+
+/// Summary of a PDF export, returned to the frontend.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PdfExportReport {
+    pub path: String,
+    pub pages: usize,
+    /// Images that could not be found or decoded and were replaced by a placeholder.
+    pub skipped_images: Vec<String>,
+}
+
+/// Lays out a markdown note and writes it to `dest` as a PDF, entirely in-process.
+pub fn export_note_to_pdf(
+    vault_root: &Path,
+    note: &Path,
+    dest: &Path,
+    options: &PdfExportOptions,
+) -> Result<PdfExportReport, OnyxError> {
+    let content = std::fs::read_to_string(note)?;
+    let resolver = LinkResolver::build(vault_root);
+    let title = note
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Untitled");
+    let (document, skipped_images) = render_pdf(title, &content, options, |specifier| {
+        let path = resolver.resolve_asset(note, specifier)?;
+        std::fs::read(path).ok()
+    });
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(dest, document.to_bytes()?)?;
+    info!(
+        "Exported {} to PDF ({} pages)",
+        note.display(),
+        document.pages.len()
+    );
+    Ok(PdfExportReport {
+        path: dest.to_string_lossy().to_string(),
+        pages: document.pages.len(),
+        skipped_images,
+    })
+}
+
+/// Renders markdown into a paginated document. `load_image` maps an image specifier to its bytes.
+pub fn render_pdf(
+    title: &str,
+    content: &str,
+    options: &PdfExportOptions,
+    load_image: impl Fn(&str) -> Option<Vec<u8>>,
+) -> (PdfDocument, Vec<String>) {
+    let mut layout = Layout::new(options);
+    let mut builder = BlockBuilder::default();
+    let mut skipped_images = Vec::new();
+
+    for event in parse_events(content) {
+        match event {
+            Event::Start(Tag::Paragraph) => builder.spans.clear(),
+            Event::End(TagEnd::Paragraph) => {
+                let spans = std::mem::take(&mut builder.spans);
+                if builder.table.is_none() {
+                    layout.paragraph(&spans, builder.indent(), None, builder.quote_depth > 0);
+                }
+            }
+            Event::Start(Tag::Heading { .. }) => builder.spans.clear(),
+            Event::End(TagEnd::Heading(level)) => {
+                let spans = std::mem::take(&mut builder.spans);
+                layout.heading(&spans, level);
+            }
+            Event::Start(Tag::BlockQuote(_)) => builder.quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => {
+                builder.quote_depth = builder.quote_depth.saturating_sub(1);
+            }
+            Event::Start(Tag::List(start)) => {
+                builder.flush_item(&mut layout);
+                builder.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                builder.flush_item(&mut layout);
+                builder.lists.pop();
+                if builder.lists.is_empty() {
+                    layout.gap(0.4);
+                }
+            }
+            Event::Start(Tag::Item) => {
+                builder.flush_item(&mut layout);
+                let marker = match builder.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}.", *number - 1)
+                    }
+                    _ => "•".to_string(),
+                };
+                builder.item_marker = Some(marker);
+            }
+            Event::End(TagEnd::Item) => builder.flush_item(&mut layout),
+            Event::TaskListMarker(checked) => {
+                builder.push_text(if checked { "[x] " } else { "[ ] " });
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                builder.flush_item(&mut layout);
+                builder.code = Some(String::new());
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                let code = builder.code.take().unwrap_or_default();
+                layout.code_block(&code, builder.indent());
+            }
+            Event::Start(Tag::Table(_)) => builder.table = Some(TableBuilder::default()),
+            Event::End(TagEnd::Table) => {
+                if let Some(table) = builder.table.take() {
+                    layout.table(&table.rows, table.header_rows);
+                }
+            }
+            Event::Start(Tag::TableHead) => {
+                if let Some(table) = &mut builder.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::End(TagEnd::TableHead) => {
+                if let Some(table) = &mut builder.table {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            Event::Start(Tag::TableRow) => {
+                if let Some(table) = &mut builder.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Event::Start(Tag::TableCell) => builder.spans.clear(),
+            Event::End(TagEnd::TableCell) => {
+                let spans = std::mem::take(&mut builder.spans);
+                if let Some(row) = builder.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(spans);
+                }
+            }
+            Event::Start(Tag::Strong) => builder.bold += 1,
+            Event::End(TagEnd::Strong) => builder.bold = builder.bold.saturating_sub(1),
+            Event::Start(Tag::Emphasis) => builder.italic += 1,
+            Event::End(TagEnd::Emphasis) => builder.italic = builder.italic.saturating_sub(1),
+            Event::Start(Tag::Link { .. }) => builder.link += 1,
+            Event::End(TagEnd::Link) => builder.link = builder.link.saturating_sub(1),
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                builder.image_depth += 1;
+                if builder.image_depth > 1 {
+                    continue;
+                }
+                let bytes = load_image(&dest_url);
+                match bytes.as_deref().map(decode_image) {
+                    Some(Ok(image)) => {
+                        builder.flush_item(&mut layout);
+                        let spans = std::mem::take(&mut builder.spans);
+                        layout.paragraph(&spans, builder.indent(), None, false);
+                        layout.image(image);
+                    }
+                    Some(Err(error)) => {
+                        warn!("Skipping image {dest_url} in PDF export: {error}");
+                        skipped_images.push(dest_url.to_string());
+                        builder.push_text(&format!("[image: {dest_url}]"));
+                    }
+                    None => {
+                        skipped_images.push(dest_url.to_string());
+                        builder.push_text(&format!("[image: {dest_url}]"));
+                    }
+                }
+            }
+            Event::End(TagEnd::Image) => {
+                builder.image_depth = builder.image_depth.saturating_sub(1)
+            }
+            Event::Text(text) => {
+                if builder.image_depth > 0 {
+                    continue;
+                }
+                match &mut builder.code {
+                    Some(code) => code.push_str(&text),
+                    None => builder.push_text(&text),
+                }
+            }
+            Event::Code(text) | Event::InlineMath(text) | Event::DisplayMath(text) => {
+                builder.push_span(&text, true);
+            }
+            Event::FootnoteReference(label) => builder.push_text(&format!("[{label}]")),
+            Event::SoftBreak => builder.push_text(" "),
+            Event::HardBreak => builder.push_text("\n"),
+            Event::Rule => layout.rule(),
+            _ => {}
+        }
+    }
+    builder.flush_item(&mut layout);
+
+    let document = layout.finish(title);
+    (document, skipped_images)
+}
+
+/// Turns raw image bytes into an XObject, passing JPEG data through untouched.
+fn decode_image(bytes: &[u8]) -> Result<PdfImage, OnyxError> {
+    if image::guess_format(bytes)? == ImageFormat::Jpeg {
+        let decoder = JpegDecoder::new(Cursor::new(bytes))?;
+        let color_space = match decoder.original_color_type() {
+            ExtendedColorType::L8 => Some("DeviceGray"),
+            ExtendedColorType::Rgb8 => Some("DeviceRGB"),
+            _ => None,
+        };
+        if let Some(color_space) = color_space {
+            let (width, height) = decoder.dimensions();
+            return Ok(PdfImage {
+                width,
+                height,
+                color_space,
+                filter: "DCTDecode",
+                data: bytes.to_vec(),
+            });
+        }
+    }
+    let rgba = image::load_from_memory(bytes)?.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+    for pixel in rgba.pixels() {
+        // Flatten transparency onto a white page.
+        let alpha = u16::from(pixel[3]);
+        for channel in &pixel.0[..3] {
+            let blended = (u16::from(*channel) * alpha + 255 * (255 - alpha)) / 255;
+            rgb.push(blended as u8);
+        }
+    }
+    Ok(PdfImage {
+        width,
+        height,
+        color_space: "DeviceRGB",
+        filter: "FlateDecode",
+        data: deflate(&rgb)?,
+    })
+}
+
+/// A run of text sharing one style.
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    bold: bool,
+    italic: bool,
+    code: bool,
+    link: bool,
+}
+
+#[derive(Default)]
+struct TableBuilder {
+    rows: Vec<Vec<Vec<Span>>>,
+    header_rows: usize,
+}
+
+/// Tracks inline styling and nesting while markdown events stream past.
+#[derive(Default)]
+struct BlockBuilder {
+    spans: Vec<Span>,
+    bold: usize,
+    italic: usize,
+    link: usize,
+    image_depth: usize,
+    quote_depth: usize,
+    lists: Vec<Option<u64>>,
+    item_marker: Option<String>,
+    code: Option<String>,
+    table: Option<TableBuilder>,
+}
+
+impl BlockBuilder {
+    fn indent(&self) -> f32 {
+        (self.lists.len() as f32 * 16.0) + (self.quote_depth as f32 * 14.0)
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.push_span(text, false);
+    }
+
+    fn push_span(&mut self, text: &str, code: bool) {
+        self.spans.push(Span {
+            text: text.to_string(),
+            bold: self.bold > 0,
+            italic: self.italic > 0,
+            code,
+            link: self.link > 0,
+        });
+    }
+
+    /// Emits the pending list item (tight lists put item text straight into the item).
+    fn flush_item(&mut self, layout: &mut Layout) {
+        let marker = self.item_marker.take();
+        if self.spans.is_empty() && marker.is_none() {
+            return;
+        }
+        let spans = std::mem::take(&mut self.spans);
+        let indent = self.indent();
+        layout.paragraph(&spans, indent, marker.as_deref(), self.quote_depth > 0);
+    }
+}
+
+/// A word (or forced line break) with the font it is set in.
+#[derive(Debug, Clone)]
+struct Word {
+    text: String,
+    font: StandardFont,
+    link: bool,
+    space_before: bool,
+    line_break: bool,
+}
+
+/// Positions content on pages, starting a new page whenever the cursor runs out of room.
+struct Layout {
+    family: FontFamily,
+    size: f32,
+    page_width: f32,
+    page_height: f32,
+    margin: f32,
+    pages: Vec<PdfPage>,
+    images: Vec<PdfImage>,
+    content: String,
+    cursor_y: f32,
+}
+
+impl Layout {
+    fn new(options: &PdfExportOptions) -> Self {
+        let (width, height) = options.page_size.dimensions();
+        let (page_width, page_height) = if options.landscape {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let margin = (options.margin_mm.max(0.0) * POINTS_PER_MM).min(page_width / 3.0);
+        Self {
+            family: options.font,
+            size: options.font_size.clamp(6.0, 32.0),
+            page_width,
+            page_height,
+            margin,
+            pages: Vec::new(),
+            images: Vec::new(),
+            content: String::new(),
+            cursor_y: page_height - margin,
+        }
+    }
+
+    fn content_width(&self) -> f32 {
+        self.page_width - 2.0 * self.margin
+    }
+
+    fn font(&self, bold: bool, italic: bool, code: bool) -> StandardFont {
+        if code {
+            return if bold {
+                StandardFont::CourierBold
+            } else {
+                StandardFont::Courier
+            };
+        }
+        match (self.family, bold, italic) {
+            (FontFamily::Helvetica, false, false) => StandardFont::Helvetica,
+            (FontFamily::Helvetica, true, false) => StandardFont::HelveticaBold,
+            (FontFamily::Helvetica, false, true) => StandardFont::HelveticaOblique,
+            (FontFamily::Helvetica, true, true) => StandardFont::HelveticaBoldOblique,
+            (FontFamily::Times, false, false) => StandardFont::TimesRoman,
+            (FontFamily::Times, true, false) => StandardFont::TimesBold,
+            (FontFamily::Times, false, true) => StandardFont::TimesItalic,
+            (FontFamily::Times, true, true) => StandardFont::TimesBoldItalic,
+            (FontFamily::Courier, true, _) => StandardFont::CourierBold,
+            (FontFamily::Courier, false, _) => StandardFont::Courier,
+        }
+    }
+
+    /// Starts a new page if fewer than `height` points remain above the bottom margin.
+    fn ensure_space(&mut self, height: f32) {
+        if self.cursor_y - height < self.margin && self.cursor_y < self.page_height - self.margin {
+            self.new_page();
+        }
+    }
+
+    fn new_page(&mut self) {
+        let number = self.pages.len() + 1;
+        let label = number.to_string();
+        let font = self.font(false, false, false);
+        let size = self.size * 0.8;
+        let x = (self.page_width - font.text_width(&label, size)) / 2.0;
+        let y = self.margin / 2.0;
+        self.draw_text(x, y, font, size, MUTED_COLOR, &label);
+        self.pages.push(PdfPage {
+            width: self.page_width,
+            height: self.page_height,
+            content: std::mem::take(&mut self.content),
+        });
+        self.cursor_y = self.page_height - self.margin;
+    }
+
+    fn finish(mut self, title: &str) -> PdfDocument {
+        if !self.content.is_empty() || self.pages.is_empty() {
+            self.new_page();
+        }
+        PdfDocument {
+            title: title.to_string(),
+            pages: self.pages,
+            images: self.images,
+        }
+    }
+
+    fn gap(&mut self, lines: f32) {
+        self.cursor_y -= self.size * lines;
+    }
+
+    fn draw_text(
+        &mut self,
+        x: f32,
+        y: f32,
+        font: StandardFont,
+        size: f32,
+        color: &str,
+        text: &str,
+    ) {
+        self.content.push_str(&format!(
+            "BT {color} rg /{} {size:.2} Tf 1 0 0 1 {x:.2} {y:.2} Tm {} Tj ET\n",
+            font.resource_name(),
+            pdf_string(text)
+        ));
+    }
+
+    fn heading(&mut self, spans: &[Span], level: HeadingLevel) {
+        let scale = match level {
+            HeadingLevel::H1 => 1.9,
+            HeadingLevel::H2 => 1.55,
+            HeadingLevel::H3 => 1.3,
+            _ => 1.1,
+        };
+        let spans: Vec<Span> = spans
+            .iter()
+            .map(|span| Span {
+                bold: true,
+                ..span.clone()
+            })
+            .collect();
+        self.gap(0.6);
+        // Keep a heading together with at least one following line.
+        self.ensure_space(self.size * scale * LINE_HEIGHT + self.size * LINE_HEIGHT);
+        self.text_block(&spans, self.size * scale, 0.0, None, false);
+        self.gap(0.3);
+    }
+
+    fn paragraph(&mut self, spans: &[Span], indent: f32, marker: Option<&str>, quoted: bool) {
+        if spans.iter().all(|span| span.text.trim().is_empty()) && marker.is_none() {
+            return;
+        }
+        self.text_block(spans, self.size, indent, marker, quoted);
+        self.gap(if marker.is_some() { 0.2 } else { 0.6 });
+    }
+
+    /// Wraps styled spans to the available width and draws them line by line.
+    fn text_block(
+        &mut self,
+        spans: &[Span],
+        size: f32,
+        indent: f32,
+        marker: Option<&str>,
+        quoted: bool,
+    ) {
+        let words = self.words(spans);
+        let left = self.margin + indent;
+        let width = self.content_width() - indent;
+        let line_height = size * LINE_HEIGHT;
+        let body_color = if quoted { MUTED_COLOR } else { TEXT_COLOR };
+
+        let mut lines: Vec<Vec<&Word>> = vec![Vec::new()];
+        let mut line_width = 0.0;
+        for word in &words {
+            if word.line_break {
+                lines.push(Vec::new());
+                line_width = 0.0;
+                continue;
+            }
+            let space = if word.space_before && line_width > 0.0 {
+                word.font.char_width(' ') * size / 1000.0
+            } else {
+                0.0
+            };
+            let word_width = word.font.text_width(&word.text, size);
+            if line_width > 0.0 && line_width + space + word_width > width {
+                lines.push(Vec::new());
+                line_width = word_width;
+            } else {
+                line_width += space + word_width;
+            }
+            if let Some(line) = lines.last_mut() {
+                line.push(word);
+            }
+        }
+
+        for (index, line) in lines.iter().enumerate() {
+            self.ensure_space(line_height);
+            let baseline = self.cursor_y - size;
+            if index == 0 {
+                if let Some(marker) = marker {
+                    let font = self.font(false, false, false);
+                    let marker_x = left - font.text_width(marker, size) - 4.0;
+                    self.draw_text(marker_x, baseline, font, size, body_color, marker);
+                }
+            }
+            if quoted {
+                let bar_x = left - 8.0;
+                self.content.push_str(&format!(
+                    "{MUTED_COLOR} RG 1.5 w {bar_x:.2} {:.2} m {bar_x:.2} {:.2} l S\n",
+                    self.cursor_y,
+                    self.cursor_y - line_height
+                ));
+            }
+            let mut x = left;
+            for (position, word) in line.iter().enumerate() {
+                if word.space_before && position > 0 {
+                    x += word.font.char_width(' ') * size / 1000.0;
+                }
+                let color = if word.link { LINK_COLOR } else { body_color };
+                self.draw_text(x, baseline, word.font, size, color, &word.text);
+                x += word.font.text_width(&word.text, size);
+            }
+            self.cursor_y -= line_height;
+        }
+    }
+
+    /// Splits spans into words, breaking any word wider than the page into chunks.
+    fn words(&self, spans: &[Span]) -> Vec<Word> {
+        let mut words = Vec::new();
+        let mut pending_space = false;
+        let max_width = self.content_width() * 0.95;
+        for span in spans {
+            let font = self.font(span.bold, span.italic, span.code);
+            for (line_index, line) in span.text.split('\n').enumerate() {
+                if line_index > 0 {
+                    words.push(Word {
+                        text: String::new(),
+                        font,
+                        link: false,
+                        space_before: false,
+                        line_break: true,
+                    });
+                    pending_space = false;
+                }
+                let starts_with_space = line.starts_with(char::is_whitespace);
+                for (word_index, piece) in line.split_whitespace().enumerate() {
+                    let space_before = pending_space || word_index > 0 || starts_with_space;
+                    for (chunk_index, chunk) in split_to_width(piece, font, self.size, max_width)
+                        .into_iter()
+                        .enumerate()
+                    {
+                        words.push(Word {
+                            text: chunk,
+                            font,
+                            link: span.link,
+                            space_before: space_before && chunk_index == 0,
+                            line_break: false,
+                        });
+                    }
+                    pending_space = false;
+                }
+                pending_space = pending_space || line.ends_with(char::is_whitespace);
+            }
+        }
+        words
+    }
+
+    fn code_block(&mut self, code: &str, indent: f32) {
+        let size = self.size * 0.85;
+        let line_height = size * LINE_HEIGHT;
+        let font = StandardFont::Courier;
+        let left = self.margin + indent;
+        let width = self.content_width() - indent;
+        let padding = 6.0;
+        let max_chars = ((width - 2.0 * padding) / font.text_width("m", size)).max(1.0) as usize;
+
+        let mut lines = Vec::new();
+        for line in code.trim_end_matches('\n').split('\n') {
+            let characters: Vec<char> = line.replace('\t', "    ").chars().collect();
+            if characters.is_empty() {
+                lines.push(String::new());
+            }
+            for chunk in characters.chunks(max_chars) {
+                lines.push(chunk.iter().collect());
+            }
+        }
+
+        self.gap(0.2);
+        for line in lines {
+            self.ensure_space(line_height);
+            self.content.push_str(&format!(
+                "{CODE_BACKGROUND} rg {left:.2} {:.2} {width:.2} {line_height:.2} re f\n",
+                self.cursor_y - line_height
+            ));
+            let baseline = self.cursor_y - size - (line_height - size) / 2.0 + 1.0;
+            self.draw_text(left + padding, baseline, font, size, TEXT_COLOR, &line);
+            self.cursor_y -= line_height;
+        }
+        self.gap(0.8);
+    }
+
+    fn table(&mut self, rows: &[Vec<Vec<Span>>], header_rows: usize) {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let size = self.size * 0.9;
+        let line_height = size * LINE_HEIGHT;
+        let padding = 4.0;
+        let column_width = self.content_width() / columns as f32;
+
+        for (row_index, row) in rows.iter().enumerate() {
+            let is_header = row_index < header_rows;
+            let cells: Vec<Vec<String>> = (0..columns)
+                .map(|column| {
+                    let text: String = row
+                        .get(column)
+                        .map(|spans| spans.iter().map(|span| span.text.as_str()).collect())
+                        .unwrap_or_default();
+                    let font = self.font(is_header, false, false);
+                    wrap_plain(&text, font, size, column_width - 2.0 * padding)
+                })
+                .collect();
+            let row_lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+            let row_height = row_lines as f32 * line_height + padding;
+            self.ensure_space(row_height);
+
+            let top = self.cursor_y;
+            for (column, lines) in cells.iter().enumerate() {
+                let x = self.margin + column as f32 * column_width;
+                if is_header {
+                    self.content.push_str(&format!(
+                        "{CODE_BACKGROUND} rg {x:.2} {:.2} {column_width:.2} {row_height:.2} re f\n",
+                        top - row_height
+                    ));
+                }
+                self.content.push_str(&format!(
+                    "{MUTED_COLOR} RG 0.5 w {x:.2} {:.2} {column_width:.2} {row_height:.2} re S\n",
+                    top - row_height
+                ));
+                let font = self.font(is_header, false, false);
+                for (line_index, line) in lines.iter().enumerate() {
+                    let baseline = top - padding / 2.0 - size - line_index as f32 * line_height;
+                    self.draw_text(x + padding, baseline, font, size, TEXT_COLOR, line);
+                }
+            }
+            self.cursor_y -= row_height;
+        }
+        self.gap(0.8);
+    }
+
+    fn image(&mut self, image: PdfImage) {
+        // Treat pixels as CSS pixels (96 dpi) and shrink to fit the text block and page.
+        let natural_width = image.width as f32 * 0.75;
+        let natural_height = image.height as f32 * 0.75;
+        let max_height = self.page_height - 2.0 * self.margin;
+        let scale = (self.content_width() / natural_width)
+            .min(max_height / natural_height)
+            .min(1.0);
+        let (width, height) = (natural_width * scale, natural_height * scale);
+
+        self.ensure_space(height);
+        self.images.push(image);
+        let name = format!("Im{}", self.images.len());
+        self.content.push_str(&format!(
+            "q {width:.2} 0 0 {height:.2} {:.2} {:.2} cm /{name} Do Q\n",
+            self.margin,
+            self.cursor_y - height
+        ));
+        self.cursor_y -= height;
+        self.gap(0.6);
+    }
+
+    fn rule(&mut self) {
+        self.gap(0.4);
+        self.ensure_space(2.0);
+        self.content.push_str(&format!(
+            "{MUTED_COLOR} RG 0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n",
+            self.margin,
+            self.cursor_y,
+            self.page_width - self.margin,
+            self.cursor_y
+        ));
+        self.gap(0.8);
+    }
+}
+
+/// Breaks a single word into pieces no wider than `max_width`.
+fn split_to_width(word: &str, font: StandardFont, size: f32, max_width: f32) -> Vec<String> {
+    if font.text_width(word, size) <= max_width {
+        return vec![word.to_string()];
+    }
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for character in word.chars() {
+        if !current.is_empty()
+            && font.text_width(&current, size) + font.char_width(character) * size / 1000.0
+                > max_width
+        {
+            pieces.push(std::mem::take(&mut current));
+        }
+        current.push(character);
+    }
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// Greedy word wrap of unstyled text, used for table cells.
+fn wrap_plain(text: &str, font: StandardFont, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        for piece in split_to_width(word, font, size, width) {
+            let candidate = if current.is_empty() {
+                piece.clone()
+            } else {
+                format!("{current} {piece}")
+            };
+            if !current.is_empty() && font.text_width(&candidate, size) > width {
+                lines.push(std::mem::replace(&mut current, piece));
+            } else {
+                current = candidate;
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// Raw drawing operators of every page, before compression.
+    fn page_contents(document: &PdfDocument) -> Vec<String> {
+        document
+            .pages
+            .iter()
+            .map(|page| page.content.clone())
+            .collect()
+    }
+
+    fn png_bytes() -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255]));
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn renders_headings_lists_code_and_tables() {
+        let markdown = "# Title\n\nSome *text* with `code`.\n\n- one\n- two\n\n```\nfn main() {}\n```\n\n| A | B |\n|---|---|\n| 1 | 2 |\n";
+        let (document, skipped) =
+            render_pdf("Note", markdown, &PdfExportOptions::default(), |_| None);
+
+        assert!(skipped.is_empty());
+        assert_eq!(document.pages.len(), 1);
+        let content = &page_contents(&document)[0];
+        assert!(
+            content.contains("/F2 20.90 Tf"),
+            "heading uses the bold face"
+        );
+        assert!(content.contains("(Title)"));
+        assert!(content.contains("(\\225)"), "bullet marker");
+        assert!(content.contains("(fn main\\(\\) {})"));
+        assert!(content.contains("re S"), "table borders");
+    }
+
+    #[test]
+    fn long_documents_paginate() {
+        let markdown = "Lorem ipsum dolor sit amet.\n\n".repeat(200);
+        let (document, _) = render_pdf("Long", &markdown, &PdfExportOptions::default(), |_| None);
+        assert!(document.pages.len() > 3);
+    }
+
+    #[test]
+    fn options_control_page_size_and_font() {
+        let options = PdfExportOptions {
+            page_size: PageSize::Letter,
+            landscape: true,
+            margin_mm: 10.0,
+            font: FontFamily::Times,
+            font_size: 12.0,
+        };
+        let (document, _) = render_pdf("Note", "hello", &options, |_| None);
+        let page = &document.pages[0];
+        assert_eq!((page.width, page.height), (792.0, 612.0));
+        assert!(page.content.contains("/F5 12.00 Tf"));
+    }
+
+    #[test]
+    fn embeds_images_and_reports_missing_ones() {
+        let markdown = "![[pixel.png]]\n\n![gone](missing.png)\n";
+        let png = png_bytes();
+        let (document, skipped) = render_pdf(
+            "Note",
+            markdown,
+            &PdfExportOptions::default(),
+            |specifier| (specifier == "pixel.png").then(|| png.clone()),
+        );
+
+        assert_eq!(document.images.len(), 1);
+        assert_eq!(document.images[0].filter, "FlateDecode");
+        assert_eq!(skipped, vec!["missing.png"]);
+        let content = &page_contents(&document)[0];
+        assert!(content.contains("/Im1 Do"));
+        assert!(content.contains("([image:) Tj") && content.contains("(missing.png]) Tj"));
+
+        let mut inflated = Vec::new();
+        flate2::read::ZlibDecoder::new(document.images[0].data.as_slice())
+            .read_to_end(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated.len(), 4 * 2 * 3);
+    }
+
+    #[test]
+    fn wrap_plain_respects_width() {
+        let lines = wrap_plain(
+            "the quick brown fox jumps over the lazy dog",
+            StandardFont::Courier,
+            10.0,
+            60.0,
+        );
+        assert!(lines.len() > 1);
+        for line in lines {
+            assert!(StandardFont::Courier.text_width(&line, 10.0) <= 60.0);
+        }
+    }
+
+    #[test]
+    fn export_writes_pdf_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let note = temp.path().join("note.md");
+        std::fs::write(&note, "# Hello\n\nWorld").unwrap();
+        let dest = temp.path().join("out/note.pdf");
+
+        let report =
+            export_note_to_pdf(temp.path(), &note, &dest, &PdfExportOptions::default()).unwrap();
+
+        assert_eq!(report.pages, 1);
+        let bytes = std::fs::read(&dest).unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.4"));
+    }
+}
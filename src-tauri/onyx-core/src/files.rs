@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use crate::canvas;
+use crate::error::OnyxError;
+use crate::pdf_annotations;
+use crate::vault::find_vault_root;
+
+/// Creates an empty file at `name` inside the vault and returns its absolute path.
+pub fn create_file(vault_root: &Path, name: &str) -> Result<PathBuf, OnyxError> {
+    let path = vault_root.join(name);
+    std::fs::write(&path, "")?;
+    Ok(path)
+}
+
+/// Creates an empty directory at `name` inside the vault and returns its absolute path.
+pub fn create_folder(vault_root: &Path, name: &str) -> Result<PathBuf, OnyxError> {
+    let path = vault_root.join(name);
+    std::fs::create_dir(&path)?;
+    Ok(path)
+}
+
+/// Permanently deletes a file or directory.
+pub fn delete_path(path: &Path) -> Result<(), OnyxError> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)?;
+    } else {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+/// Renames a file within its current directory, preserving the extension, and returns the new
+/// path. Annotation sidecars and canvas references follow the file.
+pub fn rename_file(source: &Path, new_stem: &str) -> Result<PathBuf, OnyxError> {
+    let parent = source.parent().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Path has no parent directory",
+        )
+    })?;
+    let new_file_name = match source.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) if !extension.is_empty() => format!("{new_stem}.{extension}"),
+        _ => new_stem.to_string(),
+    };
+    let destination = parent.join(&new_file_name);
+    if destination.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("A file named '{new_file_name}' already exists"),
+        )
+        .into());
+    }
+    relocate(source, &destination)?;
+    Ok(destination)
+}
+
+/// Moves a file or directory into `target_dir`, preserving its name, and returns the new path.
+pub fn move_file(source: &Path, target_dir: &Path) -> Result<PathBuf, OnyxError> {
+    let file_name = source.file_name().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid source path")
+    })?;
+    let destination = target_dir.join(file_name);
+    relocate(source, &destination)?;
+    Ok(destination)
+}
+
+fn relocate(source: &Path, destination: &Path) -> Result<(), OnyxError> {
+    std::fs::rename(source, destination)?;
+    pdf_annotations::move_sidecar(source, destination)?;
+    update_canvas_references(source, destination);
+    Ok(())
+}
+
+/// Points canvases in the enclosing vault at a renamed or moved file. The move itself already
+/// succeeded, so a failure here is only logged.
+fn update_canvas_references(source: &Path, destination: &Path) {
+    let Some(vault_root) = find_vault_root(destination) else {
+        return;
+    };
+    if let Err(e) = canvas::rewrite_file_references(&vault_root, source, destination) {
+        warn!(
+            "Failed to update canvas references to {}: {e}",
+            destination.display()
+        );
+    }
+}
+
+/// Finds a `.md` file whose stem matches `link_target` case-insensitively.
+pub fn find_note_by_stem(vault_root: &Path, link_target: &str) -> Option<PathBuf> {
+    let target_lower = link_target.to_lowercase();
+    walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .map(walkdir::DirEntry::into_path)
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("md"))
+        .find(|path| {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            stem.to_lowercase() == target_lower
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn rename_keeps_extension_and_refuses_to_overwrite() {
+        let dir = TempDir::new().unwrap();
+        let source = dir.path().join("draft.md");
+        std::fs::write(&source, "").unwrap();
+        std::fs::write(dir.path().join("taken.md"), "").unwrap();
+
+        let renamed = rename_file(&source, "final").unwrap();
+        assert_eq!(renamed, dir.path().join("final.md"));
+        assert!(!source.exists());
+
+        assert!(rename_file(&renamed, "taken").is_err());
+        assert!(renamed.exists());
+    }
+
+    #[test]
+    fn move_carries_the_annotation_sidecar() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("papers")).unwrap();
+        let pdf = dir.path().join("paper.pdf");
+        std::fs::write(&pdf, "").unwrap();
+        std::fs::write(pdf_annotations::sidecar_path(&pdf), "[]").unwrap();
+
+        let moved = move_file(&pdf, &dir.path().join("papers")).unwrap();
+
+        assert_eq!(moved, dir.path().join("papers/paper.pdf"));
+        assert!(pdf_annotations::sidecar_path(&moved).exists());
+    }
+}
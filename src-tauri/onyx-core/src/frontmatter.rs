@@ -0,0 +1,124 @@
+use log::warn;
+use serde_json::{Map, Value};
+
+/// YAML frontmatter of a note as a JSON map, so it can be handed to the frontend unchanged.
+pub type Frontmatter = Map<String, Value>;
+
+/// Splits a leading `---` YAML block off a note, returning the YAML and the remaining body.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\r', '\n']);
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (Some(yaml), body);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+/// Parses a note's frontmatter; notes without frontmatter or with invalid YAML yield an empty map.
+pub fn parse_frontmatter(content: &str) -> Frontmatter {
+    let (Some(yaml), _) = split_frontmatter(content) else {
+        return Frontmatter::new();
+    };
+    if yaml.trim().is_empty() {
+        return Frontmatter::new();
+    }
+    match serde_yaml::from_str::<Value>(yaml) {
+        Ok(Value::Object(map)) => map,
+        Ok(_) => Frontmatter::new(),
+        Err(error) => {
+            warn!("Ignoring invalid frontmatter: {error}");
+            Frontmatter::new()
+        }
+    }
+}
+
+/// Tags declared in frontmatter (`tags: [a, b]`, `tags: a, b` or `tag: a`), without a leading `#`.
+pub fn frontmatter_tags(frontmatter: &Frontmatter) -> Vec<String> {
+    let mut tags = Vec::new();
+    for key in ["tags", "tag"] {
+        match frontmatter.get(key) {
+            Some(Value::Array(values)) => {
+                tags.extend(values.iter().filter_map(Value::as_str).map(clean_tag));
+            }
+            Some(Value::String(value)) => {
+                tags.extend(
+                    value
+                        .split([',', ' '])
+                        .filter(|tag| !tag.trim().is_empty())
+                        .map(clean_tag),
+                );
+            }
+            _ => {}
+        }
+    }
+    tags.retain(|tag| !tag.is_empty());
+    tags
+}
+
+/// Whether the frontmatter sets `key` to boolean `true` (or the string `"true"`).
+pub fn is_truthy(frontmatter: &Frontmatter, key: &str) -> bool {
+    match frontmatter.get(key) {
+        Some(Value::Bool(value)) => *value,
+        Some(Value::String(value)) => value.eq_ignore_ascii_case("true"),
+        _ => false,
+    }
+}
+
+fn clean_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_frontmatter_separates_yaml_and_body() {
+        let (yaml, body) = split_frontmatter("---\ntitle: Hi\n---\n# Body\n");
+        assert_eq!(yaml, Some("title: Hi\n"));
+        assert_eq!(body, "# Body\n");
+    }
+
+    #[test]
+    fn split_frontmatter_requires_closing_delimiter() {
+        let content = "---\ntitle: Hi\n# Body\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn split_frontmatter_ignores_later_rules() {
+        let content = "# Title\n---\nnot yaml\n---\n";
+        assert_eq!(split_frontmatter(content), (None, content));
+    }
+
+    #[test]
+    fn parse_frontmatter_reads_typed_values() {
+        let frontmatter = parse_frontmatter("---\npublish: true\ncount: 3\n---\nbody");
+        assert_eq!(frontmatter.get("publish"), Some(&Value::Bool(true)));
+        assert_eq!(frontmatter.get("count").and_then(Value::as_i64), Some(3));
+    }
+
+    #[test]
+    fn parse_frontmatter_tolerates_invalid_yaml() {
+        assert!(parse_frontmatter("---\n: : :\n---\n").is_empty());
+    }
+
+    #[test]
+    fn frontmatter_tags_accepts_lists_and_strings() {
+        let list = parse_frontmatter("---\ntags: [rust, '#go']\n---\n");
+        assert_eq!(frontmatter_tags(&list), vec!["rust", "go"]);
+        let inline = parse_frontmatter("---\ntags: rust, go\n---\n");
+        assert_eq!(frontmatter_tags(&inline), vec!["rust", "go"]);
+    }
+}
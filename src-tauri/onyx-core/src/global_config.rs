@@ -14,6 +14,50 @@ pub struct GlobalConfig {
     /// Whether the CodeMirror vim keybinding extension is active in the editor.
     #[serde(default)]
     pub vim_mode: bool,
+    /// Whether dirty buffers are written to disk after the editor has been idle for a moment.
+    #[serde(default)]
+    pub autosave_on_idle: bool,
+    /// The local automation API; off unless the user turns it on.
+    #[serde(default)]
+    pub api: ApiConfig,
+}
+
+/// Settings for the localhost HTTP API.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_api_port")]
+    pub port: u16,
+    /// Bearer token clients must send; generated the first time the API is enabled.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_api_port(),
+            token: None,
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Returns the token, generating a random one first if none is set.
+    pub fn ensure_token(&mut self) -> Result<String, OnyxError> {
+        if self.token.is_none() {
+            let mut bytes = [0u8; 32];
+            getrandom::getrandom(&mut bytes).map_err(std::io::Error::from)?;
+            self.token = Some(bytes.iter().map(|byte| format!("{byte:02x}")).collect());
+        }
+        Ok(self.token.clone().unwrap_or_default())
+    }
+}
+
+fn default_api_port() -> u16 {
+    27123
 }
 
 /// Returns the directory where global config lives (`~/.config/onyx/`).
@@ -75,6 +119,12 @@ mod tests {
             vaults: vec![PathBuf::from("/tmp/vault1")],
             last_active_vault: Some(PathBuf::from("/tmp/vault1")),
             vim_mode: true,
+            autosave_on_idle: true,
+            api: ApiConfig {
+                enabled: true,
+                port: 8080,
+                token: Some("secret".to_string()),
+            },
         };
         let serialized = toml::to_string_pretty(&config).unwrap();
         let deserialized: GlobalConfig = toml::from_str(&serialized).unwrap();
@@ -94,6 +144,19 @@ mod tests {
         let toml = r#"vaults = []"#;
         let config: GlobalConfig = toml::from_str(toml).unwrap();
         assert!(!config.vim_mode);
+        assert!(!config.autosave_on_idle);
+    }
+
+    #[test]
+    fn api_is_disabled_by_default_and_gets_a_token_once() {
+        let mut config: GlobalConfig = toml::from_str(r#"vaults = []"#).unwrap();
+        assert!(!config.api.enabled);
+        assert_eq!(config.api.port, 27123);
+
+        let token = config.api.ensure_token().unwrap();
+
+        assert_eq!(token.len(), 64);
+        assert_eq!(config.api.ensure_token().unwrap(), token);
     }
 
     #[test]
@@ -0,0 +1,473 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::frontmatter::parse_frontmatter;
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::markdown::escape_html;
+use crate::query::note_tags;
+use crate::vault_health::{references, resolve, vault_files};
+
+/// Which notes and extra nodes `build_graph` returns.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct GraphFilter {
+    /// Local-graph mode: only nodes within `depth` hops of this note (absolute path).
+    pub center: Option<String>,
+    /// Hops from `center`; defaults to 1.
+    pub depth: Option<usize>,
+    /// Vault-relative folder the notes must live in.
+    pub folder: Option<String>,
+    /// Tag (without `#`) the notes must carry.
+    pub tag: Option<String>,
+    /// Adds a node per tag with an edge from each note that carries it.
+    pub include_tags: bool,
+    /// Adds non-note files that notes link to or embed.
+    pub include_attachments: bool,
+    /// Keeps nodes without any edge.
+    pub include_orphans: bool,
+}
+
+/// What a graph node stands for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphNodeKind {
+    Note,
+    Tag,
+    Attachment,
+}
+
+/// A note, tag or attachment. `id` is the vault-relative path, or `#tag` for tags.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub label: String,
+    pub kind: GraphNodeKind,
+    /// Absolute path, for notes and attachments.
+    pub path: Option<String>,
+    /// Vault-relative folder; empty at the vault root and for tags.
+    pub folder: String,
+    pub tags: Vec<String>,
+    /// Number of edges touching the node in the returned graph.
+    pub degree: usize,
+}
+
+/// Whether an edge is a link, an embed or a note carrying a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphEdgeKind {
+    Link,
+    Embed,
+    Tag,
+}
+
+/// A directed edge from the linking note to what it references.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: GraphEdgeKind,
+}
+
+/// Nodes sorted by id and edges sorted by source, target and kind.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Output format of `render_graph`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    Json,
+    Dot,
+    Graphml,
+}
+
+fn relative_id(vault_root: &Path, path: &Path) -> String {
+    path.strip_prefix(vault_root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn file_node(vault_root: &Path, path: &Path, kind: GraphNodeKind, tags: Vec<String>) -> GraphNode {
+    let id = relative_id(vault_root, path);
+    let label = if kind == GraphNodeKind::Note {
+        path.file_stem()
+    } else {
+        path.file_name()
+    }
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_default();
+    GraphNode {
+        folder: Path::new(&id)
+            .parent()
+            .map(|parent| parent.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        id,
+        label,
+        kind,
+        path: Some(path.to_string_lossy().to_string()),
+        tags,
+        degree: 0,
+    }
+}
+
+/// Builds the link graph of a vault: notes that pass the folder and tag filters, the links
+/// between them and, on request, their tags and attachments.
+pub fn build_graph(vault_root: &Path, filter: &GraphFilter) -> Result<Graph, OnyxError> {
+    let vault_root = normalize_path(vault_root);
+    let files = vault_files(&vault_root);
+    let resolver = LinkResolver::from_paths(&vault_root, files.iter().cloned());
+    let folder = filter
+        .folder
+        .as_deref()
+        .map(|folder| vault_root.join(folder.trim_matches('/')));
+    let wanted_tag = filter
+        .tag
+        .as_deref()
+        .map(|tag| tag.trim_start_matches('#').to_lowercase());
+
+    let mut nodes: BTreeMap<String, GraphNode> = BTreeMap::new();
+    let mut contents: Vec<(PathBuf, String)> = Vec::new();
+    for path in files.iter().filter(|path| is_markdown(path)) {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                warn!("Skipping unreadable note {} in graph: {e}", path.display());
+                continue;
+            }
+        };
+        let tags = note_tags(&parse_frontmatter(&content), &content);
+        let in_folder = folder
+            .as_ref()
+            .is_none_or(|folder| path.starts_with(folder));
+        let has_tag = wanted_tag
+            .as_ref()
+            .is_none_or(|wanted| tags.iter().any(|tag| tag.to_lowercase() == *wanted));
+        if in_folder && has_tag {
+            let node = file_node(&vault_root, path, GraphNodeKind::Note, tags);
+            nodes.insert(node.id.clone(), node);
+            contents.push((path.clone(), content));
+        }
+    }
+
+    let mut edges: BTreeSet<GraphEdge> = BTreeSet::new();
+    let mut extra: Vec<GraphNode> = Vec::new();
+    for (path, content) in &contents {
+        let source = relative_id(&vault_root, path);
+        for reference in references(content) {
+            let Some(target) = resolve(&resolver, path, &reference) else {
+                continue;
+            };
+            let target = normalize_path(&target);
+            let target_id = relative_id(&vault_root, &target);
+            if target_id == source {
+                continue;
+            }
+            if !is_markdown(&target) {
+                if !filter.include_attachments {
+                    continue;
+                }
+                extra.push(file_node(
+                    &vault_root,
+                    &target,
+                    GraphNodeKind::Attachment,
+                    Vec::new(),
+                ));
+            } else if !nodes.contains_key(&target_id) {
+                continue;
+            }
+            let kind = if reference.embed {
+                GraphEdgeKind::Embed
+            } else {
+                GraphEdgeKind::Link
+            };
+            edges.insert(GraphEdge {
+                source: source.clone(),
+                target: target_id,
+                kind,
+            });
+        }
+        if filter.include_tags {
+            for tag in &nodes[&source].tags {
+                let id = format!("#{tag}");
+                extra.push(GraphNode {
+                    id: id.clone(),
+                    label: id.clone(),
+                    kind: GraphNodeKind::Tag,
+                    path: None,
+                    folder: String::new(),
+                    tags: Vec::new(),
+                    degree: 0,
+                });
+                edges.insert(GraphEdge {
+                    source: source.clone(),
+                    target: id,
+                    kind: GraphEdgeKind::Tag,
+                });
+            }
+        }
+    }
+    for node in extra {
+        nodes.entry(node.id.clone()).or_insert(node);
+    }
+
+    let center = filter
+        .center
+        .as_deref()
+        .map(|center| relative_id(&vault_root, &normalize_path(Path::new(center))));
+    if let Some(center) = &center {
+        let mut seen = HashSet::from([center.clone()]);
+        let mut queue = VecDeque::from([(center.clone(), 0)]);
+        let depth = filter.depth.unwrap_or(1);
+        while let Some((id, hops)) = queue.pop_front() {
+            if hops == depth {
+                continue;
+            }
+            for edge in &edges {
+                let neighbour = if edge.source == id {
+                    &edge.target
+                } else if edge.target == id {
+                    &edge.source
+                } else {
+                    continue;
+                };
+                if seen.insert(neighbour.clone()) {
+                    queue.push_back((neighbour.clone(), hops + 1));
+                }
+            }
+        }
+        nodes.retain(|id, _| seen.contains(id));
+        edges.retain(|edge| seen.contains(&edge.source) && seen.contains(&edge.target));
+    }
+
+    for edge in &edges {
+        for id in [&edge.source, &edge.target] {
+            if let Some(node) = nodes.get_mut(id) {
+                node.degree += 1;
+            }
+        }
+    }
+    if !filter.include_orphans {
+        // The note a local graph is centred on stays even when it has no links.
+        nodes.retain(|id, node| node.degree > 0 || center.as_ref() == Some(id));
+    }
+    Ok(Graph {
+        nodes: nodes.into_values().collect(),
+        edges: edges.into_iter().collect(),
+    })
+}
+
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Serialises a graph as pretty JSON, Graphviz DOT or GraphML.
+pub fn render_graph(graph: &Graph, format: GraphFormat) -> Result<String, OnyxError> {
+    let kind_name = |kind: GraphNodeKind| match kind {
+        GraphNodeKind::Note => "note",
+        GraphNodeKind::Tag => "tag",
+        GraphNodeKind::Attachment => "attachment",
+    };
+    let edge_kind_name = |kind: GraphEdgeKind| match kind {
+        GraphEdgeKind::Link => "link",
+        GraphEdgeKind::Embed => "embed",
+        GraphEdgeKind::Tag => "tag",
+    };
+    match format {
+        GraphFormat::Json => Ok(serde_json::to_string_pretty(graph)?),
+        GraphFormat::Dot => {
+            let mut dot = String::from("digraph onyx {\n");
+            for node in &graph.nodes {
+                dot.push_str(&format!(
+                    "  {} [label={}, kind={}];\n",
+                    dot_string(&node.id),
+                    dot_string(&node.label),
+                    kind_name(node.kind)
+                ));
+            }
+            for edge in &graph.edges {
+                dot.push_str(&format!(
+                    "  {} -> {} [kind={}];\n",
+                    dot_string(&edge.source),
+                    dot_string(&edge.target),
+                    edge_kind_name(edge.kind)
+                ));
+            }
+            dot.push_str("}\n");
+            Ok(dot)
+        }
+        GraphFormat::Graphml => {
+            let mut xml = String::from(concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+                "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+                "  <key id=\"kind\" for=\"all\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+                "  <key id=\"folder\" for=\"node\" attr.name=\"folder\" attr.type=\"string\"/>\n",
+                "  <key id=\"tags\" for=\"node\" attr.name=\"tags\" attr.type=\"string\"/>\n",
+                "  <key id=\"degree\" for=\"node\" attr.name=\"degree\" attr.type=\"int\"/>\n",
+                "  <graph id=\"onyx\" edgedefault=\"directed\">\n",
+            ));
+            for node in &graph.nodes {
+                xml.push_str(&format!(
+                    "    <node id=\"{}\">\n      <data key=\"label\">{}</data>\n      <data key=\"kind\">{}</data>\n      <data key=\"folder\">{}</data>\n      <data key=\"tags\">{}</data>\n      <data key=\"degree\">{}</data>\n    </node>\n",
+                    escape_html(&node.id),
+                    escape_html(&node.label),
+                    kind_name(node.kind),
+                    escape_html(&node.folder),
+                    escape_html(&node.tags.join(",")),
+                    node.degree
+                ));
+            }
+            for edge in &graph.edges {
+                xml.push_str(&format!(
+                    "    <edge source=\"{}\" target=\"{}\">\n      <data key=\"kind\">{}</data>\n    </edge>\n",
+                    escape_html(&edge.source),
+                    escape_html(&edge.target),
+                    edge_kind_name(edge.kind)
+                ));
+            }
+            xml.push_str("  </graph>\n</graphml>\n");
+            Ok(xml)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("Projects")).unwrap();
+        std::fs::write(root.join("Home.md"), "[[Plan]] ![[chart.png]] #hub\n").unwrap();
+        std::fs::write(root.join("Projects/Plan.md"), "[[Tasks]] #project\n").unwrap();
+        std::fs::write(root.join("Projects/Tasks.md"), "[[Far]]\n").unwrap();
+        std::fs::write(root.join("Far.md"), "end\n").unwrap();
+        std::fs::write(root.join("Lonely.md"), "nothing\n").unwrap();
+        std::fs::write(root.join("chart.png"), "png").unwrap();
+        temp
+    }
+
+    fn ids(graph: &Graph) -> Vec<&str> {
+        graph.nodes.iter().map(|node| node.id.as_str()).collect()
+    }
+
+    #[test]
+    fn full_graph_links_notes_and_drops_orphans() {
+        let vault = setup_vault();
+
+        let graph = build_graph(vault.path(), &GraphFilter::default()).unwrap();
+
+        assert_eq!(
+            ids(&graph),
+            vec!["Far.md", "Home.md", "Projects/Plan.md", "Projects/Tasks.md"]
+        );
+        assert_eq!(graph.edges.len(), 3);
+        let plan = &graph.nodes[2];
+        assert_eq!((plan.degree, plan.folder.as_str()), (2, "Projects"));
+        assert_eq!(plan.tags, vec!["project"]);
+
+        let with_orphans = GraphFilter {
+            include_orphans: true,
+            ..GraphFilter::default()
+        };
+        assert!(ids(&build_graph(vault.path(), &with_orphans).unwrap()).contains(&"Lonely.md"));
+    }
+
+    #[test]
+    fn unreadable_notes_are_skipped() {
+        let vault = setup_vault();
+        std::fs::write(vault.path().join("Broken.md"), b"[[Far]] \xff").unwrap();
+
+        let graph = build_graph(vault.path(), &GraphFilter::default()).unwrap();
+
+        assert!(!ids(&graph).contains(&"Broken.md"));
+        assert_eq!(graph.edges.len(), 3);
+    }
+
+    #[test]
+    fn tags_attachments_and_folder_filter() {
+        let vault = setup_vault();
+        let filter = GraphFilter {
+            include_tags: true,
+            include_attachments: true,
+            ..GraphFilter::default()
+        };
+
+        let graph = build_graph(vault.path(), &filter).unwrap();
+
+        assert!(ids(&graph).contains(&"#hub"));
+        let chart = graph
+            .nodes
+            .iter()
+            .find(|node| node.id == "chart.png")
+            .unwrap();
+        assert_eq!(chart.kind, GraphNodeKind::Attachment);
+        assert!(graph.edges.contains(&GraphEdge {
+            source: "Home.md".to_string(),
+            target: "chart.png".to_string(),
+            kind: GraphEdgeKind::Embed,
+        }));
+
+        let projects = GraphFilter {
+            folder: Some("Projects".to_string()),
+            ..GraphFilter::default()
+        };
+        let graph = build_graph(vault.path(), &projects).unwrap();
+        assert_eq!(ids(&graph), vec!["Projects/Plan.md", "Projects/Tasks.md"]);
+    }
+
+    #[test]
+    fn local_graph_follows_links_both_ways_up_to_depth() {
+        let vault = setup_vault();
+        let mut filter = GraphFilter {
+            center: Some(
+                vault
+                    .path()
+                    .join("Projects/Plan.md")
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+            ..GraphFilter::default()
+        };
+
+        let one_hop = build_graph(vault.path(), &filter).unwrap();
+        filter.depth = Some(2);
+        let two_hops = build_graph(vault.path(), &filter).unwrap();
+
+        assert_eq!(
+            ids(&one_hop),
+            vec!["Home.md", "Projects/Plan.md", "Projects/Tasks.md"]
+        );
+        assert_eq!(ids(&two_hops).len(), 4);
+    }
+
+    #[test]
+    fn renders_dot_and_graphml() {
+        let vault = setup_vault();
+        let graph = build_graph(vault.path(), &GraphFilter::default()).unwrap();
+
+        let dot = render_graph(&graph, GraphFormat::Dot).unwrap();
+        let graphml = render_graph(&graph, GraphFormat::Graphml).unwrap();
+        let json = render_graph(&graph, GraphFormat::Json).unwrap();
+
+        assert!(dot.starts_with("digraph onyx {"));
+        assert!(dot.contains("\"Home.md\" -> \"Projects/Plan.md\" [kind=link];"));
+        let document = roxmltree::Document::parse(&graphml).unwrap();
+        let nodes = document
+            .descendants()
+            .filter(|node| node.has_tag_name("node"))
+            .count();
+        assert_eq!(nodes, 4);
+        assert!(json.contains("\"degree\""));
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::error::OnyxError;
+use crate::importer::{relative_link, sanitize_file_name, ImportPlan, ATTACHMENTS_DIR};
+
+/// File names a TextBundle may keep its text in, in order of preference.
+const TEXT_FILES: [&str; 3] = ["text.markdown", "text.md", "text.txt"];
+
+/// A Bear note as exported: its text plus the files in its `assets/` folder.
+struct Bundle {
+    name: String,
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+/// Plans the import of Bear notes from a `.bearnote`/`.textbundle`, a `.textpack`, or a folder of them.
+pub fn plan(path: &Path, plan: &mut ImportPlan) -> Result<(), OnyxError> {
+    let mut bundles = Vec::new();
+    if is_bundle_dir(path) || is_textpack(path) {
+        collect(path, &mut bundles)?;
+    } else {
+        for entry in WalkDir::new(path)
+            .min_depth(1)
+            .max_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            collect(entry.path(), &mut bundles)?;
+        }
+    }
+    if bundles.is_empty() {
+        plan.issue(
+            path.to_string_lossy(),
+            "no .bearnote, .textbundle or .textpack found",
+        );
+    }
+    for bundle in bundles {
+        import_bundle(bundle, plan);
+    }
+    Ok(())
+}
+
+fn extension_is(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|ext| extensions.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn is_bundle_dir(path: &Path) -> bool {
+    path.is_dir() && extension_is(path, &["bearnote", "textbundle"])
+}
+
+fn is_textpack(path: &Path) -> bool {
+    path.is_file() && extension_is(path, &["textpack"])
+}
+
+fn bundle_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn collect(path: &Path, bundles: &mut Vec<Bundle>) -> Result<(), OnyxError> {
+    if is_bundle_dir(path) {
+        let mut files = BTreeMap::new();
+        for entry in WalkDir::new(path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+        {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(path).unwrap_or(entry.path());
+            let name = relative.to_string_lossy().replace('\\', "/");
+            files.insert(name, std::fs::read(entry.path())?);
+        }
+        bundles.push(Bundle {
+            name: bundle_name(path),
+            files,
+        });
+    } else if is_textpack(path) {
+        bundles.push(read_textpack(path)?);
+    }
+    Ok(())
+}
+
+/// A TextPack is a zipped TextBundle, with or without the bundle folder at its root.
+fn read_textpack(path: &Path) -> Result<Bundle, OnyxError> {
+    let mut archive = ZipArchive::new(std::fs::File::open(path)?)?;
+    let mut files = BTreeMap::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let name = entry.name().replace('\\', "/");
+        if entry.is_dir() || name.starts_with("__MACOSX/") {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        files.insert(name, bytes);
+    }
+    let wrapper = files
+        .keys()
+        .next()
+        .and_then(|name| name.split_once('/'))
+        .map(|(folder, _)| format!("{folder}/"))
+        .filter(|folder| {
+            folder.ends_with(".textbundle/") && files.keys().all(|name| name.starts_with(folder))
+        });
+    if let Some(wrapper) = wrapper {
+        files = files
+            .into_iter()
+            .map(|(name, bytes)| (name[wrapper.len()..].to_string(), bytes))
+            .collect();
+    }
+    Ok(Bundle {
+        name: bundle_name(path),
+        files,
+    })
+}
+
+fn import_bundle(bundle: Bundle, plan: &mut ImportPlan) {
+    let Some(text) = TEXT_FILES
+        .iter()
+        .find_map(|name| bundle.files.get(*name))
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+    else {
+        plan.issue(&bundle.name, "bundle has no text file");
+        return;
+    };
+    let note_path = plan.reserve(Path::new(&format!(
+        "{}.md",
+        sanitize_file_name(&bundle.name)
+    )));
+
+    let mut assets: Vec<(String, PathBuf)> = Vec::new();
+    for (name, bytes) in &bundle.files {
+        let Some(asset) = name.strip_prefix("assets/") else {
+            continue;
+        };
+        let file_name = Path::new(asset)
+            .file_name()
+            .map(|file_name| sanitize_file_name(&file_name.to_string_lossy()))
+            .unwrap_or_default();
+        let path = plan.add(&Path::new(ATTACHMENTS_DIR).join(file_name), bytes.clone());
+        assets.push((asset.to_string(), path));
+    }
+
+    let mut issues = Vec::new();
+    let converted = convert_text(&text, &mut issues);
+    let mut contents = converted;
+    for (asset, path) in &assets {
+        let link = relative_link(&note_path, path);
+        let encoded = asset
+            .split('/')
+            .map(|segment| urlencoding::encode(segment).into_owned())
+            .collect::<Vec<_>>()
+            .join("/");
+        for reference in [format!("assets/{encoded}"), format!("assets/{asset}")] {
+            contents = contents
+                .replace(&format!("]({reference})"), &format!("]({link})"))
+                .replace(&format!("[image:{reference}]"), &format!("![]({link})"))
+                .replace(
+                    &format!("[file:{reference}]"),
+                    &format!("[{asset}]({link})"),
+                );
+        }
+    }
+    for leftover in ["[image:", "[file:"] {
+        if contents.contains(leftover) {
+            issues.push(format!("{leftover}…] reference points to a missing file"));
+        }
+    }
+    for issue in issues {
+        plan.issue(&bundle.name, issue);
+    }
+    plan.write(note_path, contents);
+}
+
+/// Rewrites Bear-only syntax outside code blocks: `::highlight::` becomes `==highlight==`,
+/// Bear 1 `[image:…]`/`[file:…]` references are normalized to `assets/` paths, and
+/// multi-word `#tag words#` become `#tag-words`.
+fn convert_text(text: &str, issues: &mut Vec<String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            result.push_str(line);
+            continue;
+        }
+        if in_fence || line.contains('`') {
+            result.push_str(line);
+            continue;
+        }
+        let line = convert_highlights(line);
+        let line = line
+            .replace("[image:", "[image:assets/")
+            .replace("[file:", "[file:assets/")
+            .replace("assets/assets/", "assets/");
+        result.push_str(&convert_multi_word_tags(&line, issues));
+    }
+    result
+}
+
+fn convert_highlights(line: &str) -> String {
+    let parts: Vec<&str> = line.split("::").collect();
+    if parts.len() < 3 {
+        return line.to_string();
+    }
+    let mut result = String::from(parts[0]);
+    let mut index = 1;
+    while index < parts.len() {
+        let inner = parts[index];
+        let closes = index + 1 < parts.len();
+        if closes && !inner.is_empty() && inner.trim() == inner {
+            result.push_str(&format!("=={inner}=="));
+            result.push_str(parts[index + 1]);
+            index += 2;
+        } else {
+            result.push_str("::");
+            result.push_str(inner);
+            index += 1;
+        }
+    }
+    result
+}
+
+fn convert_multi_word_tags(line: &str, issues: &mut Vec<String>) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    let mut result = String::with_capacity(line.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let at_word_start = index == 0 || chars[index - 1].is_whitespace();
+        if chars[index] == '#'
+            && at_word_start
+            && chars
+                .get(index + 1)
+                .is_some_and(|c| !c.is_whitespace() && *c != '#')
+        {
+            let rest = &chars[index + 1..];
+            let close = rest.iter().position(|c| *c == '#' || *c == '\n');
+            if let Some(close) = close.filter(|close| rest[*close] == '#') {
+                let inner: String = rest[..close].iter().collect();
+                let followed_by_word = rest.get(close + 1).is_some_and(|c| c.is_alphanumeric());
+                if inner.contains(' ') && inner.trim_end() == inner && !followed_by_word {
+                    let tag = inner.split_whitespace().collect::<Vec<_>>().join("-");
+                    issues.push(format!("multi-word tag #{inner}# renamed to #{tag}"));
+                    result.push('#');
+                    result.push_str(&tag);
+                    index += close + 2;
+                    continue;
+                }
+            }
+        }
+        result.push(chars[index]);
+        index += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn imports_bundle_folder_with_assets() {
+        let temp = TempDir::new().unwrap();
+        let export = temp.path().join("export");
+        let bundle = export.join("Recipe.bearnote");
+        std::fs::create_dir_all(bundle.join("assets")).unwrap();
+        std::fs::write(
+            bundle.join("text.markdown"),
+            "# Recipe\n::Hot:: soup #food stuff# #easy\n![](assets/bowl%20one.png)\n[file:list.txt]\n```\n::keep::\n```\n",
+        )
+        .unwrap();
+        std::fs::write(bundle.join("assets/bowl one.png"), b"png").unwrap();
+        std::fs::write(bundle.join("assets/list.txt"), b"eggs").unwrap();
+        let mut import_plan = ImportPlan::new(&temp.path().join("out"));
+
+        plan(&export, &mut import_plan).unwrap();
+        let report = import_plan.apply(false).unwrap();
+
+        let note = std::fs::read_to_string(temp.path().join("out/Recipe.md")).unwrap();
+        assert_eq!(
+            note,
+            "# Recipe\n==Hot== soup #food-stuff #easy\n![](attachments/bowl%20one.png)\n[list.txt](attachments/list.txt)\n```\n::keep::\n```\n"
+        );
+        assert!(temp.path().join("out/attachments/bowl one.png").exists());
+        assert_eq!(report.created.len(), 3);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("#food stuff#"));
+    }
+
+    #[test]
+    fn headings_are_not_mistaken_for_tags() {
+        let mut issues = Vec::new();
+        assert_eq!(
+            convert_multi_word_tags("# Title #\n", &mut issues),
+            "# Title #\n"
+        );
+        assert!(issues.is_empty());
+    }
+}
@@ -0,0 +1,595 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use md5::{Digest, Md5};
+use roxmltree::{Document, Node, ParsingOptions};
+use serde_json::{Map, Value};
+
+use crate::error::OnyxError;
+use crate::importer::{relative_link, sanitize_file_name, ImportPlan, ATTACHMENTS_DIR};
+
+/// HTML entities ENML inherits from XHTML that a plain XML parser doesn't know.
+const HTML_ENTITIES: [(&str, &str); 16] = [
+    ("&nbsp;", "&#160;"),
+    ("&copy;", "&#169;"),
+    ("&reg;", "&#174;"),
+    ("&trade;", "&#8482;"),
+    ("&mdash;", "&#8212;"),
+    ("&ndash;", "&#8211;"),
+    ("&hellip;", "&#8230;"),
+    ("&lsquo;", "&#8216;"),
+    ("&rsquo;", "&#8217;"),
+    ("&ldquo;", "&#8220;"),
+    ("&rdquo;", "&#8221;"),
+    ("&bull;", "&#8226;"),
+    ("&middot;", "&#183;"),
+    ("&euro;", "&#8364;"),
+    ("&times;", "&#215;"),
+    ("&deg;", "&#176;"),
+];
+
+fn parsing_options() -> ParsingOptions {
+    ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    }
+}
+
+/// Plans the import of an Evernote `.enex` export: one note per `<note>`, attachments decoded alongside.
+pub fn plan(enex_path: &Path, plan: &mut ImportPlan) -> Result<(), OnyxError> {
+    let source = enex_path.to_string_lossy().to_string();
+    let xml = std::fs::read_to_string(enex_path)?;
+    let document = match Document::parse_with_options(&xml, parsing_options()) {
+        Ok(document) => document,
+        Err(error) => {
+            plan.issue(source, format!("not a readable ENEX file: {error}"));
+            return Ok(());
+        }
+    };
+    for note in document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("note"))
+    {
+        import_note(note, plan);
+    }
+    Ok(())
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .and_then(|child| child.text())
+}
+
+/// An attachment decoded from a `<resource>`, keyed by the MD5 that `<en-media>` refers to.
+struct Attachment {
+    path: PathBuf,
+    name: String,
+    is_image: bool,
+    referenced: bool,
+}
+
+fn import_note(note: Node<'_, '_>, plan: &mut ImportPlan) {
+    let title = child_text(note, "title")
+        .unwrap_or("Untitled")
+        .trim()
+        .to_string();
+    let note_path = plan.reserve(Path::new(&format!("{}.md", sanitize_file_name(&title))));
+    let source = title.clone();
+
+    let mut attachments = HashMap::new();
+    for (index, resource) in note
+        .children()
+        .filter(|node| node.has_tag_name("resource"))
+        .enumerate()
+    {
+        let encoded: String = child_text(resource, "data")
+            .unwrap_or("")
+            .chars()
+            .filter(|character| !character.is_whitespace())
+            .collect();
+        let data = match general_purpose::STANDARD.decode(encoded) {
+            Ok(data) => data,
+            Err(error) => {
+                plan.issue(
+                    &source,
+                    format!("attachment {} could not be decoded: {error}", index + 1),
+                );
+                continue;
+            }
+        };
+        let mime = child_text(resource, "mime").unwrap_or("application/octet-stream");
+        let file_name = resource
+            .children()
+            .find(|node| node.has_tag_name("resource-attributes"))
+            .and_then(|attributes| child_text(attributes, "file-name"))
+            .map(sanitize_file_name)
+            .unwrap_or_else(|| format!("attachment-{}.{}", index + 1, extension_for(mime)));
+        let hash = format!("{:x}", Md5::digest(&data));
+        let path = plan.add(&Path::new(ATTACHMENTS_DIR).join(&file_name), data);
+        attachments.insert(
+            hash,
+            Attachment {
+                path,
+                name: file_name,
+                is_image: mime.starts_with("image/"),
+                referenced: false,
+            },
+        );
+    }
+
+    let enml = child_text(note, "content").unwrap_or("");
+    let mut converter = EnmlConverter {
+        note_path: &note_path,
+        attachments: &mut attachments,
+        issues: Vec::new(),
+    };
+    let mut body = converter.convert(enml);
+    let issues = std::mem::take(&mut converter.issues);
+    for issue in issues {
+        plan.issue(&source, issue);
+    }
+
+    let mut unreferenced: Vec<&Attachment> = attachments
+        .values()
+        .filter(|attachment| !attachment.referenced)
+        .collect();
+    unreferenced.sort_by(|a, b| a.path.cmp(&b.path));
+    if !unreferenced.is_empty() {
+        body.push_str("\n\n## Attachments\n\n");
+        for attachment in unreferenced {
+            body.push_str(&format!(
+                "- [{}]({})\n",
+                attachment.name,
+                relative_link(&note_path, &attachment.path)
+            ));
+        }
+    }
+
+    let frontmatter = frontmatter(note);
+    let contents = if frontmatter.is_empty() {
+        format!("# {title}\n\n{}\n", body.trim())
+    } else {
+        let yaml = serde_yaml::to_string(&Value::Object(frontmatter)).unwrap_or_default();
+        format!("---\n{yaml}---\n# {title}\n\n{}\n", body.trim())
+    };
+    plan.write(note_path, contents);
+}
+
+fn frontmatter(note: Node<'_, '_>) -> Map<String, Value> {
+    let mut frontmatter = Map::new();
+    let tags: Vec<Value> = note
+        .children()
+        .filter(|node| node.has_tag_name("tag"))
+        .filter_map(|node| node.text())
+        .map(|tag| Value::String(tag.trim().replace(' ', "-")))
+        .collect();
+    if !tags.is_empty() {
+        frontmatter.insert("tags".to_string(), Value::Array(tags));
+    }
+    for key in ["created", "updated"] {
+        if let Some(date) = child_text(note, key).and_then(evernote_date) {
+            frontmatter.insert(key.to_string(), Value::String(date));
+        }
+    }
+    let source_url = note
+        .children()
+        .find(|node| node.has_tag_name("note-attributes"))
+        .and_then(|attributes| child_text(attributes, "source-url"));
+    if let Some(url) = source_url {
+        frontmatter.insert("source".to_string(), Value::String(url.to_string()));
+    }
+    frontmatter
+}
+
+/// Converts Evernote's `20240131T093000Z` timestamps to ISO 8601.
+fn evernote_date(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.len() != 16 || !value.is_ascii() {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}T{}:{}:{}Z",
+        &value[0..4],
+        &value[4..6],
+        &value[6..8],
+        &value[9..11],
+        &value[11..13],
+        &value[13..15]
+    ))
+}
+
+fn extension_for(mime: &str) -> &'static str {
+    match mime {
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "application/pdf" => "pdf",
+        "audio/mpeg" => "mp3",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "text/plain" => "txt",
+        _ => "bin",
+    }
+}
+
+/// Walks an ENML document and writes the equivalent markdown.
+struct EnmlConverter<'a> {
+    note_path: &'a Path,
+    attachments: &'a mut HashMap<String, Attachment>,
+    issues: Vec<String>,
+}
+
+impl EnmlConverter<'_> {
+    fn convert(&mut self, enml: &str) -> String {
+        let mut prepared = enml.to_string();
+        for (entity, reference) in HTML_ENTITIES {
+            prepared = prepared.replace(entity, reference);
+        }
+        let document = match Document::parse_with_options(&prepared, parsing_options()) {
+            Ok(document) => document,
+            Err(error) => {
+                self.issues.push(format!(
+                    "note content could not be parsed, kept as raw ENML: {error}"
+                ));
+                return format!("```html\n{}\n```", enml.trim());
+            }
+        };
+        let mut out = String::new();
+        self.children(document.root_element(), &mut out, false);
+        collapse_blank_lines(&out)
+    }
+
+    fn children(&mut self, node: Node<'_, '_>, out: &mut String, in_list: bool) {
+        for child in node.children() {
+            self.node(child, out, in_list);
+        }
+    }
+
+    /// Renders `node`'s children into a fresh buffer.
+    fn render(&mut self, node: Node<'_, '_>, in_list: bool) -> String {
+        let mut buffer = String::new();
+        self.children(node, &mut buffer, in_list);
+        buffer
+    }
+
+    fn node(&mut self, node: Node<'_, '_>, out: &mut String, in_list: bool) {
+        if node.is_text() {
+            let text = node.text().unwrap_or("");
+            let collapsed = collapse_whitespace(text);
+            if out.is_empty() || out.ends_with('\n') {
+                out.push_str(collapsed.trim_start());
+            } else {
+                out.push_str(&collapsed);
+            }
+            return;
+        }
+        if !node.is_element() {
+            return;
+        }
+        let name = node.tag_name().name().to_ascii_lowercase();
+        match name.as_str() {
+            "div" | "section" | "article" | "center" => {
+                ensure_newline(out);
+                self.children(node, out, in_list);
+                ensure_newline(out);
+            }
+            "p" => {
+                ensure_blank_line(out);
+                self.children(node, out, in_list);
+                ensure_blank_line(out);
+            }
+            "br" => out.push('\n'),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                ensure_blank_line(out);
+                let text = self.render(node, in_list);
+                out.push_str(&format!("{} {}", "#".repeat(level), text.trim()));
+                ensure_blank_line(out);
+            }
+            "b" | "strong" => wrap(out, "**", &self.render(node, in_list)),
+            "i" | "em" => wrap(out, "*", &self.render(node, in_list)),
+            "s" | "strike" | "del" => wrap(out, "~~", &self.render(node, in_list)),
+            "code" => wrap(out, "`", &self.render(node, in_list)),
+            "pre" => {
+                ensure_blank_line(out);
+                let code: String = node.descendants().filter_map(|n| n.text()).collect();
+                out.push_str(&format!("```\n{}\n```", code.trim_end()));
+                ensure_blank_line(out);
+            }
+            "a" => {
+                let text = self.render(node, in_list);
+                match node.attribute("href") {
+                    Some(href) => out.push_str(&format!("[{}]({href})", text.trim())),
+                    None => out.push_str(&text),
+                }
+            }
+            "img" => {
+                let alt = node.attribute("alt").unwrap_or("");
+                if let Some(src) = node.attribute("src") {
+                    out.push_str(&format!("![{alt}]({src})"));
+                }
+            }
+            "ul" | "ol" => {
+                if !in_list {
+                    ensure_blank_line(out);
+                } else {
+                    ensure_newline(out);
+                }
+                let items = node.children().filter(|child| child.has_tag_name("li"));
+                for (number, item) in (1..).zip(items) {
+                    let marker = if name == "ol" {
+                        format!("{number}. ")
+                    } else {
+                        "- ".to_string()
+                    };
+                    let content = collapse_blank_lines(&self.render(item, true));
+                    let indent = " ".repeat(marker.len());
+                    for (index, line) in content.trim().lines().enumerate() {
+                        if index == 0 {
+                            out.push_str(&marker);
+                        } else if !line.is_empty() {
+                            out.push_str(&indent);
+                        }
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                if !in_list {
+                    ensure_blank_line(out);
+                }
+            }
+            "blockquote" => {
+                ensure_blank_line(out);
+                let content = collapse_blank_lines(&self.render(node, in_list));
+                for line in content.trim().lines() {
+                    out.push_str(&format!("> {line}\n"));
+                }
+                ensure_blank_line(out);
+            }
+            "hr" => {
+                ensure_blank_line(out);
+                out.push_str("---");
+                ensure_blank_line(out);
+            }
+            "table" => self.table(node, out),
+            "en-todo" => {
+                let mark = if node.attribute("checked") == Some("true") {
+                    "[x] "
+                } else {
+                    "[ ] "
+                };
+                if in_list {
+                    out.push_str(mark);
+                } else {
+                    out.push_str(&format!("- {mark}"));
+                }
+            }
+            "en-media" => self.media(node, out),
+            "en-crypt" => {
+                self.issues
+                    .push("encrypted content cannot be decrypted and was omitted".to_string());
+                out.push_str("*(encrypted content omitted)*");
+            }
+            "object" | "embed" | "iframe" | "video" | "audio" => {
+                self.issues.push(format!("<{name}> element was dropped"));
+            }
+            _ => self.children(node, out, in_list),
+        }
+    }
+
+    fn media(&mut self, node: Node<'_, '_>, out: &mut String) {
+        let hash = node.attribute("hash").unwrap_or("").to_ascii_lowercase();
+        match self.attachments.get_mut(&hash) {
+            Some(attachment) => {
+                attachment.referenced = true;
+                let link = relative_link(self.note_path, &attachment.path);
+                let bang = if attachment.is_image { "!" } else { "" };
+                out.push_str(&format!("{bang}[{}]({link})", attachment.name));
+            }
+            None => self.issues.push(format!(
+                "embedded attachment {hash} is missing from the export"
+            )),
+        }
+    }
+
+    fn table(&mut self, node: Node<'_, '_>, out: &mut String) {
+        let rows: Vec<Vec<String>> = node
+            .descendants()
+            .filter(|row| row.has_tag_name("tr"))
+            .map(|row| {
+                row.children()
+                    .filter(|cell| cell.has_tag_name("td") || cell.has_tag_name("th"))
+                    .map(|cell| {
+                        let text = collapse_blank_lines(&self.render(cell, false));
+                        text.trim().replace('\n', " ").replace('|', "\\|")
+                    })
+                    .collect()
+            })
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        ensure_blank_line(out);
+        for (index, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = (0..columns)
+                .map(|column| row.get(column).map(String::as_str).unwrap_or(""))
+                .collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+            if index == 0 {
+                out.push_str(&format!("|{}\n", " --- |".repeat(columns)));
+            }
+        }
+        ensure_blank_line(out);
+    }
+}
+
+fn wrap(out: &mut String, marker: &str, inner: &str) {
+    let trimmed = inner.trim();
+    if trimmed.is_empty() {
+        out.push_str(inner);
+        return;
+    }
+    // Keep surrounding spaces outside the markers so the emphasis still parses.
+    if inner.starts_with(' ') {
+        out.push(' ');
+    }
+    out.push_str(&format!("{marker}{trimmed}{marker}"));
+    if inner.ends_with(' ') {
+        out.push(' ');
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut previous_space = false;
+    for character in text.chars() {
+        // Non-breaking spaces are significant in Evernote and are kept.
+        if character.is_whitespace() && character != '\u{a0}' {
+            if !previous_space {
+                collapsed.push(' ');
+            }
+            previous_space = true;
+        } else {
+            collapsed.push(character);
+            previous_space = false;
+        }
+    }
+    collapsed
+}
+
+fn ensure_newline(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn ensure_blank_line(out: &mut String) {
+    if out.is_empty() {
+        return;
+    }
+    ensure_newline(out);
+    if !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Trims trailing spaces and squeezes runs of blank lines down to one.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut blank_run = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_run += 1;
+            if blank_run > 1 {
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn enex(content: &str, resources: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export4.dtd">
+<en-export>
+<note>
+<title>Trip / Plans</title>
+<created>20240131T093000Z</created>
+<tag>travel</tag>
+<content><![CDATA[<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note>{content}</en-note>]]></content>
+{resources}
+</note>
+</en-export>"#
+        )
+    }
+
+    fn resource(data: &[u8], mime: &str, name: &str) -> String {
+        format!(
+            "<resource><data encoding=\"base64\">{}</data><mime>{mime}</mime><resource-attributes><file-name>{name}</file-name></resource-attributes></resource>",
+            general_purpose::STANDARD.encode(data)
+        )
+    }
+
+    fn import(xml: &str) -> (TempDir, crate::importer::ImportReport) {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("notes.enex");
+        std::fs::write(&path, xml).unwrap();
+        let mut import_plan = ImportPlan::new(&temp.path().join("out"));
+        plan(&path, &mut import_plan).unwrap();
+        let report = import_plan.apply(false).unwrap();
+        (temp, report)
+    }
+
+    #[test]
+    fn converts_enml_to_markdown_with_frontmatter() {
+        let content = "<div><b>Bold</b> and&nbsp;<i>it</i></div><div><en-todo checked=\"true\"/>Pack</div><ul><li>one<ul><li>two</li></ul></li></ul><table><tr><td>A</td><td>B</td></tr><tr><td>1</td><td>2</td></tr></table><div><a href=\"https://x.test\">link</a></div>";
+        let (temp, report) = import(&enex(content, ""));
+
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+        let note = std::fs::read_to_string(temp.path().join("out/Trip - Plans.md")).unwrap();
+        assert!(
+            note.starts_with(
+                "---\ncreated: 2024-01-31T09:30:00Z\ntags:\n- travel\n---\n# Trip / Plans\n"
+            ),
+            "{note}"
+        );
+        assert!(note.contains("**Bold** and\u{a0}*it*"), "{note}");
+        assert!(note.contains("- [x] Pack"));
+        assert!(note.contains("- one\n  - two\n"), "{note}");
+        assert!(note.contains("| A | B |\n| --- | --- |\n| 1 | 2 |"));
+        assert!(note.contains("[link](https://x.test)"));
+    }
+
+    #[test]
+    fn decodes_attachments_and_links_media() {
+        let png = b"fake png bytes";
+        let pdf = b"fake pdf";
+        let hash = format!("{:x}", Md5::digest(png));
+        let content = format!(
+            "<div><en-media hash=\"{hash}\" type=\"image/png\"/></div><en-crypt>xyz</en-crypt>"
+        );
+        let resources = resource(png, "image/png", "photo one.png")
+            + &resource(pdf, "application/pdf", "doc.pdf");
+        let (temp, report) = import(&enex(&content, &resources));
+
+        assert_eq!(
+            std::fs::read(temp.path().join("out/attachments/photo one.png")).unwrap(),
+            png
+        );
+        let note = std::fs::read_to_string(temp.path().join("out/Trip - Plans.md")).unwrap();
+        assert!(
+            note.contains("![photo one.png](attachments/photo%20one.png)"),
+            "{note}"
+        );
+        assert!(note.contains("- [doc.pdf](attachments/doc.pdf)"), "{note}");
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].message.contains("encrypted"));
+    }
+
+    #[test]
+    fn evernote_date_formats_iso() {
+        assert_eq!(
+            evernote_date("20240131T093000Z").as_deref(),
+            Some("2024-01-31T09:30:00Z")
+        );
+        assert_eq!(evernote_date("garbage"), None);
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{Event, Parser, Tag};
+use zip::ZipArchive;
+
+use crate::error::OnyxError;
+use crate::importer::{relative_link, ImportPlan};
+use crate::link_resolver::{is_markdown, normalize_path};
+use crate::markdown::parser_options;
+
+/// Length of the hex page id Notion appends to every exported file and folder name.
+const NOTION_ID_LENGTH: usize = 32;
+
+/// Plans the import of a Notion "Markdown & CSV" export zip.
+pub fn plan(zip_path: &Path, plan: &mut ImportPlan) -> Result<(), OnyxError> {
+    let file = std::fs::File::open(zip_path)?;
+    let mut entries = BTreeMap::new();
+    read_entries(file, &mut entries, plan)?;
+    let entries = strip_export_folder(entries);
+
+    // Notion writes each database twice: the current view and `_all` with every row.
+    let skipped_views: Vec<String> = entries
+        .keys()
+        .filter_map(|name| {
+            let all = name.strip_suffix(".csv")?.to_string() + "_all.csv";
+            entries.contains_key(&all).then(|| name.clone())
+        })
+        .collect();
+
+    let mut renamed: HashMap<String, PathBuf> = HashMap::new();
+    for name in entries.keys() {
+        if skipped_views.contains(name) {
+            continue;
+        }
+        let clean = clean_path(name);
+        renamed.insert(name.clone(), plan.reserve(&clean));
+    }
+
+    for (name, bytes) in &entries {
+        let Some(new_path) = renamed.get(name) else {
+            continue;
+        };
+        let lower = name.to_lowercase();
+        if lower.ends_with(".md") {
+            let content = String::from_utf8_lossy(bytes);
+            let rewritten = rewrite_links(name, &content, new_path, &renamed, plan);
+            plan.write(new_path.clone(), rewritten);
+        } else if lower.ends_with(".csv") {
+            let content = String::from_utf8_lossy(bytes);
+            let table = csv_to_markdown(name, &content, new_path, &renamed);
+            plan.write(new_path.clone(), table);
+        } else {
+            plan.write(new_path.clone(), bytes.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Reads every file of the archive, unpacking the nested part zips newer exports use.
+/// Entries whose names would land outside the import folder are reported and skipped.
+fn read_entries<R: Read + Seek>(
+    reader: R,
+    entries: &mut BTreeMap<String, Vec<u8>>,
+    plan: &mut ImportPlan,
+) -> Result<(), OnyxError> {
+    let mut archive = ZipArchive::new(reader)?;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(name) = enclosed_name(entry.name()) else {
+            plan.issue(entry.name(), "skipped: path leads outside the export");
+            continue;
+        };
+        if name.starts_with("__MACOSX/") || name.ends_with(".DS_Store") {
+            continue;
+        }
+        let mut bytes = Vec::new();
+        entry.read_to_end(&mut bytes)?;
+        if name.to_lowercase().ends_with(".zip") {
+            read_entries(Cursor::new(bytes), entries, plan)?;
+        } else {
+            entries.insert(name, bytes);
+        }
+    }
+    Ok(())
+}
+
+/// The entry name as `/`-separated plain components, or `None` if it is absolute or has `..`.
+fn enclosed_name(name: &str) -> Option<String> {
+    let name = name.replace('\\', "/");
+    let mut parts = Vec::new();
+    for component in Path::new(&name).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+/// Drops a single `Export-…` wrapper folder shared by every entry.
+fn strip_export_folder(entries: BTreeMap<String, Vec<u8>>) -> BTreeMap<String, Vec<u8>> {
+    let first = entries
+        .keys()
+        .next()
+        .and_then(|name| name.split_once('/'))
+        .map(|(folder, _)| format!("{folder}/"));
+    match first {
+        Some(prefix)
+            if prefix.starts_with("Export-")
+                && entries.keys().all(|name| name.starts_with(&prefix)) =>
+        {
+            entries
+                .into_iter()
+                .map(|(name, bytes)| (name[prefix.len()..].to_string(), bytes))
+                .collect()
+        }
+        _ => entries,
+    }
+}
+
+/// Removes a trailing ` <32 hex digits>` page id from a file or folder name.
+pub fn strip_notion_id(name: &str) -> &str {
+    if name.len() <= NOTION_ID_LENGTH || !name.is_char_boundary(name.len() - NOTION_ID_LENGTH) {
+        return name;
+    }
+    let (head, id) = name.split_at(name.len() - NOTION_ID_LENGTH);
+    if id.chars().all(|character| character.is_ascii_hexdigit()) {
+        if let Some(title) = head.strip_suffix(' ') {
+            return title;
+        }
+    }
+    name
+}
+
+/// The entry's path with page ids stripped from every component; databases become notes.
+fn clean_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+    let components: Vec<&str> = name.split('/').collect();
+    for (index, component) in components.iter().enumerate() {
+        if index + 1 < components.len() {
+            path.push(strip_component_id(component));
+            continue;
+        }
+        let (stem, extension) = match component.rsplit_once('.') {
+            Some((stem, extension)) => (stem, extension),
+            None => (*component, ""),
+        };
+        let (stem, extension) = if extension.eq_ignore_ascii_case("csv") {
+            (stem.strip_suffix("_all").unwrap_or(stem), "md")
+        } else {
+            (stem, extension)
+        };
+        let stem = strip_component_id(stem);
+        if extension.is_empty() {
+            path.push(stem);
+        } else {
+            path.push(format!("{stem}.{extension}"));
+        }
+    }
+    path
+}
+
+/// Strips the page id from a path component unless that would leave `.` or `..`.
+fn strip_component_id(component: &str) -> &str {
+    match strip_notion_id(component) {
+        "." | ".." => component,
+        stripped => stripped,
+    }
+}
+
+/// Resolves a link from `from` (an entry name) to another entry of the export.
+fn resolve_entry(from: &str, dest: &str, renamed: &HashMap<String, PathBuf>) -> Option<PathBuf> {
+    let decoded = urlencoding::decode(dest).ok()?;
+    let base = Path::new(from).parent().unwrap_or(Path::new(""));
+    let joined = normalize_path(&base.join(decoded.as_ref()));
+    let key = joined.to_string_lossy().replace('\\', "/");
+    renamed
+        .get(&key)
+        .or_else(|| {
+            // Links to a database view point at the CSV we replaced with the `_all` table.
+            let all = key.strip_suffix(".csv")?.to_string() + "_all.csv";
+            renamed.get(&all)
+        })
+        .cloned()
+}
+
+/// Points links and images at the renamed files.
+fn rewrite_links(
+    name: &str,
+    content: &str,
+    new_path: &Path,
+    renamed: &HashMap<String, PathBuf>,
+    plan: &mut ImportPlan,
+) -> String {
+    let mut replacements = Vec::new();
+    let mut has_html = false;
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        let dest = match &event {
+            Event::Start(Tag::Link { dest_url, .. })
+            | Event::Start(Tag::Image { dest_url, .. }) => dest_url.to_string(),
+            Event::Html(_) => {
+                has_html = true;
+                continue;
+            }
+            _ => continue,
+        };
+        if dest.is_empty()
+            || dest.contains("://")
+            || dest.starts_with('#')
+            || dest.starts_with("mailto:")
+        {
+            continue;
+        }
+        let (path_part, fragment) = match dest.split_once('#') {
+            Some((path, fragment)) => (path, format!("#{fragment}")),
+            None => (dest.as_str(), String::new()),
+        };
+        match resolve_entry(name, path_part, renamed) {
+            Some(target) => {
+                let slice = &content[range.clone()];
+                if let Some(offset) = slice.rfind(dest.as_str()) {
+                    let start = range.start + offset;
+                    let link = relative_link(new_path, &target) + &fragment;
+                    replacements.push((start..start + dest.len(), link));
+                }
+            }
+            None => plan.issue(name, format!("link to {dest} points outside the export")),
+        }
+    }
+    if has_html {
+        plan.issue(name, "raw HTML (callouts, embeds) kept as-is");
+    }
+
+    let mut rewritten = content.to_string();
+    for (range, link) in replacements.into_iter().rev() {
+        rewritten.replace_range(range, &link);
+    }
+    rewritten
+}
+
+/// Renders a database export as a markdown table, linking each row to its page when exported.
+fn csv_to_markdown(
+    name: &str,
+    content: &str,
+    new_path: &Path,
+    renamed: &HashMap<String, PathBuf>,
+) -> String {
+    let rows = parse_csv(content.trim_start_matches('\u{feff}'));
+    let Some((header, body)) = rows.split_first() else {
+        return String::new();
+    };
+    let title = new_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    // Row pages live in a folder named like the CSV, keyed by their cleaned title.
+    let folder = name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem.strip_suffix("_all").unwrap_or(stem))
+        .unwrap_or(name);
+    let mut row_pages: HashMap<String, &PathBuf> = HashMap::new();
+    for (entry, path) in renamed {
+        if let Some(file) = entry.strip_prefix(&format!("{folder}/")) {
+            if !file.contains('/') && is_markdown(Path::new(file)) {
+                let stem = file.trim_end_matches(".md");
+                row_pages.insert(strip_notion_id(stem).to_string(), path);
+            }
+        }
+    }
+
+    let cell = |value: &str| value.replace('|', "\\|").replace(['\r', '\n'], " ");
+    let mut markdown = format!("# {title}\n\n");
+    markdown.push_str(&format!(
+        "| {} |\n",
+        header
+            .iter()
+            .map(|value| cell(value))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    ));
+    markdown.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in body {
+        let cells: Vec<String> = (0..header.len())
+            .map(|column| {
+                let value = row.get(column).map(String::as_str).unwrap_or("");
+                match row_pages.get(value) {
+                    Some(page) if column == 0 => {
+                        format!("[{}]({})", cell(value), relative_link(new_path, page))
+                    }
+                    _ => cell(value),
+                }
+            })
+            .collect();
+        markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    markdown
+}
+
+/// Minimal RFC 4180 parser: quoted fields, doubled quotes and embedded newlines.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut characters = content.chars().peekable();
+    while let Some(character) = characters.next() {
+        match (character, in_quotes) {
+            ('"', true) if characters.peek() == Some(&'"') => {
+                field.push('"');
+                characters.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (character, _) => field.push(character),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+    use zip::write::SimpleFileOptions;
+    use zip::ZipWriter;
+
+    const ID_A: &str = "0123456789abcdef0123456789abcdef";
+    const ID_B: &str = "fedcba9876543210fedcba9876543210";
+    const ID_C: &str = "00112233445566778899aabbccddeeff";
+
+    fn build_export(path: &Path) {
+        let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+        let options = SimpleFileOptions::default();
+        let mut add = |name: String, contents: &str| {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        };
+        add(
+            format!("Home {ID_A}.md"),
+            &format!(
+                "# Home\n\nSee [Child](Home%20{ID_A}/Child%20{ID_B}.md#Part) and ![pic](Home%20{ID_A}/pic.png).\n\n[Tasks](Home%20{ID_A}/Tasks%20{ID_C}.csv)\n"
+            ),
+        );
+        add(
+            format!("Home {ID_A}/Child {ID_B}.md"),
+            &format!("# Child\n\nBack to [Home](../Home%20{ID_A}.md)\n"),
+        );
+        add(format!("Home {ID_A}/pic.png"), "png");
+        add(
+            format!("Home {ID_A}/Tasks {ID_C}.csv"),
+            "Name,Status\nWrite,Done\n",
+        );
+        add(
+            format!("Home {ID_A}/Tasks {ID_C}_all.csv"),
+            "\u{feff}Name,Status\nWrite,Done\n\"Plan, then ship\",\"Say \"\"hi\"\"\"\n",
+        );
+        add(
+            format!("Home {ID_A}/Tasks {ID_C}/Write {ID_B}.md"),
+            "# Write\n",
+        );
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn notion_export_strips_ids_and_fixes_links() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = temp.path().join("export.zip");
+        build_export(&zip_path);
+        let target = temp.path().join("vault/Notion");
+        let mut plan = ImportPlan::new(&target);
+
+        super::plan(&zip_path, &mut plan).unwrap();
+        let report = plan.apply(false).unwrap();
+
+        assert_eq!(report.created.len(), 5, "{:?}", report.created);
+        let home = std::fs::read_to_string(target.join("Home.md")).unwrap();
+        assert!(home.contains("[Child](Home/Child.md#Part)"), "{home}");
+        assert!(home.contains("![pic](Home/pic.png)"));
+        assert!(home.contains("[Tasks](Home/Tasks.md)"));
+        let child = std::fs::read_to_string(target.join("Home/Child.md")).unwrap();
+        assert!(child.contains("[Home](../Home.md)"));
+
+        let tasks = std::fs::read_to_string(target.join("Home/Tasks.md")).unwrap();
+        assert!(
+            tasks.contains("| [Write](Tasks/Write.md) | Done |"),
+            "{tasks}"
+        );
+        assert!(tasks.contains("| Plan, then ship | Say \"hi\" |"));
+        assert!(target.join("Home/Tasks/Write.md").exists());
+    }
+
+    #[test]
+    fn entries_outside_the_export_are_skipped() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = temp.path().join("export.zip");
+        let mut zip = ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        for name in [
+            "../../escaped.md",
+            "/etc/absolute.md",
+            "Notes/../../up.md",
+            "Kept.md",
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(b"# Note\n").unwrap();
+        }
+        zip.start_file(format!(".. {ID_A}"), SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"data").unwrap();
+        zip.finish().unwrap();
+        let target = temp.path().join("vault/Notion");
+        let mut plan = ImportPlan::new(&target);
+
+        super::plan(&zip_path, &mut plan).unwrap();
+        let report = plan.apply(false).unwrap();
+
+        assert_eq!(report.created.len(), 2, "{:?}", report.created);
+        assert!(target.join("Kept.md").exists());
+        assert!(target.join(format!(".. {ID_A}")).exists());
+        assert!(!temp.path().join("escaped.md").exists());
+        assert!(!temp.path().join("vault/up.md").exists());
+        assert_eq!(report.issues.len(), 3, "{:?}", report.issues);
+    }
+
+    #[test]
+    fn strip_notion_id_only_removes_hex_suffixes() {
+        assert_eq!(strip_notion_id(&format!("Page {ID_A}")), "Page");
+        assert_eq!(strip_notion_id("Plain name"), "Plain name");
+        assert_eq!(
+            strip_notion_id(&format!("Page{ID_A}")),
+            format!("Page{ID_A}")
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::{import_bear, import_evernote, import_notion};
+
+/// Folder, inside the import target, that receives attachments.
+pub const ATTACHMENTS_DIR: &str = "attachments";
+/// Longest file stem the importers will produce.
+const MAX_STEM_LENGTH: usize = 120;
+
+/// An export from another note-taking tool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "path", rename_all = "snake_case")]
+pub enum ImportSource {
+    /// A Notion "Markdown & CSV" export zip.
+    Notion(String),
+    /// An Evernote `.enex` file.
+    Evernote(String),
+    /// A Bear `.bearnote`/`.textbundle` directory, a `.textpack` zip, or a folder of them.
+    Bear(String),
+}
+
+/// Content that could not be carried over faithfully.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportIssue {
+    /// The note or file in the export the problem was found in.
+    pub source: String,
+    pub message: String,
+}
+
+/// Files an import created (or would create, for a dry run) and what it could not convert.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: Vec<String>,
+    pub issues: Vec<ImportIssue>,
+}
+
+/// Converts an export into notes under `target` (relative to the vault root).
+/// With `dry_run`, nothing is written and the report lists what would be created.
+pub fn import_notes(
+    vault_root: &Path,
+    source: &ImportSource,
+    target: &str,
+    dry_run: bool,
+) -> Result<ImportReport, OnyxError> {
+    let target_dir = vault_root.join(vault_relative(target)?);
+    let mut plan = ImportPlan::new(&target_dir);
+    match source {
+        ImportSource::Notion(path) => import_notion::plan(Path::new(path), &mut plan)?,
+        ImportSource::Evernote(path) => import_evernote::plan(Path::new(path), &mut plan)?,
+        ImportSource::Bear(path) => import_bear::plan(Path::new(path), &mut plan)?,
+    }
+    let report = plan.apply(dry_run)?;
+    info!(
+        "Import into {} {} {} files ({} issues)",
+        target_dir.display(),
+        if dry_run { "would create" } else { "created" },
+        report.created.len(),
+        report.issues.len()
+    );
+    Ok(report)
+}
+
+/// Validates a vault-relative folder, rejecting absolute paths and `..` components.
+fn vault_relative(target: &str) -> Result<PathBuf, OnyxError> {
+    let path = Path::new(target.trim_matches('/'));
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path.to_path_buf())
+    } else {
+        Err(OnyxError::OutsideVault(path.to_path_buf()))
+    }
+}
+
+/// Everything an importer wants to write, collected in memory so a dry run touches nothing.
+pub struct ImportPlan {
+    target: PathBuf,
+    files: Vec<(PathBuf, Vec<u8>)>,
+    reserved: HashSet<String>,
+    issues: Vec<ImportIssue>,
+}
+
+impl ImportPlan {
+    pub fn new(target: &Path) -> Self {
+        Self {
+            target: target.to_path_buf(),
+            files: Vec::new(),
+            reserved: HashSet::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    /// Claims a path relative to the target, numbering the stem (`Note 1.md`) if it is taken
+    /// on disk or by an earlier file of this import.
+    pub fn reserve(&mut self, relative: &Path) -> PathBuf {
+        let parent = relative.parent().unwrap_or(Path::new(""));
+        let stem = relative
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let extension = relative
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        let mut candidate = relative.to_path_buf();
+        let mut counter = 1;
+        loop {
+            let key = candidate.to_string_lossy().to_lowercase();
+            if !self.reserved.contains(&key) && !self.target.join(&candidate).exists() {
+                self.reserved.insert(key);
+                return candidate;
+            }
+            candidate = parent.join(format!("{stem} {counter}{extension}"));
+            counter += 1;
+        }
+    }
+
+    /// Queues a file at a path previously returned by `reserve`.
+    pub fn write(&mut self, relative: PathBuf, contents: impl Into<Vec<u8>>) {
+        self.files.push((relative, contents.into()));
+    }
+
+    /// Reserves a free path derived from `relative` and queues `contents` there.
+    pub fn add(&mut self, relative: &Path, contents: impl Into<Vec<u8>>) -> PathBuf {
+        let path = self.reserve(relative);
+        self.write(path.clone(), contents);
+        path
+    }
+
+    pub fn issue(&mut self, source: impl Into<String>, message: impl Into<String>) {
+        self.issues.push(ImportIssue {
+            source: source.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Writes the queued files (unless `dry_run`) and summarizes the import. Nothing is written
+    /// if any queued path would land outside the target folder.
+    pub fn apply(self, dry_run: bool) -> Result<ImportReport, OnyxError> {
+        if let Some((relative, _)) = self.files.iter().find(|(relative, _)| {
+            relative
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_)))
+        }) {
+            return Err(OnyxError::OutsideVault(self.target.join(relative)));
+        }
+        let mut created = Vec::new();
+        for (relative, contents) in &self.files {
+            let path = self.target.join(relative);
+            if !dry_run {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(&path, contents)?;
+            }
+            created.push(path.to_string_lossy().to_string());
+        }
+        Ok(ImportReport {
+            dry_run,
+            created,
+            issues: self.issues,
+        })
+    }
+}
+
+/// Turns a title into a safe file stem: no path separators or characters that some filesystems reject.
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|character| match character {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            character if character.is_control() => ' ',
+            character => character,
+        })
+        .collect();
+    let trimmed = cleaned.trim().trim_matches('.').trim();
+    if trimmed.is_empty() {
+        return "Untitled".to_string();
+    }
+    trimmed.chars().take(MAX_STEM_LENGTH).collect()
+}
+
+/// A markdown link destination from `from` (a note path relative to the target) to `to`.
+pub fn relative_link(from: &Path, to: &Path) -> String {
+    let from_dir: Vec<_> = from
+        .parent()
+        .map(|parent| parent.components().collect())
+        .unwrap_or_default();
+    let to_parts: Vec<_> = to.components().collect();
+    let common = from_dir
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut segments: Vec<String> = vec!["..".to_string(); from_dir.len() - common];
+    segments.extend(
+        to_parts[common..]
+            .iter()
+            .map(|part| urlencoding::encode(&part.as_os_str().to_string_lossy()).into_owned()),
+    );
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn reserve_numbers_clashing_names() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("Note.md"), "").unwrap();
+        let mut plan = ImportPlan::new(temp.path());
+
+        assert_eq!(
+            plan.reserve(Path::new("Note.md")),
+            PathBuf::from("Note 1.md")
+        );
+        assert_eq!(
+            plan.reserve(Path::new("Note.md")),
+            PathBuf::from("Note 2.md")
+        );
+        assert_eq!(
+            plan.reserve(Path::new("NOTE 2.md")),
+            PathBuf::from("NOTE 2 1.md")
+        );
+        assert_eq!(
+            plan.reserve(Path::new("Other.md")),
+            PathBuf::from("Other.md")
+        );
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = ImportPlan::new(&temp.path().join("Imported"));
+        plan.add(Path::new("a.md"), "hello");
+
+        let report = plan.apply(true).unwrap();
+
+        assert_eq!(report.created.len(), 1);
+        assert!(!temp.path().join("Imported").exists());
+    }
+
+    #[test]
+    fn apply_refuses_paths_outside_the_target() {
+        let temp = TempDir::new().unwrap();
+        let mut plan = ImportPlan::new(&temp.path().join("Imported"));
+        plan.write(PathBuf::from("a.md"), "fine");
+        plan.write(PathBuf::from("../escaped.md"), "nope");
+
+        assert!(matches!(plan.apply(false), Err(OnyxError::OutsideVault(_))));
+        assert!(!temp.path().join("Imported").exists());
+        assert!(!temp.path().join("escaped.md").exists());
+    }
+
+    #[test]
+    fn target_must_stay_inside_the_vault() {
+        let temp = TempDir::new().unwrap();
+        let source = ImportSource::Evernote("missing.enex".to_string());
+        let result = import_notes(temp.path(), &source, "../outside", true);
+        assert!(matches!(result, Err(OnyxError::OutsideVault(_))));
+    }
+
+    #[test]
+    fn sanitize_file_name_strips_separators() {
+        assert_eq!(sanitize_file_name("a/b: c?"), "a-b- c-");
+        assert_eq!(sanitize_file_name(" .. "), "Untitled");
+    }
+
+    #[test]
+    fn relative_link_walks_up_and_encodes() {
+        assert_eq!(
+            relative_link(Path::new("a/b/Note.md"), Path::new("a/Other Note.md")),
+            "../Other%20Note.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("Note.md"), Path::new("attachments/x.png")),
+            "attachments/x.png"
+        );
+    }
+}
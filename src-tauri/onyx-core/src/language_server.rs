@@ -0,0 +1,1176 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use log::{info, warn};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, References, Rename, Request as _,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionTextEdit,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DidSaveTextDocumentParams, DocumentChangeOperation, DocumentChanges,
+    DocumentSymbol, DocumentSymbolParams, GotoDefinitionParams, Location, OneOf,
+    OptionalVersionedTextDocumentIdentifier, Position, PublishDiagnosticsParams, ReferenceParams,
+    RenameFile, RenameParams, ResourceOp, SaveOptions, ServerCapabilities, SymbolKind,
+    TextDocumentEdit, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
+    TextDocumentSyncSaveOptions, TextEdit, Uri, WorkspaceEdit,
+};
+use pulldown_cmark::{Event, LinkType, Parser, Tag, TagEnd};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use crate::error::OnyxError;
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::markdown::{heading_slug, parser_options, split_link_target};
+use crate::vault_handle::VaultHandle;
+use crate::vault_health::{resolve, vault_files, Reference};
+
+/// A Language Server Protocol server for one vault, so other editors get Onyx's link and tag
+/// features on its markdown files. Positions are UTF-16 as the protocol requires.
+pub struct LanguageServer {
+    vault: VaultHandle,
+    /// Unsaved text of the documents the client has open, which wins over the copy on disk.
+    documents: HashMap<PathBuf, String>,
+}
+
+/// A link, wikilink, image or embed in a note.
+#[derive(Debug)]
+struct Link {
+    /// Bytes of the whole link, e.g. `[[Plan#Goals|the plan]]`.
+    range: Range<usize>,
+    /// Bytes of the written target (`Plan#Goals`), when it can be located in the source.
+    target_range: Option<Range<usize>>,
+    target: String,
+    wikilink: bool,
+    embed: bool,
+}
+
+/// A heading in a note.
+#[derive(Debug)]
+struct Heading {
+    level: usize,
+    text: String,
+    /// Bytes of the whole heading line(s).
+    range: Range<usize>,
+    /// Bytes of the heading's text, without the `#` markers.
+    text_range: Range<usize>,
+}
+
+impl LanguageServer {
+    pub fn new(vault: VaultHandle) -> Self {
+        Self {
+            vault,
+            documents: HashMap::new(),
+        }
+    }
+
+    /// What the server offers: full-text sync, completion after `[[` and `#`, definitions,
+    /// references, rename and document symbols.
+    pub fn capabilities() -> ServerCapabilities {
+        ServerCapabilities {
+            text_document_sync: Some(TextDocumentSyncCapability::Options(
+                TextDocumentSyncOptions {
+                    open_close: Some(true),
+                    change: Some(TextDocumentSyncKind::FULL),
+                    save: Some(TextDocumentSyncSaveOptions::SaveOptions(SaveOptions {
+                        include_text: Some(true),
+                    })),
+                    ..TextDocumentSyncOptions::default()
+                },
+            )),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec!["[".to_string(), "#".to_string()]),
+                ..CompletionOptions::default()
+            }),
+            definition_provider: Some(OneOf::Left(true)),
+            references_provider: Some(OneOf::Left(true)),
+            rename_provider: Some(OneOf::Left(true)),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    /// Runs the initialize handshake on `connection`, then answers messages until the client
+    /// shuts the server down or disconnects.
+    pub fn serve(&mut self, connection: &Connection) -> Result<(), OnyxError> {
+        info!(
+            "Language server started for {}",
+            self.vault.root().display()
+        );
+        let (id, _) = connection.initialize_start().map_err(protocol_error)?;
+        let result = json!({
+            "capabilities": Self::capabilities(),
+            "serverInfo": { "name": "onyx", "version": env!("CARGO_PKG_VERSION") },
+        });
+        connection
+            .initialize_finish(id, result)
+            .map_err(protocol_error)?;
+
+        for message in &connection.receiver {
+            let replies = match message {
+                Message::Request(request) => {
+                    if connection
+                        .handle_shutdown(&request)
+                        .map_err(protocol_error)?
+                    {
+                        return Ok(());
+                    }
+                    vec![Message::Response(self.handle_request(request))]
+                }
+                Message::Notification(notification) => self
+                    .handle_notification(notification)
+                    .into_iter()
+                    .map(Message::Notification)
+                    .collect(),
+                Message::Response(_) => Vec::new(),
+            };
+            for reply in replies {
+                connection
+                    .sender
+                    .send(reply)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::BrokenPipe, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        let method = request.method.clone();
+        let result = match method.as_str() {
+            Completion::METHOD => params::<CompletionParams>(request).and_then(|params| {
+                let position = params.text_document_position;
+                let path = document_path(&position.text_document.uri)?;
+                Ok(json!(self.completion(&path, position.position)))
+            }),
+            GotoDefinition::METHOD => params::<GotoDefinitionParams>(request).and_then(|params| {
+                let position = params.text_document_position_params;
+                let path = document_path(&position.text_document.uri)?;
+                Ok(json!(self.definition(&path, position.position)))
+            }),
+            References::METHOD => params::<ReferenceParams>(request).and_then(|params| {
+                let path = document_path(&params.text_document_position.text_document.uri)?;
+                self.references(&path)
+                    .map(|locations| json!(locations))
+                    .map_err(|e| (ErrorCode::RequestFailed, e.to_string()))
+            }),
+            Rename::METHOD => params::<RenameParams>(request).and_then(|params| {
+                let position = params.text_document_position;
+                let path = document_path(&position.text_document.uri)?;
+                self.rename(&path, position.position, &params.new_name)
+                    .map(|edit| json!(edit))
+                    .map_err(|message| (ErrorCode::RequestFailed, message))
+            }),
+            DocumentSymbolRequest::METHOD => {
+                params::<DocumentSymbolParams>(request).and_then(|params| {
+                    let path = document_path(&params.text_document.uri)?;
+                    Ok(json!(self.document_symbols(&path)))
+                })
+            }
+            method => Err((
+                ErrorCode::MethodNotFound,
+                format!("Unknown method {method}"),
+            )),
+        };
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => {
+                warn!("Language server request failed: {message}");
+                Response::new_err(id, code as i32, message)
+            }
+        }
+    }
+
+    /// Tracks document text and returns the diagnostics to publish, if any.
+    fn handle_notification(&mut self, notification: Notification) -> Option<Notification> {
+        let (uri, text) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = notification_params(notification)?;
+                (params.text_document.uri, Some(params.text_document.text))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams = notification_params(notification)?;
+                let text = params.content_changes.into_iter().last()?.text;
+                (params.text_document.uri, Some(text))
+            }
+            DidSaveTextDocument::METHOD => {
+                let params: DidSaveTextDocumentParams = notification_params(notification)?;
+                (params.text_document.uri, params.text)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = notification_params(notification)?;
+                let path = uri_to_path(&params.text_document.uri)?;
+                self.close_document(&path);
+                return None;
+            }
+            _ => return None,
+        };
+        let path = uri_to_path(&uri).filter(|path| is_markdown(path))?;
+        if let Some(text) = text {
+            self.open_document(&path, text);
+        }
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics: self.diagnostics(&path),
+            version: None,
+        };
+        Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        ))
+    }
+
+    /// Records the editor's current text of `path` and re-indexes its tags and tasks.
+    pub fn open_document(&mut self, path: &Path, text: String) {
+        self.vault.update_file(path, &text);
+        self.documents.insert(normalize_path(path), text);
+    }
+
+    /// Forgets the editor's copy of `path` and re-indexes what is on disk.
+    pub fn close_document(&mut self, path: &Path) {
+        self.documents.remove(&normalize_path(path));
+        let content = std::fs::read_to_string(path).unwrap_or_default();
+        self.vault.update_file(path, &content);
+    }
+
+    /// Note names inside an open `[[`, headings after `[[Note#`, and vault tags after `#`.
+    pub fn completion(&mut self, path: &Path, position: Position) -> Vec<CompletionItem> {
+        let Some(content) = self.text(path) else {
+            return Vec::new();
+        };
+        let offset = offset_at(&content, position);
+        let line_start = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let before = &content[line_start..offset];
+
+        if let Some(open) = before
+            .rfind("[[")
+            .filter(|open| !before[*open..].contains("]]"))
+        {
+            let typed = &before[open + 2..];
+            if typed.contains('|') {
+                return Vec::new();
+            }
+            let replace_from = line_start + open + 2;
+            return match typed.split_once('#') {
+                Some((note, _)) => {
+                    let replace_from = replace_from + note.len() + 1;
+                    self.heading_completions(path, note, &content, replace_from..offset)
+                }
+                None => self.note_completions(&content, replace_from..offset),
+            };
+        }
+
+        let word_start = before
+            .rfind(|character: char| !is_tag_character(character))
+            .map_or(0, |index| index + 1);
+        let hash = before[..word_start].strip_suffix('#');
+        let tag_position =
+            hash.is_some_and(|prefix| prefix.is_empty() || prefix.ends_with(char::is_whitespace));
+        if !tag_position {
+            return Vec::new();
+        }
+        let range = lsp_range(&content, line_start + word_start..offset);
+        self.vault
+            .tags()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tag| CompletionItem {
+                label: tag.clone(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: tag,
+                })),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    fn note_completions(&self, content: &str, replace: Range<usize>) -> Vec<CompletionItem> {
+        let root = self.vault.root();
+        let notes: Vec<PathBuf> = vault_files(root)
+            .into_iter()
+            .filter(|path| is_markdown(path))
+            .collect();
+        let mut stem_counts: HashMap<String, usize> = HashMap::new();
+        for note in &notes {
+            *stem_counts.entry(stem(note).to_lowercase()).or_default() += 1;
+        }
+        let range = lsp_range(content, replace);
+        notes
+            .iter()
+            .map(|note| {
+                let relative = note
+                    .strip_prefix(root)
+                    .unwrap_or(note)
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                // Notes sharing a name need their folder to resolve to the right one.
+                let label = if stem_counts[&stem(note).to_lowercase()] > 1 {
+                    relative.trim_end_matches(".md").to_string()
+                } else {
+                    stem(note).to_string()
+                };
+                CompletionItem {
+                    label: label.clone(),
+                    kind: Some(CompletionItemKind::FILE),
+                    detail: Some(relative),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                        range,
+                        new_text: label,
+                    })),
+                    ..CompletionItem::default()
+                }
+            })
+            .collect()
+    }
+
+    fn heading_completions(
+        &self,
+        path: &Path,
+        note: &str,
+        content: &str,
+        replace: Range<usize>,
+    ) -> Vec<CompletionItem> {
+        let target = if note.is_empty() {
+            Some(path.to_path_buf())
+        } else {
+            self.resolver().resolve_note(note).map(Path::to_path_buf)
+        };
+        let Some(target_content) = target.and_then(|target| self.text(&target)) else {
+            return Vec::new();
+        };
+        let range = lsp_range(content, replace);
+        headings(&target_content)
+            .into_iter()
+            .map(|heading| CompletionItem {
+                label: heading.text.clone(),
+                kind: Some(CompletionItemKind::REFERENCE),
+                detail: Some("#".repeat(heading.level)),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range,
+                    new_text: heading.text,
+                })),
+                ..CompletionItem::default()
+            })
+            .collect()
+    }
+
+    /// Where the link under `position` points: the target note (at its heading, if the link
+    /// names one) or the linked file.
+    pub fn definition(&self, path: &Path, position: Position) -> Option<Location> {
+        let content = self.text(path)?;
+        let offset = offset_at(&content, position);
+        let link = links(&content)
+            .into_iter()
+            .find(|link| link.range.contains(&offset))?;
+        let target = resolve_link(&self.resolver(), path, &link)?;
+        let mut range = lsp_types::Range::default();
+        if let (Some(fragment), true) = (split_link_target(&link.target).1, is_markdown(&target)) {
+            let target_content = self.text(&target).unwrap_or_default();
+            if let Some(heading) = find_heading(&target_content, fragment) {
+                range = lsp_range(&target_content, heading.range);
+            }
+        }
+        Some(Location {
+            uri: path_to_uri(&target),
+            range,
+        })
+    }
+
+    /// Every place in the vault that links to `path`, from the backlinks index.
+    pub fn references(&self, path: &Path) -> Result<Vec<Location>, OnyxError> {
+        let mut locations = Vec::new();
+        for backlink in self.vault.backlinks(path)? {
+            let source = PathBuf::from(&backlink.source);
+            let range = match (backlink.line, self.text(&source)) {
+                (Some(line), Some(content)) => line_range(&content, line - 1),
+                _ => lsp_types::Range::default(),
+            };
+            locations.push(Location {
+                uri: path_to_uri(&source),
+                range,
+            });
+        }
+        Ok(locations)
+    }
+
+    /// Renames the heading under `position`, or the note linked under it, and rewrites every
+    /// link that points at the old name.
+    pub fn rename(
+        &self,
+        path: &Path,
+        position: Position,
+        new_name: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        let content = self
+            .text(path)
+            .ok_or_else(|| format!("Cannot read {}", path.display()))?;
+        let offset = offset_at(&content, position);
+        let resolver = self.resolver();
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err("The new name is empty".to_string());
+        }
+
+        if let Some(heading) = headings(&content)
+            .into_iter()
+            .find(|heading| heading.range.contains(&offset))
+        {
+            return Ok(self.rename_heading(&resolver, path, &content, &heading, new_name));
+        }
+        let link = links(&content)
+            .into_iter()
+            .find(|link| link.range.contains(&offset))
+            .ok_or("Place the cursor on a heading or a link to a note")?;
+        let target = resolve_link(&resolver, path, &link)
+            .filter(|target| is_markdown(target))
+            .ok_or_else(|| format!("'{}' is not a note in this vault", link.target))?;
+        if let Some(fragment) = split_link_target(&link.target).1 {
+            let target_content = self.text(&target).unwrap_or_default();
+            if let Some(heading) = find_heading(&target_content, fragment) {
+                return Ok(self.rename_heading(
+                    &resolver,
+                    &target,
+                    &target_content,
+                    &heading,
+                    new_name,
+                ));
+            }
+        }
+        self.rename_note(&resolver, &target, new_name)
+    }
+
+    fn rename_note(
+        &self,
+        resolver: &LinkResolver,
+        note: &Path,
+        new_stem: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        let new_stem = new_stem.strip_suffix(".md").unwrap_or(new_stem);
+        if new_stem.contains(['/', '\\']) {
+            return Err("A note name cannot contain a path separator".to_string());
+        }
+        let destination = note.with_file_name(format!("{new_stem}.md"));
+        if destination.exists() && normalize_path(&destination) != normalize_path(note) {
+            return Err(format!("A file named '{new_stem}.md' already exists"));
+        }
+
+        let mut operations = self.link_edits(resolver, note, |link, written| {
+            let (written_note, fragment) =
+                written.split_at(written.find('#').unwrap_or(written.len()));
+            if written_note.is_empty() {
+                return None;
+            }
+            let folder_end = written_note.rfind('/').map_or(0, |index| index + 1);
+            let (folder, name) = written_note.split_at(folder_end);
+            let mut replacement = if link.wikilink {
+                new_stem.to_string()
+            } else {
+                new_stem.replace(' ', "%20")
+            };
+            if let Some(extension) = Path::new(name).extension() {
+                replacement.push('.');
+                replacement.push_str(&extension.to_string_lossy());
+            }
+            Some(format!("{folder}{replacement}{fragment}"))
+        });
+        // Edits refer to the old file names, so the rename itself goes last.
+        operations.push(DocumentChangeOperation::Op(ResourceOp::Rename(
+            RenameFile {
+                old_uri: path_to_uri(note),
+                new_uri: path_to_uri(&destination),
+                options: None,
+                annotation_id: None,
+            },
+        )));
+        Ok(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..WorkspaceEdit::default()
+        })
+    }
+
+    fn rename_heading(
+        &self,
+        resolver: &LinkResolver,
+        note: &Path,
+        content: &str,
+        heading: &Heading,
+        new_text: &str,
+    ) -> WorkspaceEdit {
+        let old_slug = heading_slug(&heading.text);
+        let mut operations = self.link_edits(resolver, note, |_, written| {
+            let (written_note, fragment) = written.split_once('#')?;
+            if heading_slug(fragment) != old_slug {
+                return None;
+            }
+            // Keep slug-style fragments (`#project-goals`) in slug style.
+            let replacement = if fragment != heading.text && fragment == old_slug {
+                heading_slug(new_text)
+            } else {
+                new_text.to_string()
+            };
+            Some(format!("{written_note}#{replacement}"))
+        });
+        let heading_edit = TextEdit {
+            range: lsp_range(content, heading.text_range.clone()),
+            new_text: new_text.to_string(),
+        };
+        let uri = path_to_uri(note);
+        match operations.iter_mut().find_map(|operation| match operation {
+            DocumentChangeOperation::Edit(edit) if edit.text_document.uri == uri => Some(edit),
+            _ => None,
+        }) {
+            Some(edit) => edit.edits.push(OneOf::Left(heading_edit)),
+            None => operations.push(document_edit(uri, vec![heading_edit])),
+        }
+        WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..WorkspaceEdit::default()
+        }
+    }
+
+    /// One text edit per note for the links that resolve to `target`, rewriting each written
+    /// target with `rewrite` (which returns `None` to leave a link alone).
+    fn link_edits(
+        &self,
+        resolver: &LinkResolver,
+        target: &Path,
+        rewrite: impl Fn(&Link, &str) -> Option<String>,
+    ) -> Vec<DocumentChangeOperation> {
+        let target = normalize_path(target);
+        let mut operations = Vec::new();
+        for note in vault_files(self.vault.root())
+            .into_iter()
+            .filter(|path| is_markdown(path))
+        {
+            let Some(content) = self.text(&note) else {
+                continue;
+            };
+            let mut edits = Vec::new();
+            for link in links(&content) {
+                let Some(target_range) = link.target_range.clone() else {
+                    continue;
+                };
+                if resolve_link(resolver, &note, &link).map(|path| normalize_path(&path))
+                    != Some(target.clone())
+                {
+                    continue;
+                }
+                if let Some(new_text) = rewrite(&link, &content[target_range.clone()]) {
+                    edits.push(TextEdit {
+                        range: lsp_range(&content, target_range),
+                        new_text,
+                    });
+                }
+            }
+            if !edits.is_empty() {
+                operations.push(document_edit(path_to_uri(&note), edits));
+            }
+        }
+        operations
+    }
+
+    /// Warnings for links and embeds in `path` whose note, file or heading does not exist.
+    pub fn diagnostics(&self, path: &Path) -> Vec<Diagnostic> {
+        let Some(content) = self.text(path) else {
+            return Vec::new();
+        };
+        let resolver = self.resolver();
+        let mut diagnostics = Vec::new();
+        for link in links(&content) {
+            if is_external(&link.target) {
+                continue;
+            }
+            let message = match resolve_link(&resolver, path, &link) {
+                None => format!("No file found for '{}'", split_link_target(&link.target).0),
+                Some(target) => {
+                    let fragment = split_link_target(&link.target)
+                        .1
+                        .filter(|fragment| !fragment.starts_with('^'));
+                    match fragment {
+                        Some(fragment) if link.wikilink && is_markdown(&target) => {
+                            let target_content = self.text(&target).unwrap_or_default();
+                            if find_heading(&target_content, fragment).is_some() {
+                                continue;
+                            }
+                            format!("No heading '{fragment}' in {}", stem(&target))
+                        }
+                        _ => continue,
+                    }
+                }
+            };
+            diagnostics.push(Diagnostic {
+                range: lsp_range(&content, link.range),
+                severity: Some(DiagnosticSeverity::WARNING),
+                source: Some("onyx".to_string()),
+                message,
+                ..Diagnostic::default()
+            });
+        }
+        diagnostics
+    }
+
+    /// The note's outline, with each heading nesting the deeper headings below it.
+    pub fn document_symbols(&self, path: &Path) -> Vec<DocumentSymbol> {
+        let Some(content) = self.text(path) else {
+            return Vec::new();
+        };
+        nest_headings(&content, &headings(&content), content.len())
+    }
+
+    fn text(&self, path: &Path) -> Option<String> {
+        match self.documents.get(&normalize_path(path)) {
+            Some(text) => Some(text.clone()),
+            None => std::fs::read_to_string(path).ok(),
+        }
+    }
+
+    /// Built per request so files created, renamed or deleted by the client are picked up.
+    fn resolver(&self) -> LinkResolver {
+        LinkResolver::build(self.vault.root())
+    }
+}
+
+/// Serves `vault` over stdin and stdout until the client exits.
+pub fn run_stdio(vault: VaultHandle) -> Result<(), OnyxError> {
+    let (connection, io_threads) = Connection::stdio();
+    LanguageServer::new(vault).serve(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+fn protocol_error(error: lsp_server::ProtocolError) -> OnyxError {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error.to_string()).into()
+}
+
+fn params<P: DeserializeOwned>(request: Request) -> Result<P, (ErrorCode, String)> {
+    serde_json::from_value(request.params).map_err(|e| (ErrorCode::InvalidParams, e.to_string()))
+}
+
+fn notification_params<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params)
+        .map_err(|e| warn!("Ignoring malformed {}: {e}", notification.method))
+        .ok()
+}
+
+fn document_path(uri: &Uri) -> Result<PathBuf, (ErrorCode, String)> {
+    uri_to_path(uri).ok_or_else(|| {
+        (
+            ErrorCode::InvalidParams,
+            format!("Not a file URI: {}", uri.as_str()),
+        )
+    })
+}
+
+/// `file://` URI of an absolute path, with every segment percent-encoded.
+pub fn path_to_uri(path: &Path) -> Uri {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded: Vec<String> = path
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect();
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    Uri::from_str(&format!("file://{separator}{}", encoded.join("/")))
+        .expect("percent-encoded paths are valid URIs")
+}
+
+/// The local path of a `file://` URI.
+pub fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    let path = uri.as_str().strip_prefix("file://")?;
+    let path = urlencoding::decode(path).ok()?;
+    // `file:///C:/notes` carries a Windows drive after the leading slash.
+    let path = match path.as_bytes() {
+        [b'/', _, b':', ..] => &path[1..],
+        _ => &path,
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Byte offset of a UTF-16 `position`, clamped to the end of its line.
+fn offset_at(content: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        if index == position.line as usize {
+            let mut units = 0;
+            for (byte, character) in line.char_indices() {
+                if units >= position.character as usize || character == '\n' || character == '\r' {
+                    return line_start + byte;
+                }
+                units += character.len_utf16();
+            }
+            return line_start + line.len();
+        }
+        line_start += line.len();
+    }
+    content.len()
+}
+
+/// UTF-16 position of a byte offset.
+fn position_at(content: &str, offset: usize) -> Position {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+fn lsp_range(content: &str, range: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range {
+        start: position_at(content, range.start),
+        end: position_at(content, range.end),
+    }
+}
+
+/// The whole of 0-based `line`.
+fn line_range(content: &str, line: usize) -> lsp_types::Range {
+    let text = content.lines().nth(line).unwrap_or_default();
+    lsp_types::Range {
+        start: Position::new(line as u32, 0),
+        end: Position::new(line as u32, text.encode_utf16().count() as u32),
+    }
+}
+
+fn document_edit(uri: Uri, edits: Vec<TextEdit>) -> DocumentChangeOperation {
+    DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+        edits: edits.into_iter().map(OneOf::Left).collect(),
+    })
+}
+
+fn stem(path: &Path) -> &str {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or_default()
+}
+
+fn is_external(target: &str) -> bool {
+    target.contains("://") || target.starts_with("mailto:") || target.starts_with("data:")
+}
+
+fn is_tag_character(character: char) -> bool {
+    character.is_alphanumeric() || character == '_' || character == '-'
+}
+
+fn resolve_link(resolver: &LinkResolver, note: &Path, link: &Link) -> Option<PathBuf> {
+    let reference = Reference {
+        target: link.target.clone(),
+        offset: link.range.start,
+        wikilink: link.wikilink,
+        embed: link.embed,
+    };
+    resolve(resolver, note, &reference)
+}
+
+/// Every link in `content`, including same-note `#heading` links.
+fn links(content: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        let (link_type, dest_url, embed) = match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) => (link_type, dest_url, false),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => (link_type, dest_url, true),
+            _ => continue,
+        };
+        if dest_url.is_empty() {
+            continue;
+        }
+        let wikilink = matches!(link_type, LinkType::WikiLink { .. });
+        let source = &content[range.clone()];
+        let target_range = if wikilink {
+            source.find("[[").map(|open| {
+                let start = range.start + open + 2;
+                let end = content[start..range.end]
+                    .find(['|', ']'])
+                    .map_or(range.end, |index| start + index);
+                start..end
+            })
+        } else if link_type == LinkType::Inline {
+            source.rfind("](").and_then(|open| {
+                let start = range.start + open + 2;
+                content[start..range.end]
+                    .starts_with(dest_url.as_ref())
+                    .then(|| start..start + dest_url.len())
+            })
+        } else {
+            None
+        };
+        links.push(Link {
+            range,
+            target_range,
+            target: dest_url.to_string(),
+            wikilink,
+            embed,
+        });
+    }
+    links
+}
+
+/// Every heading in `content`, in document order.
+fn headings(content: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<Heading> = None;
+    // Set once the current heading's first inline event has been seen.
+    let mut has_text = false;
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                has_text = false;
+                current = Some(Heading {
+                    level: level as usize,
+                    text: String::new(),
+                    text_range: range.end..range.end,
+                    range,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut heading) = current.take() {
+                    heading.text = heading.text.trim().to_string();
+                    headings.push(heading);
+                }
+            }
+            event => {
+                if let Some(heading) = &mut current {
+                    match &event {
+                        Event::Text(text) | Event::Code(text) => heading.text.push_str(text),
+                        Event::SoftBreak | Event::HardBreak => heading.text.push(' '),
+                        _ => {}
+                    }
+                    if has_text {
+                        heading.text_range.end = heading.text_range.end.max(range.end);
+                    } else {
+                        heading.text_range = range;
+                        has_text = true;
+                    }
+                }
+            }
+        }
+    }
+    headings
+}
+
+/// The heading a `#fragment` names, by its text or its slug.
+fn find_heading(content: &str, fragment: &str) -> Option<Heading> {
+    let slug = heading_slug(fragment);
+    headings(content)
+        .into_iter()
+        .find(|heading| heading_slug(&heading.text) == slug)
+}
+
+#[allow(deprecated)] // `DocumentSymbol::deprecated` must still be set.
+fn nest_headings(content: &str, headings: &[Heading], section_end: usize) -> Vec<DocumentSymbol> {
+    let mut symbols = Vec::new();
+    let mut index = 0;
+    while index < headings.len() {
+        let heading = &headings[index];
+        let end = headings[index + 1..]
+            .iter()
+            .position(|next| next.level <= heading.level)
+            .map_or(headings.len(), |position| index + 1 + position);
+        let end_offset = headings
+            .get(end)
+            .map_or(section_end, |next| next.range.start);
+        let children = nest_headings(content, &headings[index + 1..end], end_offset);
+        symbols.push(DocumentSymbol {
+            name: heading.text.clone(),
+            detail: None,
+            kind: SymbolKind::STRING,
+            tags: None,
+            deprecated: None,
+            range: lsp_range(content, heading.range.start..end_offset),
+            selection_range: lsp_range(content, heading.range.clone()),
+            children: (!children.is_empty()).then_some(children),
+        });
+        index = end;
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn server() -> (TempDir, LanguageServer) {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "Home.md",
+            "# Home\n\nSee [[Projects/Plan#Goals|the goals]] and [[Missing]].\n\n## Tasks #work\n\n[plan](Projects/Plan.md)\n",
+        );
+        write(
+            root,
+            "Projects/Plan.md",
+            "# Plan\n\n## Goals\n\nShip it. [[#Goals]]\n\n### Stretch\n\n## Risks\n",
+        );
+        write(root, "Journal.md", "Plans: [[plan]], [[Plan#goals]]\n");
+        let vault = VaultHandle::open(root).unwrap();
+        (dir, LanguageServer::new(vault))
+    }
+
+    fn text_edits(edit: &WorkspaceEdit) -> Vec<(String, String)> {
+        let Some(DocumentChanges::Operations(operations)) = &edit.document_changes else {
+            panic!("expected document operations");
+        };
+        let mut edits = Vec::new();
+        for operation in operations {
+            if let DocumentChangeOperation::Edit(edit) = operation {
+                let path = uri_to_path(&edit.text_document.uri).unwrap();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                for text_edit in &edit.edits {
+                    if let OneOf::Left(text_edit) = text_edit {
+                        edits.push((name.clone(), text_edit.new_text.clone()));
+                    }
+                }
+            }
+        }
+        edits.sort();
+        edits
+    }
+
+    #[test]
+    fn uris_and_positions_round_trip() {
+        let path = Path::new("/vault/My Notes/Café.md");
+        let uri = path_to_uri(path);
+        assert_eq!(uri.as_str(), "file:///vault/My%20Notes/Caf%C3%A9.md");
+        assert_eq!(uri_to_path(&uri).unwrap(), path);
+
+        let content = "ab\n😀x\n";
+        let offset = content.find('x').unwrap();
+        assert_eq!(position_at(content, offset), Position::new(1, 2));
+        assert_eq!(offset_at(content, Position::new(1, 2)), offset);
+        assert_eq!(offset_at(content, Position::new(0, 99)), 2);
+    }
+
+    #[test]
+    fn completes_notes_headings_and_tags() {
+        let (dir, mut server) = server();
+        let path = dir.path().join("Draft.md");
+        server.open_document(&path, "Link [[Pl\nSee [[Plan#\nTag #wo".to_string());
+
+        let notes = server.completion(&path, Position::new(0, 9));
+        let labels: Vec<&str> = notes.iter().map(|item| item.label.as_str()).collect();
+        assert!(labels.contains(&"Plan") && labels.contains(&"Home"));
+        let Some(CompletionTextEdit::Edit(edit)) = &notes[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(0, 7));
+
+        let headings = server.completion(&path, Position::new(1, 11));
+        let labels: Vec<&str> = headings.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["Plan", "Goals", "Stretch", "Risks"]);
+
+        let tags = server.completion(&path, Position::new(2, 7));
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].label, "work");
+        assert!(server.completion(&path, Position::new(2, 2)).is_empty());
+    }
+
+    #[test]
+    fn goes_to_definitions_and_references() {
+        let (dir, server) = server();
+        let home = dir.path().join("Home.md");
+        let plan = dir.path().join("Projects/Plan.md");
+
+        let location = server.definition(&home, Position::new(2, 8)).unwrap();
+        assert_eq!(uri_to_path(&location.uri).unwrap(), plan);
+        assert_eq!(location.range.start, Position::new(2, 0));
+        assert!(server.definition(&home, Position::new(0, 2)).is_none());
+
+        let references = server.references(&plan).unwrap();
+        let mut sources: Vec<(String, u32)> = references
+            .iter()
+            .map(|location| {
+                let path = uri_to_path(&location.uri).unwrap();
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                (name, location.range.start.line)
+            })
+            .collect();
+        sources.dedup();
+        assert_eq!(
+            sources,
+            [
+                ("Home.md".to_string(), 2),
+                ("Home.md".to_string(), 6),
+                ("Journal.md".to_string(), 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn renames_notes_and_rewrites_links() {
+        let (dir, server) = server();
+        let journal = dir.path().join("Journal.md");
+        assert!(server
+            .rename(&journal, Position::new(0, 2), "Roadmap")
+            .is_err());
+
+        let edit = server
+            .rename(&journal, Position::new(0, 10), "Road map")
+            .unwrap();
+        assert_eq!(
+            text_edits(&edit),
+            [
+                ("Home.md".to_string(), "Projects/Road map#Goals".to_string()),
+                ("Home.md".to_string(), "Projects/Road%20map.md".to_string()),
+                ("Journal.md".to_string(), "Road map".to_string()),
+                ("Journal.md".to_string(), "Road map#goals".to_string()),
+            ]
+        );
+        let Some(DocumentChanges::Operations(operations)) = &edit.document_changes else {
+            panic!("expected document operations");
+        };
+        let Some(DocumentChangeOperation::Op(ResourceOp::Rename(rename))) = operations.last()
+        else {
+            panic!("expected the file rename last");
+        };
+        assert_eq!(
+            uri_to_path(&rename.new_uri).unwrap(),
+            dir.path().join("Projects/Road map.md")
+        );
+
+        write(dir.path(), "Projects/Archive.md", "");
+        assert!(server
+            .rename(&journal, Position::new(0, 10), "Archive")
+            .is_err());
+    }
+
+    #[test]
+    fn renames_headings_and_rewrites_fragments() {
+        let (dir, server) = server();
+        let plan = dir.path().join("Projects/Plan.md");
+        let edit = server
+            .rename(&plan, Position::new(2, 4), "Objectives")
+            .unwrap();
+        assert_eq!(
+            text_edits(&edit),
+            [
+                (
+                    "Home.md".to_string(),
+                    "Projects/Plan#Objectives".to_string()
+                ),
+                ("Journal.md".to_string(), "Plan#objectives".to_string()),
+                ("Plan.md".to_string(), "#Objectives".to_string()),
+                ("Plan.md".to_string(), "Objectives".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_broken_links_and_outlines_headings() {
+        let (dir, mut server) = server();
+        let home = dir.path().join("Home.md");
+        let diagnostics = server.diagnostics(&home);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "No file found for 'Missing'");
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 42));
+
+        server.open_document(&home, "[[Plan#Nowhere]] [[#Top]]\n# Top\n".to_string());
+        let messages: Vec<String> = server
+            .diagnostics(&home)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        assert_eq!(messages, ["No heading 'Nowhere' in Plan"]);
+
+        let plan = dir.path().join("Projects/Plan.md");
+        let symbols = server.document_symbols(&plan);
+        assert_eq!(symbols.len(), 1);
+        let children = symbols[0].children.as_ref().unwrap();
+        let names: Vec<&str> = children.iter().map(|symbol| symbol.name.as_str()).collect();
+        assert_eq!(names, ["Goals", "Risks"]);
+        assert_eq!(children[0].children.as_ref().unwrap()[0].name, "Stretch");
+        assert_eq!(children[0].range.end, Position::new(8, 0));
+    }
+
+    #[test]
+    fn serves_requests_over_a_connection() {
+        let (dir, mut server) = server();
+        let (client, connection) = Connection::memory();
+        let handle = std::thread::spawn(move || server.serve(&connection));
+
+        let send = |id: i32, method: &str, params: Value| {
+            client
+                .sender
+                .send(Request::new(id.into(), method.to_string(), params).into())
+                .unwrap();
+            match client.receiver.recv().unwrap() {
+                Message::Response(response) => response,
+                other => panic!("unexpected message {other:?}"),
+            }
+        };
+        let initialized = send(1, "initialize", json!({ "capabilities": {} }));
+        assert_eq!(
+            initialized.result.unwrap()["capabilities"]["renameProvider"],
+            true
+        );
+        client
+            .sender
+            .send(Notification::new("initialized".to_string(), json!({})).into())
+            .unwrap();
+
+        let uri = path_to_uri(&dir.path().join("Journal.md"));
+        client
+            .sender
+            .send(
+                Notification::new(
+                    DidOpenTextDocument::METHOD.to_string(),
+                    json!({ "textDocument": {
+                        "uri": uri, "languageId": "markdown", "version": 1,
+                        "text": "[[Nope]]\n# Title\n",
+                    }}),
+                )
+                .into(),
+            )
+            .unwrap();
+        let Message::Notification(published) = client.receiver.recv().unwrap() else {
+            panic!("expected diagnostics");
+        };
+        assert_eq!(published.method, PublishDiagnostics::METHOD);
+        assert_eq!(published.params["diagnostics"].as_array().unwrap().len(), 1);
+
+        let symbols = send(
+            2,
+            DocumentSymbolRequest::METHOD,
+            json!({ "textDocument": { "uri": uri } }),
+        );
+        assert_eq!(symbols.result.unwrap()[0]["name"], "Title");
+
+        let unknown = send(3, "textDocument/hover", json!({}));
+        assert_eq!(
+            unknown.error.unwrap().code,
+            ErrorCode::MethodNotFound as i32
+        );
+
+        send(4, "shutdown", Value::Null);
+        client
+            .sender
+            .send(Notification::new("exit".to_string(), Value::Null).into())
+            .unwrap();
+        handle.join().unwrap().unwrap();
+    }
+}
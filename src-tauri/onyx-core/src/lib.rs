@@ -0,0 +1,53 @@
+//! Vault logic behind the Onyx app, usable without Tauri.
+//!
+//! [`VaultHandle`] is the entry point for working with an open vault and its indexes; the
+//! remaining modules expose the underlying operations (import, export, parsing, config) as
+//! plain functions over paths. Every fallible call returns [`OnyxError`].
+
+pub mod api_server;
+pub mod attachments;
+pub mod backlinks;
+pub mod book;
+pub mod buffer;
+pub mod cache;
+pub mod canvas;
+pub mod dates;
+pub mod deep_link;
+pub mod error;
+pub mod export_docx;
+pub mod export_epub;
+pub mod export_html;
+pub mod export_pdf;
+pub mod file_tree;
+pub mod files;
+pub mod frontmatter;
+pub mod global_config;
+pub mod graph;
+pub mod import_bear;
+pub mod import_evernote;
+pub mod import_notion;
+pub mod importer;
+pub mod language_server;
+pub mod link_resolver;
+pub mod markdown;
+pub mod markdown_ast;
+pub mod mcp_server;
+pub mod obsidian_import;
+pub mod pdf_annotations;
+pub mod pdf_text;
+pub mod pdf_writer;
+pub mod query;
+pub mod recovery;
+pub mod search;
+pub mod site;
+pub mod tag_index;
+pub mod task_index;
+pub mod theme;
+pub mod thumbnails;
+pub mod vault;
+pub mod vault_config;
+pub mod vault_handle;
+pub mod vault_health;
+
+pub use error::OnyxError;
+pub use vault_handle::VaultHandle;
@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Case-insensitive lookup of vault files by note stem and by file name, built from a single walk.
+#[derive(Debug, Default)]
+pub struct LinkResolver {
+    root: PathBuf,
+    notes_by_stem: HashMap<String, PathBuf>,
+    notes_by_relative_path: HashMap<String, PathBuf>,
+    files_by_name: HashMap<String, PathBuf>,
+}
+
+impl LinkResolver {
+    /// Walks every non-hidden file under `vault_root` and records it for lookup.
+    pub fn build(vault_root: &Path) -> Self {
+        let paths = walkdir::WalkDir::new(vault_root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !is_hidden(entry.file_name()))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(walkdir::DirEntry::into_path);
+        Self::from_paths(vault_root, paths)
+    }
+
+    /// Builds a resolver from an explicit list of files; the first path wins on name clashes.
+    pub fn from_paths(vault_root: &Path, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut resolver = Self {
+            root: vault_root.to_path_buf(),
+            ..Self::default()
+        };
+        for path in paths {
+            resolver.insert(path);
+        }
+        resolver
+    }
+
+    /// Records one more file, keeping any earlier entry with the same stem or name.
+    pub fn insert(&mut self, path: PathBuf) {
+        if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+            self.files_by_name
+                .entry(name.to_lowercase())
+                .or_insert_with(|| path.clone());
+        }
+        if !is_markdown(&path) {
+            return;
+        }
+        if let Ok(relative) = path.with_extension("").strip_prefix(&self.root) {
+            let key = relative.to_string_lossy().replace('\\', "/").to_lowercase();
+            self.notes_by_relative_path.insert(key, path.clone());
+        }
+        if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+            self.notes_by_stem
+                .entry(stem.to_lowercase())
+                .or_insert(path);
+        }
+    }
+
+    /// Resolves the note part of a `[[wikilink]]` (`Note`, `Note.md` or `folder/Note`).
+    pub fn resolve_note(&self, target: &str) -> Option<&Path> {
+        let target = target.trim();
+        let without_ext = target
+            .strip_suffix(".md")
+            .or_else(|| target.strip_suffix(".MD"))
+            .unwrap_or(target);
+        if without_ext.contains('/') {
+            let key = without_ext.trim_start_matches('/').to_lowercase();
+            return self.notes_by_relative_path.get(&key).map(PathBuf::as_path);
+        }
+        self.notes_by_stem
+            .get(&without_ext.to_lowercase())
+            .map(PathBuf::as_path)
+    }
+
+    /// Resolves any vault file by its full name, e.g. `photo.png` or `paper.pdf`.
+    pub fn resolve_file(&self, name: &str) -> Option<&Path> {
+        self.files_by_name
+            .get(&name.trim().to_lowercase())
+            .map(PathBuf::as_path)
+    }
+
+    /// Resolves an embed or image specifier the same way the editor does: bare names are looked
+    /// up across the vault, anything with a path is taken relative to the note's directory.
+    /// Paths that lead outside the vault never resolve.
+    pub fn resolve_asset(&self, note_path: &Path, specifier: &str) -> Option<PathBuf> {
+        let specifier = urlencoding::decode(specifier)
+            .map(|decoded| decoded.into_owned())
+            .unwrap_or_else(|_| specifier.to_string());
+        let is_bare_name = !specifier.contains('/') && !specifier.starts_with('.');
+        if is_bare_name {
+            if let Some(path) = self.resolve_file(&specifier) {
+                return Some(path.to_path_buf());
+            }
+        }
+        let root = normalize_path(&self.root);
+        let base = note_path.parent().unwrap_or(&self.root);
+        [base.join(&specifier), self.root.join(&specifier)]
+            .into_iter()
+            .map(|candidate| normalize_path(&candidate))
+            .find(|candidate| candidate.starts_with(&root) && candidate.is_file())
+    }
+}
+
+/// Lexically removes `.` and `..` components so the same file always maps to the same path.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Whether the path points at a markdown note.
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+fn is_hidden(name: &std::ffi::OsStr) -> bool {
+    name.to_str().is_some_and(|name| name.starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("notes")).unwrap();
+        std::fs::create_dir_all(root.join("media")).unwrap();
+        std::fs::create_dir_all(root.join(".onyx")).unwrap();
+        std::fs::write(root.join("notes/Meeting.md"), "").unwrap();
+        std::fs::write(root.join("media/photo.png"), "png").unwrap();
+        std::fs::write(root.join(".onyx/hidden.md"), "").unwrap();
+        temp
+    }
+
+    #[test]
+    fn resolve_note_is_case_insensitive() {
+        let temp = setup_vault();
+        let resolver = LinkResolver::build(temp.path());
+        let found = resolver.resolve_note("meeting").unwrap();
+        assert!(found.ends_with("notes/Meeting.md"));
+        assert!(resolver.resolve_note("Meeting.md").is_some());
+        assert!(resolver.resolve_note("notes/meeting").is_some());
+    }
+
+    #[test]
+    fn build_skips_hidden_directories() {
+        let temp = setup_vault();
+        let resolver = LinkResolver::build(temp.path());
+        assert!(resolver.resolve_note("hidden").is_none());
+    }
+
+    #[test]
+    fn resolve_asset_finds_bare_names_anywhere() {
+        let temp = setup_vault();
+        let resolver = LinkResolver::build(temp.path());
+        let note = temp.path().join("notes/Meeting.md");
+        let found = resolver.resolve_asset(&note, "photo.png").unwrap();
+        assert!(found.ends_with("media/photo.png"));
+    }
+
+    #[test]
+    fn resolve_asset_resolves_relative_paths() {
+        let temp = setup_vault();
+        let resolver = LinkResolver::build(temp.path());
+        let note = temp.path().join("notes/Meeting.md");
+        let found = resolver.resolve_asset(&note, "../media/photo.png").unwrap();
+        assert_eq!(found, temp.path().join("media/photo.png"));
+        assert!(resolver
+            .resolve_asset(&note, "../media/ghost.png")
+            .is_none());
+    }
+
+    #[test]
+    fn resolve_asset_stays_inside_the_vault() {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        std::fs::create_dir_all(vault.join("notes")).unwrap();
+        std::fs::write(temp.path().join("secret.txt"), "outside").unwrap();
+        let resolver = LinkResolver::build(&vault);
+        let note = vault.join("notes/Meeting.md");
+        assert!(resolver.resolve_asset(&note, "../../secret.txt").is_none());
+        let absolute = temp.path().join("secret.txt");
+        assert!(resolver
+            .resolve_asset(&note, &absolute.to_string_lossy())
+            .is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use pulldown_cmark::{CowStr, Event, Options, Parser, Tag, TagEnd};
+
+/// Parser options shared by every renderer so notes look the same in each export format.
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_WIKILINKS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+}
+
+/// Parses `content` into a fully materialised event list with heading ids filled in.
+pub fn parse_events(content: &str) -> Vec<Event<'_>> {
+    let events: Vec<Event<'_>> = Parser::new_ext(content, parser_options()).collect();
+    with_heading_ids(events)
+}
+
+/// Splits a link target such as `Note#Heading` into the note part and the optional fragment.
+pub fn split_link_target(target: &str) -> (&str, Option<&str>) {
+    match target.split_once('#') {
+        Some((note, fragment)) if !fragment.is_empty() => (note, Some(fragment)),
+        Some((note, _)) => (note, None),
+        None => (target, None),
+    }
+}
+
+/// Converts heading text into the anchor id used for `#heading` fragments.
+pub fn heading_slug(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    for character in text.trim().chars() {
+        if character.is_alphanumeric() {
+            slug.extend(character.to_lowercase());
+        } else if (character.is_whitespace() || character == '-' || character == '_')
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Collects the plain text of the events between a `Start` and its matching `End`.
+pub fn inner_text(events: &[Event<'_>]) -> String {
+    let mut text = String::new();
+    for event in events {
+        match event {
+            Event::Text(value) | Event::Code(value) => text.push_str(value),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Extracts the readable text of a note (no markup, no frontmatter), one block per line.
+pub fn plain_text(content: &str) -> String {
+    let mut text = String::new();
+    let mut in_metadata = false;
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => in_metadata = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_metadata = false,
+            Event::Text(value) | Event::Code(value) if !in_metadata => text.push_str(&value),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableRow,
+            ) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text.trim_end().to_string()
+}
+
+/// Escapes text for safe inclusion in HTML or XML content and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Assigns a slug id to every heading that doesn't declare one explicitly.
+fn with_heading_ids(mut events: Vec<Event<'_>>) -> Vec<Event<'_>> {
+    let mut index = 0;
+    while index < events.len() {
+        if let Event::Start(Tag::Heading { id: None, .. }) = &events[index] {
+            let end = events[index..]
+                .iter()
+                .position(|event| matches!(event, Event::End(TagEnd::Heading(_))))
+                .map_or(events.len(), |offset| index + offset);
+            let slug = heading_slug(&inner_text(&events[index + 1..end]));
+            if let Event::Start(Tag::Heading { id, .. }) = &mut events[index] {
+                *id = Some(CowStr::from(slug));
+            }
+        }
+        index += 1;
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_link_target_separates_fragment() {
+        assert_eq!(split_link_target("Note#Intro"), ("Note", Some("Intro")));
+        assert_eq!(split_link_target("Note#"), ("Note", None));
+        assert_eq!(split_link_target("Note"), ("Note", None));
+    }
+
+    #[test]
+    fn heading_slug_lowercases_and_dashes() {
+        assert_eq!(heading_slug("Hello, World!"), "hello-world");
+        assert_eq!(heading_slug("  Two  Spaces "), "two-spaces");
+        assert_eq!(heading_slug("snake_case-title"), "snake-case-title");
+    }
+
+    #[test]
+    fn escape_html_escapes_markup() {
+        assert_eq!(
+            escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn parse_events_assigns_heading_ids() {
+        let events = parse_events("# My Heading\n\ntext");
+        let id = events.iter().find_map(|event| match event {
+            Event::Start(Tag::Heading { id, .. }) => id.clone(),
+            _ => None,
+        });
+        assert_eq!(id.as_deref(), Some("my-heading"));
+    }
+
+    #[test]
+    fn plain_text_strips_markup_and_frontmatter() {
+        let text = plain_text("---\ntitle: x\n---\n# Title\n\nSome **bold** `code`.\n");
+        assert_eq!(text, "Title\nSome bold code.");
+    }
+
+    #[test]
+    fn parse_events_recognises_wikilinks() {
+        let events = parse_events("See [[Other Note|other]]");
+        let has_wikilink = events.iter().any(|event| {
+            matches!(
+                event,
+                Event::Start(Tag::Link { dest_url, .. }) if dest_url.as_ref() == "Other Note"
+            )
+        });
+        assert!(has_wikilink);
+    }
+}
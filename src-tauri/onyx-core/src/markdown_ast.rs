@@ -0,0 +1,428 @@
+use pulldown_cmark::{Event, LinkType, Tag, TagEnd};
+
+use crate::markdown::parse_events;
+
+/// Block-level node of a parsed note.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        id: Option<String>,
+        content: Vec<Inline>,
+    },
+    Paragraph(Vec<Inline>),
+    List {
+        /// First number of an ordered list; `None` for bullets.
+        start: Option<u64>,
+        items: Vec<ListItem>,
+    },
+    Quote(Vec<Block>),
+    Code {
+        language: Option<String>,
+        text: String,
+    },
+    Table {
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+    Footnote {
+        label: String,
+        blocks: Vec<Block>,
+    },
+    Rule,
+}
+
+/// One entry of a list; `checked` is set for task list items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    pub checked: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+/// Inline node inside a paragraph, heading or table cell.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Code(String),
+    Strong(Vec<Inline>),
+    Emphasis(Vec<Inline>),
+    Strikethrough(Vec<Inline>),
+    Link {
+        /// Destination as written, e.g. `Other note#Heading` for a wikilink.
+        dest: String,
+        wikilink: bool,
+        content: Vec<Inline>,
+    },
+    Image {
+        dest: String,
+        alt: String,
+    },
+    FootnoteReference(String),
+    LineBreak,
+}
+
+/// Plain text of a run of inlines, as used for headings, alt text and table of contents entries.
+pub fn inline_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text(value) | Inline::Code(value) => text.push_str(value),
+            Inline::Strong(children)
+            | Inline::Emphasis(children)
+            | Inline::Strikethrough(children)
+            | Inline::Link {
+                content: children, ..
+            } => text.push_str(&inline_text(children)),
+            Inline::Image { alt, .. } => text.push_str(alt),
+            Inline::FootnoteReference(label) => text.push_str(&format!("[{label}]")),
+            Inline::LineBreak => text.push(' '),
+        }
+    }
+    text
+}
+
+/// Open container while the event stream is folded into a tree.
+enum Frame {
+    Blocks(BlockKind, Vec<Block>),
+    Inlines(InlineKind, Vec<Inline>),
+    List(Option<u64>, Vec<ListItem>),
+    Table {
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+        in_head: bool,
+    },
+    Row(Vec<Vec<Inline>>),
+    Code(Option<String>, String),
+    Skip,
+}
+
+enum BlockKind {
+    Root,
+    Quote,
+    Item(Option<bool>),
+    Footnote(String),
+}
+
+enum InlineKind {
+    Paragraph,
+    /// Text of a tight list item, which pulldown-cmark emits without a paragraph.
+    ImplicitParagraph,
+    Heading(u8, Option<String>),
+    Cell,
+    Strong,
+    Emphasis,
+    Strikethrough,
+    Link(String, bool),
+    Image(String),
+}
+
+/// Parses a note (frontmatter included) into a block tree.
+pub fn parse_document(content: &str) -> Vec<Block> {
+    let mut stack = vec![Frame::Blocks(BlockKind::Root, Vec::new())];
+
+    for event in parse_events(content) {
+        match event {
+            Event::Start(tag) => {
+                if is_block_tag(&tag) {
+                    close_implicit_paragraph(&mut stack);
+                }
+                let frame = match tag {
+                    Tag::Paragraph => Frame::Inlines(InlineKind::Paragraph, Vec::new()),
+                    Tag::Heading { level, id, .. } => Frame::Inlines(
+                        InlineKind::Heading(level as u8, id.map(|id| id.to_string())),
+                        Vec::new(),
+                    ),
+                    Tag::BlockQuote(_) => Frame::Blocks(BlockKind::Quote, Vec::new()),
+                    Tag::CodeBlock(kind) => {
+                        let language = match kind {
+                            pulldown_cmark::CodeBlockKind::Fenced(info) => info
+                                .split_whitespace()
+                                .next()
+                                .map(|language| language.to_string()),
+                            pulldown_cmark::CodeBlockKind::Indented => None,
+                        };
+                        Frame::Code(language, String::new())
+                    }
+                    Tag::List(start) => Frame::List(start, Vec::new()),
+                    Tag::Item => Frame::Blocks(BlockKind::Item(None), Vec::new()),
+                    Tag::FootnoteDefinition(label) => {
+                        Frame::Blocks(BlockKind::Footnote(label.to_string()), Vec::new())
+                    }
+                    Tag::Table(_) => Frame::Table {
+                        header: Vec::new(),
+                        rows: Vec::new(),
+                        in_head: false,
+                    },
+                    Tag::TableHead => {
+                        if let Some(Frame::Table { in_head, .. }) = stack.last_mut() {
+                            *in_head = true;
+                        }
+                        Frame::Row(Vec::new())
+                    }
+                    Tag::TableRow => Frame::Row(Vec::new()),
+                    Tag::TableCell => Frame::Inlines(InlineKind::Cell, Vec::new()),
+                    Tag::Emphasis => Frame::Inlines(InlineKind::Emphasis, Vec::new()),
+                    Tag::Strong => Frame::Inlines(InlineKind::Strong, Vec::new()),
+                    Tag::Strikethrough => Frame::Inlines(InlineKind::Strikethrough, Vec::new()),
+                    Tag::Link {
+                        link_type,
+                        dest_url,
+                        ..
+                    } => Frame::Inlines(
+                        InlineKind::Link(
+                            dest_url.to_string(),
+                            matches!(link_type, LinkType::WikiLink { .. }),
+                        ),
+                        Vec::new(),
+                    ),
+                    Tag::Image { dest_url, .. } => {
+                        Frame::Inlines(InlineKind::Image(dest_url.to_string()), Vec::new())
+                    }
+                    _ => Frame::Skip,
+                };
+                stack.push(frame);
+            }
+            Event::End(tag) => {
+                if matches!(
+                    tag,
+                    TagEnd::Item | TagEnd::BlockQuote(_) | TagEnd::FootnoteDefinition
+                ) {
+                    close_implicit_paragraph(&mut stack);
+                }
+                close_frame(&mut stack);
+            }
+            Event::Text(text) => match stack.last_mut() {
+                Some(Frame::Code(_, code)) => code.push_str(&text),
+                Some(Frame::Skip) => {}
+                _ => push_inline(&mut stack, Inline::Text(text.to_string())),
+            },
+            Event::Code(code) | Event::InlineMath(code) | Event::DisplayMath(code) => {
+                push_inline(&mut stack, Inline::Code(code.to_string()));
+            }
+            Event::FootnoteReference(label) => {
+                push_inline(&mut stack, Inline::FootnoteReference(label.to_string()));
+            }
+            Event::SoftBreak => push_inline(&mut stack, Inline::Text(" ".to_string())),
+            Event::HardBreak => push_inline(&mut stack, Inline::LineBreak),
+            Event::TaskListMarker(checked) => {
+                if let Some(Frame::Blocks(kind @ BlockKind::Item(_), _)) = stack.last_mut() {
+                    *kind = BlockKind::Item(Some(checked));
+                }
+            }
+            Event::Rule => {
+                close_implicit_paragraph(&mut stack);
+                push_block(&mut stack, Block::Rule);
+            }
+            _ => {}
+        }
+    }
+
+    while stack.len() > 1 {
+        close_frame(&mut stack);
+    }
+    match stack.pop() {
+        Some(Frame::Blocks(_, blocks)) => blocks,
+        _ => Vec::new(),
+    }
+}
+
+fn is_block_tag(tag: &Tag<'_>) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::FootnoteDefinition(_)
+            | Tag::Table(_)
+            | Tag::HtmlBlock
+            | Tag::MetadataBlock(_)
+    )
+}
+
+fn close_implicit_paragraph(stack: &mut Vec<Frame>) {
+    if matches!(
+        stack.last(),
+        Some(Frame::Inlines(InlineKind::ImplicitParagraph, _))
+    ) {
+        close_frame(stack);
+    }
+}
+
+/// Adds an inline to the innermost inline container, opening an implicit paragraph if needed.
+fn push_inline(stack: &mut Vec<Frame>, inline: Inline) {
+    if matches!(stack.last(), Some(Frame::Blocks(..))) {
+        stack.push(Frame::Inlines(InlineKind::ImplicitParagraph, Vec::new()));
+    }
+    if let Some(Frame::Inlines(_, inlines)) = stack.last_mut() {
+        inlines.push(inline);
+    }
+}
+
+fn push_block(stack: &mut [Frame], block: Block) {
+    match stack.last_mut() {
+        Some(Frame::Blocks(_, blocks)) => blocks.push(block),
+        Some(Frame::List(_, items)) => {
+            // Content outside any item cannot occur in valid markdown; keep it rather than drop it.
+            items.push(ListItem {
+                checked: None,
+                blocks: vec![block],
+            });
+        }
+        _ => {}
+    }
+}
+
+/// Pops the innermost frame and attaches what it built to its parent.
+fn close_frame(stack: &mut Vec<Frame>) {
+    let Some(frame) = stack.pop() else {
+        return;
+    };
+    match frame {
+        Frame::Blocks(BlockKind::Root, blocks) => {
+            stack.push(Frame::Blocks(BlockKind::Root, blocks))
+        }
+        Frame::Blocks(BlockKind::Quote, blocks) => push_block(stack, Block::Quote(blocks)),
+        Frame::Blocks(BlockKind::Footnote(label), blocks) => {
+            push_block(stack, Block::Footnote { label, blocks });
+        }
+        Frame::Blocks(BlockKind::Item(checked), blocks) => {
+            if let Some(Frame::List(_, items)) = stack.last_mut() {
+                items.push(ListItem { checked, blocks });
+            }
+        }
+        Frame::List(start, items) => push_block(stack, Block::List { start, items }),
+        Frame::Code(language, text) => push_block(stack, Block::Code { language, text }),
+        Frame::Table { header, rows, .. } => push_block(stack, Block::Table { header, rows }),
+        Frame::Row(cells) => {
+            if let Some(Frame::Table {
+                header,
+                rows,
+                in_head,
+            }) = stack.last_mut()
+            {
+                if *in_head {
+                    *header = cells;
+                    *in_head = false;
+                } else {
+                    rows.push(cells);
+                }
+            }
+        }
+        Frame::Inlines(kind, inlines) => match kind {
+            InlineKind::Paragraph | InlineKind::ImplicitParagraph => {
+                push_block(stack, Block::Paragraph(inlines));
+            }
+            InlineKind::Heading(level, id) => push_block(
+                stack,
+                Block::Heading {
+                    level,
+                    id,
+                    content: inlines,
+                },
+            ),
+            InlineKind::Cell => {
+                if let Some(Frame::Row(cells)) = stack.last_mut() {
+                    cells.push(inlines);
+                }
+            }
+            InlineKind::Strong => push_inline(stack, Inline::Strong(inlines)),
+            InlineKind::Emphasis => push_inline(stack, Inline::Emphasis(inlines)),
+            InlineKind::Strikethrough => push_inline(stack, Inline::Strikethrough(inlines)),
+            InlineKind::Link(dest, wikilink) => push_inline(
+                stack,
+                Inline::Link {
+                    dest,
+                    wikilink,
+                    content: inlines,
+                },
+            ),
+            InlineKind::Image(dest) => push_inline(
+                stack,
+                Inline::Image {
+                    dest,
+                    alt: inline_text(&inlines),
+                },
+            ),
+        },
+        Frame::Skip => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Inline {
+        Inline::Text(value.to_string())
+    }
+
+    #[test]
+    fn parses_headings_with_slug_ids() {
+        let blocks = parse_document("---\ntitle: x\n---\n# Hello *World*\n");
+        assert_eq!(
+            blocks,
+            vec![Block::Heading {
+                level: 1,
+                id: Some("hello-world".to_string()),
+                content: vec![text("Hello "), Inline::Emphasis(vec![text("World")])],
+            }]
+        );
+    }
+
+    #[test]
+    fn tight_and_nested_lists_keep_their_structure() {
+        let blocks = parse_document("- [x] done\n  - child\n- two\n");
+        let Block::List { start, items } = &blocks[0] else {
+            panic!("expected a list, got {blocks:?}");
+        };
+        assert_eq!(*start, None);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].checked, Some(true));
+        assert_eq!(items[0].blocks[0], Block::Paragraph(vec![text("done")]));
+        assert!(matches!(items[0].blocks[1], Block::List { .. }));
+        assert_eq!(items[1].checked, None);
+    }
+
+    #[test]
+    fn parses_wikilinks_images_and_code() {
+        let blocks =
+            parse_document("See [[Other#Part|there]] ![[pic.png]]\n\n```rust\nfn x() {}\n```\n");
+        assert_eq!(
+            blocks[0],
+            Block::Paragraph(vec![
+                text("See "),
+                Inline::Link {
+                    dest: "Other#Part".to_string(),
+                    wikilink: true,
+                    content: vec![text("there")],
+                },
+                text(" "),
+                Inline::Image {
+                    dest: "pic.png".to_string(),
+                    alt: "pic.png".to_string(),
+                },
+            ])
+        );
+        assert_eq!(
+            blocks[1],
+            Block::Code {
+                language: Some("rust".to_string()),
+                text: "fn x() {}\n".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_tables_into_header_and_rows() {
+        let blocks = parse_document("| A | B |\n|---|---|\n| 1 | **2** |\n");
+        assert_eq!(
+            blocks,
+            vec![Block::Table {
+                header: vec![vec![text("A")], vec![text("B")]],
+                rows: vec![vec![vec![text("1")], vec![Inline::Strong(vec![text("2")])]]],
+            }]
+        );
+    }
+}
@@ -0,0 +1,434 @@
+use std::io::{BufRead, Write};
+
+use log::{info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::OnyxError;
+use crate::link_resolver::is_markdown;
+use crate::vault_handle::VaultHandle;
+
+/// Protocol revisions this server speaks, newest first.
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Tools that change files; hidden and refused in read-only mode.
+const WRITE_TOOLS: [&str; 2] = ["create_note", "edit_note"];
+
+/// A Model Context Protocol server exposing one vault as tools, speaking JSON-RPC over a
+/// newline-delimited stream such as stdio.
+pub struct McpServer {
+    vault: VaultHandle,
+    read_only: bool,
+}
+
+/// One replacement applied by `edit_note`.
+#[derive(Debug, Deserialize)]
+struct Edit {
+    old_text: String,
+    new_text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ToolCall {
+    name: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+impl McpServer {
+    pub fn new(vault: VaultHandle, read_only: bool) -> Self {
+        Self { vault, read_only }
+    }
+
+    /// Answers messages from `input` until it closes.
+    pub fn serve(&mut self, input: impl BufRead, mut output: impl Write) -> Result<(), OnyxError> {
+        info!(
+            "MCP server started for {} ({})",
+            self.vault.root().display(),
+            if self.read_only {
+                "read-only"
+            } else {
+                "read-write"
+            }
+        );
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_message(&line) {
+                writeln!(output, "{response}")?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one JSON-RPC message; notifications get no response.
+    pub fn handle_message(&mut self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        let id = message.get("id").cloned()?;
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "initialize" => Ok(self.initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": self.tools() })),
+            "tools/call" => match serde_json::from_value::<ToolCall>(params) {
+                Ok(call) => Ok(self.call_tool(&call)),
+                Err(e) => Err((INVALID_PARAMS, e.to_string())),
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method {method}"))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, &message),
+        })
+    }
+
+    fn initialize(&self, params: &Value) -> Value {
+        let requested = params["protocolVersion"].as_str().unwrap_or_default();
+        let version = PROTOCOL_VERSIONS
+            .into_iter()
+            .find(|version| *version == requested)
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "onyx", "version": env!("CARGO_PKG_VERSION") },
+            "instructions": format!(
+                "Notes of the Onyx vault \"{}\". Paths are relative to the vault root.",
+                self.vault.config().name
+            ),
+        })
+    }
+
+    fn tools(&self) -> Vec<Value> {
+        let path = json!({ "type": "string", "description": "Vault-relative path" });
+        let tools = vec![
+            tool(
+                "list_notes",
+                "List the vault's notes, optionally only those under a folder.",
+                json!({ "folder": { "type": "string", "description": "Vault-relative folder" } }),
+                &[],
+            ),
+            tool(
+                "read_note",
+                "Read a note's markdown.",
+                json!({ "path": path }),
+                &["path"],
+            ),
+            tool(
+                "search",
+                "Find lines in notes and pages in PDFs containing every word of the query.",
+                json!({
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer", "minimum": 1 },
+                }),
+                &["query"],
+            ),
+            tool(
+                "get_backlinks",
+                "List the notes and canvases that link to a file.",
+                json!({ "path": path }),
+                &["path"],
+            ),
+            tool("get_tags", "List every tag in the vault.", json!({}), &[]),
+            tool(
+                "create_note",
+                "Create a new note; fails if it already exists.",
+                json!({ "path": path, "content": { "type": "string" } }),
+                &["path"],
+            ),
+            tool(
+                "edit_note",
+                "Apply replacements to a note. Each old_text must occur exactly once.",
+                json!({
+                    "path": path,
+                    "edits": {
+                        "type": "array",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "old_text": { "type": "string" },
+                                "new_text": { "type": "string" },
+                            },
+                            "required": ["old_text", "new_text"],
+                        },
+                    },
+                }),
+                &["path", "edits"],
+            ),
+        ];
+        tools
+            .into_iter()
+            .filter(|tool| {
+                !(self.read_only
+                    && WRITE_TOOLS.contains(&tool["name"].as_str().unwrap_or_default()))
+            })
+            .collect()
+    }
+
+    /// Runs a tool; failures are reported to the model as error results, not protocol errors.
+    fn call_tool(&mut self, call: &ToolCall) -> Value {
+        let outcome = if self.read_only && WRITE_TOOLS.contains(&call.name.as_str()) {
+            Err(format!("{} is unavailable in read-only mode", call.name))
+        } else {
+            self.run_tool(&call.name, &call.arguments)
+        };
+        let (text, is_error) = match outcome {
+            Ok(text) => (text, false),
+            Err(message) => {
+                warn!("MCP tool {} failed: {message}", call.name);
+                (message, true)
+            }
+        };
+        json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+    }
+
+    fn run_tool(&mut self, name: &str, arguments: &Value) -> Result<String, String> {
+        let string = |key: &str| {
+            arguments[key]
+                .as_str()
+                .ok_or_else(|| format!("Missing string argument {key}"))
+        };
+        match name {
+            "list_notes" => {
+                let folder = arguments["folder"].as_str().unwrap_or_default();
+                let folder = folder.trim_matches('/');
+                let notes: Vec<String> = self
+                    .vault
+                    .list_files()
+                    .into_iter()
+                    .filter(|path| is_markdown(path.as_ref()))
+                    .filter(|path| folder.is_empty() || path.starts_with(&format!("{folder}/")))
+                    .collect();
+                Ok(notes.join("\n"))
+            }
+            "read_note" => self.vault.read_file(string("path")?).map_err(message),
+            "search" => {
+                let limit = arguments["limit"].as_u64().unwrap_or(50) as usize;
+                let hits = self
+                    .vault
+                    .search(string("query")?, limit)
+                    .map_err(message)?;
+                to_text(&hits)
+            }
+            "get_backlinks" => {
+                let target = self.vault.resolve(string("path")?).map_err(message)?;
+                to_text(&self.vault.backlinks(&target).map_err(message)?)
+            }
+            "get_tags" => Ok(self.vault.tags().map_err(message)?.join("\n")),
+            "create_note" => {
+                let path = string("path")?;
+                let path = if is_markdown(path.as_ref()) {
+                    path.to_string()
+                } else {
+                    format!("{path}.md")
+                };
+                if self.vault.resolve(&path).map_err(message)?.exists() {
+                    return Err(format!("{path} already exists"));
+                }
+                let content = arguments["content"].as_str().unwrap_or_default();
+                self.vault.write_file(&path, content).map_err(message)?;
+                Ok(format!("Created {path}"))
+            }
+            "edit_note" => {
+                let path = string("path")?;
+                let edits: Vec<Edit> =
+                    serde_json::from_value(arguments["edits"].clone()).map_err(message)?;
+                let content = apply_edits(&self.vault.read_file(path).map_err(message)?, &edits)?;
+                self.vault.write_file(path, &content).map_err(message)?;
+                Ok(format!("Applied {} edit(s) to {path}", edits.len()))
+            }
+            _ => Err(format!("Unknown tool {name}")),
+        }
+    }
+}
+
+/// Applies every edit in order; nothing is written unless all of them match exactly once.
+fn apply_edits(content: &str, edits: &[Edit]) -> Result<String, String> {
+    let mut content = content.to_string();
+    for (index, edit) in edits.iter().enumerate() {
+        match content.matches(&edit.old_text).count() {
+            1 => content = content.replacen(&edit.old_text, &edit.new_text, 1),
+            0 => return Err(format!("Edit {}: old_text not found", index + 1)),
+            count => {
+                return Err(format!(
+                    "Edit {}: old_text occurs {count} times; include more context",
+                    index + 1
+                ))
+            }
+        }
+    }
+    Ok(content)
+}
+
+fn tool(name: &str, description: &str, properties: Value, required: &[&str]) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "inputSchema": { "type": "object", "properties": properties, "required": required },
+    })
+}
+
+fn to_text(value: &impl serde::Serialize) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(message)
+}
+
+fn message(error: impl std::fmt::Display) -> String {
+    error.to_string()
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn server(read_only: bool) -> (TempDir, McpServer) {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("Projects")).unwrap();
+        std::fs::write(
+            dir.path().join("Projects/Plan.md"),
+            "# Plan #work\n\nShip it.\n",
+        )
+        .unwrap();
+        let vault = VaultHandle::open(dir.path()).unwrap();
+        (dir, McpServer::new(vault, read_only))
+    }
+
+    fn call(server: &mut McpServer, name: &str, arguments: Value) -> (String, bool) {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments },
+        });
+        let response = server.handle_message(&request.to_string()).unwrap();
+        let result = &response["result"];
+        (
+            result["content"][0]["text"].as_str().unwrap().to_string(),
+            result["isError"].as_bool().unwrap(),
+        )
+    }
+
+    #[test]
+    fn speaks_json_rpc_over_lines() {
+        let (_dir, mut server) = server(false);
+        let input = concat!(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+            "\n",
+            r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+            "\n",
+            "not json\n",
+            r#"{"jsonrpc":"2.0","id":2,"method":"resources/list"}"#,
+            "\n",
+        );
+        let mut output = Vec::new();
+
+        server.serve(input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2024-11-05");
+        assert_eq!(responses[1]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[2]["id"], 2);
+        assert_eq!(responses[2]["error"]["code"], METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn notes_can_be_listed_created_edited_and_read() {
+        let (dir, mut server) = server(false);
+
+        assert_eq!(
+            call(&mut server, "list_notes", json!({ "folder": "Projects" })).0,
+            "Projects/Plan.md"
+        );
+        assert!(
+            !call(
+                &mut server,
+                "create_note",
+                json!({ "path": "Inbox/Idea", "content": "Draft #idea" })
+            )
+            .1
+        );
+        let (text, is_error) = call(
+            &mut server,
+            "edit_note",
+            json!({ "path": "Inbox/Idea.md", "edits": [{ "old_text": "Draft", "new_text": "Final" }] }),
+        );
+        assert!(!is_error, "{text}");
+
+        assert_eq!(
+            call(&mut server, "read_note", json!({ "path": "Inbox/Idea.md" })).0,
+            "Final #idea"
+        );
+        assert_eq!(call(&mut server, "get_tags", json!({})).0, "idea\nwork");
+        assert!(
+            call(
+                &mut server,
+                "create_note",
+                json!({ "path": "Inbox/Idea.md" })
+            )
+            .1
+        );
+        let ambiguous = call(
+            &mut server,
+            "edit_note",
+            json!({ "path": "Projects/Plan.md", "edits": [{ "old_text": "i", "new_text": "" }] }),
+        );
+        assert!(ambiguous.1);
+        assert!(std::fs::read_to_string(dir.path().join("Projects/Plan.md"))
+            .unwrap()
+            .contains("Ship it."));
+    }
+
+    #[test]
+    fn paths_outside_the_vault_are_tool_errors() {
+        let (dir, mut server) = server(false);
+
+        let (_, is_error) = call(
+            &mut server,
+            "create_note",
+            json!({ "path": "../escape.md" }),
+        );
+
+        assert!(is_error);
+        assert!(!dir.path().parent().unwrap().join("escape.md").exists());
+    }
+
+    #[test]
+    fn read_only_mode_hides_and_refuses_write_tools() {
+        let (dir, mut server) = server(true);
+
+        let listed = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#)
+            .unwrap();
+        let names: Vec<&str> = listed["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        let (_, is_error) = call(&mut server, "create_note", json!({ "path": "New.md" }));
+
+        assert!(!names.contains(&"create_note") && names.contains(&"read_note"));
+        assert!(is_error);
+        assert!(!dir.path().join("New.md").exists());
+    }
+}
@@ -0,0 +1,514 @@
+use std::path::Path;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::OnyxError;
+use crate::vault::Vault;
+use crate::vault_config::{
+    load_vault_session, save_vault_config, save_vault_session, AttachmentsFolder, DailyNotesConfig,
+    VaultConfig,
+};
+
+/// `app.json` keys that map onto `VaultConfig`.
+const MAPPED_APP_KEYS: [&str; 3] = [
+    "attachmentFolderPath",
+    "newFileLocation",
+    "newFileFolderPath",
+];
+
+/// Files under `.obsidian/` that are read by the importer (or are plugin state handled separately).
+const HANDLED_FILES: [&str; 7] = [
+    "app.json",
+    "daily-notes.json",
+    "templates.json",
+    "bookmarks.json",
+    "starred.json",
+    "community-plugins.json",
+    "plugins",
+];
+
+/// A setting or piece of plugin data that has no Onyx equivalent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UntranslatedSetting {
+    /// Where the setting came from, relative to `.obsidian/`.
+    pub source: String,
+    pub key: String,
+    pub reason: String,
+}
+
+/// What an Obsidian import carried over and what it had to leave behind.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObsidianImportReport {
+    pub name: String,
+    pub root: String,
+    pub config: VaultConfig,
+    /// Human-readable descriptions of the settings that were migrated.
+    pub imported: Vec<String>,
+    pub untranslated: Vec<UntranslatedSetting>,
+}
+
+/// Opens an Obsidian vault in Onyx, migrating what it can from `.obsidian/` into `.onyx/`.
+pub fn import_vault(path: &Path) -> Result<ObsidianImportReport, OnyxError> {
+    let obsidian_dir = path.join(".obsidian");
+    if !obsidian_dir.is_dir() {
+        return Err(OnyxError::NotObsidianVault(path.to_path_buf()));
+    }
+    let vault = Vault::open(path)?;
+    let mut importer = Importer {
+        root: path,
+        obsidian_dir: &obsidian_dir,
+        config: vault.config,
+        imported: Vec::new(),
+        untranslated: Vec::new(),
+    };
+
+    importer.app_settings()?;
+    importer.daily_notes()?;
+    importer.templates()?;
+    let bookmarks = importer.bookmarks()?;
+    importer.plugins()?;
+    importer.other_files()?;
+
+    save_vault_config(path, &importer.config)?;
+    if !bookmarks.is_empty() {
+        let mut session = load_vault_session(path)?;
+        let before = session.bookmarks.len();
+        for bookmark in bookmarks {
+            if !session.bookmarks.contains(&bookmark) {
+                session.bookmarks.push(bookmark);
+            }
+        }
+        importer
+            .imported
+            .push(format!("{} bookmarks", session.bookmarks.len() - before));
+        save_vault_session(path, &session)?;
+    }
+
+    info!(
+        "Imported Obsidian vault at {} ({} settings left behind)",
+        path.display(),
+        importer.untranslated.len()
+    );
+    Ok(ObsidianImportReport {
+        name: importer.config.name.clone(),
+        root: path.to_string_lossy().to_string(),
+        config: importer.config,
+        imported: importer.imported,
+        untranslated: importer.untranslated,
+    })
+}
+
+struct Importer<'a> {
+    root: &'a Path,
+    obsidian_dir: &'a Path,
+    config: VaultConfig,
+    imported: Vec<String>,
+    untranslated: Vec<UntranslatedSetting>,
+}
+
+impl Importer<'_> {
+    /// Reads a JSON file from `.obsidian/`; missing files yield `None`, malformed ones are reported.
+    fn read_json(&mut self, name: &str) -> Result<Option<Value>, OnyxError> {
+        let path = self.obsidian_dir.join(name);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(&path)?;
+        match serde_json::from_str(&contents) {
+            Ok(value) => Ok(Some(value)),
+            Err(error) => {
+                warn!("Skipping unreadable {}: {error}", path.display());
+                self.skip(name, "*", &format!("could not be parsed: {error}"));
+                Ok(None)
+            }
+        }
+    }
+
+    fn read_object(&mut self, name: &str) -> Result<Option<Map<String, Value>>, OnyxError> {
+        Ok(match self.read_json(name)? {
+            Some(Value::Object(map)) => Some(map),
+            Some(_) => {
+                self.skip(name, "*", "expected a JSON object");
+                None
+            }
+            None => None,
+        })
+    }
+
+    fn skip(&mut self, source: &str, key: &str, reason: &str) {
+        self.untranslated.push(UntranslatedSetting {
+            source: source.to_string(),
+            key: key.to_string(),
+            reason: reason.to_string(),
+        });
+    }
+
+    fn app_settings(&mut self) -> Result<(), OnyxError> {
+        let Some(app) = self.read_object("app.json")? else {
+            return Ok(());
+        };
+        if let Some(folder) = app.get("attachmentFolderPath").and_then(Value::as_str) {
+            self.config.attachments_folder = attachments_folder(folder);
+            self.imported.push(format!(
+                "attachment folder: {}",
+                match &self.config.attachments_folder {
+                    AttachmentsFolder::VaultRoot => "vault root".to_string(),
+                    AttachmentsFolder::Folder(folder) => folder.clone(),
+                    AttachmentsFolder::NextToNote(folder) => format!("./{folder}"),
+                }
+            ));
+        }
+        match app.get("newFileLocation").and_then(Value::as_str) {
+            Some("folder") => {
+                let folder = app
+                    .get("newFileFolderPath")
+                    .and_then(Value::as_str)
+                    .and_then(vault_folder);
+                self.imported.push(format!(
+                    "new note folder: {}",
+                    folder.as_deref().unwrap_or("vault root")
+                ));
+                self.config.new_note_folder = folder;
+            }
+            Some("current") => self.skip(
+                "app.json",
+                "newFileLocation",
+                "creating notes next to the current file is not supported; new notes go to the vault root",
+            ),
+            Some(_) | None => {}
+        }
+
+        for key in app.keys() {
+            if MAPPED_APP_KEYS.contains(&key.as_str()) {
+                continue;
+            }
+            let reason = if key == "vimMode" {
+                "vim mode is an application-wide Onyx setting; enable it in Settings"
+            } else {
+                "no equivalent Onyx setting"
+            };
+            self.skip("app.json", key, reason);
+        }
+        Ok(())
+    }
+
+    fn daily_notes(&mut self) -> Result<(), OnyxError> {
+        let Some(settings) = self.read_object("daily-notes.json")? else {
+            return Ok(());
+        };
+        let folder = settings
+            .get("folder")
+            .and_then(Value::as_str)
+            .and_then(vault_folder);
+        let format = settings
+            .get("format")
+            .and_then(Value::as_str)
+            .filter(|format| !format.trim().is_empty())
+            .unwrap_or("YYYY-MM-DD")
+            .to_string();
+        let template = settings
+            .get("template")
+            .and_then(Value::as_str)
+            .and_then(note_path);
+        self.imported.push(format!(
+            "daily notes in {} named {format}",
+            folder.as_deref().unwrap_or("vault root")
+        ));
+        self.config.daily_notes = Some(DailyNotesConfig {
+            folder,
+            format,
+            template,
+        });
+        for key in settings.keys() {
+            if !["folder", "format", "template"].contains(&key.as_str()) {
+                self.skip("daily-notes.json", key, "no equivalent Onyx setting");
+            }
+        }
+        Ok(())
+    }
+
+    fn templates(&mut self) -> Result<(), OnyxError> {
+        let Some(settings) = self.read_object("templates.json")? else {
+            return Ok(());
+        };
+        if let Some(folder) = settings
+            .get("folder")
+            .and_then(Value::as_str)
+            .and_then(vault_folder)
+        {
+            self.imported.push(format!("templates folder: {folder}"));
+            self.config.templates_folder = Some(folder);
+        }
+        for key in settings.keys() {
+            if key != "folder" {
+                self.skip(
+                    "templates.json",
+                    key,
+                    "template variables use Onyx's default formats",
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects bookmarks (or legacy stars) as absolute paths, flattening groups.
+    fn bookmarks(&mut self) -> Result<Vec<String>, OnyxError> {
+        let mut bookmarks = Vec::new();
+        for source in ["bookmarks.json", "starred.json"] {
+            let Some(settings) = self.read_object(source)? else {
+                continue;
+            };
+            if let Some(Value::Array(items)) = settings.get("items") {
+                self.bookmark_items(source, items, &mut bookmarks);
+            }
+        }
+        Ok(bookmarks)
+    }
+
+    fn bookmark_items(&mut self, source: &str, items: &[Value], bookmarks: &mut Vec<String>) {
+        for item in items {
+            let kind = item.get("type").and_then(Value::as_str).unwrap_or("");
+            let path = item.get("path").and_then(Value::as_str);
+            let title = item
+                .get("title")
+                .or_else(|| item.get("query"))
+                .or_else(|| item.get("url"))
+                .and_then(Value::as_str)
+                .unwrap_or(kind);
+            match (kind, path) {
+                ("file" | "folder" | "heading" | "block", Some(path)) => {
+                    let subpath = item.get("subpath").and_then(Value::as_str).unwrap_or("");
+                    let absolute = self.root.join(path.trim_start_matches('/'));
+                    bookmarks.push(format!("{}{subpath}", absolute.to_string_lossy()));
+                }
+                ("group", _) => {
+                    if let Some(Value::Array(children)) = item.get("items") {
+                        self.skip(source, title, "bookmark group flattened into a single list");
+                        self.bookmark_items(source, children, bookmarks);
+                    }
+                }
+                _ => self.skip(
+                    source,
+                    title,
+                    &format!("{kind} bookmarks are not supported"),
+                ),
+            }
+        }
+    }
+
+    /// Reports community plugins and any data they stored; none of it can be carried over.
+    fn plugins(&mut self) -> Result<(), OnyxError> {
+        if let Some(Value::Array(ids)) = self.read_json("community-plugins.json")? {
+            for id in ids.iter().filter_map(Value::as_str) {
+                self.skip(
+                    "community-plugins.json",
+                    id,
+                    "community plugins are not supported",
+                );
+            }
+        }
+        let plugins_dir = self.obsidian_dir.join("plugins");
+        if plugins_dir.is_dir() {
+            let mut entries: Vec<_> = std::fs::read_dir(&plugins_dir)?
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.join("data.json").is_file())
+                .collect();
+            entries.sort();
+            for entry in entries {
+                let id = entry
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                self.skip(
+                    &format!("plugins/{id}/data.json"),
+                    &id,
+                    "plugin data left in .obsidian",
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports the remaining `.obsidian/` files (appearance, hotkeys, workspace, themes...).
+    fn other_files(&mut self) -> Result<(), OnyxError> {
+        let mut names: Vec<String> = std::fs::read_dir(self.obsidian_dir)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| !HANDLED_FILES.contains(&name.as_str()))
+            .collect();
+        names.sort();
+        for name in names {
+            self.skip(&name, "*", "no equivalent Onyx setting");
+        }
+        Ok(())
+    }
+}
+
+/// Normalizes a vault-relative folder; the vault root (`/` or empty) becomes `None`.
+fn vault_folder(folder: &str) -> Option<String> {
+    let trimmed = folder.trim().trim_matches('/');
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// Maps Obsidian's attachment location; a `./` prefix means relative to the note.
+fn attachments_folder(folder: &str) -> AttachmentsFolder {
+    let folder = folder.trim();
+    match folder.strip_prefix("./") {
+        Some(relative) => AttachmentsFolder::NextToNote(relative.trim_matches('/').to_string()),
+        None if folder == "." => AttachmentsFolder::NextToNote(String::new()),
+        None => {
+            vault_folder(folder).map_or(AttachmentsFolder::VaultRoot, AttachmentsFolder::Folder)
+        }
+    }
+}
+
+/// Obsidian stores template notes without their extension.
+fn note_path(path: &str) -> Option<String> {
+    let path = vault_folder(path)?;
+    if Path::new(&path).extension().is_some_and(|ext| ext == "md") {
+        Some(path)
+    } else {
+        Some(format!("{path}.md"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, name: &str, contents: &str) {
+        let path = root.join(".obsidian").join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn setup_vault() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        write(
+            root,
+            "app.json",
+            r#"{"attachmentFolderPath": "./assets/", "newFileLocation": "folder", "newFileFolderPath": "Inbox/", "vimMode": true, "showLineNumber": true}"#,
+        );
+        write(
+            root,
+            "daily-notes.json",
+            r#"{"folder": "Journal", "format": "YYYY/MM/DD", "template": "Templates/Daily", "autorun": true}"#,
+        );
+        write(
+            root,
+            "templates.json",
+            r#"{"folder": "/Templates", "dateFormat": "DD.MM"}"#,
+        );
+        write(
+            root,
+            "bookmarks.json",
+            r##"{"items": [
+                {"type": "file", "path": "Home.md"},
+                {"type": "group", "title": "Work", "items": [
+                    {"type": "heading", "path": "Work/Plan.md", "subpath": "#Goals"},
+                    {"type": "search", "query": "tag:#todo"}
+                ]}
+            ]}"##,
+        );
+        write(root, "community-plugins.json", r#"["dataview"]"#);
+        write(root, "plugins/dataview/data.json", "{}");
+        write(root, "hotkeys.json", "{}");
+        dir
+    }
+
+    #[test]
+    fn import_maps_settings_into_vault_config() {
+        let vault = setup_vault();
+
+        let report = import_vault(vault.path()).unwrap();
+
+        let config = &report.config;
+        assert_eq!(
+            config.attachments_folder,
+            AttachmentsFolder::NextToNote("assets".to_string())
+        );
+        assert_eq!(config.new_note_folder.as_deref(), Some("Inbox"));
+        assert_eq!(config.templates_folder.as_deref(), Some("Templates"));
+        assert_eq!(
+            config.daily_notes,
+            Some(DailyNotesConfig {
+                folder: Some("Journal".to_string()),
+                format: "YYYY/MM/DD".to_string(),
+                template: Some("Templates/Daily.md".to_string()),
+            })
+        );
+        let saved = crate::vault_config::ensure_vault_config(vault.path()).unwrap();
+        assert_eq!(&saved, config);
+    }
+
+    #[test]
+    fn import_moves_bookmarks_into_the_session() {
+        let vault = setup_vault();
+
+        import_vault(vault.path()).unwrap();
+
+        let session = load_vault_session(vault.path()).unwrap();
+        let root = vault.path().to_string_lossy().to_string();
+        assert_eq!(
+            session.bookmarks,
+            vec![
+                format!("{root}/Home.md"),
+                format!("{root}/Work/Plan.md#Goals")
+            ]
+        );
+    }
+
+    #[test]
+    fn import_reports_what_it_could_not_translate() {
+        let vault = setup_vault();
+
+        let report = import_vault(vault.path()).unwrap();
+
+        let keys: Vec<(&str, &str)> = report
+            .untranslated
+            .iter()
+            .map(|setting| (setting.source.as_str(), setting.key.as_str()))
+            .collect();
+        for expected in [
+            ("app.json", "vimMode"),
+            ("app.json", "showLineNumber"),
+            ("daily-notes.json", "autorun"),
+            ("templates.json", "dateFormat"),
+            ("bookmarks.json", "Work"),
+            ("bookmarks.json", "tag:#todo"),
+            ("community-plugins.json", "dataview"),
+            ("plugins/dataview/data.json", "dataview"),
+            ("hotkeys.json", "*"),
+        ] {
+            assert!(keys.contains(&expected), "missing {expected:?} in {keys:?}");
+        }
+        assert!(!keys.iter().any(|(_, key)| *key == "attachmentFolderPath"));
+    }
+
+    #[test]
+    fn import_requires_an_obsidian_folder() {
+        let temp = TempDir::new().unwrap();
+        assert!(matches!(
+            import_vault(temp.path()),
+            Err(OnyxError::NotObsidianVault(_))
+        ));
+    }
+
+    #[test]
+    fn attachments_folder_keeps_note_relative_prefix() {
+        assert_eq!(attachments_folder("/"), AttachmentsFolder::VaultRoot);
+        assert_eq!(
+            attachments_folder("./"),
+            AttachmentsFolder::NextToNote(String::new())
+        );
+        assert_eq!(
+            attachments_folder("Files/"),
+            AttachmentsFolder::Folder("Files".to_string())
+        );
+    }
+}
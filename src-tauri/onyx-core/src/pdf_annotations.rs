@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::dates::utc_timestamp;
+use crate::error::OnyxError;
+
+/// Appended to a PDF's file name to get its annotation sidecar (`paper.pdf.annotations.json`).
+pub const ANNOTATIONS_SUFFIX: &str = ".annotations.json";
+const SIDECAR_VERSION: u32 = 1;
+
+fn default_color() -> String {
+    "yellow".to_string()
+}
+
+/// A rectangle on a page in PDF points, measured from the page's top-left corner.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A highlight (or a bare comment when `text` is empty) on one page of a PDF.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: String,
+    /// 1-based page number.
+    pub page: u32,
+    #[serde(default)]
+    pub rects: Vec<AnnotationRect>,
+    /// The selected text the highlight covers.
+    #[serde(default)]
+    pub text: String,
+    #[serde(default = "default_color")]
+    pub color: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+    pub created: String,
+}
+
+/// What the viewer sends when the user highlights or comments.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAnnotation {
+    pub page: u32,
+    #[serde(default)]
+    pub rects: Vec<AnnotationRect>,
+    #[serde(default)]
+    pub text: String,
+    pub color: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// The sidecar file's contents.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AnnotationFile {
+    version: u32,
+    annotations: Vec<Annotation>,
+}
+
+/// The sidecar path for a PDF: `paper.pdf` → `paper.pdf.annotations.json` in the same folder.
+pub fn sidecar_path(pdf_path: &Path) -> PathBuf {
+    let mut name = pdf_path.file_name().unwrap_or_default().to_os_string();
+    name.push(ANNOTATIONS_SUFFIX);
+    pdf_path.with_file_name(name)
+}
+
+/// Whether `path` is an annotation sidecar rather than a file of its own.
+pub fn is_sidecar(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(ANNOTATIONS_SUFFIX))
+}
+
+/// Keeps a PDF's annotations with it when the PDF is renamed or moved.
+pub fn move_sidecar(from: &Path, to: &Path) -> Result<(), OnyxError> {
+    let sidecar = sidecar_path(from);
+    if sidecar.is_file() {
+        std::fs::rename(sidecar, sidecar_path(to))?;
+    }
+    Ok(())
+}
+
+fn load(pdf_path: &Path) -> Result<AnnotationFile, OnyxError> {
+    let path = sidecar_path(pdf_path);
+    if !path.exists() {
+        return Ok(AnnotationFile::default());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+fn save(pdf_path: &Path, file: &AnnotationFile) -> Result<(), OnyxError> {
+    let path = sidecar_path(pdf_path);
+    if file.annotations.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    std::fs::write(path, serde_json::to_string_pretty(file)?)?;
+    Ok(())
+}
+
+/// Annotations of a PDF in reading order (page, then top to bottom).
+pub fn list_annotations(pdf_path: &Path) -> Result<Vec<Annotation>, OnyxError> {
+    let mut annotations = load(pdf_path)?.annotations;
+    annotations.sort_by(|a, b| {
+        let top = |annotation: &Annotation| annotation.rects.first().map_or(0.0, |rect| rect.y);
+        a.page.cmp(&b.page).then(top(a).total_cmp(&top(b)))
+    });
+    Ok(annotations)
+}
+
+/// Stores a new annotation in the PDF's sidecar and returns it with its id.
+pub fn add_annotation(pdf_path: &Path, new: NewAnnotation) -> Result<Annotation, OnyxError> {
+    if !pdf_path.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("{} does not exist", pdf_path.display()),
+        )
+        .into());
+    }
+    let mut file = load(pdf_path)?;
+    let now = SystemTime::now();
+    let nanos = now
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or(0);
+    let mut id = format!("{nanos:x}");
+    while file.annotations.iter().any(|existing| existing.id == id) {
+        id.push('0');
+    }
+    let annotation = Annotation {
+        id,
+        page: new.page.max(1),
+        rects: new.rects,
+        text: new.text,
+        color: new.color.unwrap_or_else(default_color),
+        comment: new.comment.filter(|comment| !comment.trim().is_empty()),
+        created: utc_timestamp(now),
+    };
+    file.version = SIDECAR_VERSION;
+    file.annotations.push(annotation.clone());
+    save(pdf_path, &file)?;
+    Ok(annotation)
+}
+
+/// Removes an annotation; returns whether it existed. The sidecar is deleted once empty.
+pub fn delete_annotation(pdf_path: &Path, id: &str) -> Result<bool, OnyxError> {
+    let mut file = load(pdf_path)?;
+    let before = file.annotations.len();
+    file.annotations.retain(|annotation| annotation.id != id);
+    if file.annotations.len() == before {
+        return Ok(false);
+    }
+    save(pdf_path, &file)?;
+    Ok(true)
+}
+
+/// Writes a literature note quoting every highlight with a `[[paper.pdf#page=N]]` link back.
+/// Defaults to `<stem> - Notes.md` next to the PDF and refuses to overwrite an existing note.
+pub fn create_literature_note(pdf_path: &Path, dest: Option<&Path>) -> Result<PathBuf, OnyxError> {
+    let file_name = pdf_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stem = pdf_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dest = dest
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| pdf_path.with_file_name(format!("{stem} - Notes.md")));
+    if dest.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", dest.display()),
+        )
+        .into());
+    }
+
+    let mut note = format!("---\nsource: \"[[{file_name}]]\"\n---\n# {stem}\n");
+    let mut current_page = 0;
+    for annotation in list_annotations(pdf_path)? {
+        if annotation.page != current_page {
+            current_page = annotation.page;
+            note.push_str(&format!("\n## Page {current_page}\n"));
+        }
+        note.push('\n');
+        let link = format!(
+            "[[{file_name}#page={}|p. {}]]",
+            annotation.page, annotation.page
+        );
+        if annotation.text.trim().is_empty() {
+            note.push_str(&format!("{link}\n"));
+        } else {
+            for line in annotation.text.trim().lines() {
+                note.push_str(&format!("> {}\n", line.trim()));
+            }
+            note.push_str(&format!("> — {link}\n"));
+        }
+        if let Some(comment) = &annotation.comment {
+            note.push_str(&format!("\n{}\n", comment.trim()));
+        }
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&dest, note)?;
+    info!("Created literature note {}", dest.display());
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn highlight(page: u32, y: f64, text: &str, comment: Option<&str>) -> NewAnnotation {
+        NewAnnotation {
+            page,
+            rects: vec![AnnotationRect {
+                x: 72.0,
+                y,
+                width: 200.0,
+                height: 12.0,
+            }],
+            text: text.to_string(),
+            color: None,
+            comment: comment.map(str::to_string),
+        }
+    }
+
+    fn pdf(temp: &TempDir) -> PathBuf {
+        let path = temp.path().join("paper.pdf");
+        std::fs::write(&path, "%PDF-1.4").unwrap();
+        path
+    }
+
+    #[test]
+    fn annotations_round_trip_through_the_sidecar() {
+        let temp = TempDir::new().unwrap();
+        let pdf = pdf(&temp);
+
+        let second = add_annotation(&pdf, highlight(3, 100.0, "later", None)).unwrap();
+        let first = add_annotation(&pdf, highlight(1, 500.0, "earlier", Some("why?"))).unwrap();
+
+        assert!(temp.path().join("paper.pdf.annotations.json").exists());
+        let listed = list_annotations(&pdf).unwrap();
+        assert_eq!(listed, vec![first.clone(), second]);
+        assert_eq!(listed[0].color, "yellow");
+
+        assert!(delete_annotation(&pdf, &first.id).unwrap());
+        assert!(!delete_annotation(&pdf, &first.id).unwrap());
+        assert_eq!(list_annotations(&pdf).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn sidecar_follows_a_moved_pdf() {
+        let temp = TempDir::new().unwrap();
+        let pdf = pdf(&temp);
+        add_annotation(&pdf, highlight(1, 0.0, "x", None)).unwrap();
+        let moved = temp.path().join("renamed.pdf");
+        std::fs::rename(&pdf, &moved).unwrap();
+
+        move_sidecar(&pdf, &moved).unwrap();
+
+        assert_eq!(list_annotations(&moved).unwrap().len(), 1);
+        assert!(!sidecar_path(&pdf).exists());
+    }
+
+    #[test]
+    fn deleting_the_last_annotation_removes_the_sidecar() {
+        let temp = TempDir::new().unwrap();
+        let pdf = pdf(&temp);
+        let annotation = add_annotation(&pdf, highlight(1, 0.0, "x", None)).unwrap();
+
+        delete_annotation(&pdf, &annotation.id).unwrap();
+
+        assert!(!sidecar_path(&pdf).exists());
+    }
+
+    #[test]
+    fn literature_note_quotes_highlights_with_page_links() {
+        let temp = TempDir::new().unwrap();
+        let pdf = pdf(&temp);
+        add_annotation(&pdf, highlight(2, 300.0, "Second point", None)).unwrap();
+        add_annotation(&pdf, highlight(2, 100.0, "First point", Some("Key claim"))).unwrap();
+
+        let note = create_literature_note(&pdf, None).unwrap();
+
+        assert_eq!(note, temp.path().join("paper - Notes.md"));
+        let content = std::fs::read_to_string(&note).unwrap();
+        assert_eq!(
+            content,
+            "---\nsource: \"[[paper.pdf]]\"\n---\n# paper\n\n## Page 2\n\n> First point\n> — [[paper.pdf#page=2|p. 2]]\n\nKey claim\n\n> Second point\n> — [[paper.pdf#page=2|p. 2]]\n"
+        );
+        assert!(create_literature_note(&pdf, None).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use log::info;
+use serde::Serialize;
+
+use crate::cache::cache_entry;
+use crate::error::OnyxError;
+
+/// Cache folder (under `.onyx/cache`) holding extracted page text.
+const PDF_TEXT_CACHE: &str = "pdf-text";
+
+/// Bookmarks and page count of a PDF.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PdfOutline {
+    pub page_count: u32,
+    pub entries: Vec<OutlineEntry>,
+}
+
+/// One bookmark; `level` starts at 1 for top-level entries and `page` at 1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlineEntry {
+    pub level: usize,
+    pub title: String,
+    pub page: u32,
+}
+
+/// Text of every page of an in-memory PDF, page 1 first.
+pub fn extract_pages(bytes: &[u8]) -> Result<Vec<String>, OnyxError> {
+    // The extractor panics on some malformed files; treat that like any other parse failure.
+    match std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes)) {
+        Ok(Ok(pages)) => Ok(pages),
+        Ok(Err(error)) => Err(OnyxError::Pdf(error.to_string())),
+        Err(_) => Err(OnyxError::Pdf(
+            "the document could not be parsed".to_string(),
+        )),
+    }
+}
+
+/// Page text of a vault PDF, extracted on first use and cached under `.onyx/cache/pdf-text`.
+pub fn page_texts(vault_root: &Path, pdf_path: &Path) -> Result<Vec<String>, OnyxError> {
+    let cached = cache_entry(vault_root, PDF_TEXT_CACHE, pdf_path, ".json")?;
+    if let Ok(contents) = std::fs::read_to_string(&cached) {
+        if let Ok(pages) = serde_json::from_str(&contents) {
+            return Ok(pages);
+        }
+    }
+    let pages = extract_pages(&std::fs::read(pdf_path)?)?;
+    if let Some(parent) = cached.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&cached, serde_json::to_string(&pages)?)?;
+    info!(
+        "Extracted text from {} pages of {}",
+        pages.len(),
+        pdf_path.display()
+    );
+    Ok(pages)
+}
+
+/// Reads the bookmarks of a PDF; documents without an outline just report their page count.
+pub fn pdf_outline(path: &Path) -> Result<PdfOutline, OnyxError> {
+    let document =
+        pdf_extract::Document::load(path).map_err(|error| OnyxError::Pdf(error.to_string()))?;
+    let page_count = document.get_pages().len() as u32;
+    let entries = document
+        .get_toc()
+        .map(|toc| {
+            toc.toc
+                .into_iter()
+                .map(|entry| OutlineEntry {
+                    level: entry.level,
+                    title: entry.title,
+                    page: entry.page as u32,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(PdfOutline {
+        page_count,
+        entries,
+    })
+}
+
+/// Parses the `page=5` fragment of a `[[paper.pdf#page=5]]` link.
+pub fn page_fragment(fragment: &str) -> Option<u32> {
+    fragment.strip_prefix("page=")?.trim().parse().ok()
+}
+
+/// Whether `page` exists in a vault PDF. Unreadable PDFs are given the benefit of the doubt.
+pub fn page_exists(vault_root: &Path, pdf_path: &Path, page: u32) -> bool {
+    match page_texts(vault_root, pdf_path) {
+        Ok(pages) => page >= 1 && page as usize <= pages.len(),
+        Err(_) => true,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pdf_writer::{pdf_string, PdfDocument, PdfPage};
+    use tempfile::TempDir;
+
+    /// Writes a PDF with one line of Helvetica text per page.
+    pub(crate) fn write_pdf(path: &Path, pages: &[&str]) {
+        let document = PdfDocument {
+            title: "Test".to_string(),
+            pages: pages
+                .iter()
+                .map(|text| PdfPage {
+                    width: 595.0,
+                    height: 842.0,
+                    content: format!("BT /F1 12 Tf 72 720 Td {} Tj ET", pdf_string(text)),
+                })
+                .collect(),
+            images: Vec::new(),
+        };
+        std::fs::write(path, document.to_bytes().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn extracts_and_caches_text_per_page() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("paper.pdf");
+        write_pdf(&path, &["Quantum basics", "Entanglement results"]);
+
+        let pages = page_texts(temp.path(), &path).unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert!(pages[1].contains("Entanglement results"), "{pages:?}");
+        let cache = temp.path().join(".onyx/cache/pdf-text");
+        assert_eq!(cache.read_dir().unwrap().count(), 1);
+        assert_eq!(page_texts(temp.path(), &path).unwrap(), pages);
+    }
+
+    #[test]
+    fn outline_reports_page_count_without_bookmarks() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("paper.pdf");
+        write_pdf(&path, &["one", "two", "three"]);
+
+        let outline = pdf_outline(&path).unwrap();
+
+        assert_eq!(outline.page_count, 3);
+        assert!(outline.entries.is_empty());
+    }
+
+    #[test]
+    fn page_fragments_and_bounds() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("paper.pdf");
+        write_pdf(&path, &["one", "two"]);
+
+        assert_eq!(page_fragment("page=2"), Some(2));
+        assert_eq!(page_fragment("Heading"), None);
+        assert!(page_exists(temp.path(), &path, 2));
+        assert!(!page_exists(temp.path(), &path, 3));
+        assert!(!page_exists(temp.path(), &path, 0));
+    }
+
+    #[test]
+    fn garbage_is_a_pdf_error() {
+        assert!(matches!(
+            extract_pages(b"not a pdf"),
+            Err(OnyxError::Pdf(_))
+        ));
+    }
+}
@@ -0,0 +1,378 @@
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::error::OnyxError;
+
+/// Advance widths (1/1000 em) of the printable ASCII range 32..=126, taken from the Adobe AFMs.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667,
+    611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500,
+    222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556,
+    556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667,
+    611, 778, 722, 278, 556, 722, 611, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667,
+    667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556,
+    278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+const TIMES_WIDTHS: [u16; 95] = [
+    250, 333, 408, 500, 500, 833, 778, 180, 333, 333, 500, 564, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 278, 278, 564, 564, 564, 444, 921, 722, 667, 667, 722, 611,
+    556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722,
+    722, 611, 333, 278, 333, 469, 500, 333, 444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500,
+    278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444, 480, 200, 480, 541,
+];
+const TIMES_BOLD_WIDTHS: [u16; 95] = [
+    250, 333, 555, 500, 500, 1000, 833, 278, 333, 333, 500, 570, 250, 333, 250, 278, 500, 500, 500,
+    500, 500, 500, 500, 500, 500, 500, 333, 333, 570, 570, 570, 500, 930, 722, 667, 722, 722, 667,
+    611, 778, 778, 389, 500, 778, 667, 944, 722, 778, 611, 778, 722, 556, 667, 722, 722, 1000, 722,
+    722, 667, 333, 278, 333, 581, 500, 333, 500, 556, 444, 556, 444, 333, 500, 556, 278, 333, 556,
+    278, 833, 556, 500, 556, 556, 444, 389, 333, 556, 500, 722, 500, 500, 444, 394, 220, 394, 520,
+];
+
+/// The standard 14 fonts the exporter uses; they need no embedding, which keeps output small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StandardFont {
+    Helvetica,
+    HelveticaBold,
+    HelveticaOblique,
+    HelveticaBoldOblique,
+    TimesRoman,
+    TimesBold,
+    TimesItalic,
+    TimesBoldItalic,
+    Courier,
+    CourierBold,
+}
+
+impl StandardFont {
+    pub const ALL: [StandardFont; 10] = [
+        Self::Helvetica,
+        Self::HelveticaBold,
+        Self::HelveticaOblique,
+        Self::HelveticaBoldOblique,
+        Self::TimesRoman,
+        Self::TimesBold,
+        Self::TimesItalic,
+        Self::TimesBoldItalic,
+        Self::Courier,
+        Self::CourierBold,
+    ];
+
+    /// PostScript name written into the font dictionary.
+    pub fn base_name(self) -> &'static str {
+        match self {
+            Self::Helvetica => "Helvetica",
+            Self::HelveticaBold => "Helvetica-Bold",
+            Self::HelveticaOblique => "Helvetica-Oblique",
+            Self::HelveticaBoldOblique => "Helvetica-BoldOblique",
+            Self::TimesRoman => "Times-Roman",
+            Self::TimesBold => "Times-Bold",
+            Self::TimesItalic => "Times-Italic",
+            Self::TimesBoldItalic => "Times-BoldItalic",
+            Self::Courier => "Courier",
+            Self::CourierBold => "Courier-Bold",
+        }
+    }
+
+    /// Name of the font in each page's resource dictionary (`/F1` …).
+    pub fn resource_name(self) -> String {
+        let index = Self::ALL.iter().position(|font| *font == self).unwrap_or(0);
+        format!("F{}", index + 1)
+    }
+
+    /// Advance width of one character in 1/1000 em. Italic faces share their upright metrics,
+    /// which is exact for Helvetica and close enough for Times when wrapping lines.
+    pub fn char_width(self, character: char) -> f32 {
+        let table = match self {
+            Self::Courier | Self::CourierBold => return 600.0,
+            Self::Helvetica | Self::HelveticaOblique => &HELVETICA_WIDTHS,
+            Self::HelveticaBold | Self::HelveticaBoldOblique => &HELVETICA_BOLD_WIDTHS,
+            Self::TimesRoman | Self::TimesItalic => &TIMES_WIDTHS,
+            Self::TimesBold | Self::TimesBoldItalic => &TIMES_BOLD_WIDTHS,
+        };
+        let code = character as u32;
+        if (32..=126).contains(&code) {
+            f32::from(table[(code - 32) as usize])
+        } else {
+            // Latin-1 letters are close to the width of an average lowercase glyph.
+            f32::from(table[('n' as u32 - 32) as usize])
+        }
+    }
+
+    /// Width of `text` in points when set at `size`.
+    pub fn text_width(self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c)).sum::<f32>() * size / 1000.0
+    }
+}
+
+/// Encodes text as a PDF literal string in WinAnsiEncoding, escaping anything non-ASCII as octal.
+pub fn pdf_string(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len() + 2);
+    encoded.push('(');
+    for character in text.chars() {
+        let byte = win_ansi_byte(character);
+        match byte {
+            b'(' | b')' | b'\\' => {
+                encoded.push('\\');
+                encoded.push(byte as char);
+            }
+            32..=126 => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    encoded.push(')');
+    encoded
+}
+
+/// Maps a character to its WinAnsiEncoding byte, substituting `?` for anything unencodable.
+fn win_ansi_byte(character: char) -> u8 {
+    match character {
+        '€' => 128,
+        '…' => 133,
+        '‘' => 145,
+        '’' => 146,
+        '“' => 147,
+        '”' => 148,
+        '•' => 149,
+        '–' => 150,
+        '—' => 151,
+        '™' => 153,
+        '\t' => b' ',
+        c if (c as u32) < 128 || (160..=255).contains(&(c as u32)) => c as u8,
+        _ => b'?',
+    }
+}
+
+/// A raster image ready to be written as an image XObject.
+pub struct PdfImage {
+    pub width: u32,
+    pub height: u32,
+    pub color_space: &'static str,
+    /// `DCTDecode` for JPEG passthrough, `FlateDecode` for everything else.
+    pub filter: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// One page: its size in points and the content stream operators that draw it.
+pub struct PdfPage {
+    pub width: f32,
+    pub height: f32,
+    pub content: String,
+}
+
+/// An in-memory PDF assembled page by page and serialised in one go.
+#[derive(Default)]
+pub struct PdfDocument {
+    pub title: String,
+    pub pages: Vec<PdfPage>,
+    pub images: Vec<PdfImage>,
+}
+
+impl PdfDocument {
+    /// Serialises the document: catalog, page tree, fonts, images, pages and the xref table.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, OnyxError> {
+        let mut writer = ObjectWriter::default();
+        writer
+            .output
+            .extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+        // Object numbering: 1 catalog, 2 page tree, 3 info, then fonts, images and pages.
+        let font_base = 4;
+        let image_base = font_base + StandardFont::ALL.len();
+        let page_base = image_base + self.images.len();
+
+        writer.object(1, b"<< /Type /Catalog /Pages 2 0 R >>");
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|index| format!("{} 0 R", page_base + index * 2))
+            .collect();
+        writer.object(
+            2,
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .as_bytes(),
+        );
+        writer.object(
+            3,
+            format!("<< /Title {} /Producer (Onyx) >>", pdf_string(&self.title)).as_bytes(),
+        );
+
+        for (offset, font) in StandardFont::ALL.iter().enumerate() {
+            writer.object(
+                font_base + offset,
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                    font.base_name()
+                )
+                .as_bytes(),
+            );
+        }
+
+        for (offset, image) in self.images.iter().enumerate() {
+            let dictionary = format!(
+                "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} \
+                 /BitsPerComponent 8 /Filter /{}",
+                image.width, image.height, image.color_space, image.filter
+            );
+            writer.stream(image_base + offset, &dictionary, &image.data);
+        }
+
+        let font_resources: String = StandardFont::ALL
+            .iter()
+            .enumerate()
+            .map(|(offset, font)| format!("/{} {} 0 R ", font.resource_name(), font_base + offset))
+            .collect();
+        let image_resources: String = (0..self.images.len())
+            .map(|offset| format!("/Im{} {} 0 R ", offset + 1, image_base + offset))
+            .collect();
+
+        for (index, page) in self.pages.iter().enumerate() {
+            let page_number = page_base + index * 2;
+            writer.object(
+                page_number,
+                format!(
+                    "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                     /Resources << /Font << {font_resources}>> /XObject << {image_resources}>> >> \
+                     /Contents {} 0 R >>",
+                    page.width,
+                    page.height,
+                    page_number + 1
+                )
+                .as_bytes(),
+            );
+            let compressed = deflate(page.content.as_bytes())?;
+            writer.stream(page_number + 1, "/Filter /FlateDecode", &compressed);
+        }
+
+        Ok(writer.finish(1, 3))
+    }
+}
+
+/// Zlib-compresses a stream body for `/FlateDecode`.
+pub fn deflate(data: &[u8]) -> Result<Vec<u8>, OnyxError> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Appends numbered objects and remembers their byte offsets for the xref table.
+#[derive(Default)]
+struct ObjectWriter {
+    output: Vec<u8>,
+    offsets: Vec<(usize, usize)>,
+}
+
+impl ObjectWriter {
+    fn object(&mut self, number: usize, body: &[u8]) {
+        self.offsets.push((number, self.output.len()));
+        self.output
+            .extend_from_slice(format!("{number} 0 obj\n").as_bytes());
+        self.output.extend_from_slice(body);
+        self.output.extend_from_slice(b"\nendobj\n");
+    }
+
+    fn stream(&mut self, number: usize, dictionary: &str, data: &[u8]) {
+        let mut body = format!("<< {dictionary} /Length {} >>\nstream\n", data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        self.object(number, &body);
+    }
+
+    fn finish(mut self, root: usize, info: usize) -> Vec<u8> {
+        self.offsets.sort_unstable();
+        let size = self.offsets.last().map_or(1, |(number, _)| number + 1);
+        let xref_offset = self.output.len();
+        let mut xref = format!("xref\n0 {size}\n0000000000 65535 f \n");
+        let mut expected = 1;
+        for (number, offset) in &self.offsets {
+            while expected < *number {
+                xref.push_str("0000000000 65535 f \n");
+                expected += 1;
+            }
+            xref.push_str(&format!("{offset:010} 00000 n \n"));
+            expected += 1;
+        }
+        xref.push_str(&format!(
+            "trailer\n<< /Size {size} /Root {root} 0 R /Info {info} 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n"
+        ));
+        self.output.extend_from_slice(xref.as_bytes());
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pdf_string_escapes_delimiters_and_non_ascii() {
+        assert_eq!(pdf_string("a(b)\\c"), "(a\\(b\\)\\\\c)");
+        assert_eq!(pdf_string("café — ok"), "(caf\\351 \\227 ok)");
+        assert_eq!(pdf_string("日本"), "(??)");
+    }
+
+    #[test]
+    fn text_width_uses_font_metrics() {
+        assert_eq!(StandardFont::Courier.text_width("abcd", 10.0), 24.0);
+        let narrow = StandardFont::Helvetica.text_width("iiii", 10.0);
+        let wide = StandardFont::Helvetica.text_width("MMMM", 10.0);
+        assert!(narrow < wide);
+    }
+
+    #[test]
+    fn resource_names_are_stable() {
+        assert_eq!(StandardFont::Helvetica.resource_name(), "F1");
+        assert_eq!(StandardFont::CourierBold.resource_name(), "F10");
+    }
+
+    #[test]
+    fn document_has_valid_xref_offsets() {
+        let document = PdfDocument {
+            title: "Test".into(),
+            pages: vec![PdfPage {
+                width: 595.0,
+                height: 842.0,
+                content: "BT /F1 12 Tf 72 770 Td (Hello) Tj ET".into(),
+            }],
+            images: Vec::new(),
+        };
+        let bytes = document.to_bytes().unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.4"));
+        assert!(bytes.ends_with(b"%%EOF\n"));
+
+        let startxref = rfind(&bytes, b"startxref\n").unwrap();
+        let offset: usize = std::str::from_utf8(&bytes[startxref + 10..])
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(bytes[offset..].starts_with(b"xref"));
+
+        // Every in-use xref entry must point at the start of its object.
+        let table = std::str::from_utf8(&bytes[offset..]).unwrap();
+        for (number, line) in table.lines().skip(2).enumerate() {
+            if !line.ends_with(" n ") {
+                continue;
+            }
+            let object_offset: usize = line[..10].parse().unwrap();
+            let header = format!("{number} 0 obj");
+            assert!(bytes[object_offset..].starts_with(header.as_bytes()));
+        }
+    }
+
+    fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+        haystack
+            .windows(needle.len())
+            .rposition(|window| window == needle)
+    }
+}
@@ -0,0 +1,912 @@
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use log::warn;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::OnyxError;
+use crate::frontmatter::{frontmatter_tags, parse_frontmatter, split_frontmatter, Frontmatter};
+use crate::link_resolver::is_markdown;
+use crate::tag_index::extract_tags;
+
+/// Info string of a fenced block whose query results the editor renders in place.
+pub const QUERY_BLOCK_LANGUAGE: &str = "query";
+
+/// Whether a query asked for a table or a list, so the editor can render it accordingly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryKind {
+    Table,
+    List,
+}
+
+/// Rows of a query. `columns` names each value of a row; LIST queries without a field have none.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryResult {
+    pub kind: QueryKind,
+    pub columns: Vec<String>,
+    pub rows: Vec<QueryRow>,
+}
+
+/// One matching note and its column values, typed as they appear in frontmatter.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryRow {
+    pub path: String,
+    pub values: Vec<Value>,
+}
+
+/// A `query` fenced block in a note, with its results or the reason it failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmbeddedQuery {
+    /// 1-based line of the opening fence.
+    pub line: usize,
+    pub query: String,
+    pub result: Option<QueryResult>,
+    pub error: Option<String>,
+}
+
+/// A parsed query:
+/// `(TABLE field [AS "name"], … | LIST [field]) [FROM source] [WHERE expr] [SORT field [ASC|DESC], …] [LIMIT n]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    kind: QueryKind,
+    columns: Vec<(String, String)>,
+    from: Option<Source>,
+    filter: Option<Expr>,
+    sort: Vec<(String, bool)>,
+    limit: Option<usize>,
+}
+
+/// Which notes a query reads: `#tag`, `"folder"`, combined with `AND`, `OR` and `-`.
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Tag(String),
+    Folder(String),
+    Not(Box<Source>),
+    And(Box<Source>, Box<Source>),
+    Or(Box<Source>, Box<Source>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Field(String),
+    Literal(Value),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    Contains(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    Number(f64),
+    Tag(String),
+    Symbol(&'static str),
+}
+
+fn query_error(message: impl Into<String>) -> OnyxError {
+    OnyxError::Query(message.into())
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, OnyxError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let is_word = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '/');
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+        } else if c == '"' {
+            let mut value = String::new();
+            index += 1;
+            loop {
+                match chars.get(index) {
+                    None => return Err(query_error("unterminated string")),
+                    Some('"') => break,
+                    Some('\\') if index + 1 < chars.len() => {
+                        value.push(chars[index + 1]);
+                        index += 2;
+                    }
+                    Some(&other) => {
+                        value.push(other);
+                        index += 1;
+                    }
+                }
+            }
+            index += 1;
+            tokens.push(Token::Str(value));
+        } else if c == '#' {
+            let start = index + 1;
+            index = start;
+            while index < chars.len() && is_word(chars[index]) {
+                index += 1;
+            }
+            tokens.push(Token::Tag(chars[start..index].iter().collect()));
+        } else if c.is_ascii_digit() {
+            let start = index;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            let text: String = chars[start..index].iter().collect();
+            let number = text
+                .parse()
+                .map_err(|_| query_error(format!("invalid number {text}")))?;
+            tokens.push(Token::Number(number));
+        } else if is_word(c) {
+            let start = index;
+            while index < chars.len() && is_word(chars[index]) {
+                index += 1;
+            }
+            tokens.push(Token::Word(chars[start..index].iter().collect()));
+        } else {
+            let next = chars.get(index + 1).copied();
+            let symbol = match (c, next) {
+                ('!', Some('=')) => "!=",
+                ('<', Some('=')) => "<=",
+                ('>', Some('=')) => ">=",
+                ('(', _) => "(",
+                (')', _) => ")",
+                (',', _) => ",",
+                ('!', _) => "!",
+                ('=', _) => "=",
+                ('<', _) => "<",
+                ('>', _) => ">",
+                _ => return Err(query_error(format!("unexpected character '{c}'"))),
+            };
+            index += symbol.len();
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+    Ok(tokens)
+}
+
+const KEYWORDS: [&str; 11] = [
+    "TABLE", "LIST", "FROM", "WHERE", "SORT", "LIMIT", "ASC", "DESC", "AND", "OR", "AS",
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn at_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.at_keyword(keyword);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(found)) if *found == symbol);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn at_clause(&self) -> bool {
+        ["FROM", "WHERE", "SORT", "LIMIT"]
+            .iter()
+            .any(|keyword| self.at_keyword(keyword))
+    }
+
+    fn field(&mut self) -> Result<String, OnyxError> {
+        match self.next() {
+            Some(Token::Word(word))
+                if !KEYWORDS
+                    .iter()
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+            {
+                Ok(word)
+            }
+            other => Err(query_error(format!("expected a field, found {other:?}"))),
+        }
+    }
+
+    fn query(&mut self) -> Result<Query, OnyxError> {
+        let kind = if self.eat_keyword("LIST") {
+            QueryKind::List
+        } else if self.eat_keyword("TABLE") {
+            QueryKind::Table
+        } else {
+            return Err(query_error("queries start with TABLE or LIST"));
+        };
+        let mut columns = Vec::new();
+        while self.peek().is_some() && !self.at_clause() {
+            let field = self.field()?;
+            let name = if self.eat_keyword("AS") {
+                match self.next() {
+                    Some(Token::Str(name) | Token::Word(name)) => name,
+                    other => return Err(query_error(format!("expected a name, found {other:?}"))),
+                }
+            } else {
+                field.clone()
+            };
+            columns.push((field, name));
+            if kind == QueryKind::List || !self.eat_symbol(",") {
+                break;
+            }
+        }
+        let from = if self.eat_keyword("FROM") {
+            Some(self.source_or()?)
+        } else {
+            None
+        };
+        let filter = if self.eat_keyword("WHERE") {
+            Some(self.expr_or()?)
+        } else {
+            None
+        };
+        let mut sort = Vec::new();
+        if self.eat_keyword("SORT") {
+            loop {
+                let field = self.field()?;
+                let descending = self.eat_keyword("DESC");
+                if !descending {
+                    self.eat_keyword("ASC");
+                }
+                sort.push((field, descending));
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+        let limit = if self.eat_keyword("LIMIT") {
+            match self.next() {
+                Some(Token::Number(number)) if number >= 0.0 => Some(number as usize),
+                other => return Err(query_error(format!("expected a limit, found {other:?}"))),
+            }
+        } else {
+            None
+        };
+        if let Some(token) = self.peek() {
+            return Err(query_error(format!("unexpected {token:?}")));
+        }
+        Ok(Query {
+            kind,
+            columns,
+            from,
+            filter,
+            sort,
+            limit,
+        })
+    }
+
+    fn source_or(&mut self) -> Result<Source, OnyxError> {
+        let mut source = self.source_and()?;
+        while self.eat_keyword("OR") {
+            source = Source::Or(Box::new(source), Box::new(self.source_and()?));
+        }
+        Ok(source)
+    }
+
+    fn source_and(&mut self) -> Result<Source, OnyxError> {
+        let mut source = self.source_atom()?;
+        while self.eat_keyword("AND") {
+            source = Source::And(Box::new(source), Box::new(self.source_atom()?));
+        }
+        Ok(source)
+    }
+
+    fn source_atom(&mut self) -> Result<Source, OnyxError> {
+        match self.next() {
+            Some(Token::Tag(tag)) => Ok(Source::Tag(tag)),
+            Some(Token::Str(folder)) => Ok(Source::Folder(folder)),
+            Some(Token::Word(word)) if word == "-" => {
+                Ok(Source::Not(Box::new(self.source_atom()?)))
+            }
+            Some(Token::Symbol("!")) => Ok(Source::Not(Box::new(self.source_atom()?))),
+            Some(Token::Symbol("(")) => {
+                let source = self.source_or()?;
+                if !self.eat_symbol(")") {
+                    return Err(query_error("expected ')'"));
+                }
+                Ok(source)
+            }
+            other => Err(query_error(format!(
+                "expected #tag or \"folder\", found {other:?}"
+            ))),
+        }
+    }
+
+    fn expr_or(&mut self) -> Result<Expr, OnyxError> {
+        let mut expr = self.expr_and()?;
+        while self.eat_keyword("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.expr_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn expr_and(&mut self) -> Result<Expr, OnyxError> {
+        let mut expr = self.expr_not()?;
+        while self.eat_keyword("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.expr_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn expr_not(&mut self) -> Result<Expr, OnyxError> {
+        if self.eat_symbol("!") {
+            return Ok(Expr::Not(Box::new(self.expr_not()?)));
+        }
+        let left = self.expr_primary()?;
+        let op = match self.peek() {
+            Some(Token::Symbol("=")) => CompareOp::Eq,
+            Some(Token::Symbol("!=")) => CompareOp::Ne,
+            Some(Token::Symbol("<")) => CompareOp::Lt,
+            Some(Token::Symbol("<=")) => CompareOp::Le,
+            Some(Token::Symbol(">")) => CompareOp::Gt,
+            Some(Token::Symbol(">=")) => CompareOp::Ge,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        let right = self.expr_primary()?;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    fn expr_primary(&mut self) -> Result<Expr, OnyxError> {
+        match self.next() {
+            Some(Token::Str(value)) => Ok(Expr::Literal(Value::String(value))),
+            Some(Token::Number(number)) => Ok(Expr::Literal(Value::from(number))),
+            Some(Token::Symbol("(")) => {
+                let expr = self.expr_or()?;
+                if !self.eat_symbol(")") {
+                    return Err(query_error("expected ')'"));
+                }
+                Ok(expr)
+            }
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("contains") => {
+                if !self.eat_symbol("(") {
+                    return Err(query_error("expected '(' after contains"));
+                }
+                let haystack = self.expr_or()?;
+                if !self.eat_symbol(",") {
+                    return Err(query_error("contains takes two arguments"));
+                }
+                let needle = self.expr_or()?;
+                if !self.eat_symbol(")") {
+                    return Err(query_error("expected ')'"));
+                }
+                Ok(Expr::Contains(Box::new(haystack), Box::new(needle)))
+            }
+            Some(Token::Word(word)) => match word.to_lowercase().as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if KEYWORDS
+                    .iter()
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+                {
+                    Err(query_error(format!("unexpected keyword {word}")))
+                }
+                _ => Ok(Expr::Field(word)),
+            },
+            other => Err(query_error(format!("expected a value, found {other:?}"))),
+        }
+    }
+}
+
+/// Parses query text; errors describe the first token that doesn't fit the grammar.
+pub fn parse_query(source: &str) -> Result<Query, OnyxError> {
+    Parser {
+        tokens: tokenize(source)?,
+        position: 0,
+    }
+    .query()
+}
+
+/// The note body minus fenced code blocks, so tags written inside code (or inside a query
+/// block) don't count as tags of the note.
+fn without_code_blocks(body: &str) -> String {
+    let mut in_fence = false;
+    let mut text = String::new();
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        } else if !in_fence {
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Sorted tags of a note from its frontmatter and its body, ignoring code blocks.
+pub(crate) fn note_tags(frontmatter: &Frontmatter, content: &str) -> Vec<String> {
+    let mut tags = frontmatter_tags(frontmatter);
+    tags.extend(extract_tags(&without_code_blocks(
+        split_frontmatter(content).1,
+    )));
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// What a query can see of one note.
+struct NoteMetadata {
+    path: PathBuf,
+    relative: String,
+    frontmatter: Frontmatter,
+    tags: Vec<String>,
+    size: u64,
+    modified_secs: u64,
+}
+
+impl NoteMetadata {
+    fn read(vault_root: &Path, path: PathBuf) -> Result<Self, OnyxError> {
+        let content = std::fs::read_to_string(&path)?;
+        let metadata = std::fs::metadata(&path)?;
+        let frontmatter = parse_frontmatter(&content);
+        let tags = note_tags(&frontmatter, &content);
+        Ok(Self {
+            relative: path
+                .strip_prefix(vault_root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            frontmatter,
+            tags,
+            size: metadata.len(),
+            modified_secs: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs()),
+            path,
+        })
+    }
+
+    /// `file.*` built-ins, then frontmatter keys (case-insensitive, `a.b` reaching into maps).
+    fn field(&self, name: &str) -> Value {
+        let folder = || {
+            Path::new(&self.relative)
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .unwrap_or_default()
+        };
+        match name.to_lowercase().as_str() {
+            "file.name" => {
+                return Value::from(
+                    self.path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                )
+            }
+            "file.path" => return Value::from(self.relative.clone()),
+            "file.folder" => return Value::from(folder()),
+            "file.tags" => return Value::from(self.tags.clone()),
+            "file.size" => return Value::from(self.size),
+            "file.mtime" => return Value::from(self.modified_secs),
+            _ => {}
+        }
+        let mut parts = name.split('.');
+        let first = parts.next().unwrap_or_default();
+        let mut value = self
+            .frontmatter
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(first))
+            .map(|(_, value)| value);
+        for part in parts {
+            value = value.and_then(|value| value.get(part));
+        }
+        value.cloned().unwrap_or(Value::Null)
+    }
+
+    fn in_source(&self, source: &Source) -> bool {
+        match source {
+            Source::Tag(tag) => self.tags.iter().any(|own| {
+                own.eq_ignore_ascii_case(tag)
+                    || own
+                        .to_lowercase()
+                        .starts_with(&format!("{}/", tag.to_lowercase()))
+            }),
+            Source::Folder(folder) => {
+                let folder = folder.trim_matches('/');
+                folder.is_empty() || Path::new(&self.relative).starts_with(folder)
+            }
+            Source::Not(inner) => !self.in_source(inner),
+            Source::And(left, right) => self.in_source(left) && self.in_source(right),
+            Source::Or(left, right) => self.in_source(left) || self.in_source(right),
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(number) => number.as_f64().is_some_and(|number| number != 0.0),
+        Value::String(value) => !value.is_empty(),
+        Value::Array(values) => !values.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
+}
+
+/// Orders values of the same kind; anything else (including null) is incomparable.
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+/// A total order for sorting: booleans, then numbers, then strings, then lists and objects;
+/// values of the same kind compare as `compare_values` does.
+fn sort_order(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    rank(left)
+        .cmp(&rank(right))
+        .then_with(|| compare_values(left, right).unwrap_or(Ordering::Equal))
+}
+
+fn loosely_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::String(left), Value::String(right)) => left.eq_ignore_ascii_case(right),
+        _ => compare_values(left, right).map_or(left == right, Ordering::is_eq),
+    }
+}
+
+fn evaluate(expr: &Expr, note: &NoteMetadata) -> Value {
+    match expr {
+        Expr::Field(name) => note.field(name),
+        Expr::Literal(value) => value.clone(),
+        Expr::Not(inner) => Value::Bool(!truthy(&evaluate(inner, note))),
+        Expr::And(left, right) => {
+            Value::Bool(truthy(&evaluate(left, note)) && truthy(&evaluate(right, note)))
+        }
+        Expr::Or(left, right) => {
+            Value::Bool(truthy(&evaluate(left, note)) || truthy(&evaluate(right, note)))
+        }
+        Expr::Compare(left, op, right) => {
+            let (left, right) = (evaluate(left, note), evaluate(right, note));
+            let result = match op {
+                CompareOp::Eq => loosely_equal(&left, &right),
+                CompareOp::Ne => !loosely_equal(&left, &right),
+                CompareOp::Lt => compare_values(&left, &right) == Some(Ordering::Less),
+                CompareOp::Le => compare_values(&left, &right).is_some_and(Ordering::is_le),
+                CompareOp::Gt => compare_values(&left, &right) == Some(Ordering::Greater),
+                CompareOp::Ge => compare_values(&left, &right).is_some_and(Ordering::is_ge),
+            };
+            Value::Bool(result)
+        }
+        Expr::Contains(haystack, needle) => {
+            let needle = evaluate(needle, note);
+            let found = match evaluate(haystack, note) {
+                Value::Array(values) => values.iter().any(|value| loosely_equal(value, &needle)),
+                Value::String(text) => needle
+                    .as_str()
+                    .is_some_and(|needle| text.to_lowercase().contains(&needle.to_lowercase())),
+                _ => false,
+            };
+            Value::Bool(found)
+        }
+    }
+}
+
+/// Every non-hidden note in the vault, sorted.
+fn vault_notes(vault_root: &Path) -> Vec<PathBuf> {
+    let mut notes: Vec<PathBuf> = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_markdown(entry.path()))
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    notes.sort();
+    notes
+}
+
+/// Runs a query against every note's frontmatter, tags and file metadata. Without `SORT`,
+/// rows come in path order; notes missing a sort field go last in either direction.
+pub fn run_query(vault_root: &Path, source: &str) -> Result<QueryResult, OnyxError> {
+    let query = parse_query(source)?;
+    let mut notes = Vec::new();
+    for path in vault_notes(vault_root) {
+        let note = match NoteMetadata::read(vault_root, path.clone()) {
+            Ok(note) => note,
+            Err(e) => {
+                warn!("Skipping unreadable note {} in query: {e}", path.display());
+                continue;
+            }
+        };
+        let in_source = query.from.as_ref().is_none_or(|from| note.in_source(from));
+        let matches = query
+            .filter
+            .as_ref()
+            .is_none_or(|filter| truthy(&evaluate(filter, &note)));
+        if in_source && matches {
+            notes.push(note);
+        }
+    }
+    notes.sort_by(|a, b| {
+        for (field, descending) in &query.sort {
+            let (left, right) = (a.field(field), b.field(field));
+            let ordering = match (left.is_null(), right.is_null()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                _ => {
+                    let ordering = sort_order(&left, &right);
+                    if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                }
+            };
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+    if let Some(limit) = query.limit {
+        notes.truncate(limit);
+    }
+    let rows = notes
+        .iter()
+        .map(|note| QueryRow {
+            path: note.path.to_string_lossy().to_string(),
+            values: query
+                .columns
+                .iter()
+                .map(|(field, _)| note.field(field))
+                .collect(),
+        })
+        .collect();
+    Ok(QueryResult {
+        kind: query.kind,
+        columns: query.columns.into_iter().map(|(_, name)| name).collect(),
+        rows,
+    })
+}
+
+/// The ```` ```query ```` blocks of a note as (line of the opening fence, query text).
+pub fn query_blocks(content: &str) -> Vec<(usize, String)> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(usize, String)> = None;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if let Some((_, text)) = open.as_mut() {
+            if trimmed == "```" {
+                blocks.extend(open.take());
+            } else {
+                text.push_str(line);
+                text.push('\n');
+            }
+        } else if trimmed.strip_prefix("```").map(str::trim) == Some(QUERY_BLOCK_LANGUAGE) {
+            open = Some((index + 1, String::new()));
+        }
+    }
+    blocks
+}
+
+/// Runs every query block of a note; a failing block reports its error without stopping the rest.
+pub fn run_note_queries(vault_root: &Path, note: &Path) -> Result<Vec<EmbeddedQuery>, OnyxError> {
+    let content = std::fs::read_to_string(note)?;
+    Ok(query_blocks(&content)
+        .into_iter()
+        .map(|(line, query)| match run_query(vault_root, &query) {
+            Ok(result) => EmbeddedQuery {
+                line,
+                query,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => EmbeddedQuery {
+                line,
+                query,
+                result: None,
+                error: Some(e.to_string()),
+            },
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("Projects")).unwrap();
+        std::fs::write(
+            root.join("Projects/Alpha.md"),
+            "---\nstatus: active\ndue: 2026-11-01\npriority: 2\ntags: [project]\n---\n# Alpha\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("Projects/Beta.md"),
+            "---\nstatus: done\ndue: 2026-10-01\npriority: 1\n---\n#project body\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("Projects/Gamma.md"),
+            "---\nstatus: planned\npriority: 3\ntags: project/side\n---\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("Inbox.md"), "---\nstatus: active\n---\n").unwrap();
+        temp
+    }
+
+    fn names(result: &QueryResult) -> Vec<String> {
+        result
+            .rows
+            .iter()
+            .map(|row| {
+                Path::new(&row.path)
+                    .file_stem()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_query_filters_sorts_and_types_values() {
+        let vault = setup_vault();
+
+        let result = run_query(
+            vault.path(),
+            r#"TABLE status, due AS "Due" FROM #project WHERE status != "done" SORT due ASC"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.kind, QueryKind::Table);
+        assert_eq!(result.columns, vec!["status", "Due"]);
+        assert_eq!(names(&result), vec!["Alpha", "Gamma"]);
+        assert_eq!(
+            result.rows[0].values,
+            vec![Value::from("active"), Value::from("2026-11-01")]
+        );
+        assert_eq!(result.rows[1].values[1], Value::Null);
+    }
+
+    #[test]
+    fn list_query_with_folder_source_numbers_and_limit() {
+        let vault = setup_vault();
+
+        let result = run_query(
+            vault.path(),
+            r#"LIST file.name FROM "Projects" AND -#project/side WHERE priority >= 1 AND !(status = "active") SORT priority DESC LIMIT 5"#,
+        )
+        .unwrap();
+
+        assert_eq!(result.kind, QueryKind::List);
+        assert_eq!(names(&result), vec!["Beta"]);
+        assert_eq!(result.rows[0].values, vec![Value::from("Beta")]);
+    }
+
+    #[test]
+    fn contains_matches_list_items_and_substrings() {
+        let vault = setup_vault();
+
+        let tagged =
+            run_query(vault.path(), r#"LIST WHERE contains(file.tags, "PROJECT")"#).unwrap();
+        let titled = run_query(vault.path(), r#"LIST WHERE contains(file.name, "amm")"#).unwrap();
+
+        assert_eq!(names(&tagged), vec!["Alpha", "Beta"]);
+        assert!(tagged.columns.is_empty());
+        assert_eq!(names(&titled), vec!["Gamma"]);
+    }
+
+    #[test]
+    fn sorting_mixed_types_groups_them_by_kind() {
+        let temp = TempDir::new().unwrap();
+        for (name, rank) in [
+            ("a", "10"),
+            ("b", "\"high\""),
+            ("c", "true"),
+            ("d", "2"),
+            ("e", "\"low\""),
+            ("f", "false"),
+        ] {
+            std::fs::write(
+                temp.path().join(format!("{name}.md")),
+                format!("---\nrank: {rank}\n---\n"),
+            )
+            .unwrap();
+        }
+
+        let ascending = run_query(temp.path(), "LIST SORT rank").unwrap();
+        let descending = run_query(temp.path(), "LIST SORT rank DESC").unwrap();
+
+        assert_eq!(names(&ascending), vec!["f", "c", "d", "a", "b", "e"]);
+        assert_eq!(names(&descending), vec!["e", "b", "a", "d", "c", "f"]);
+    }
+
+    #[test]
+    fn unreadable_notes_are_skipped() {
+        let vault = setup_vault();
+        std::fs::write(vault.path().join("Broken.md"), b"---\nstatus: \xff\n---\n").unwrap();
+
+        let result = run_query(vault.path(), "LIST FROM #project").unwrap();
+
+        assert_eq!(names(&result), vec!["Alpha", "Beta", "Gamma"]);
+    }
+
+    #[test]
+    fn malformed_queries_are_query_errors() {
+        for source in [
+            "SELECT *",
+            "TABLE status WHERE",
+            "LIST FROM",
+            "TABLE a SORT",
+            "LIST \"open",
+        ] {
+            assert!(
+                matches!(parse_query(source), Err(OnyxError::Query(_))),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn query_blocks_in_notes_report_results_and_errors() {
+        let vault = setup_vault();
+        let note = vault.path().join("Dashboard.md");
+        std::fs::write(
+            &note,
+            "# Dashboard\n\n```query\nLIST FROM #project\n```\n\n```query\nnonsense\n```\n",
+        )
+        .unwrap();
+
+        let queries = run_note_queries(vault.path(), &note).unwrap();
+
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].line, 3);
+        assert_eq!(queries[0].query, "LIST FROM #project\n");
+        assert_eq!(queries[0].result.as_ref().unwrap().rows.len(), 3);
+        assert!(queries[1].error.is_some());
+    }
+}
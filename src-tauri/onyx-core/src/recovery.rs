@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{info, warn};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::link_resolver::normalize_path;
+
+/// Journal of unsaved editor buffers, relative to the vault root.
+pub const RECOVERY_DIR: &str = ".onyx/recovery";
+
+/// Above this many line pairs a diff shows the whole file as replaced instead of matching lines.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// What is stored for one unsaved buffer.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    /// Vault-relative path of the file being edited.
+    path: String,
+    content: String,
+    saved_secs: u64,
+}
+
+/// An unsaved buffer left behind by a crash or a closed window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RecoverableBuffer {
+    /// Absolute path of the file the buffer belongs to.
+    pub path: String,
+    /// The unsaved text.
+    pub content: String,
+    /// When the buffer was last journaled, in seconds since the Unix epoch.
+    pub saved_secs: u64,
+    /// Whether the file still exists on disk.
+    pub file_exists: bool,
+    /// Line diff from the file on disk to the unsaved text.
+    pub diff: Vec<DiffLine>,
+}
+
+/// One line of a diff from the file on disk to a buffer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "text", rename_all = "snake_case")]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Records the unsaved text of `path`, replacing any earlier copy. The entry is written to a
+/// temporary file first so a crash mid-write never leaves a torn journal.
+pub fn journal_buffer(vault_root: &Path, path: &Path, content: &str) -> Result<(), OnyxError> {
+    let (entry_path, relative) = entry_location(vault_root, path)?;
+    let entry = JournalEntry {
+        path: relative,
+        content: content.to_string(),
+        saved_secs: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+    };
+    if let Some(parent) = entry_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = entry_path.with_extension("json.tmp");
+    std::fs::write(&partial, serde_json::to_vec(&entry)?)?;
+    std::fs::rename(&partial, &entry_path)?;
+    Ok(())
+}
+
+/// Forgets the journaled copy of `path`, e.g. after it was saved. Missing entries are fine.
+pub fn clear_buffer(vault_root: &Path, path: &Path) -> Result<(), OnyxError> {
+    let (entry_path, _) = entry_location(vault_root, path)?;
+    match std::fs::remove_file(&entry_path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Journaled buffers that differ from their files, oldest first. Entries that match the file
+/// on disk are stale and removed; unreadable ones are skipped.
+pub fn list_recoverable_buffers(vault_root: &Path) -> Result<Vec<RecoverableBuffer>, OnyxError> {
+    let dir = vault_root.join(RECOVERY_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut buffers = Vec::new();
+    for item in std::fs::read_dir(&dir)? {
+        let entry_path = item?.path();
+        if entry_path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let entry: JournalEntry = match std::fs::read(&entry_path)
+            .map_err(OnyxError::from)
+            .and_then(|bytes| Ok(serde_json::from_slice(&bytes)?))
+        {
+            Ok(entry) => entry,
+            Err(e) => {
+                warn!("Skipping recovery entry {}: {e}", entry_path.display());
+                continue;
+            }
+        };
+        let path = vault_root.join(&entry.path);
+        let on_disk = std::fs::read_to_string(&path).ok();
+        if on_disk.as_deref() == Some(entry.content.as_str()) {
+            std::fs::remove_file(&entry_path)?;
+            continue;
+        }
+        buffers.push(RecoverableBuffer {
+            path: path.to_string_lossy().to_string(),
+            diff: diff_lines(on_disk.as_deref().unwrap_or_default(), &entry.content),
+            file_exists: on_disk.is_some(),
+            content: entry.content,
+            saved_secs: entry.saved_secs,
+        });
+    }
+    buffers.sort_by(|a, b| (a.saved_secs, &a.path).cmp(&(b.saved_secs, &b.path)));
+    info!(
+        "Found {} recoverable buffers in {}",
+        buffers.len(),
+        vault_root.display()
+    );
+    Ok(buffers)
+}
+
+/// Writes the journaled text of `path` back to the file and clears the entry; returns the text.
+pub fn restore_buffer(vault_root: &Path, path: &Path) -> Result<String, OnyxError> {
+    let (entry_path, _) = entry_location(vault_root, path)?;
+    let entry: JournalEntry = serde_json::from_slice(&std::fs::read(&entry_path)?)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, &entry.content)?;
+    std::fs::remove_file(&entry_path)?;
+    info!("Restored unsaved changes to {}", path.display());
+    Ok(entry.content)
+}
+
+/// Line-based diff from `old` to `new` via their longest common subsequence.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len().saturating_mul(new.len()) > MAX_DIFF_CELLS {
+        return old
+            .iter()
+            .map(|line| DiffLine::Removed(line.to_string()))
+            .chain(new.iter().map(|line| DiffLine::Added(line.to_string())))
+            .collect();
+    }
+    // common[i][j]: length of the longest common subsequence of old[i..] and new[j..].
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut diff = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Same(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || common[i][j + 1] >= common[i + 1][j]) {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        } else {
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        }
+    }
+    diff
+}
+
+/// The journal file for `path` (named by a hash of its vault-relative path) and that path.
+fn entry_location(vault_root: &Path, path: &Path) -> Result<(PathBuf, String), OnyxError> {
+    let root = normalize_path(vault_root);
+    let path = normalize_path(path);
+    let Ok(relative) = path.strip_prefix(&root) else {
+        return Err(OnyxError::OutsideVault(path));
+    };
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let name = format!("{:x}.json", Md5::digest(relative.as_bytes()));
+    Ok((root.join(RECOVERY_DIR).join(name), relative))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn journaled_buffers_are_listed_until_saved_or_restored() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let note = root.join("Plan.md");
+        std::fs::write(&note, "# Plan\nShip it\n").unwrap();
+
+        journal_buffer(root, &note, "# Plan\nShip it today\n").unwrap();
+        journal_buffer(root, &note, "# Plan\nShip it tomorrow\n").unwrap();
+        let buffers = list_recoverable_buffers(root).unwrap();
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(buffers[0].path, note.to_string_lossy());
+        assert_eq!(buffers[0].content, "# Plan\nShip it tomorrow\n");
+        assert!(buffers[0].file_exists);
+
+        clear_buffer(root, &note).unwrap();
+        clear_buffer(root, &note).unwrap();
+        assert!(list_recoverable_buffers(root).unwrap().is_empty());
+
+        journal_buffer(root, &note, "draft").unwrap();
+        assert_eq!(restore_buffer(root, &note).unwrap(), "draft");
+        assert_eq!(std::fs::read_to_string(&note).unwrap(), "draft");
+        assert!(list_recoverable_buffers(root).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stale_and_outside_entries_are_not_offered() {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        let note = vault.join("Inbox/new.md");
+
+        journal_buffer(&vault, &note, "never saved").unwrap();
+        let buffers = list_recoverable_buffers(&vault).unwrap();
+        assert!(!buffers[0].file_exists);
+        assert_eq!(
+            buffers[0].diff,
+            [DiffLine::Added("never saved".to_string())]
+        );
+
+        std::fs::create_dir_all(note.parent().unwrap()).unwrap();
+        std::fs::write(&note, "never saved").unwrap();
+        assert!(list_recoverable_buffers(&vault).unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_dir(vault.join(RECOVERY_DIR)).unwrap().count(),
+            0
+        );
+
+        let outside = temp.path().join("elsewhere.md");
+        assert!(matches!(
+            journal_buffer(&vault, &outside, "x"),
+            Err(OnyxError::OutsideVault(_))
+        ));
+    }
+
+    #[test]
+    fn diffs_keep_common_lines() {
+        let diff = diff_lines("a\nb\nc\n", "a\nc\nd\n");
+        assert_eq!(
+            diff,
+            [
+                DiffLine::Same("a".to_string()),
+                DiffLine::Removed("b".to_string()),
+                DiffLine::Same("c".to_string()),
+                DiffLine::Added("d".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::error::OnyxError;
+use crate::link_resolver::is_markdown;
+use crate::pdf_text::page_texts;
+
+/// Characters of context kept on each side of the first match in a snippet.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// One matching line of a note or page of a PDF.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    /// 1-based line, for notes.
+    pub line: Option<usize>,
+    /// 1-based page, for PDFs.
+    pub page: Option<u32>,
+    pub snippet: String,
+}
+
+/// Case-insensitive search across notes (line by line) and PDF text (page by page).
+/// A line or page matches when it contains every word of `query`.
+pub fn search_vault(
+    vault_root: &Path,
+    query: &str,
+    limit: usize,
+) -> Result<Vec<SearchHit>, OnyxError> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+    let mut hits = Vec::new();
+    if terms.is_empty() {
+        return Ok(hits);
+    }
+    for path in searchable_files(vault_root) {
+        if hits.len() >= limit {
+            break;
+        }
+        let path_string = path.to_string_lossy().to_string();
+        if is_markdown(&path) {
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Skipping unreadable note {} in search: {e}", path.display());
+                    continue;
+                }
+            };
+            for (index, line) in content.lines().enumerate() {
+                if let Some(snippet) = matching_snippet(line, &terms) {
+                    hits.push(SearchHit {
+                        path: path_string.clone(),
+                        line: Some(index + 1),
+                        page: None,
+                        snippet,
+                    });
+                }
+            }
+        } else {
+            let pages = match page_texts(vault_root, &path) {
+                Ok(pages) => pages,
+                Err(e) => {
+                    warn!("Skipping unreadable PDF {} in search: {e}", path.display());
+                    continue;
+                }
+            };
+            for (index, text) in pages.iter().enumerate() {
+                if let Some(snippet) = matching_snippet(text, &terms) {
+                    hits.push(SearchHit {
+                        path: path_string.clone(),
+                        line: None,
+                        page: Some(index as u32 + 1),
+                        snippet,
+                    });
+                }
+            }
+        }
+    }
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+/// Notes and PDFs in the vault, skipping hidden folders, sorted by path.
+fn searchable_files(vault_root: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(walkdir::DirEntry::into_path)
+        .filter(|path| {
+            is_markdown(path)
+                || path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// The text around the first term, with whitespace collapsed, if `text` contains every term.
+fn matching_snippet(text: &str, terms: &[String]) -> Option<String> {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let lower = collapsed.to_lowercase();
+    if !terms.iter().all(|term| lower.contains(term.as_str())) {
+        return None;
+    }
+    // Lowercasing can change byte lengths outside ASCII; fall back to the start of the text.
+    let found = lower.find(terms[0].as_str()).unwrap_or(0);
+    let position = if collapsed.is_char_boundary(found) {
+        found
+    } else {
+        0
+    };
+    let before: Vec<char> = collapsed[..position].chars().collect();
+    let start = before.len().saturating_sub(SNIPPET_CONTEXT);
+    let prefix: String = before[start..].iter().collect();
+    let rest: String = collapsed[position..]
+        .chars()
+        .take(SNIPPET_CONTEXT + terms[0].chars().count())
+        .collect();
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&prefix);
+    snippet.push_str(&rest);
+    if position + rest.len() < collapsed.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdf_text::tests::write_pdf;
+    use tempfile::TempDir;
+
+    #[test]
+    fn finds_note_lines_and_pdf_pages() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(
+            temp.path().join("Ideas.md"),
+            "# Ideas\n\nRead about Quantum entanglement\nunrelated\n",
+        )
+        .unwrap();
+        write_pdf(
+            &temp.path().join("paper.pdf"),
+            &["Introduction", "Entanglement in quantum systems"],
+        );
+
+        let hits = search_vault(temp.path(), "quantum ENTANGLEMENT", 50).unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].line, Some(3));
+        assert_eq!(hits[0].snippet, "Read about Quantum entanglement");
+        assert!(hits[1].path.ends_with("paper.pdf"));
+        assert_eq!(hits[1].page, Some(2));
+    }
+
+    #[test]
+    fn long_lines_are_trimmed_around_the_match() {
+        let text = format!("{} needle {}", "a".repeat(100), "b".repeat(100));
+        let snippet = matching_snippet(&text, &["needle".to_string()]).unwrap();
+
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+    }
+
+    #[test]
+    fn unreadable_notes_are_skipped() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("a.md"), b"needle \xff\xfe").unwrap();
+        std::fs::write(temp.path().join("b.md"), "needle").unwrap();
+
+        let hits = search_vault(temp.path(), "needle", 10).unwrap();
+
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].path.ends_with("b.md"));
+    }
+
+    #[test]
+    fn empty_query_has_no_hits() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("a.md"), "text").unwrap();
+        assert!(search_vault(temp.path(), "  ", 10).unwrap().is_empty());
+    }
+}
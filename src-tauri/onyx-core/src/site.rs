@@ -0,0 +1,536 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::error::OnyxError;
+use crate::export_html::{
+    collect_notes, encode_path, index_listing, page_document, page_path, render_note, root_prefix,
+    stylesheet, AssetSink, BrokenLink, PageContext, RenderedNote,
+};
+use crate::frontmatter::{frontmatter_tags, is_truthy, parse_frontmatter, Frontmatter};
+use crate::link_resolver::LinkResolver;
+use crate::markdown::{escape_html, plain_text};
+use crate::tag_index::extract_tags;
+
+const SEARCH_PAGE_SCRIPT: &str = r#"
+<input id="q" type="search" placeholder="Search" autofocus>
+<ul id="results"></ul>
+<script>
+fetch("search-index.json").then((r) => r.json()).then((notes) => {
+  const input = document.getElementById("q");
+  const results = document.getElementById("results");
+  input.addEventListener("input", () => {
+    const terms = input.value.toLowerCase().split(/\s+/).filter(Boolean);
+    results.innerHTML = "";
+    if (!terms.length) return;
+    for (const note of notes) {
+      const haystack = (note.title + " " + note.tags.join(" ") + " " + note.text).toLowerCase();
+      if (!terms.every((term) => haystack.includes(term))) continue;
+      const item = document.createElement("li");
+      const link = document.createElement("a");
+      link.href = note.url;
+      link.textContent = note.title;
+      item.appendChild(link);
+      results.appendChild(item);
+    }
+  });
+});
+</script>
+"#;
+
+/// Which notes of the vault get published.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum PublishFilter {
+    /// Every note under this folder, relative to the vault root.
+    Folder(String),
+    /// Every note carrying this tag in its body or frontmatter.
+    Tag(String),
+    /// Every note whose frontmatter sets `publish: true`.
+    Frontmatter,
+}
+
+/// Caller-tunable knobs for a static site build.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SiteOptions {
+    pub filter: PublishFilter,
+    /// Site title shown on the home page; defaults to the vault directory name.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Absolute URL the site will be served from; sitemap entries are relative without it.
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+/// Summary of a site build, returned to the frontend.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SiteReport {
+    pub pages: Vec<String>,
+    pub tags: Vec<String>,
+    /// Links from published notes to notes that are missing or unpublished.
+    pub broken_links: Vec<BrokenLink>,
+    pub missing_assets: Vec<String>,
+}
+
+/// One entry of `search-index.json`.
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchEntry {
+    title: String,
+    url: String,
+    tags: Vec<String>,
+    text: String,
+}
+
+/// A vault note together with the metadata the publish filter and templates need.
+struct SiteNote {
+    path: PathBuf,
+    content: String,
+    title: String,
+    tags: BTreeSet<String>,
+}
+
+/// Builds a self-contained static site from the notes of `vault_root` selected by the filter.
+/// Links to unpublished notes are reported and rendered as plain text, never emitted as links.
+pub fn build_site(
+    vault_root: &Path,
+    dest: &Path,
+    options: &SiteOptions,
+    theme_json: &str,
+) -> Result<SiteReport, OnyxError> {
+    let mut notes = Vec::new();
+    for path in collect_notes(&[vault_root.to_path_buf()]) {
+        let content = std::fs::read_to_string(&path)?;
+        let frontmatter = parse_frontmatter(&content);
+        if is_published(vault_root, &path, &content, &frontmatter, &options.filter) {
+            notes.push(site_note(path, content, &frontmatter));
+        }
+    }
+
+    let pages: HashMap<PathBuf, String> = notes
+        .iter()
+        .map(|note| (note.path.clone(), page_path(vault_root, &note.path)))
+        .collect();
+    let resolver = LinkResolver::build(vault_root);
+    let stylesheet = stylesheet(theme_json);
+    let mut assets = AssetSink::new(dest, false);
+    let mut report = SiteReport::default();
+
+    std::fs::create_dir_all(dest)?;
+    let mut rendered: Vec<RenderedNote> = Vec::with_capacity(notes.len());
+    for note in &notes {
+        let context = PageContext {
+            note: &note.path,
+            root_prefix: root_prefix(&pages[&note.path]),
+            pages: &pages,
+            resolver: &resolver,
+        };
+        rendered.push(render_note(&note.content, &context, &mut assets)?);
+    }
+
+    let mut backlinks: HashMap<PathBuf, BTreeSet<usize>> = HashMap::new();
+    for (source, page) in rendered.iter().enumerate() {
+        for target in &page.links {
+            if target != &notes[source].path {
+                backlinks.entry(target.clone()).or_default().insert(source);
+            }
+        }
+    }
+
+    let mut tag_pages: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut search_index = Vec::with_capacity(notes.len());
+    for (position, (note, page)) in notes.iter().zip(rendered).enumerate() {
+        let page_rel = &pages[&note.path];
+        let prefix = root_prefix(page_rel);
+        let mut body = page.html;
+        body.push_str(&tag_list(&note.tags, &prefix));
+        if let Some(sources) = backlinks.get(&note.path) {
+            body.push_str("<section class=\"onyx-backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+            for &source in sources {
+                body.push_str(&format!(
+                    "<li><a href=\"{prefix}{}\">{}</a></li>\n",
+                    encode_path(&pages[&notes[source].path]),
+                    escape_html(&notes[source].title)
+                ));
+            }
+            body.push_str("</ul>\n</section>\n");
+        }
+        let output = dest.join(page_rel);
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(
+            &output,
+            page_document(&note.title, &stylesheet, &site_nav(&prefix), &body),
+        )?;
+
+        for tag in &note.tags {
+            tag_pages.entry(tag.as_str()).or_default().push(position);
+        }
+        search_index.push(SearchEntry {
+            title: note.title.clone(),
+            url: encode_path(page_rel),
+            tags: note.tags.iter().cloned().collect(),
+            text: plain_text(&note.content),
+        });
+        report.pages.push(output.to_string_lossy().to_string());
+        report.missing_assets.extend(page.missing_assets);
+        report
+            .broken_links
+            .extend(page.broken_links.into_iter().map(|target| BrokenLink {
+                source: note.path.to_string_lossy().to_string(),
+                target,
+            }));
+    }
+
+    write_tag_pages(dest, &stylesheet, &tag_pages, &notes, &pages)?;
+    report.tags = tag_pages.keys().map(|tag| tag.to_string()).collect();
+
+    let title = options.title.clone().unwrap_or_else(|| {
+        vault_root
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Onyx site")
+            .to_string()
+    });
+    let home = index_listing(&title, pages.values().map(String::as_str));
+    std::fs::write(
+        dest.join("index.html"),
+        page_document(&title, &stylesheet, &site_nav(""), &home),
+    )?;
+    std::fs::write(
+        dest.join("search.html"),
+        page_document("Search", &stylesheet, &site_nav(""), SEARCH_PAGE_SCRIPT),
+    )?;
+    std::fs::write(
+        dest.join("search-index.json"),
+        serde_json::to_string(&search_index)?,
+    )?;
+    std::fs::write(
+        dest.join("sitemap.xml"),
+        sitemap(options.base_url.as_deref(), pages.values()),
+    )?;
+
+    info!(
+        "Built site with {} pages and {} tags at {}",
+        report.pages.len(),
+        report.tags.len(),
+        dest.display()
+    );
+    Ok(report)
+}
+
+fn site_note(path: PathBuf, content: String, frontmatter: &Frontmatter) -> SiteNote {
+    let mut tags: BTreeSet<String> = extract_tags(&content).into_iter().collect();
+    tags.extend(frontmatter_tags(frontmatter));
+    let title = frontmatter
+        .get("title")
+        .and_then(|title| title.as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or("Untitled")
+                .to_string()
+        });
+    SiteNote {
+        path,
+        content,
+        title,
+        tags,
+    }
+}
+
+/// Applies the publish filter; an explicit `publish: false` always wins.
+fn is_published(
+    vault_root: &Path,
+    path: &Path,
+    content: &str,
+    frontmatter: &Frontmatter,
+    filter: &PublishFilter,
+) -> bool {
+    if frontmatter.get("publish") == Some(&serde_json::Value::Bool(false)) {
+        return false;
+    }
+    match filter {
+        PublishFilter::Folder(folder) => {
+            let folder = vault_root.join(folder.trim_matches('/'));
+            path.starts_with(folder)
+        }
+        PublishFilter::Tag(tag) => {
+            let wanted = tag.trim_start_matches('#').to_lowercase();
+            extract_tags(content)
+                .into_iter()
+                .chain(frontmatter_tags(frontmatter))
+                .any(|tag| {
+                    let tag = tag.to_lowercase();
+                    tag == wanted || tag.starts_with(&format!("{wanted}/"))
+                })
+        }
+        PublishFilter::Frontmatter => is_truthy(frontmatter, "publish"),
+    }
+}
+
+fn site_nav(prefix: &str) -> String {
+    format!(
+        "<a href=\"{prefix}index.html\">Home</a> · <a href=\"{prefix}tags/index.html\">Tags</a> · \
+         <a href=\"{prefix}search.html\">Search</a>"
+    )
+}
+
+/// File name of a tag's page; characters outside `[A-Za-z0-9_-]` are replaced with `-`.
+fn tag_file_name(tag: &str) -> String {
+    let safe: String = tag
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || character == '_' || character == '-' {
+                character
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("{safe}.html")
+}
+
+fn tag_list(tags: &BTreeSet<String>, prefix: &str) -> String {
+    if tags.is_empty() {
+        return String::new();
+    }
+    let links: Vec<String> = tags
+        .iter()
+        .map(|tag| {
+            format!(
+                "<a href=\"{prefix}tags/{}\">#{}</a>",
+                tag_file_name(tag),
+                escape_html(tag)
+            )
+        })
+        .collect();
+    format!("<p class=\"onyx-tags\">{}</p>\n", links.join(" "))
+}
+
+fn write_tag_pages(
+    dest: &Path,
+    stylesheet: &str,
+    tag_pages: &BTreeMap<&str, Vec<usize>>,
+    notes: &[SiteNote],
+    pages: &HashMap<PathBuf, String>,
+) -> Result<(), OnyxError> {
+    let tags_dir = dest.join("tags");
+    std::fs::create_dir_all(&tags_dir)?;
+    let mut overview = String::from("<h1>Tags</h1>\n<ul>\n");
+    for (tag, members) in tag_pages {
+        overview.push_str(&format!(
+            "<li><a href=\"{}\">#{}</a> ({})</li>\n",
+            tag_file_name(tag),
+            escape_html(tag),
+            members.len()
+        ));
+        let mut body = format!("<h1>#{}</h1>\n<ul>\n", escape_html(tag));
+        for &member in members {
+            body.push_str(&format!(
+                "<li><a href=\"../{}\">{}</a></li>\n",
+                encode_path(&pages[&notes[member].path]),
+                escape_html(&notes[member].title)
+            ));
+        }
+        body.push_str("</ul>\n");
+        std::fs::write(
+            tags_dir.join(tag_file_name(tag)),
+            page_document(&format!("#{tag}"), stylesheet, &site_nav("../"), &body),
+        )?;
+    }
+    overview.push_str("</ul>\n");
+    std::fs::write(
+        tags_dir.join("index.html"),
+        page_document("Tags", stylesheet, &site_nav("../"), &overview),
+    )?;
+    Ok(())
+}
+
+fn sitemap<'a>(base_url: Option<&str>, pages: impl Iterator<Item = &'a String>) -> String {
+    let base = base_url
+        .map(|url| format!("{}/", url.trim_end_matches('/')))
+        .unwrap_or_default();
+    let mut sorted: Vec<&String> = pages.collect();
+    sorted.sort();
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    xml.push_str(&format!(
+        "  <url><loc>{}index.html</loc></url>\n",
+        escape_html(&base)
+    ));
+    for page in sorted {
+        xml.push_str(&format!(
+            "  <url><loc>{}</loc></url>\n",
+            escape_html(&format!("{base}{}", encode_path(page)))
+        ));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::create_dir_all(root.join("private")).unwrap();
+        std::fs::write(
+            root.join("docs/Intro.md"),
+            "---\ntitle: Introduction\n---\n# Intro #guide\n\nRead [[Setup]] then [[Diary]].\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docs/Setup.md"),
+            "# Setup\n\nBack to [[Intro]].\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("docs/Draft.md"),
+            "---\npublish: false\n---\nunfinished",
+        )
+        .unwrap();
+        std::fs::write(root.join("private/Diary.md"), "secret #guide").unwrap();
+        temp
+    }
+
+    fn options(filter: PublishFilter) -> SiteOptions {
+        SiteOptions {
+            filter,
+            title: Some("Docs".into()),
+            base_url: Some("https://example.com/docs/".into()),
+        }
+    }
+
+    #[test]
+    fn folder_filter_publishes_folder_and_reports_broken_links() {
+        let temp = setup_vault();
+        let dest = temp.path().join("site");
+
+        let report = build_site(
+            temp.path(),
+            &dest,
+            &options(PublishFilter::Folder("docs".into())),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(
+            report.pages.len(),
+            2,
+            "draft with publish: false is skipped"
+        );
+        assert_eq!(report.broken_links.len(), 1);
+        assert_eq!(report.broken_links[0].target, "Diary");
+        let intro = std::fs::read_to_string(dest.join("docs/Intro.html")).unwrap();
+        assert!(!intro.contains("Diary.html"));
+        assert!(intro.contains("<title>Introduction</title>"));
+    }
+
+    #[test]
+    fn pages_list_their_backlinks() {
+        let temp = setup_vault();
+        let dest = temp.path().join("site");
+
+        build_site(
+            temp.path(),
+            &dest,
+            &options(PublishFilter::Folder("docs".into())),
+            "{}",
+        )
+        .unwrap();
+
+        let setup = std::fs::read_to_string(dest.join("docs/Setup.html")).unwrap();
+        assert!(setup.contains("<h2>Backlinks</h2>"));
+        assert!(setup.contains(r#"<a href="../docs/Intro.html">Introduction</a>"#));
+    }
+
+    #[test]
+    fn tag_filter_builds_tag_pages() {
+        let temp = setup_vault();
+        let dest = temp.path().join("site");
+
+        let report = build_site(
+            temp.path(),
+            &dest,
+            &options(PublishFilter::Tag("#guide".into())),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(report.pages.len(), 2);
+        assert_eq!(report.tags, vec!["guide"]);
+        let tag_page = std::fs::read_to_string(dest.join("tags/guide.html")).unwrap();
+        assert!(tag_page.contains("../docs/Intro.html"));
+        assert!(tag_page.contains("../private/Diary.html"));
+    }
+
+    #[test]
+    fn frontmatter_filter_requires_publish_true() {
+        let temp = setup_vault();
+        std::fs::write(
+            temp.path().join("docs/Public.md"),
+            "---\npublish: true\n---\nhello",
+        )
+        .unwrap();
+        let dest = temp.path().join("site");
+
+        let report = build_site(
+            temp.path(),
+            &dest,
+            &options(PublishFilter::Frontmatter),
+            "{}",
+        )
+        .unwrap();
+
+        assert_eq!(report.pages.len(), 1);
+        assert!(report.pages[0].ends_with("Public.html"));
+    }
+
+    #[test]
+    fn writes_search_index_and_sitemap() {
+        let temp = setup_vault();
+        let dest = temp.path().join("site");
+
+        build_site(
+            temp.path(),
+            &dest,
+            &options(PublishFilter::Folder("docs".into())),
+            "{}",
+        )
+        .unwrap();
+
+        let index: Vec<SearchEntry> =
+            serde_json::from_str(&std::fs::read_to_string(dest.join("search-index.json")).unwrap())
+                .unwrap();
+        let intro = index
+            .iter()
+            .find(|entry| entry.title == "Introduction")
+            .unwrap();
+        assert_eq!(intro.url, "docs/Intro.html");
+        assert_eq!(intro.tags, vec!["guide"]);
+        assert!(intro.text.contains("Read Setup then Diary."));
+
+        let sitemap = std::fs::read_to_string(dest.join("sitemap.xml")).unwrap();
+        assert!(sitemap.contains("<loc>https://example.com/docs/docs/Setup.html</loc>"));
+        assert!(dest.join("search.html").exists());
+    }
+
+    #[test]
+    fn publish_filter_deserializes_from_tagged_json() {
+        let filter: PublishFilter =
+            serde_json::from_str(r#"{"kind":"folder","value":"docs"}"#).unwrap();
+        assert_eq!(filter, PublishFilter::Folder("docs".into()));
+        let filter: PublishFilter = serde_json::from_str(r#"{"kind":"frontmatter"}"#).unwrap();
+        assert_eq!(filter, PublishFilter::Frontmatter);
+    }
+}
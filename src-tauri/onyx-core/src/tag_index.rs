@@ -3,15 +3,16 @@ use std::path::Path;
 
 use crate::error::OnyxError;
 
-/// Maps each file path to the set of tags found in that file, enabling O(1) incremental updates on save.
+/// Maps each file path to how often each tag occurs in that file, enabling O(1) incremental
+/// updates on save and per-edit updates from the lines an edit touched.
 pub struct TagIndex {
-    file_tags: HashMap<String, HashSet<String>>,
+    file_tags: HashMap<String, HashMap<String, usize>>,
 }
 
 impl TagIndex {
     /// Walks all `.md` files under `vault_root` and builds the initial index.
     pub fn build(vault_root: &Path) -> Result<Self, OnyxError> {
-        let mut file_tags: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut file_tags: HashMap<String, HashMap<String, usize>> = HashMap::new();
 
         for entry in walkdir::WalkDir::new(vault_root)
             .into_iter()
@@ -22,7 +23,7 @@ impl TagIndex {
                 continue;
             }
             let content = std::fs::read_to_string(path)?;
-            let tags = extract_tags(&content);
+            let tags = count_tags(&content);
             if !tags.is_empty() {
                 file_tags.insert(path.to_string_lossy().to_string(), tags);
             }
@@ -33,7 +34,7 @@ impl TagIndex {
 
     /// Replaces the tag set for a single file; called after every save so no full re-scan is needed.
     pub fn update_file(&mut self, path: &str, content: &str) {
-        let tags = extract_tags(content);
+        let tags = count_tags(content);
         if tags.is_empty() {
             self.file_tags.remove(path);
         } else {
@@ -41,11 +42,31 @@ impl TagIndex {
         }
     }
 
+    /// Updates a file's tags after an edit replaced the whole lines `old_text` with `new_text`.
+    /// Tags never span lines, so only the touched lines need scanning.
+    pub fn apply_delta(&mut self, path: &str, old_text: &str, new_text: &str) {
+        let counts = self.file_tags.entry(path.to_string()).or_default();
+        for (tag, removed) in count_tags(old_text) {
+            if let Some(count) = counts.get_mut(&tag) {
+                *count = count.saturating_sub(removed);
+                if *count == 0 {
+                    counts.remove(&tag);
+                }
+            }
+        }
+        for (tag, added) in count_tags(new_text) {
+            *counts.entry(tag).or_default() += added;
+        }
+        if counts.is_empty() {
+            self.file_tags.remove(path);
+        }
+    }
+
     /// Returns a sorted, deduplicated list of every tag across all indexed files.
     pub fn all_tags(&self) -> Vec<String> {
         let mut tags: HashSet<&str> = HashSet::new();
-        for tag_set in self.file_tags.values() {
-            for tag in tag_set {
+        for counts in self.file_tags.values() {
+            for tag in counts.keys() {
                 tags.insert(tag.as_str());
             }
         }
@@ -58,9 +79,14 @@ impl TagIndex {
 /// Scans `content` for tokens matching `#[a-zA-Z][a-zA-Z0-9_-]*`.
 /// The leading `#` is excluded from the returned tag strings.
 pub fn extract_tags(content: &str) -> HashSet<String> {
+    count_tags(content).into_keys().collect()
+}
+
+/// Like [`extract_tags`], but with the number of times each tag occurs.
+pub fn count_tags(content: &str) -> HashMap<String, usize> {
     let chars: Vec<char> = content.chars().collect();
     let len = chars.len();
-    let mut tags = HashSet::new();
+    let mut tags = HashMap::new();
     let mut index = 0;
 
     while index < len {
@@ -91,7 +117,7 @@ pub fn extract_tags(content: &str) -> HashSet<String> {
         }
 
         let tag: String = chars[start..end].iter().collect();
-        tags.insert(tag);
+        *tags.entry(tag).or_default() += 1;
         index = end;
     }
 
@@ -162,4 +188,18 @@ mod tests {
         let tags = index.all_tags();
         assert_eq!(tags, vec!["apple", "mango", "zebra"]);
     }
+
+    #[test]
+    fn tag_index_applies_line_deltas() {
+        let mut index = TagIndex {
+            file_tags: HashMap::new(),
+        };
+        index.update_file("/tmp/a.md", "#rust here\n#rust and #go\n");
+        index.apply_delta("/tmp/a.md", "#rust and #go\n", "and #zig\n");
+        assert_eq!(index.all_tags(), vec!["rust", "zig"]);
+
+        index.apply_delta("/tmp/a.md", "#rust here\nand #zig\n", "plain\n");
+        assert!(index.all_tags().is_empty());
+        assert!(index.file_tags.is_empty());
+    }
 }
@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::buffer::{Buffer, LineDelta};
+use crate::error::OnyxError;
+use crate::link_resolver::is_markdown;
+use crate::tag_index::extract_tags;
+
+/// A `- [ ]` / `- [x]` checklist item in a note.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Task {
+    pub path: String,
+    /// 1-based line of the checkbox.
+    pub line: usize,
+    /// The item's text after the checkbox.
+    pub text: String,
+    pub completed: bool,
+    /// Sorted tags found in the text, without the leading `#`.
+    pub tags: Vec<String>,
+    /// Due date as `YYYY-MM-DD`, from `📅 2026-10-20` or `due:2026-10-20`.
+    pub due: Option<String>,
+    /// Line of the enclosing task when this is a subtask.
+    pub parent_line: Option<usize>,
+}
+
+/// Whether to return open tasks, completed tasks or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Open,
+    Completed,
+}
+
+/// Criteria for `query_tasks`; every field that is set must match. Due bounds are inclusive
+/// `YYYY-MM-DD` dates and exclude tasks without a due date.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+    pub status: Option<TaskStatus>,
+    /// Tag without the leading `#`, matched case-insensitively.
+    pub tag: Option<String>,
+    /// Vault-relative folder; tasks in nested folders match too.
+    pub folder: Option<String>,
+    pub due_after: Option<String>,
+    pub due_before: Option<String>,
+}
+
+/// Maps each note to its tasks so saves and toggles only re-parse one file.
+pub struct TaskIndex {
+    root: PathBuf,
+    file_tasks: HashMap<String, Vec<Task>>,
+}
+
+impl TaskIndex {
+    /// Walks every non-hidden note under `vault_root` and extracts its tasks.
+    pub fn build(vault_root: &Path) -> Result<Self, OnyxError> {
+        let mut index = Self {
+            root: vault_root.to_path_buf(),
+            file_tasks: HashMap::new(),
+        };
+        for entry in walkdir::WalkDir::new(vault_root)
+            .into_iter()
+            .filter_entry(|entry| {
+                entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+            })
+            .filter_map(|entry| entry.ok())
+        {
+            if entry.file_type().is_file() && is_markdown(entry.path()) {
+                let content = std::fs::read_to_string(entry.path())?;
+                index.update_file(&entry.path().to_string_lossy(), &content);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Replaces the tasks of a single file; called after saves and toggles.
+    pub fn update_file(&mut self, path: &str, content: &str) {
+        let tasks = extract_tasks(path, content);
+        if tasks.is_empty() {
+            self.file_tasks.remove(path);
+        } else {
+            self.file_tasks.insert(path.to_string(), tasks);
+        }
+    }
+
+    /// Updates a file's tasks after an edit to its open `buffer`. Edits that add or remove no
+    /// task or fence lines only renumber the tasks below them; anything else, or an edit that
+    /// could change which task a later one nests under, re-parses the buffer.
+    pub fn apply_delta(&mut self, path: &str, delta: &LineDelta, buffer: &Buffer) {
+        if !self.shift_tasks(path, delta, buffer) {
+            self.update_file(path, &buffer.to_string());
+        }
+    }
+
+    /// Moves the tasks below an edit by the number of lines it added; false when that isn't enough.
+    fn shift_tasks(&mut self, path: &str, delta: &LineDelta, buffer: &Buffer) -> bool {
+        let structural = |text: &str| {
+            text.lines().any(|line| {
+                let trimmed = line.trim_start();
+                trimmed.starts_with("```")
+                    || trimmed.starts_with("~~~")
+                    || parse_task_line(line).is_some()
+            })
+        };
+        if structural(&delta.old_text) || structural(&delta.new_text) {
+            return false;
+        }
+        let Some(tasks) = self.file_tasks.get_mut(path) else {
+            return true;
+        };
+        // Tasks on 1-based lines past `old_end` sit below the edit.
+        let old_end = delta.start_line + delta.old_line_count;
+        let shift = |line: usize| line + delta.new_line_count - delta.old_line_count;
+        // A later task keeps its parent only if the first one after the edit starts a new chain.
+        if let Some(first) = tasks.iter().find(|task| task.line > old_end) {
+            let starts_chain = buffer
+                .line(shift(first.line) - 1)
+                .and_then(|line| parse_task_line(&line).map(|task| task.indent == 0));
+            if starts_chain != Some(true) {
+                return false;
+            }
+        }
+        for task in tasks.iter_mut().filter(|task| task.line > old_end) {
+            task.line = shift(task.line);
+            task.parent_line = task.parent_line.map(shift);
+        }
+        true
+    }
+
+    /// Whether `path` lies inside this index's vault.
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(&self.root)
+    }
+
+    /// Tasks matching `filter`, ordered by path and then line.
+    pub fn query(&self, filter: &TaskFilter) -> Vec<Task> {
+        let folder = filter
+            .folder
+            .as_deref()
+            .map(|folder| self.root.join(folder.trim_matches('/')));
+        let tag = filter.tag.as_deref().map(|tag| tag.trim_start_matches('#'));
+        let mut tasks: Vec<Task> = self
+            .file_tasks
+            .iter()
+            .filter(|(path, _)| {
+                folder
+                    .as_ref()
+                    .is_none_or(|folder| Path::new(path).starts_with(folder))
+            })
+            .flat_map(|(_, tasks)| tasks.iter())
+            .filter(|task| match filter.status {
+                Some(TaskStatus::Open) => !task.completed,
+                Some(TaskStatus::Completed) => task.completed,
+                None => true,
+            })
+            .filter(|task| {
+                tag.is_none_or(|tag| task.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            })
+            .filter(|task| {
+                let after = filter.due_after.as_deref();
+                let before = filter.due_before.as_deref();
+                if after.is_none() && before.is_none() {
+                    return true;
+                }
+                task.due.as_deref().is_some_and(|due| {
+                    after.is_none_or(|after| due >= after)
+                        && before.is_none_or(|before| due <= before)
+                })
+            })
+            .cloned()
+            .collect();
+        tasks.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+        tasks
+    }
+}
+
+/// The parts of a checklist line: indentation width, byte offset of the checkbox mark, whether
+/// it is checked, and the text after the checkbox.
+struct TaskLine<'a> {
+    indent: usize,
+    mark: usize,
+    completed: bool,
+    text: &'a str,
+}
+
+/// Parses `- [ ] text`, `* [x] text` or `1. [ ] text`, with any indentation.
+fn parse_task_line(line: &str) -> Option<TaskLine<'_>> {
+    let body = line.trim_start();
+    let leading = &line[..line.len() - body.len()];
+    let indent = leading.chars().map(|c| if c == '\t' { 4 } else { 1 }).sum();
+    let after_bullet = if let Some(rest) = body
+        .strip_prefix("- ")
+        .or_else(|| body.strip_prefix("* "))
+        .or_else(|| body.strip_prefix("+ "))
+    {
+        rest
+    } else {
+        let digits = body.len() - body.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return None;
+        }
+        body[digits..]
+            .strip_prefix(". ")
+            .or_else(|| body[digits..].strip_prefix(") "))?
+    };
+    let after_spaces = after_bullet.trim_start_matches(' ');
+    let bytes = after_spaces.as_bytes();
+    if bytes.len() < 3 || bytes[0] != b'[' || bytes[2] != b']' {
+        return None;
+    }
+    let completed = match bytes[1] {
+        b' ' => false,
+        b'x' | b'X' => true,
+        _ => return None,
+    };
+    let rest = &after_spaces[3..];
+    if !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    Some(TaskLine {
+        indent,
+        mark: line.len() - after_spaces.len() + 1,
+        completed,
+        text: rest.trim(),
+    })
+}
+
+/// A `YYYY-MM-DD` date at the start of `text`.
+fn leading_date(text: &str) -> Option<String> {
+    let candidate = text.get(..10)?;
+    let valid = candidate.char_indices().all(|(index, c)| match index {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    valid.then(|| candidate.to_string())
+}
+
+fn due_date(text: &str) -> Option<String> {
+    ["📅", "due:"].iter().find_map(|marker| {
+        text.match_indices(marker)
+            .find_map(|(index, _)| leading_date(text[index + marker.len()..].trim_start()))
+    })
+}
+
+/// Every checklist item in `content` outside fenced code blocks, with subtasks linked to the
+/// nearest less-indented task above them.
+pub fn extract_tasks(path: &str, content: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    // (indent, line) of the tasks enclosing the current position.
+    let mut parents: Vec<(usize, usize)> = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let Some(task) = parse_task_line(line) else {
+            if !trimmed.is_empty() && trimmed.len() == line.len() {
+                parents.clear();
+            }
+            continue;
+        };
+        while parents
+            .last()
+            .is_some_and(|(indent, _)| *indent >= task.indent)
+        {
+            parents.pop();
+        }
+        let mut tags: Vec<String> = extract_tags(task.text).into_iter().collect();
+        tags.sort();
+        tasks.push(Task {
+            path: path.to_string(),
+            line: index + 1,
+            text: task.text.to_string(),
+            completed: task.completed,
+            tags,
+            due: due_date(task.text),
+            parent_line: parents.last().map(|(_, line)| *line),
+        });
+        parents.push((task.indent, index + 1));
+    }
+    tasks
+}
+
+/// The task on 1-based `line` of a note, if that line is one.
+pub fn task_at(path: &str, content: &str, line: usize) -> Option<Task> {
+    extract_tasks(path, content)
+        .into_iter()
+        .find(|task| task.line == line)
+}
+
+/// Flips the checkbox on 1-based `line` of `path`, leaving every other byte of the file as it
+/// was, and returns the file's new content with the task as it now reads. Lines inside code
+/// fences are not tasks, so the file is left alone.
+pub fn toggle_task(path: &Path, line: usize) -> Result<(String, Task), OnyxError> {
+    let mut content = std::fs::read_to_string(path)?;
+    let name = path.to_string_lossy();
+    let not_a_task = || OnyxError::NotATask(path.to_path_buf(), line);
+    task_at(&name, &content, line).ok_or_else(not_a_task)?;
+    let start = content
+        .split_inclusive('\n')
+        .take(line - 1)
+        .map(str::len)
+        .sum::<usize>();
+    let text = content[start..].lines().next().unwrap_or_default();
+    let task = parse_task_line(text).ok_or_else(not_a_task)?;
+    let mark = if task.completed { " " } else { "x" };
+    content.replace_range(start + task.mark..start + task.mark + 1, mark);
+    let toggled = task_at(&name, &content, line).ok_or_else(not_a_task)?;
+    std::fs::write(path, &content)?;
+    Ok((content, toggled))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const NOTE: &str = "# Week\n\n- [ ] Ship release #work 📅 2026-10-20\n  - [x] Write changelog\n  - [ ] Tag build due:2026-10-18\n- [X] Call mom #family\n\n```\n- [ ] not a task\n```\n1. [ ] Numbered\n- [?] unknown\n";
+
+    #[test]
+    fn extracts_tasks_with_subtasks_tags_and_due_dates() {
+        let tasks = extract_tasks("/v/Week.md", NOTE);
+
+        let summary: Vec<(usize, bool, Option<usize>)> = tasks
+            .iter()
+            .map(|task| (task.line, task.completed, task.parent_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (3, false, None),
+                (4, true, Some(3)),
+                (5, false, Some(3)),
+                (6, true, None),
+                (11, false, None)
+            ]
+        );
+        assert_eq!(tasks[0].tags, vec!["work"]);
+        assert_eq!(tasks[0].due.as_deref(), Some("2026-10-20"));
+        assert_eq!(tasks[2].due.as_deref(), Some("2026-10-18"));
+        assert_eq!(tasks[2].text, "Tag build due:2026-10-18");
+    }
+
+    #[test]
+    fn line_deltas_keep_tasks_in_step_with_the_buffer() {
+        use crate::buffer::ChangeSet;
+
+        let path = "/v/Week.md";
+        let mut index = TaskIndex {
+            root: PathBuf::from("/v"),
+            file_tasks: HashMap::new(),
+        };
+        index.update_file(path, NOTE);
+        let mut buffer = Buffer::new(NOTE);
+        let mut insert_line = |index: &mut TaskIndex, line: usize, text: &str| {
+            let position = buffer
+                .line_to_char(line)
+                .and_then(|char_idx| buffer.char_to_utf16(char_idx))
+                .unwrap();
+            let changes = ChangeSet::default()
+                .retain(position)
+                .replace(0, text)
+                .retain(buffer.len_utf16() - position);
+            let delta = buffer.apply_edits(&changes).unwrap();
+            index.apply_delta(path, &delta, &buffer);
+            assert_eq!(
+                index.file_tasks[path],
+                extract_tasks(path, &buffer.to_string())
+            );
+        };
+
+        // Plain text above every task only renumbers them.
+        insert_line(&mut index, 0, "Intro\n\n");
+        // Unindented text between a task and its subtasks detaches them.
+        insert_line(&mut index, 5, "Aside\n");
+        // A new task is parsed.
+        insert_line(&mut index, 0, "- [ ] First\n");
+        // Opening a fence hides everything below it.
+        insert_line(&mut index, 2, "```\n");
+    }
+
+    #[test]
+    fn queries_filter_by_status_tag_folder_and_due_range() {
+        let temp = TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("Journal")).unwrap();
+        std::fs::write(temp.path().join("Journal/Week.md"), NOTE).unwrap();
+        std::fs::write(temp.path().join("Inbox.md"), "- [ ] Buy milk #family\n").unwrap();
+        let index = TaskIndex::build(temp.path()).unwrap();
+        let lines = |filter: TaskFilter| -> Vec<usize> {
+            index.query(&filter).iter().map(|task| task.line).collect()
+        };
+
+        let open = TaskFilter {
+            status: Some(TaskStatus::Open),
+            ..TaskFilter::default()
+        };
+        assert_eq!(lines(open), vec![1, 3, 5, 11]);
+        let family = TaskFilter {
+            tag: Some("#Family".to_string()),
+            ..TaskFilter::default()
+        };
+        assert_eq!(lines(family), vec![1, 6]);
+        let journal = TaskFilter {
+            folder: Some("Journal".to_string()),
+            status: Some(TaskStatus::Completed),
+            ..TaskFilter::default()
+        };
+        assert_eq!(lines(journal), vec![4, 6]);
+        let this_week = TaskFilter {
+            due_after: Some("2026-10-19".to_string()),
+            due_before: Some("2026-10-25".to_string()),
+            ..TaskFilter::default()
+        };
+        assert_eq!(lines(this_week), vec![3]);
+    }
+
+    #[test]
+    fn toggling_only_touches_the_checkbox() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Week.md");
+        let original = NOTE.replace('\n', "\r\n");
+        std::fs::write(&path, &original).unwrap();
+
+        let (toggled, task) = toggle_task(&path, 4).unwrap();
+
+        assert_eq!(toggled, original.replace("  - [x] Write", "  - [ ] Write"));
+        assert!(!task.completed);
+        toggle_task(&path, 4).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), original);
+        assert!(toggle_task(&path, 0).is_err());
+        assert!(toggle_task(&path, 1).is_err());
+        assert!(toggle_task(&path, 99).is_err());
+    }
+
+    #[test]
+    fn fenced_checkboxes_are_not_toggled() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("Week.md");
+        std::fs::write(&path, NOTE).unwrap();
+
+        let result = toggle_task(&path, 9);
+
+        assert!(matches!(result, Err(OnyxError::NotATask(_, 9))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), NOTE);
+    }
+}
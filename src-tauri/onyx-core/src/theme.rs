@@ -0,0 +1,43 @@
+use std::path::Path;
+
+use crate::error::OnyxError;
+
+/// Reads `~/.config/onyx/theme.json` and returns its contents as a raw JSON string.
+/// Returns "{}" if the file does not exist, so callers fall back to the default palette.
+pub fn load_theme() -> Result<String, OnyxError> {
+    let config_dir = dirs_next::config_dir().ok_or(OnyxError::NoHomeDir)?;
+    read_theme_from_dir(&config_dir)
+}
+
+/// Reads `onyx/theme.json` under `config_dir`, or "{}" when there is none.
+pub fn read_theme_from_dir(config_dir: &Path) -> Result<String, OnyxError> {
+    let path = config_dir.join("onyx").join("theme.json");
+    if !path.exists() {
+        return Ok("{}".to_string());
+    }
+    Ok(std::fs::read_to_string(&path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_theme_from_dir;
+    use std::fs;
+
+    #[test]
+    fn returns_empty_object_when_file_is_absent() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let result = read_theme_from_dir(dir.path()).unwrap();
+        assert_eq!(result, "{}");
+    }
+
+    #[test]
+    fn returns_raw_json_when_file_exists() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let onyx_dir = dir.path().join("onyx");
+        fs::create_dir_all(&onyx_dir).expect("create onyx dir");
+        let theme_path = onyx_dir.join("theme.json");
+        fs::write(&theme_path, r##"{"accent":"#ff0000"}"##).expect("write theme");
+        let result = read_theme_from_dir(dir.path()).unwrap();
+        assert_eq!(result, r##"{"accent":"#ff0000"}"##);
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use image::{ImageDecoder, ImageFormat, ImageReader};
+use log::info;
+use serde::Serialize;
+
+use crate::cache::cache_entry;
+use crate::error::OnyxError;
+use crate::link_resolver::normalize_path;
+
+/// Cache folder (under `.onyx/cache`) holding thumbnails.
+const THUMBNAIL_CACHE: &str = "thumbs";
+/// Longest edge of a thumbnail when the caller doesn't ask for a size.
+pub const DEFAULT_THUMBNAIL_SIZE: u32 = 256;
+/// Requested sizes are clamped to this range so a bad argument can't produce empty or huge images.
+const THUMBNAIL_SIZES: std::ops::RangeInclusive<u32> = 16..=2048;
+
+/// Size, timestamps and (for images) pixel metadata of a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileInfo {
+    pub path: String,
+    pub size: u64,
+    pub modified_secs: u64,
+    pub created_secs: u64,
+    pub image: Option<ImageInfo>,
+}
+
+/// Pixel dimensions as stored in the file, before any EXIF rotation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    /// EXIF orientation tag (1–8); 1 means the image is displayed as stored.
+    pub orientation: u8,
+}
+
+fn secs(time: std::io::Result<std::time::SystemTime>) -> u64 {
+    time.ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Reads a file's metadata; image headers are parsed without decoding the pixels.
+pub fn file_info(path: &Path) -> Result<FileInfo, OnyxError> {
+    let metadata = std::fs::metadata(path)?;
+    Ok(FileInfo {
+        path: path.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified_secs: secs(metadata.modified()),
+        created_secs: secs(metadata.created()),
+        image: image_info(path),
+    })
+}
+
+/// The image format for `path` if this build can decode it (SVG and AVIF are left to the webview).
+fn decodable_format(path: &Path) -> Option<ImageFormat> {
+    ImageFormat::from_path(path)
+        .ok()
+        .filter(|format| format.reading_enabled())
+}
+
+fn image_info(path: &Path) -> Option<ImageInfo> {
+    let format = decodable_format(path)?;
+    let mut reader = ImageReader::open(path).ok()?;
+    reader.set_format(format);
+    let mut decoder = reader.into_decoder().ok()?;
+    let (width, height) = decoder.dimensions();
+    let orientation = decoder
+        .orientation()
+        .map(|orientation| orientation.to_exif())
+        .unwrap_or(1);
+    Some(ImageInfo {
+        width,
+        height,
+        orientation,
+    })
+}
+
+/// Returns a cached, EXIF-rotated PNG preview of `image_path` no larger than `max_size` on
+/// either edge, generating it on first use. `max_size` is clamped to 16–2048 pixels. Cache
+/// entries are keyed by the file's vault path and modification time, so an edited image gets a
+/// fresh thumbnail. Returns `None` for formats that can't be decoded here.
+pub fn thumbnail(
+    vault_root: &Path,
+    image_path: &Path,
+    max_size: u32,
+) -> Result<Option<PathBuf>, OnyxError> {
+    let max_size = max_size.clamp(*THUMBNAIL_SIZES.start(), *THUMBNAIL_SIZES.end());
+    let image_path = normalize_path(image_path);
+    let cached = cache_entry(
+        vault_root,
+        THUMBNAIL_CACHE,
+        &image_path,
+        &format!("-{max_size}.png"),
+    )?;
+    let Some(format) = decodable_format(&image_path) else {
+        return Ok(None);
+    };
+    if cached.is_file() {
+        return Ok(Some(cached));
+    }
+
+    let mut reader = ImageReader::open(&image_path)?;
+    reader.set_format(format);
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = image::DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    let preview = image.thumbnail(max_size, max_size);
+
+    if let Some(parent) = cached.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    preview.save_with_format(&cached, ImageFormat::Png)?;
+    info!("Generated thumbnail for {}", image_path.display());
+    Ok(Some(cached))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CACHE_DIR;
+    use image::{Rgb, RgbImage};
+    use tempfile::TempDir;
+
+    fn write_image(path: &Path, width: u32, height: u32) {
+        RgbImage::from_pixel(width, height, Rgb([200, 10, 10]))
+            .save(path)
+            .unwrap();
+    }
+
+    #[test]
+    fn file_info_reports_size_and_dimensions() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("photo.png");
+        write_image(&path, 40, 30);
+
+        let info = file_info(&path).unwrap();
+
+        assert_eq!(info.size, std::fs::metadata(&path).unwrap().len());
+        assert_eq!(
+            info.image,
+            Some(ImageInfo {
+                width: 40,
+                height: 30,
+                orientation: 1
+            })
+        );
+    }
+
+    #[test]
+    fn file_info_has_no_image_section_for_other_files() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("notes.md");
+        std::fs::write(&path, "# Notes").unwrap();
+
+        assert_eq!(file_info(&path).unwrap().image, None);
+    }
+
+    #[test]
+    fn thumbnails_are_downscaled_and_cached() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("big.jpg");
+        write_image(&path, 800, 400);
+
+        let first = thumbnail(temp.path(), &path, 100).unwrap().unwrap();
+        let second = thumbnail(temp.path(), &path, 100).unwrap().unwrap();
+
+        assert_eq!(first, second);
+        assert!(first.starts_with(temp.path().join(CACHE_DIR).join(THUMBNAIL_CACHE)));
+        assert_eq!(image::image_dimensions(&first).unwrap(), (100, 50));
+    }
+
+    #[test]
+    fn thumbnail_sizes_are_clamped() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("wide.png");
+        write_image(&path, 4000, 40);
+
+        let tiny = thumbnail(temp.path(), &path, 0).unwrap().unwrap();
+        let huge = thumbnail(temp.path(), &path, u32::MAX).unwrap().unwrap();
+
+        assert_eq!(image::image_dimensions(&tiny).unwrap().0, 16);
+        assert_eq!(image::image_dimensions(&huge).unwrap(), (2048, 20));
+    }
+
+    #[test]
+    fn undecodable_formats_have_no_thumbnail() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("icon.svg");
+        std::fs::write(&path, "<svg/>").unwrap();
+
+        assert_eq!(thumbnail(temp.path(), &path, 64).unwrap(), None);
+    }
+}
@@ -35,6 +35,13 @@ impl Vault {
     }
 }
 
+/// The nearest ancestor of `path` (or `path` itself) holding a `.onyx/config.toml`.
+pub fn find_vault_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors()
+        .find(|dir| dir.join(".onyx").join("config.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +68,18 @@ mod tests {
         let vault = Vault::open(&vault_path).unwrap();
         assert_eq!(vault.config.name, "existing");
     }
+
+    #[test]
+    fn find_vault_root_walks_up_to_the_config() {
+        let temp = TempDir::new().unwrap();
+        let vault_path = temp.path().join("vault");
+        std::fs::create_dir_all(vault_path.join("notes")).unwrap();
+        ensure_vault_config(&vault_path).unwrap();
+
+        assert_eq!(
+            find_vault_root(&vault_path.join("notes/a.md")),
+            Some(vault_path)
+        );
+        assert_eq!(find_vault_root(temp.path()), None);
+    }
 }
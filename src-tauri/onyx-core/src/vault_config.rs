@@ -5,9 +5,53 @@ use serde::{Deserialize, Serialize};
 use crate::error::OnyxError;
 
 /// Per-vault settings stored at `<vault>/.onyx/config.toml`.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct VaultConfig {
     pub name: String,
+    /// Where pasted and dropped attachments are stored.
+    #[serde(default)]
+    pub attachments_folder: AttachmentsFolder,
+    /// Reuse an existing attachment with identical content instead of saving a copy.
+    #[serde(default)]
+    pub deduplicate_attachments: bool,
+    /// Folder new notes are created in, relative to the vault root; `None` means the vault root.
+    #[serde(default)]
+    pub new_note_folder: Option<String>,
+    /// Folder holding note templates, relative to the vault root.
+    #[serde(default)]
+    pub templates_folder: Option<String>,
+    #[serde(default)]
+    pub daily_notes: Option<DailyNotesConfig>,
+}
+
+/// Where new attachments are saved.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", content = "folder", rename_all = "snake_case")]
+pub enum AttachmentsFolder {
+    #[default]
+    VaultRoot,
+    /// A fixed folder relative to the vault root.
+    Folder(String),
+    /// A subfolder of the note's own folder; empty means next to the note.
+    NextToNote(String),
+}
+
+/// Where daily notes live and how they are named.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DailyNotesConfig {
+    /// Folder relative to the vault root; `None` means the vault root.
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// Moment.js-style date format used for the file name, e.g. `YYYY-MM-DD`.
+    #[serde(default = "default_daily_note_format")]
+    pub format: String,
+    /// Template note applied to new daily notes, relative to the vault root.
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+fn default_daily_note_format() -> String {
+    "YYYY-MM-DD".to_string()
 }
 
 /// UI session state stored at `<vault>/.onyx/session.toml`.
@@ -18,6 +62,9 @@ pub struct VaultSession {
     pub active_tab: Option<String>,
     /// The file sort order selected in the sidebar (e.g. "name-asc", "modified-desc").
     pub sort_order: Option<String>,
+    /// Bookmarked files and folders as absolute paths, optionally with a `#heading` suffix.
+    #[serde(default)]
+    pub bookmarks: Vec<String>,
 }
 
 /// Loads the session from `<vault>/.onyx/session.toml`, returning defaults if absent.
@@ -39,6 +86,15 @@ pub fn save_vault_session(vault_path: &Path, session: &VaultSession) -> Result<(
     Ok(())
 }
 
+/// Persists the vault config to `<vault>/.onyx/config.toml`.
+pub fn save_vault_config(vault_path: &Path, config: &VaultConfig) -> Result<(), OnyxError> {
+    let onyx_dir = vault_path.join(".onyx");
+    std::fs::create_dir_all(&onyx_dir)?;
+    let contents = toml::to_string_pretty(config)?;
+    std::fs::write(onyx_dir.join("config.toml"), contents)?;
+    Ok(())
+}
+
 /// Creates the `.onyx/` directory and default config file if they don't exist.
 pub fn ensure_vault_config(vault_path: &Path) -> Result<VaultConfig, OnyxError> {
     let onyx_dir = vault_path.join(".onyx");
@@ -57,7 +113,10 @@ pub fn ensure_vault_config(vault_path: &Path) -> Result<VaultConfig, OnyxError>
         .unwrap_or("vault")
         .to_string();
 
-    let config = VaultConfig { name };
+    let config = VaultConfig {
+        name,
+        ..VaultConfig::default()
+    };
     let contents = toml::to_string_pretty(&config)?;
     std::fs::write(&config_path, contents)?;
 
@@ -93,6 +152,21 @@ mod tests {
         assert_eq!(first, second);
     }
 
+    #[test]
+    fn attachments_folder_round_trips_through_toml() {
+        let temp = TempDir::new().unwrap();
+        let config = VaultConfig {
+            name: "notes".to_string(),
+            attachments_folder: AttachmentsFolder::NextToNote("assets".to_string()),
+            deduplicate_attachments: true,
+            ..VaultConfig::default()
+        };
+
+        save_vault_config(temp.path(), &config).unwrap();
+
+        assert_eq!(ensure_vault_config(temp.path()).unwrap(), config);
+    }
+
     #[test]
     fn load_vault_session_returns_default_when_missing() {
         let temp = TempDir::new().unwrap();
@@ -115,6 +189,7 @@ mod tests {
             open_tabs: vec!["/vault/a.md".to_string(), "/vault/b.md".to_string()],
             active_tab: Some("/vault/b.md".to_string()),
             sort_order: Some("modified-desc".to_string()),
+            bookmarks: vec!["/vault/a.md#Intro".to_string()],
         };
 
         save_vault_session(&vault_path, &session).unwrap();
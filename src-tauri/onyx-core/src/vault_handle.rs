@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use log::info;
+
+use crate::backlinks::{self, Backlink};
+use crate::buffer::{Buffer, ChangeSet, LineDelta, Replayed};
+use crate::error::OnyxError;
+use crate::file_tree::{self, FileTreeEntry};
+use crate::files;
+use crate::graph::{self, Graph, GraphFilter};
+use crate::link_resolver::normalize_path;
+use crate::query::{self, EmbeddedQuery, QueryResult};
+use crate::search::{self, SearchHit};
+use crate::tag_index::TagIndex;
+use crate::task_index::{self, Task, TaskFilter, TaskIndex};
+use crate::vault::Vault;
+use crate::vault_config::VaultConfig;
+use crate::vault_health::{self, VaultHealthReport};
+
+/// Open vaults keyed by root path, shared between the app and its servers.
+pub type OpenVaults = Arc<Mutex<HashMap<PathBuf, VaultHandle>>>;
+
+/// An open vault together with its in-memory indexes and the buffers of notes being edited.
+/// Indexes are built on first use and kept current through [`VaultHandle::update_file`], the
+/// file operations on the handle and edits to open buffers.
+pub struct VaultHandle {
+    vault: Vault,
+    tags: Option<TagIndex>,
+    tasks: Option<TaskIndex>,
+    buffers: HashMap<PathBuf, Buffer>,
+}
+
+impl VaultHandle {
+    /// Opens the vault at `root`, creating its `.onyx/config.toml` if absent.
+    pub fn open(root: &Path) -> Result<Self, OnyxError> {
+        Ok(Self::from_vault(Vault::open(root)?))
+    }
+
+    /// Creates `root` if needed and initialises it as a vault.
+    pub fn create(root: &Path) -> Result<Self, OnyxError> {
+        Ok(Self::from_vault(Vault::create(root)?))
+    }
+
+    fn from_vault(vault: Vault) -> Self {
+        Self {
+            vault,
+            tags: None,
+            tasks: None,
+            buffers: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.vault.root
+    }
+
+    pub fn config(&self) -> &VaultConfig {
+        &self.vault.config
+    }
+
+    /// Whether `path` lies inside this vault.
+    pub fn contains(&self, path: &Path) -> bool {
+        path.starts_with(self.root())
+    }
+
+    /// Resolves a vault-relative path, rejecting anything that would land outside the vault.
+    pub fn resolve(&self, relative: &str) -> Result<PathBuf, OnyxError> {
+        let root = normalize_path(self.root());
+        let path = normalize_path(&root.join(relative.trim_start_matches('/')));
+        if path.starts_with(&root) && path != root {
+            Ok(path)
+        } else {
+            Err(OnyxError::OutsideVault(path))
+        }
+    }
+
+    /// The vault-relative form of an absolute path, rejecting anything outside the vault.
+    pub fn relative_path(&self, path: &Path) -> Result<String, OnyxError> {
+        let root = normalize_path(self.root());
+        let path = normalize_path(path);
+        match path.strip_prefix(&root) {
+            Ok(relative) if !relative.as_os_str().is_empty() => {
+                Ok(relative.to_string_lossy().replace('\\', "/"))
+            }
+            _ => Err(OnyxError::OutsideVault(path)),
+        }
+    }
+
+    /// Vault-relative paths of every non-hidden file, sorted.
+    pub fn list_files(&self) -> Vec<String> {
+        vault_health::vault_files(self.root())
+            .iter()
+            .filter_map(|path| path.strip_prefix(self.root()).ok())
+            .map(|path| path.to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// Reads a vault-relative file as UTF-8.
+    pub fn read_file(&self, relative: &str) -> Result<String, OnyxError> {
+        Ok(std::fs::read_to_string(self.resolve(relative)?)?)
+    }
+
+    /// Writes a vault-relative file, creating missing folders, and re-indexes it.
+    pub fn write_file(&mut self, relative: &str, content: &str) -> Result<PathBuf, OnyxError> {
+        let path = self.resolve(relative)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        self.update_file(&path, content);
+        Ok(path)
+    }
+
+    /// Appends `text` to a vault-relative file on a line of its own, creating the file if needed.
+    pub fn append_to_file(&mut self, relative: &str, text: &str) -> Result<PathBuf, OnyxError> {
+        let mut content = match self.read_file(relative) {
+            Ok(content) => content,
+            Err(OnyxError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound => {
+                String::new()
+            }
+            Err(error) => return Err(error),
+        };
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(text);
+        self.write_file(relative, &content)
+    }
+
+    /// The vault's folders and files, directories first.
+    pub fn file_tree(&self) -> Result<Vec<FileTreeEntry>, OnyxError> {
+        file_tree::scan_file_tree(self.root())
+    }
+
+    /// Re-scans every note for tags, replacing the current tag index.
+    pub fn rebuild_tag_index(&mut self) -> Result<(), OnyxError> {
+        let mut tags = TagIndex::build(self.root())?;
+        // Open buffers may hold edits the files on disk don't have yet.
+        for (path, buffer) in &self.buffers {
+            tags.update_file(&path.to_string_lossy(), &buffer.to_string());
+        }
+        self.tags = Some(tags);
+        info!("Tag index built for {}", self.root().display());
+        Ok(())
+    }
+
+    /// Every tag in the vault, sorted.
+    pub fn tags(&mut self) -> Result<Vec<String>, OnyxError> {
+        if self.tags.is_none() {
+            self.rebuild_tag_index()?;
+        }
+        Ok(self.tags.as_ref().map_or_else(Vec::new, TagIndex::all_tags))
+    }
+
+    /// Tasks across the vault matching `filter`.
+    pub fn tasks(&mut self, filter: &TaskFilter) -> Result<Vec<Task>, OnyxError> {
+        if self.tasks.is_none() {
+            let mut tasks = TaskIndex::build(self.root())?;
+            for (path, buffer) in &self.buffers {
+                tasks.update_file(&path.to_string_lossy(), &buffer.to_string());
+            }
+            self.tasks = Some(tasks);
+            info!("Task index built for {}", self.root().display());
+        }
+        Ok(self
+            .tasks
+            .as_ref()
+            .map_or_else(Vec::new, |index| index.query(filter)))
+    }
+
+    /// Flips the checkbox on 1-based `line` of `path` and returns the task as it now reads.
+    pub fn toggle_task(&mut self, path: &Path, line: usize) -> Result<Task, OnyxError> {
+        let (content, task) = task_index::toggle_task(path, line)?;
+        self.update_file(path, &content);
+        Ok(task)
+    }
+
+    /// Re-indexes a single note after it has been saved with `content`. While the note is open
+    /// with unsaved edits the indexes keep describing its buffer; a clean buffer is dropped, so
+    /// the editor sends the new text with its next edit.
+    pub fn update_file(&mut self, path: &Path, content: &str) {
+        match self.buffers.get(path).map(Buffer::is_dirty) {
+            Some(true) => return,
+            Some(false) => {
+                self.buffers.remove(path);
+            }
+            None => {}
+        }
+        self.index_content(path, content);
+    }
+
+    fn index_content(&mut self, path: &Path, content: &str) {
+        let path = path.to_string_lossy();
+        if let Some(tags) = &mut self.tags {
+            tags.update_file(&path, content);
+        }
+        if let Some(tasks) = &mut self.tasks {
+            tasks.update_file(&path, content);
+        }
+    }
+
+    /// Renames a file in place, keeping the extension, and re-indexes it under its new path.
+    pub fn rename_file(&mut self, source: &Path, new_stem: &str) -> Result<PathBuf, OnyxError> {
+        let destination = files::rename_file(source, new_stem)?;
+        self.reindex_moved(source, &destination);
+        Ok(destination)
+    }
+
+    /// Moves a file or folder into `target_dir` and re-indexes it under its new path.
+    pub fn move_file(&mut self, source: &Path, target_dir: &Path) -> Result<PathBuf, OnyxError> {
+        let destination = files::move_file(source, target_dir)?;
+        self.reindex_moved(source, &destination);
+        Ok(destination)
+    }
+
+    fn reindex_moved(&mut self, source: &Path, destination: &Path) {
+        let moved: Vec<PathBuf> = self
+            .buffers
+            .keys()
+            .filter(|path| path.starts_with(source))
+            .cloned()
+            .collect();
+        for old in moved {
+            let Some(buffer) = self.buffers.remove(&old) else {
+                continue;
+            };
+            let new = match old.strip_prefix(source) {
+                Ok(rest) if !rest.as_os_str().is_empty() => destination.join(rest),
+                _ => destination.to_path_buf(),
+            };
+            self.buffers.insert(new, buffer);
+        }
+        if destination.is_dir() {
+            // Every note below the folder changed path; rebuild lazily on next use.
+            self.tags = None;
+            self.tasks = None;
+            return;
+        }
+        self.index_content(source, "");
+        let content = match self.buffers.get(destination) {
+            Some(buffer) => Some(buffer.to_string()),
+            None => std::fs::read_to_string(destination).ok(),
+        };
+        if let Some(content) = content {
+            self.index_content(destination, &content);
+        }
+    }
+
+    /// Starts editing `path` with `content` as the editor shows it, replacing any buffer already
+    /// open for it, and indexes that text. The buffer starts dirty when `content` differs from
+    /// the file, so unsaved edits survive a resync.
+    pub fn open_buffer(&mut self, path: &Path, content: &str) {
+        self.index_content(path, content);
+        let mut buffer = Buffer::new(content);
+        let on_disk = std::fs::read_to_string(path).map(|text| Buffer::new(&text).to_string());
+        if on_disk.ok() != Some(buffer.to_string()) {
+            buffer.mark_dirty();
+        }
+        self.buffers.insert(path.to_path_buf(), buffer);
+    }
+
+    /// The open buffer of `path`, if any.
+    pub fn buffer(&self, path: &Path) -> Option<&Buffer> {
+        self.buffers.get(path)
+    }
+
+    /// Applies an editor change set to the open buffer of `path` and updates the indexes from the
+    /// lines it touched.
+    pub fn apply_edits(&mut self, path: &Path, changes: &ChangeSet) -> Result<(), OnyxError> {
+        let buffer = self.buffers.get_mut(path).ok_or_else(|| not_open(path))?;
+        let delta = buffer.apply_edits(changes)?;
+        index_delta(&mut self.tags, &mut self.tasks, path, &delta, buffer);
+        Ok(())
+    }
+
+    /// Undoes the last group of edits to `path`, returning the change sets the editor must
+    /// apply, in order.
+    pub fn undo_buffer(&mut self, path: &Path) -> Result<Vec<ChangeSet>, OnyxError> {
+        self.replay(path, Buffer::undo)
+    }
+
+    /// Redoes the last undone group of edits to `path`; see [`VaultHandle::undo_buffer`].
+    pub fn redo_buffer(&mut self, path: &Path) -> Result<Vec<ChangeSet>, OnyxError> {
+        self.replay(path, Buffer::redo)
+    }
+
+    fn replay(
+        &mut self,
+        path: &Path,
+        step: fn(&mut Buffer) -> Result<Replayed, OnyxError>,
+    ) -> Result<Vec<ChangeSet>, OnyxError> {
+        let buffer = self.buffers.get_mut(path).ok_or_else(|| not_open(path))?;
+        let mut changes = Vec::new();
+        for (applied, delta) in step(buffer)? {
+            index_delta(&mut self.tags, &mut self.tasks, path, &delta, buffer);
+            changes.push(applied);
+        }
+        Ok(changes)
+    }
+
+    /// Writes the open buffer of `path` to disk.
+    pub fn save_buffer(&mut self, path: &Path) -> Result<(), OnyxError> {
+        let buffer = self.buffers.get_mut(path).ok_or_else(|| not_open(path))?;
+        buffer.save(path)?;
+        info!("Saved buffer {}", path.display());
+        Ok(())
+    }
+
+    /// Stops editing `path`. Unsaved edits are dropped and the indexes go back to the file.
+    pub fn close_buffer(&mut self, path: &Path) {
+        if self.buffers.remove(path).is_some() {
+            let content = std::fs::read_to_string(path).unwrap_or_default();
+            self.index_content(path, &content);
+        }
+    }
+
+    /// Lines matching every word of `query`, in notes and PDF text.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>, OnyxError> {
+        search::search_vault(self.root(), query, limit)
+    }
+
+    /// Notes and canvases that reference `target`.
+    pub fn backlinks(&self, target: &Path) -> Result<Vec<Backlink>, OnyxError> {
+        backlinks::find_backlinks(self.root(), target)
+    }
+
+    /// Runs a `TABLE`/`LIST` query over the vault's notes.
+    pub fn run_query(&self, source: &str) -> Result<QueryResult, OnyxError> {
+        query::run_query(self.root(), source)
+    }
+
+    /// Runs every embedded query block of `note`.
+    pub fn note_queries(&self, note: &Path) -> Result<Vec<EmbeddedQuery>, OnyxError> {
+        query::run_note_queries(self.root(), note)
+    }
+
+    /// The link graph narrowed by `filter`.
+    pub fn graph(&self, filter: &GraphFilter) -> Result<Graph, OnyxError> {
+        graph::build_graph(self.root(), filter)
+    }
+
+    /// Broken links, orphan notes and unused attachments, optionally trashing the latter.
+    pub fn check_health(&self, trash_unused: bool) -> Result<VaultHealthReport, OnyxError> {
+        vault_health::check_vault_health(self.root(), trash_unused)
+    }
+}
+
+fn not_open(path: &Path) -> OnyxError {
+    OnyxError::Edit(format!("{} is not open", path.display()))
+}
+
+/// Feeds the lines an edit touched to whichever indexes are built.
+fn index_delta(
+    tags: &mut Option<TagIndex>,
+    tasks: &mut Option<TaskIndex>,
+    path: &Path,
+    delta: &LineDelta,
+    buffer: &Buffer,
+) {
+    if delta.is_empty() {
+        return;
+    }
+    let path = path.to_string_lossy();
+    if let Some(tags) = tags {
+        tags.apply_delta(&path, &delta.old_text, &delta.new_text);
+    }
+    if let Some(tasks) = tasks {
+        tasks.apply_delta(&path, delta, buffer);
+    }
+}
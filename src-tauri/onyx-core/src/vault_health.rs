@@ -0,0 +1,339 @@
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use serde::Serialize;
+
+use crate::canvas::{is_canvas, read_canvas};
+use crate::error::OnyxError;
+use crate::link_resolver::{is_markdown, normalize_path, LinkResolver};
+use crate::markdown::{parser_options, split_link_target};
+use crate::pdf_annotations::is_sidecar;
+use crate::pdf_text::{page_exists, page_fragment};
+
+/// A link or embed in a note whose target does not exist.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BrokenReference {
+    pub note: String,
+    /// 1-based line of the link in the note.
+    pub line: usize,
+    pub target: String,
+}
+
+/// Problems found across a vault. Paths are absolute.
+#[derive(Debug, Default, Serialize)]
+pub struct VaultHealthReport {
+    /// Non-note files no note links to, embeds or places on a canvas (canvases and annotation
+    /// sidecars excepted).
+    pub unused_attachments: Vec<String>,
+    /// Wikilinks and markdown links that resolve to nothing.
+    pub broken_links: Vec<BrokenReference>,
+    /// Embeds and images pointing at missing files.
+    pub missing_embeds: Vec<BrokenReference>,
+    /// Notes with no incoming or outgoing links to other notes and not placed on any canvas.
+    pub orphan_notes: Vec<String>,
+    /// Unused attachments that were moved to the system trash.
+    pub trashed: Vec<String>,
+}
+
+/// A reference found in a note, before resolution.
+pub(crate) struct Reference {
+    pub(crate) target: String,
+    /// Byte offset of the link in the note.
+    pub(crate) offset: usize,
+    pub(crate) wikilink: bool,
+    pub(crate) embed: bool,
+}
+
+/// Scans every note in the vault for dangling references and unreferenced files.
+/// With `trash_unused`, unused attachments are moved to the system trash in one batch.
+pub fn check_vault_health(
+    vault_root: &Path,
+    trash_unused: bool,
+) -> Result<VaultHealthReport, OnyxError> {
+    let files = vault_files(vault_root);
+    let resolver = LinkResolver::from_paths(vault_root, files.iter().cloned());
+    let notes: Vec<&PathBuf> = files.iter().filter(|path| is_markdown(path)).collect();
+
+    let mut report = VaultHealthReport::default();
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    let mut linked_notes: HashSet<PathBuf> = HashSet::new();
+    for note in &notes {
+        let content = std::fs::read_to_string(note)?;
+        for reference in references(&content) {
+            let resolved = resolve(&resolver, note, &reference)
+                .filter(|path| pdf_page_in_range(vault_root, path, &reference.target));
+            match resolved {
+                Some(path) => {
+                    let path = normalize_path(&path);
+                    if is_markdown(&path) && path != normalize_path(note) {
+                        linked_notes.insert(path.clone());
+                        linked_notes.insert(normalize_path(note));
+                    }
+                    referenced.insert(path);
+                }
+                None => {
+                    let broken = BrokenReference {
+                        note: note.to_string_lossy().to_string(),
+                        line: content[..reference.offset].matches('\n').count() + 1,
+                        target: reference.target,
+                    };
+                    if reference.embed {
+                        report.missing_embeds.push(broken);
+                    } else {
+                        report.broken_links.push(broken);
+                    }
+                }
+            }
+        }
+    }
+
+    for canvas_path in files.iter().filter(|path| is_canvas(path)) {
+        let canvas = match read_canvas(canvas_path) {
+            Ok(canvas) => canvas,
+            Err(e) => {
+                warn!(
+                    "Skipping canvas {} in health check: {e}",
+                    canvas_path.display()
+                );
+                continue;
+            }
+        };
+        for (_, file) in canvas.file_references() {
+            let path = normalize_path(&vault_root.join(file));
+            if is_markdown(&path) {
+                linked_notes.insert(path.clone());
+            }
+            referenced.insert(path);
+        }
+    }
+
+    let unused: Vec<&PathBuf> = files
+        .iter()
+        .filter(|path| !is_markdown(path) && !is_canvas(path) && !is_sidecar(path))
+        .filter(|path| !referenced.contains(&normalize_path(path)))
+        .collect();
+    report.unused_attachments = unused
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    report.orphan_notes = notes
+        .iter()
+        .filter(|note| !linked_notes.contains(&normalize_path(note)))
+        .map(|note| note.to_string_lossy().to_string())
+        .collect();
+
+    if trash_unused && !unused.is_empty() {
+        trash::delete_all(&unused)?;
+        report.trashed = report.unused_attachments.clone();
+    }
+    info!(
+        "Vault health for {}: {} unused attachments, {} broken links, {} missing embeds, {} orphans",
+        vault_root.display(),
+        report.unused_attachments.len(),
+        report.broken_links.len(),
+        report.missing_embeds.len(),
+        report.orphan_notes.len()
+    );
+    Ok(report)
+}
+
+/// Every non-hidden file in the vault, sorted.
+pub(crate) fn vault_files(vault_root: &Path) -> Vec<PathBuf> {
+    let files: BTreeSet<PathBuf> = walkdir::WalkDir::new(vault_root)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.file_name().to_string_lossy().starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(walkdir::DirEntry::into_path)
+        .collect();
+    files.into_iter().collect()
+}
+
+fn is_external(target: &str) -> bool {
+    target.contains("://") || target.starts_with("mailto:") || target.starts_with("data:")
+}
+
+/// Links, wikilinks, images and embeds of a note in document order, skipping external URLs
+/// and same-note `#heading` links.
+pub(crate) fn references(content: &str) -> Vec<Reference> {
+    let mut references = Vec::new();
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        let (link_type, dest_url, embed) = match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) => (link_type, dest_url, false),
+            Event::Start(Tag::Image {
+                link_type,
+                dest_url,
+                ..
+            }) => (link_type, dest_url, true),
+            _ => continue,
+        };
+        let target = dest_url.to_string();
+        if target.is_empty() || target.starts_with('#') || is_external(&target) {
+            continue;
+        }
+        references.push(Reference {
+            target,
+            offset: range.start,
+            wikilink: matches!(link_type, LinkType::WikiLink { .. }),
+            embed,
+        });
+    }
+    references
+}
+
+/// Resolves a reference the way the editor does: wikilinks by note name (or file name when
+/// they carry a non-markdown extension), everything else relative to the note.
+pub(crate) fn resolve(
+    resolver: &LinkResolver,
+    note: &Path,
+    reference: &Reference,
+) -> Option<PathBuf> {
+    let (target, _) = split_link_target(&reference.target);
+    if target.is_empty() {
+        return Some(note.to_path_buf());
+    }
+    let has_extension = Path::new(target).extension().is_some();
+    if reference.wikilink && (!has_extension || is_markdown(Path::new(target))) {
+        return resolver.resolve_note(target).map(Path::to_path_buf);
+    }
+    resolver.resolve_asset(note, target)
+}
+
+/// A `paper.pdf#page=N` reference only resolves when the PDF has page N.
+fn pdf_page_in_range(vault_root: &Path, path: &Path, target: &str) -> bool {
+    let is_pdf = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    match split_link_target(target).1.and_then(page_fragment) {
+        Some(page) if is_pdf => page_exists(vault_root, path, page),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, relative: &str, contents: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn setup_vault() -> TempDir {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write(
+            root,
+            "Home.md",
+            "[[Projects/Plan]] and [[Missing]]\n\n![[photo.png]] ![[gone.png]]\n\n[spec](docs/spec%20v1.pdf) [dead](nowhere.md) [web](https://x.test)\n",
+        );
+        write(
+            root,
+            "Projects/Plan.md",
+            "# Plan\n\n![chart](../img/chart.png)\n",
+        );
+        write(root, "Lonely.md", "Nothing links here. [[#Section]]\n");
+        write(root, "assets/photo.png", "png");
+        write(root, "img/chart.png", "png");
+        write(root, "docs/spec v1.pdf", "pdf");
+        write(root, "old/unused.jpg", "jpg");
+        write(root, ".onyx/config.toml", "name = \"v\"");
+        temp
+    }
+
+    #[test]
+    fn reports_broken_links_missing_embeds_and_unused_files() {
+        let vault = setup_vault();
+        let root = vault.path();
+
+        let report = check_vault_health(root, false).unwrap();
+
+        let home = root.join("Home.md").to_string_lossy().to_string();
+        assert_eq!(
+            report.broken_links,
+            vec![
+                BrokenReference {
+                    note: home.clone(),
+                    line: 1,
+                    target: "Missing".to_string()
+                },
+                BrokenReference {
+                    note: home.clone(),
+                    line: 5,
+                    target: "nowhere.md".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            report.missing_embeds,
+            vec![BrokenReference {
+                note: home,
+                line: 3,
+                target: "gone.png".to_string()
+            }]
+        );
+        assert_eq!(
+            report.unused_attachments,
+            vec![root.join("old/unused.jpg").to_string_lossy().to_string()]
+        );
+        assert!(report.trashed.is_empty());
+    }
+
+    #[test]
+    fn pdf_page_links_are_checked_against_the_page_count() {
+        let temp = TempDir::new().unwrap();
+        crate::pdf_text::tests::write_pdf(&temp.path().join("paper.pdf"), &["one", "two"]);
+        write(
+            temp.path(),
+            "Reading.md",
+            "[[paper.pdf#page=2]]\n[[paper.pdf#page=9]]\n",
+        );
+
+        let report = check_vault_health(temp.path(), false).unwrap();
+
+        let targets: Vec<&str> = report
+            .broken_links
+            .iter()
+            .map(|broken| broken.target.as_str())
+            .collect();
+        assert_eq!(targets, vec!["paper.pdf#page=9"]);
+    }
+
+    #[test]
+    fn notes_without_links_either_way_are_orphans() {
+        let vault = setup_vault();
+
+        let report = check_vault_health(vault.path(), false).unwrap();
+
+        assert_eq!(
+            report.orphan_notes,
+            vec![vault.path().join("Lonely.md").to_string_lossy().to_string()]
+        );
+    }
+
+    #[test]
+    fn canvas_file_nodes_count_as_references() {
+        let vault = setup_vault();
+        let root = vault.path();
+        write(
+            root,
+            "Board.canvas",
+            r#"{"nodes":[{"id":"a","type":"file","x":0,"y":0,"width":10,"height":10,"file":"old/unused.jpg"},{"id":"b","type":"file","x":0,"y":0,"width":10,"height":10,"file":"Lonely.md"}],"edges":[]}"#,
+        );
+
+        let report = check_vault_health(root, false).unwrap();
+
+        assert!(report.unused_attachments.is_empty());
+        assert!(report.orphan_notes.is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::Path;
+
+use onyx_core::buffer::ChangeSet;
+use onyx_core::graph::GraphFilter;
+use onyx_core::task_index::{TaskFilter, TaskStatus};
+use onyx_core::VaultHandle;
+use tempfile::TempDir;
+
+fn write(root: &Path, relative: &str, content: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+fn sample_vault() -> (TempDir, VaultHandle) {
+    let dir = TempDir::new().unwrap();
+    write(
+        dir.path(),
+        "Projects/Launch.md",
+        "---\nstatus: active\n---\n# Launch #project\n\n- [ ] Book venue\n- [x] Pick date\n\nSee [[Ideas]].\n",
+    );
+    write(dir.path(), "Ideas.md", "Loose thoughts #idea\n");
+    let handle = VaultHandle::open(dir.path()).unwrap();
+    (dir, handle)
+}
+
+#[test]
+fn open_creates_the_vault_config() {
+    let (dir, handle) = sample_vault();
+
+    assert!(dir.path().join(".onyx/config.toml").is_file());
+    assert_eq!(handle.root(), dir.path());
+    assert!(handle.contains(&dir.path().join("Ideas.md")));
+}
+
+#[test]
+fn writes_stay_inside_the_vault_and_are_indexed() {
+    let (dir, mut handle) = sample_vault();
+    let note = dir.path().join("Inbox/New.md");
+
+    let relative = handle.relative_path(&note).unwrap();
+    handle.write_file(&relative, "Fresh #inbox\n").unwrap();
+
+    assert_eq!(relative, "Inbox/New.md");
+    assert_eq!(fs::read_to_string(&note).unwrap(), "Fresh #inbox\n");
+    assert!(handle.tags().unwrap().contains(&"inbox".to_string()));
+    let outside = dir.path().join("Inbox/../../escaped.md");
+    assert!(handle.relative_path(&outside).is_err());
+    assert!(handle.relative_path(dir.path()).is_err());
+}
+
+#[test]
+fn tags_follow_saved_content() {
+    let (dir, mut handle) = sample_vault();
+    assert_eq!(handle.tags().unwrap(), vec!["idea", "project"]);
+
+    handle.update_file(&dir.path().join("Ideas.md"), "Now #archive\n");
+
+    assert_eq!(handle.tags().unwrap(), vec!["archive", "project"]);
+}
+
+#[test]
+fn buffer_edits_update_the_indexes_and_save_to_disk() {
+    let (dir, mut handle) = sample_vault();
+    let note = dir.path().join("Ideas.md");
+    let open = TaskFilter {
+        status: Some(TaskStatus::Open),
+        ..TaskFilter::default()
+    };
+    handle.tags().unwrap();
+    assert_eq!(handle.tasks(&open).unwrap().len(), 1);
+
+    let typed = ChangeSet::default()
+        .retain(15)
+        .replace(6, "#later\n- [ ] Sketch it");
+    assert!(handle.apply_edits(&note, &typed).is_err());
+    handle.open_buffer(&note, "Loose thoughts #idea\n");
+    handle.apply_edits(&note, &typed).unwrap();
+
+    assert_eq!(handle.tags().unwrap(), vec!["later", "project"]);
+    assert_eq!(handle.tasks(&open).unwrap().len(), 2);
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "Loose thoughts #idea\n",
+        "edits stay in memory until saved"
+    );
+
+    let undone = handle.undo_buffer(&note).unwrap();
+    assert_eq!(
+        undone,
+        vec![ChangeSet::default().retain(15).replace(22, "#idea\n")]
+    );
+    assert_eq!(handle.tags().unwrap(), vec!["idea", "project"]);
+    handle.redo_buffer(&note).unwrap();
+    handle.save_buffer(&note).unwrap();
+    assert_eq!(
+        fs::read_to_string(&note).unwrap(),
+        "Loose thoughts #later\n- [ ] Sketch it"
+    );
+
+    handle.close_buffer(&note);
+    assert_eq!(handle.tasks(&open).unwrap().len(), 2);
+    assert!(handle.buffer(&note).is_none());
+}
+
+#[test]
+fn buffers_opened_with_unsaved_text_start_dirty() {
+    let (dir, mut handle) = sample_vault();
+    let note = dir.path().join("Ideas.md");
+
+    handle.open_buffer(&note, "Loose thoughts #idea\r\n");
+    assert!(!handle.buffer(&note).unwrap().is_dirty());
+
+    handle.open_buffer(&note, "Loose thoughts #draft\n");
+    assert!(handle.buffer(&note).unwrap().is_dirty());
+    handle.update_file(&note, "Loose thoughts #idea\n");
+    assert_eq!(handle.tags().unwrap(), vec!["draft", "project"]);
+}
+
+#[test]
+fn toggling_a_task_updates_the_file_and_the_index() {
+    let (dir, mut handle) = sample_vault();
+    let note = dir.path().join("Projects/Launch.md");
+    let open = TaskFilter {
+        status: Some(TaskStatus::Open),
+        ..TaskFilter::default()
+    };
+    assert_eq!(handle.tasks(&open).unwrap().len(), 1);
+
+    let task = handle.toggle_task(&note, 6).unwrap();
+
+    assert!(task.completed);
+    assert!(fs::read_to_string(&note)
+        .unwrap()
+        .contains("- [x] Book venue"));
+    assert!(handle.tasks(&open).unwrap().is_empty());
+}
+
+#[test]
+fn moved_notes_are_reindexed_under_their_new_path() {
+    let (dir, mut handle) = sample_vault();
+    let all = TaskFilter::default();
+    handle.tags().unwrap();
+    handle.tasks(&all).unwrap();
+
+    let renamed = handle
+        .rename_file(&dir.path().join("Projects/Launch.md"), "Kickoff")
+        .unwrap();
+    let moved = handle.move_file(&renamed, dir.path()).unwrap();
+
+    assert_eq!(moved, dir.path().join("Kickoff.md"));
+    assert_eq!(handle.tags().unwrap(), vec!["idea", "project"]);
+    let tasks = handle.tasks(&all).unwrap();
+    assert_eq!(tasks.len(), 2);
+    assert!(tasks
+        .iter()
+        .all(|task| task.path == moved.to_string_lossy()));
+}
+
+#[test]
+fn search_queries_and_graph_run_against_the_vault() {
+    let (dir, handle) = sample_vault();
+
+    let hits = handle.search("venue", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+
+    let result = handle.run_query("TABLE status FROM #project").unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].values[0], "active");
+
+    let backlinks = handle.backlinks(&dir.path().join("Ideas.md")).unwrap();
+    assert_eq!(backlinks.len(), 1);
+
+    let graph = handle.graph(&GraphFilter::default()).unwrap();
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.edges.len(), 1);
+}
@@ -0,0 +1,384 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::UNIX_EPOCH;
+
+use log::{error, warn};
+use tauri::http::{header, HeaderValue, Method, Request, Response, StatusCode};
+use tauri::Url;
+
+use onyx_core::global_config::load_global_config;
+
+/// URI scheme the webview uses to load vault files, e.g. via `convertFileSrc(path, "onyx-asset")`.
+pub const ASSET_SCHEME: &str = "onyx-asset";
+/// Largest body returned for a range; players and PDF.js request the rest as they go.
+const MAX_RANGE_CHUNK: u64 = 4 * 1024 * 1024;
+/// Largest file sent whole to a request without a range, so images load in one piece; bigger
+/// files get their first chunk as a partial response instead of being read into memory.
+const MAX_WHOLE_FILE: u64 = 64 * 1024 * 1024;
+
+/// The vaults registered in the global config, loaded on the first asset request.
+pub type AssetRoots = RwLock<Vec<PathBuf>>;
+
+/// Serves a request for a vault file, confined to the registered vaults. The cached list is
+/// reloaded only when a request falls outside it, e.g. for a vault added since it was loaded.
+/// Only `origin`, the page of the webview that asked, may read the response from script.
+pub fn handle_asset_request(
+    request: &Request<Vec<u8>>,
+    roots: &AssetRoots,
+    origin: Option<&str>,
+) -> Response<Vec<u8>> {
+    let mut response = cached_response(request, roots);
+    if let Some(origin) = origin.and_then(|origin| HeaderValue::from_str(origin).ok()) {
+        response
+            .headers_mut()
+            .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    response
+}
+
+/// Origin of a page loaded from `url`, spelled the way the webview sends it (`tauri://localhost`,
+/// `http://tauri.localhost`, `http://localhost:1420`).
+pub fn page_origin(url: &Url) -> Option<String> {
+    let host = url.host_str()?;
+    let port = url
+        .port()
+        .map(|port| format!(":{port}"))
+        .unwrap_or_default();
+    Some(format!("{}://{host}{port}", url.scheme()))
+}
+
+fn cached_response(request: &Request<Vec<u8>>, roots: &AssetRoots) -> Response<Vec<u8>> {
+    let cached = roots.read().map(|roots| roots.clone()).unwrap_or_default();
+    let response = asset_response(request, &cached);
+    if response.status() != StatusCode::FORBIDDEN {
+        return response;
+    }
+    let vaults = match load_global_config() {
+        Ok(config) => config.vaults,
+        Err(e) => {
+            error!("Failed to load global config for asset request: {e}");
+            return status(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if vaults == cached {
+        return response;
+    }
+    if let Ok(mut roots) = roots.write() {
+        roots.clone_from(&vaults);
+    }
+    asset_response(request, &vaults)
+}
+
+/// Builds the response for `request`: the whole file (its first chunk when large), a single byte
+/// range, or `304` when the client's cached copy is still current. Anything outside `vault_roots` is refused.
+pub fn asset_response(request: &Request<Vec<u8>>, vault_roots: &[PathBuf]) -> Response<Vec<u8>> {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+    let Some(path) = request_path(request.uri().path()) else {
+        return status(StatusCode::BAD_REQUEST);
+    };
+    let Ok(path) = path.canonicalize() else {
+        return status(StatusCode::NOT_FOUND);
+    };
+    let inside_vault = vault_roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| path.starts_with(root));
+    if !inside_vault {
+        warn!(
+            "Refused asset request outside the vaults: {}",
+            path.display()
+        );
+        return status(StatusCode::FORBIDDEN);
+    }
+    match serve_file(request, &path) {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to serve asset {}: {e}", path.display());
+            status(StatusCode::NOT_FOUND)
+        }
+    }
+}
+
+/// The file path carried by the URI path: a single percent-encoded segment holding the absolute path.
+fn request_path(uri_path: &str) -> Option<PathBuf> {
+    let encoded = uri_path.strip_prefix('/').unwrap_or(uri_path);
+    let decoded = urlencoding::decode(encoded).ok()?;
+    if decoded.is_empty() {
+        return None;
+    }
+    let path = PathBuf::from(decoded.into_owned());
+    path.is_absolute().then_some(path)
+}
+
+fn serve_file(request: &Request<Vec<u8>>, path: &Path) -> std::io::Result<Response<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        return Ok(status(StatusCode::NOT_FOUND));
+    }
+    let length = metadata.len();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let etag = format!("\"{length:x}-{modified:x}\"");
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, mime_type(path))
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .header(header::ETAG, &etag);
+
+    let header_value = |name| {
+        request
+            .headers()
+            .get(name)
+            .and_then(|value: &header::HeaderValue| value.to_str().ok())
+    };
+    if header_value(header::IF_NONE_MATCH)
+        .is_some_and(|tags| tags.split(',').any(|tag| tag.trim() == etag))
+    {
+        return Ok(builder
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Vec::new())
+            .unwrap_or_default());
+    }
+
+    let (status, start, end) = match header_value(header::RANGE) {
+        None if length <= MAX_WHOLE_FILE => (StatusCode::OK, 0, length.saturating_sub(1)),
+        // Never read a large file whole; the `Content-Range` tells the client there is more.
+        None => (StatusCode::PARTIAL_CONTENT, 0, MAX_RANGE_CHUNK - 1),
+        Some(range) => match parse_range(range, length) {
+            Some((start, end)) => (StatusCode::PARTIAL_CONTENT, start, end),
+            None => {
+                return Ok(builder
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{length}"))
+                    .body(Vec::new())
+                    .unwrap_or_default());
+            }
+        },
+    };
+    let body_length = if length == 0 { 0 } else { end - start + 1 };
+    let mut builder = builder
+        .status(status)
+        .header(header::CONTENT_LENGTH, body_length);
+    if status == StatusCode::PARTIAL_CONTENT {
+        builder = builder.header(
+            header::CONTENT_RANGE,
+            format!("bytes {start}-{end}/{length}"),
+        );
+    }
+
+    let mut body = Vec::new();
+    if request.method() == Method::GET && body_length > 0 {
+        file.seek(SeekFrom::Start(start))?;
+        file.take(body_length).read_to_end(&mut body)?;
+    }
+    Ok(builder.body(body).unwrap_or_default())
+}
+
+/// Parses a single `bytes=` range into inclusive offsets, clamping to the file length.
+/// Ranges are capped at `MAX_RANGE_CHUNK` bytes. Multiple ranges are not supported.
+fn parse_range(header: &str, length: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') || length == 0 {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            if suffix == 0 {
+                return None;
+            }
+            (length.saturating_sub(suffix), length - 1)
+        }
+        (start, "") => (start.parse().ok()?, length - 1),
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            (start, end.min(length - 1))
+        }
+    };
+    let end = end.min(start.saturating_add(MAX_RANGE_CHUNK - 1));
+    (start <= end && start < length).then_some((start, end))
+}
+
+/// MIME type for the file types the vault shows, falling back to a generic binary type.
+pub fn mime_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    match ext.as_str() {
+        "md" => "text/markdown; charset=utf-8",
+        "canvas" | "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "avif" => "image/avif",
+        "flac" => "audio/flac",
+        "m4a" => "audio/mp4",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "3gp" => "video/3gpp",
+        "mkv" => "video/x-matroska",
+        "mov" => "video/quicktime",
+        "mp4" => "video/mp4",
+        "ogv" => "video/ogg",
+        "webm" => "video/webm",
+        _ => "application/octet-stream",
+    }
+}
+
+fn status(code: StatusCode) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(code)
+        .body(Vec::new())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn request(path: &Path, range: Option<&str>) -> Request<Vec<u8>> {
+        let encoded = urlencoding::encode(&path.to_string_lossy()).into_owned();
+        let mut builder = Request::builder().uri(format!("onyx-asset://localhost/{encoded}"));
+        if let Some(range) = range {
+            builder = builder.header(header::RANGE, range);
+        }
+        builder.body(Vec::new()).unwrap()
+    }
+
+    fn vault_with_clip() -> (TempDir, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        std::fs::create_dir_all(&vault).unwrap();
+        std::fs::write(vault.join("clip one.mp4"), b"0123456789").unwrap();
+        (temp, vault)
+    }
+
+    #[test]
+    fn serves_whole_file_with_mime_and_etag() {
+        let (_temp, vault) = vault_with_clip();
+
+        let response = asset_response(&request(&vault.join("clip one.mp4"), None), &[vault]);
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "video/mp4");
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        assert!(response.headers().contains_key(header::ETAG));
+        assert_eq!(response.body(), b"0123456789");
+    }
+
+    #[test]
+    fn serves_byte_ranges() {
+        let (_temp, vault) = vault_with_clip();
+        let path = vault.join("clip one.mp4");
+
+        let response = asset_response(
+            &request(&path, Some("bytes=2-5")),
+            std::slice::from_ref(&vault),
+        );
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 2-5/10");
+        assert_eq!(response.body(), b"2345");
+
+        let response = asset_response(
+            &request(&path, Some("bytes=-3")),
+            std::slice::from_ref(&vault),
+        );
+        assert_eq!(response.body(), b"789");
+
+        let response = asset_response(&request(&path, Some("bytes=20-")), &[vault]);
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+    }
+
+    #[test]
+    fn large_files_are_served_in_chunks() {
+        let (_temp, vault) = vault_with_clip();
+        let path = vault.join("film.mp4");
+        let length = MAX_WHOLE_FILE + 10;
+        File::create(&path).unwrap().set_len(length).unwrap();
+
+        let first = asset_response(&request(&path, None), std::slice::from_ref(&vault));
+        assert_eq!(first.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            first.headers()[header::CONTENT_RANGE],
+            format!("bytes 0-{}/{length}", MAX_RANGE_CHUNK - 1)
+        );
+        assert_eq!(first.body().len() as u64, MAX_RANGE_CHUNK);
+
+        let closed = asset_response(
+            &request(&path, Some(&format!("bytes=5-{}", length - 1))),
+            &[vault],
+        );
+        assert_eq!(
+            closed.headers()[header::CONTENT_RANGE],
+            format!("bytes 5-{}/{length}", MAX_RANGE_CHUNK + 4)
+        );
+        assert_eq!(closed.body().len() as u64, MAX_RANGE_CHUNK);
+    }
+
+    #[test]
+    fn matching_etag_returns_not_modified() {
+        let (_temp, vault) = vault_with_clip();
+        let path = vault.join("clip one.mp4");
+        let first = asset_response(&request(&path, None), std::slice::from_ref(&vault));
+        let etag = first.headers()[header::ETAG].clone();
+
+        let mut second = request(&path, None);
+        second.headers_mut().insert(header::IF_NONE_MATCH, etag);
+        let response = asset_response(&second, &[vault]);
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    fn only_the_requesting_page_may_read_assets() {
+        let (_temp, vault) = vault_with_clip();
+        let roots = AssetRoots::new(vec![vault.clone()]);
+        let request = request(&vault.join("clip one.mp4"), None);
+
+        let response = handle_asset_request(&request, &roots, Some("tauri://localhost"));
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "tauri://localhost"
+        );
+        let response = handle_asset_request(&request, &roots, None);
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        let url = Url::parse("http://localhost:1420/?vault=x").unwrap();
+        assert_eq!(page_origin(&url).as_deref(), Some("http://localhost:1420"));
+    }
+
+    #[test]
+    fn refuses_files_outside_the_vaults() {
+        let (temp, vault) = vault_with_clip();
+        std::fs::write(temp.path().join("secret.txt"), "no").unwrap();
+        let escaped = vault.join("../secret.txt");
+
+        let response = asset_response(&request(&escaped, None), &[vault]);
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,488 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use onyx_core::backlinks::find_backlinks;
+use onyx_core::book::{BookOptions, ChapterSource};
+use onyx_core::error::OnyxError;
+use onyx_core::export_docx::export_book_to_docx;
+use onyx_core::export_epub::export_book_to_epub;
+use onyx_core::export_html::{export_notes_to_html, HtmlExportOptions};
+use onyx_core::export_pdf::{export_note_to_pdf, PdfExportOptions};
+use onyx_core::language_server;
+use onyx_core::link_resolver::LinkResolver;
+use onyx_core::mcp_server::McpServer;
+use onyx_core::search::search_vault;
+use onyx_core::tag_index::TagIndex;
+use onyx_core::theme::load_theme;
+use onyx_core::vault::find_vault_root;
+use onyx_core::vault_health::check_vault_health;
+use onyx_core::VaultHandle;
+
+/// Headless access to a vault for scripts; run `Onyx <command> --help` for details.
+#[derive(Debug, Parser)]
+#[command(name = "onyx", version)]
+pub struct Cli {
+    /// Vault to work in; defaults to the vault containing the current directory.
+    #[arg(long, global = true)]
+    pub vault: Option<PathBuf>,
+    /// Print machine-readable JSON instead of text.
+    #[arg(long, global = true)]
+    pub json: bool,
+    #[command(subcommand)]
+    pub command: CliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Create an empty note and print its path.
+    New {
+        /// Note name, with or without `.md`.
+        name: String,
+        /// Vault-relative folder; defaults to the vault's new-note folder.
+        #[arg(long)]
+        folder: Option<String>,
+    },
+    /// Search notes and PDFs; every word must appear on the same line or page.
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+    },
+    /// List every tag in the vault.
+    Tags,
+    /// List the notes and canvases that reference a file.
+    Backlinks {
+        /// A path, or a note name resolved like a wikilink.
+        file: String,
+    },
+    /// Open a file or vault in the Onyx app.
+    Open { path: PathBuf },
+    /// Export notes to another format.
+    Export {
+        #[arg(value_enum)]
+        format: ExportFormat,
+        #[arg(required = true)]
+        notes: Vec<PathBuf>,
+        /// Output file, or output folder for HTML.
+        #[arg(long, short)]
+        out: PathBuf,
+    },
+    /// Report broken links, missing embeds, unused attachments and orphan notes.
+    Doctor {
+        /// Move unused attachments to the system trash.
+        #[arg(long)]
+        trash: bool,
+    },
+    /// Serve the vault to AI agents over the Model Context Protocol on stdin/stdout.
+    Mcp {
+        /// Only expose tools that read the vault.
+        #[arg(long)]
+        read_only: bool,
+    },
+    /// Serve the vault to other editors over the Language Server Protocol on stdin/stdout.
+    Lsp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Html,
+    Pdf,
+    Docx,
+    Epub,
+}
+
+/// What a command produced, as JSON for `--json` and as text otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOutput {
+    pub json: Value,
+    pub text: String,
+}
+
+impl CliOutput {
+    fn new(json: impl Serialize, text: String) -> Result<Self, OnyxError> {
+        Ok(Self {
+            json: serde_json::to_value(json)?,
+            text,
+        })
+    }
+}
+
+const SUBCOMMANDS: [&str; 9] = [
+    "new",
+    "search",
+    "tags",
+    "backlinks",
+    "open",
+    "export",
+    "doctor",
+    "mcp",
+    "lsp",
+];
+
+/// Whether the process was started as a command-line tool rather than as the app: the first
+/// argument names a subcommand or asks for help or the version.
+pub fn is_cli_invocation(args: &[String]) -> bool {
+    args.get(1).is_some_and(|first| {
+        SUBCOMMANDS.contains(&first.as_str())
+            || matches!(
+                first.as_str(),
+                "help" | "-h" | "--help" | "-V" | "--version"
+            )
+    })
+}
+
+/// Parses `args`, runs the command and prints its result; returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    #[cfg(windows)]
+    attach_parent_console();
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(error) => {
+            let _ = error.print();
+            return error.exit_code();
+        }
+    };
+    let json = cli.json;
+    match execute(cli) {
+        Ok(output) => {
+            let mut stdout = std::io::stdout().lock();
+            let printed = if output.json.is_null() && output.text.is_empty() {
+                Ok(())
+            } else if json {
+                serde_json::to_string_pretty(&output.json)
+                    .map_err(std::io::Error::from)
+                    .and_then(|text| writeln!(stdout, "{text}"))
+            } else if output.text.is_empty() {
+                Ok(())
+            } else {
+                writeln!(stdout, "{}", output.text.trim_end())
+            };
+            i32::from(printed.is_err())
+        }
+        Err(error) => {
+            if json {
+                println!("{}", json!({ "error": error.to_string() }));
+            } else {
+                eprintln!("onyx: {error}");
+            }
+            1
+        }
+    }
+}
+
+/// Release builds on Windows are GUI programs, which start without a console. Attaches to the
+/// terminal that started the process so output reaches it; handles a parent already redirected,
+/// like the pipes of an MCP or LSP client, are left alone.
+#[cfg(windows)]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{
+        AttachConsole, GetStdHandle, ATTACH_PARENT_PROCESS, STD_OUTPUT_HANDLE,
+    };
+    // SAFETY: both calls only read or set this process's standard handles.
+    unsafe {
+        if GetStdHandle(STD_OUTPUT_HANDLE).is_null() {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+/// The vault given with `--vault`, or else the one containing `current`.
+fn vault_root(cli_vault: Option<PathBuf>, current: &Path) -> Result<PathBuf, OnyxError> {
+    if let Some(vault) = cli_vault {
+        return Ok(std::path::absolute(vault)?);
+    }
+    find_vault_root(current).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "not inside a vault; pass --vault",
+        )
+        .into()
+    })
+}
+
+/// Runs a parsed command against its vault.
+pub fn execute(cli: Cli) -> Result<CliOutput, OnyxError> {
+    let root = match cli.command {
+        // The app works out the vault of the opened path itself.
+        CliCommand::Open { .. } => PathBuf::new(),
+        _ => vault_root(cli.vault, &std::env::current_dir()?)?,
+    };
+    match cli.command {
+        CliCommand::New { name, folder } => {
+            let mut vault = VaultHandle::open(&root)?;
+            let folder = folder
+                .or_else(|| vault.config().new_note_folder.clone())
+                .unwrap_or_default();
+            let file_name = if name.to_lowercase().ends_with(".md") {
+                name
+            } else {
+                format!("{name}.md")
+            };
+            let relative = Path::new(&folder)
+                .join(file_name)
+                .to_string_lossy()
+                .to_string();
+            let path = vault.resolve(&relative)?;
+            if path.exists() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} already exists", path.display()),
+                )
+                .into());
+            }
+            let path = vault.write_file(&relative, "")?;
+            let path = path.to_string_lossy().to_string();
+            CliOutput::new(json!({ "path": path }), path)
+        }
+        CliCommand::Search { query, limit } => {
+            let hits = search_vault(&root, &query.join(" "), limit)?;
+            let text = hits
+                .iter()
+                .map(|hit| match (hit.line, hit.page) {
+                    (Some(line), _) => format!("{}:{line}: {}", hit.path, hit.snippet),
+                    (_, Some(page)) => format!("{}#page={page}: {}", hit.path, hit.snippet),
+                    _ => format!("{}: {}", hit.path, hit.snippet),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            CliOutput::new(hits, text)
+        }
+        CliCommand::Tags => {
+            let tags = TagIndex::build(&root)?.all_tags();
+            let text = tags.join("\n");
+            CliOutput::new(tags, text)
+        }
+        CliCommand::Backlinks { file } => {
+            let target = resolve_target(&root, &file).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    format!("no file or note named {file}"),
+                )
+            })?;
+            let backlinks = find_backlinks(&root, &target)?;
+            let text = backlinks
+                .iter()
+                .map(|backlink| match (&backlink.line, &backlink.node) {
+                    (Some(line), _) => format!("{}:{line}: {}", backlink.source, backlink.context),
+                    (_, Some(node)) => format!("{} (node {node})", backlink.source),
+                    _ => backlink.source.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            CliOutput::new(backlinks, text)
+        }
+        CliCommand::Open { path } => {
+            let path = std::fs::canonicalize(&path)?;
+            std::process::Command::new(std::env::current_exe()?)
+                .arg(&path)
+                .spawn()?;
+            let path = path.to_string_lossy().to_string();
+            CliOutput::new(json!({ "opened": path }), format!("Opening {path}"))
+        }
+        CliCommand::Export { format, notes, out } => {
+            let report = match format {
+                ExportFormat::Html => {
+                    let theme = load_theme().unwrap_or_else(|_| "{}".to_string());
+                    let options = HtmlExportOptions::default();
+                    serde_json::to_value(export_notes_to_html(
+                        &root, &notes, &out, &options, &theme,
+                    )?)?
+                }
+                ExportFormat::Pdf => {
+                    let [note] = notes.as_slice() else {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "PDF export takes exactly one note",
+                        )
+                        .into());
+                    };
+                    serde_json::to_value(export_note_to_pdf(
+                        &root,
+                        note,
+                        &out,
+                        &PdfExportOptions::default(),
+                    )?)?
+                }
+                ExportFormat::Docx | ExportFormat::Epub => {
+                    let source = ChapterSource::Notes(
+                        notes
+                            .iter()
+                            .map(|note| note.to_string_lossy().to_string())
+                            .collect(),
+                    );
+                    let options = BookOptions::default();
+                    if format == ExportFormat::Docx {
+                        serde_json::to_value(export_book_to_docx(&root, &source, &out, &options)?)?
+                    } else {
+                        serde_json::to_value(export_book_to_epub(&root, &source, &out, &options)?)?
+                    }
+                }
+            };
+            let text = format!("Exported to {}", out.display());
+            CliOutput::new(report, text)
+        }
+        CliCommand::Doctor { trash } => {
+            let report = check_vault_health(&root, trash)?;
+            let mut text = String::new();
+            for broken in &report.broken_links {
+                text.push_str(&format!(
+                    "broken link: {}:{} -> {}\n",
+                    broken.note, broken.line, broken.target
+                ));
+            }
+            for missing in &report.missing_embeds {
+                text.push_str(&format!(
+                    "missing embed: {}:{} -> {}\n",
+                    missing.note, missing.line, missing.target
+                ));
+            }
+            for unused in &report.unused_attachments {
+                text.push_str(&format!("unused attachment: {unused}\n"));
+            }
+            for orphan in &report.orphan_notes {
+                text.push_str(&format!("orphan note: {orphan}\n"));
+            }
+            if text.is_empty() {
+                text.push_str("No problems found.");
+            }
+            CliOutput::new(report, text)
+        }
+        CliCommand::Mcp { read_only } => {
+            // stdout carries the protocol, so nothing else may be printed once serving starts.
+            let mut server = McpServer::new(VaultHandle::open(&root)?, read_only);
+            server.serve(std::io::stdin().lock(), std::io::stdout().lock())?;
+            Ok(CliOutput {
+                json: Value::Null,
+                text: String::new(),
+            })
+        }
+        CliCommand::Lsp => {
+            language_server::run_stdio(VaultHandle::open(&root)?)?;
+            Ok(CliOutput {
+                json: Value::Null,
+                text: String::new(),
+            })
+        }
+    }
+}
+
+/// A path as given (relative to the current directory or the vault) or a note name.
+fn resolve_target(root: &Path, file: &str) -> Option<PathBuf> {
+    let given = Path::new(file);
+    [given.to_path_buf(), root.join(given)]
+        .into_iter()
+        .find(|path| path.is_file())
+        .and_then(|path| std::path::absolute(path).ok())
+        .or_else(|| {
+            let resolver = LinkResolver::build(root);
+            resolver
+                .resolve_note(file)
+                .or_else(|| resolver.resolve_file(file))
+                .map(Path::to_path_buf)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run_in(vault: &Path, args: &[&str]) -> Result<CliOutput, OnyxError> {
+        let vault = vault.to_string_lossy().to_string();
+        let mut argv = vec!["onyx", "--vault", vault.as_str()];
+        argv.extend(args);
+        execute(Cli::try_parse_from(argv).unwrap())
+    }
+
+    #[test]
+    fn subcommands_are_told_apart_from_app_launches() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        assert!(is_cli_invocation(&args(&["Onyx", "search", "x"])));
+        assert!(is_cli_invocation(&args(&["Onyx", "--help"])));
+        assert!(is_cli_invocation(&args(&["Onyx", "mcp", "--read-only"])));
+        assert!(is_cli_invocation(&args(&["Onyx", "lsp"])));
+        assert!(!is_cli_invocation(&args(&["Onyx"])));
+        assert!(!is_cli_invocation(&args(&["Onyx", "/notes/Vault"])));
+        assert!(!is_cli_invocation(&args(&["Onyx", "-psn_0_12345"])));
+    }
+
+    #[test]
+    fn new_search_tags_and_backlinks() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        let created = run_in(root, &["new", "Plan", "--folder", "Projects"]).unwrap();
+        let plan = root.join("Projects/Plan.md");
+        assert_eq!(created.text, plan.to_string_lossy());
+        assert!(run_in(root, &["new", "Plan.md", "--folder", "Projects"]).is_err());
+        assert!(matches!(
+            run_in(root, &["new", "Escape", "--folder", "../.."]),
+            Err(OnyxError::OutsideVault(_))
+        ));
+        std::fs::write(&plan, "Launch the rocket #space\n").unwrap();
+        std::fs::write(root.join("Home.md"), "See [[Plan]] #hub\n").unwrap();
+
+        let search = run_in(root, &["search", "launch", "ROCKET"]).unwrap();
+        assert_eq!(search.json.as_array().unwrap().len(), 1);
+        assert!(search.text.ends_with("Plan.md:1: Launch the rocket #space"));
+
+        let tags = run_in(root, &["tags"]).unwrap();
+        assert_eq!(tags.json, json!(["hub", "space"]));
+
+        let backlinks = run_in(root, &["backlinks", "plan"]).unwrap();
+        assert_eq!(backlinks.json[0]["line"], json!(1));
+        assert!(backlinks.text.contains("Home.md:1: See [[Plan]] #hub"));
+    }
+
+    #[test]
+    fn commands_outside_a_vault_need_the_vault_flag() {
+        let temp = TempDir::new().unwrap();
+        let vault = temp.path().join("vault");
+        VaultHandle::create(&vault).unwrap();
+        std::fs::create_dir_all(vault.join("Notes")).unwrap();
+
+        assert_eq!(vault_root(None, &vault.join("Notes")).unwrap(), vault);
+        let error = vault_root(None, temp.path()).unwrap_err();
+        assert!(error.to_string().contains("pass --vault"));
+        assert!(!temp.path().join(".onyx").exists());
+    }
+
+    #[test]
+    fn doctor_reports_broken_links_as_text_and_json() {
+        let temp = TempDir::new().unwrap();
+        std::fs::write(temp.path().join("Home.md"), "[[Missing]]\n").unwrap();
+
+        let report = run_in(temp.path(), &["doctor"]).unwrap();
+
+        assert_eq!(report.json["broken_links"][0]["target"], json!("Missing"));
+        assert!(report.text.contains("broken link:"));
+    }
+
+    #[test]
+    fn export_writes_the_requested_format() {
+        let temp = TempDir::new().unwrap();
+        let note = temp.path().join("Note.md");
+        std::fs::write(&note, "# Hello\n\nWorld\n").unwrap();
+        let out = temp.path().join("out.epub");
+
+        run_in(
+            temp.path(),
+            &[
+                "export",
+                "epub",
+                &note.to_string_lossy(),
+                "--out",
+                &out.to_string_lossy(),
+            ],
+        )
+        .unwrap();
+
+        assert!(out.is_file());
+        assert!(Cli::try_parse_from(["onyx", "export", "rtf", "a.md", "--out", "x"]).is_err());
+    }
+}
@@ -1,20 +1,64 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use base64::{engine::general_purpose, Engine as _};
 
 use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Manager, State, TitleBarStyle, WebviewUrl, WebviewWindowBuilder};
+use tauri::{AppHandle, Emitter, Manager, State, TitleBarStyle, WebviewUrl, WebviewWindowBuilder};
 
-use crate::file_tree::{scan_file_tree, FileTreeEntry};
-use crate::global_config::{load_global_config, register_vault, save_global_config, GlobalConfig};
-use crate::tag_index::TagIndex;
-use crate::vault::Vault;
-use crate::vault_config::{load_vault_session, save_vault_session, VaultSession};
+use onyx_core::api_server::{ApiServer, FileChange};
+use onyx_core::attachments;
+use onyx_core::backlinks::{find_backlinks, Backlink};
+use onyx_core::book::{BookExportReport, BookOptions, ChapterSource};
+use onyx_core::buffer::ChangeSet;
+use onyx_core::canvas::{self, Canvas};
+use onyx_core::deep_link::OpenRequest;
+use onyx_core::export_docx::export_book_to_docx;
+use onyx_core::export_epub::export_book_to_epub;
+use onyx_core::export_html::{export_notes_to_html, HtmlExportOptions, HtmlExportReport};
+use onyx_core::export_pdf::{export_note_to_pdf, PdfExportOptions, PdfExportReport};
+use onyx_core::file_tree::{scan_file_tree, FileTreeEntry};
+use onyx_core::files;
+use onyx_core::global_config::{
+    load_global_config, register_vault, save_global_config, ApiConfig, GlobalConfig,
+};
+use onyx_core::graph::{build_graph, render_graph, Graph, GraphFilter, GraphFormat};
+use onyx_core::importer::{self, ImportReport, ImportSource};
+use onyx_core::obsidian_import::{self, ObsidianImportReport};
+use onyx_core::pdf_annotations::{self, Annotation, NewAnnotation};
+use onyx_core::pdf_text::{self, PdfOutline};
+use onyx_core::query::{self, EmbeddedQuery, QueryResult};
+use onyx_core::recovery::{self, RecoverableBuffer};
+use onyx_core::search::{self, SearchHit};
+use onyx_core::site::{build_site, SiteOptions, SiteReport};
+use onyx_core::task_index::{self, Task, TaskFilter};
+use onyx_core::theme;
+use onyx_core::thumbnails::{self, FileInfo, DEFAULT_THUMBNAIL_SIZE};
+use onyx_core::vault::{find_vault_root, Vault};
+use onyx_core::vault_config::{
+    ensure_vault_config, load_vault_session, save_vault_session, VaultSession,
+};
+use onyx_core::vault_handle::OpenVaults;
+use onyx_core::vault_health::{self, VaultHealthReport};
+use onyx_core::{OnyxError, VaultHandle};
+
+/// Open vaults keyed by root path, shared by the commands that use the in-memory indexes and by
+/// the local API.
+pub type VaultHandles = OpenVaults;
+
+/// The running local API server, if enabled.
+pub type ApiState = Mutex<Option<ApiServer>>;
+
+/// Emitted to every window when a file changes outside the editor.
+pub const FILE_CHANGED_EVENT: &str = "vault-file-changed";
+
+/// Emitted to a vault window that should open a file or search, e.g. for an `onyx://` link.
+pub const OPEN_REQUEST_EVENT: &str = "open-request";
 
 /// Serializable vault summary returned to the frontend.
 #[derive(Debug, Serialize, Deserialize)]
@@ -80,6 +124,33 @@ pub fn open_vault(path: String) -> Result<VaultInfo, String> {
     })
 }
 
+/// Opens an Obsidian vault, migrating its `.obsidian/` settings into Onyx's config and session.
+#[tauri::command]
+pub fn import_obsidian_vault(path: String) -> Result<ObsidianImportReport, String> {
+    let vault_path = PathBuf::from(&path);
+    let report = obsidian_import::import_vault(&vault_path).map_err(|e| {
+        error!("Failed to import Obsidian vault at {}: {e}", path);
+        e.to_string()
+    })?;
+    register_vault(vault_path).map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Converts a Notion, Evernote or Bear export into notes under `target` inside the vault.
+/// With `dry_run`, reports what would be created without writing anything.
+#[tauri::command]
+pub fn import_notes(
+    vault_path: String,
+    source: ImportSource,
+    target: String,
+    dry_run: bool,
+) -> Result<ImportReport, String> {
+    importer::import_notes(Path::new(&vault_path), &source, &target, dry_run).map_err(|e| {
+        error!("Failed to import {:?} into {}: {e}", source, vault_path);
+        e.to_string()
+    })
+}
+
 /// Returns the file tree for the given vault root path.
 #[tauri::command]
 pub fn get_file_tree(vault_path: String) -> Result<Vec<FileTreeEntryDto>, String> {
@@ -127,11 +198,245 @@ pub fn read_binary_as_data_url(path: String) -> Result<String, String> {
     Ok(format!("data:{mime};base64,{encoded}"))
 }
 
-/// Writes content to a file, creating it if it doesn't exist.
+/// Saves pasted or dropped bytes as an attachment of `note_path`, following the vault's
+/// attachment folder policy, and returns the markdown link to insert into the note.
+#[tauri::command]
+pub fn save_attachment(
+    vault_path: String,
+    note_path: String,
+    file_name: String,
+    bytes: Vec<u8>,
+) -> Result<String, String> {
+    let root = Path::new(&vault_path);
+    let config = ensure_vault_config(root).map_err(|e| e.to_string())?;
+    let saved =
+        attachments::save_attachment(root, &config, Path::new(&note_path), &file_name, &bytes)
+            .map_err(|e| {
+                error!(
+                    "Failed to save attachment {} for {}: {e}",
+                    file_name, note_path
+                );
+                e.to_string()
+            })?;
+    Ok(saved.link)
+}
+
+/// Reports unused attachments, broken links, missing embeds and orphan notes in the vault,
+/// optionally moving the unused attachments to the system trash.
+#[tauri::command]
+pub fn check_vault_health(
+    vault_path: String,
+    trash_unused: bool,
+) -> Result<VaultHealthReport, String> {
+    vault_health::check_vault_health(Path::new(&vault_path), trash_unused).map_err(|e| {
+        error!("Failed to check vault health at {}: {e}", vault_path);
+        e.to_string()
+    })
+}
+
+/// Returns the path of a cached preview of an image, generating it if needed.
+/// `None` means the format can't be thumbnailed and the original should be shown.
+#[tauri::command]
+pub fn get_thumbnail(
+    vault_path: String,
+    path: String,
+    max_size: Option<u32>,
+) -> Result<Option<String>, String> {
+    let size = max_size.unwrap_or(DEFAULT_THUMBNAIL_SIZE);
+    let thumbnail =
+        thumbnails::thumbnail(Path::new(&vault_path), Path::new(&path), size).map_err(|e| {
+            error!("Failed to create thumbnail for {}: {e}", path);
+            e.to_string()
+        })?;
+    Ok(thumbnail.map(|thumbnail| thumbnail.to_string_lossy().to_string()))
+}
+
+/// Returns size, timestamps and, for images, dimensions and EXIF orientation of a file.
+#[tauri::command]
+pub fn get_file_info(path: String) -> Result<FileInfo, String> {
+    thumbnails::file_info(Path::new(&path)).map_err(|e| {
+        error!("Failed to read file info for {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Searches note lines and PDF pages for every word of `query`.
+#[tauri::command]
+pub fn search_vault(
+    vault_path: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    search::search_vault(Path::new(&vault_path), &query, limit.unwrap_or(200)).map_err(|e| {
+        error!("Failed to search {} for {:?}: {e}", vault_path, query);
+        e.to_string()
+    })
+}
+
+/// Returns the bookmarks and page count of a PDF.
+#[tauri::command]
+pub fn get_pdf_outline(path: String) -> Result<PdfOutline, String> {
+    pdf_text::pdf_outline(Path::new(&path)).map_err(|e| {
+        error!("Failed to read PDF outline of {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Lists the highlights and comments stored for a PDF, in reading order.
+#[tauri::command]
+pub fn list_pdf_annotations(path: String) -> Result<Vec<Annotation>, String> {
+    pdf_annotations::list_annotations(Path::new(&path)).map_err(|e| {
+        error!("Failed to read annotations of {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Saves a highlight or comment to the PDF's sidecar file.
+#[tauri::command]
+pub fn add_pdf_annotation(path: String, annotation: NewAnnotation) -> Result<Annotation, String> {
+    pdf_annotations::add_annotation(Path::new(&path), annotation).map_err(|e| {
+        error!("Failed to add annotation to {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Deletes an annotation by id; returns whether it existed.
+#[tauri::command]
+pub fn delete_pdf_annotation(path: String, id: String) -> Result<bool, String> {
+    pdf_annotations::delete_annotation(Path::new(&path), &id).map_err(|e| {
+        error!("Failed to delete annotation {} of {}: {e}", id, path);
+        e.to_string()
+    })
+}
+
+/// Creates a note quoting the PDF's highlights, each linking back to its page.
+#[tauri::command]
+pub fn create_literature_note(path: String, dest: Option<String>) -> Result<String, String> {
+    let note =
+        pdf_annotations::create_literature_note(Path::new(&path), dest.as_deref().map(Path::new))
+            .map_err(|e| {
+            error!("Failed to create literature note for {}: {e}", path);
+            e.to_string()
+        })?;
+    Ok(note.to_string_lossy().to_string())
+}
+
+/// Reads and validates a JSON Canvas file.
+#[tauri::command]
+pub fn read_canvas(path: String) -> Result<Canvas, String> {
+    canvas::read_canvas(Path::new(&path)).map_err(|e| {
+        error!("Failed to read canvas {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Validates a canvas and writes it to `path`; an invalid canvas leaves the file untouched.
+#[tauri::command]
+pub fn write_canvas(path: String, canvas: Canvas) -> Result<(), String> {
+    canvas::write_canvas(Path::new(&path), &canvas).map_err(|e| {
+        error!("Failed to write canvas {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Creates an empty canvas inside the vault and returns its absolute path.
+#[tauri::command]
+pub fn create_canvas(vault_path: String, name: String) -> Result<String, String> {
+    canvas::create_canvas(&PathBuf::from(&vault_path).join(&name))
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| {
+            error!("Failed to create canvas {name}: {e}");
+            e.to_string()
+        })
+}
+
+/// Lists the notes and canvases that reference `path`.
+#[tauri::command]
+pub fn get_backlinks(vault_path: String, path: String) -> Result<Vec<Backlink>, String> {
+    find_backlinks(Path::new(&vault_path), Path::new(&path)).map_err(|e| {
+        error!("Failed to find backlinks to {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Writes content to a file, creating it if it doesn't exist. Files in a vault are written
+/// through its handle, the same pipeline the local API and MCP use: confined to the vault and
+/// re-indexed. Without `vault_path` the vault containing `path` is used; a loose note outside
+/// every vault is written as is.
+#[tauri::command]
+pub fn write_file(
+    path: String,
+    content: String,
+    vault_path: Option<String>,
+    state: State<'_, VaultHandles>,
+) -> Result<(), String> {
+    let file = PathBuf::from(&path);
+    let vault_path = vault_path
+        .or_else(|| find_vault_root(&file).map(|root| root.to_string_lossy().to_string()));
+    let Some(vault_path) = vault_path else {
+        return std::fs::write(&file, content).map_err(|e| {
+            error!("Failed to write file {}: {e}", path);
+            e.to_string()
+        });
+    };
+    with_vault(&state, &vault_path, |handle| {
+        handle
+            .relative_path(&file)
+            .and_then(|relative| handle.write_file(&relative, &content))
+            .map_err(|e| {
+                error!("Failed to write file {}: {e}", path);
+                e.to_string()
+            })
+    })?;
+    // The file now holds the buffer, so its recovery copy is no longer needed.
+    if let Err(e) = recovery::clear_buffer(Path::new(&vault_path), &file) {
+        warn!("Failed to clear recovery copy of {}: {e}", path);
+    }
+    Ok(())
+}
+
+/// Records the unsaved text of an open file so it survives a crash or a closed window.
+#[tauri::command]
+pub fn journal_buffer(vault_path: String, path: String, content: String) -> Result<(), String> {
+    recovery::journal_buffer(Path::new(&vault_path), Path::new(&path), &content).map_err(|e| {
+        error!("Failed to journal unsaved changes of {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Unsaved buffers left from an earlier session, each with a diff against the file on disk.
 #[tauri::command]
-pub fn write_file(path: String, content: String) -> Result<(), String> {
-    std::fs::write(&path, content).map_err(|e| {
-        error!("Failed to write file {}: {e}", path);
+pub fn list_recoverable_buffers(vault_path: String) -> Result<Vec<RecoverableBuffer>, String> {
+    recovery::list_recoverable_buffers(Path::new(&vault_path)).map_err(|e| {
+        error!("Failed to list recoverable buffers in {}: {e}", vault_path);
+        e.to_string()
+    })
+}
+
+/// Writes a recovered buffer to its file and returns the restored text.
+#[tauri::command]
+pub fn restore_buffer(
+    vault_path: String,
+    path: String,
+    state: State<'_, VaultHandles>,
+) -> Result<String, String> {
+    let content =
+        recovery::restore_buffer(Path::new(&vault_path), Path::new(&path)).map_err(|e| {
+            error!("Failed to restore unsaved changes of {}: {e}", path);
+            e.to_string()
+        })?;
+    with_vault(&state, &vault_path, |handle| {
+        handle.update_file(Path::new(&path), &content);
+        Ok(())
+    })?;
+    Ok(content)
+}
+
+/// Throws away the recovery copy of a file, keeping the file as it is on disk.
+#[tauri::command]
+pub fn discard_buffer(vault_path: String, path: String) -> Result<(), String> {
+    recovery::clear_buffer(Path::new(&vault_path), Path::new(&path)).map_err(|e| {
+        error!("Failed to discard unsaved changes of {}: {e}", path);
         e.to_string()
     })
 }
@@ -145,17 +450,17 @@ pub fn maximize_window(window: tauri::Window) -> Result<(), String> {
 /// Creates a new empty file inside the vault and returns its absolute path.
 #[tauri::command]
 pub fn create_file(vault_path: String, name: String) -> Result<String, String> {
-    let path = PathBuf::from(&vault_path).join(&name);
-    std::fs::write(&path, "").map_err(|e| e.to_string())?;
-    Ok(path.to_string_lossy().to_string())
+    files::create_file(Path::new(&vault_path), &name)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
 }
 
 /// Creates a new empty directory inside the vault and returns its absolute path.
 #[tauri::command]
 pub fn create_folder(vault_path: String, name: String) -> Result<String, String> {
-    let path = PathBuf::from(&vault_path).join(&name);
-    std::fs::create_dir(&path).map_err(|e| e.to_string())?;
-    Ok(path.to_string_lossy().to_string())
+    files::create_folder(Path::new(&vault_path), &name)
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
 }
 
 /// Loads the saved session (open tabs, active tab) for the given vault.
@@ -168,17 +473,26 @@ pub fn load_vault_session_cmd(vault_path: String) -> Result<VaultSession, String
 }
 
 /// Persists the session (open tabs, active tab, sort order) for the given vault.
+/// Bookmarks are kept as saved unless the caller passes a new list.
 #[tauri::command]
 pub fn save_vault_session_cmd(
     vault_path: String,
     open_tabs: Vec<String>,
     active_tab: Option<String>,
     sort_order: Option<String>,
+    bookmarks: Option<Vec<String>>,
 ) -> Result<(), String> {
+    let bookmarks = match bookmarks {
+        Some(bookmarks) => bookmarks,
+        None => load_vault_session(Path::new(&vault_path))
+            .map(|session| session.bookmarks)
+            .unwrap_or_default(),
+    };
     let session = VaultSession {
         open_tabs,
         active_tab,
         sort_order,
+        bookmarks,
     };
     save_vault_session(Path::new(&vault_path), &session).map_err(|e| {
         warn!("Failed to save vault session for {}: {e}", vault_path);
@@ -216,95 +530,205 @@ pub fn get_settings() -> Result<GlobalConfig, String> {
 /// Returns "{}" if the file does not exist, so the frontend falls back to the default palette.
 #[tauri::command]
 pub fn load_theme() -> Result<String, String> {
-    let config_dir =
-        dirs_next::config_dir().ok_or_else(|| "Cannot determine config directory".to_string())?;
-    read_theme_from_dir(&config_dir)
+    theme::load_theme().map_err(|e| e.to_string())
 }
 
-fn read_theme_from_dir(config_dir: &std::path::Path) -> Result<String, String> {
-    let path = config_dir.join("onyx").join("theme.json");
-    if !path.exists() {
-        return Ok("{}".to_string());
-    }
-    std::fs::read_to_string(&path).map_err(|e| e.to_string())
+/// Renders the given notes and folders to a standalone HTML folder at `dest`, styled with the
+/// current `theme.json` palette.
+#[tauri::command]
+pub fn export_html(
+    vault_path: String,
+    paths: Vec<String>,
+    dest: String,
+    options: Option<HtmlExportOptions>,
+) -> Result<HtmlExportReport, String> {
+    let theme = load_theme()?;
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    export_notes_to_html(
+        Path::new(&vault_path),
+        &paths,
+        Path::new(&dest),
+        &options.unwrap_or_default(),
+        &theme,
+    )
+    .map_err(|e| {
+        error!("Failed to export HTML to {}: {e}", dest);
+        e.to_string()
+    })
 }
 
-#[cfg(test)]
-mod theme_tests {
-    use super::read_theme_from_dir;
-    use std::fs;
+/// Publishes the notes selected by `options.filter` as a self-contained static site at `dest`.
+#[tauri::command]
+pub fn export_site(
+    vault_path: String,
+    dest: String,
+    options: SiteOptions,
+) -> Result<SiteReport, String> {
+    let theme = load_theme()?;
+    build_site(Path::new(&vault_path), Path::new(&dest), &options, &theme).map_err(|e| {
+        error!("Failed to build site at {}: {e}", dest);
+        e.to_string()
+    })
+}
 
-    #[test]
-    fn returns_empty_object_when_file_is_absent() {
-        let dir = tempfile::tempdir().expect("tempdir");
-        let result = read_theme_from_dir(dir.path()).unwrap();
-        assert_eq!(result, "{}");
-    }
+/// Lays out a single note as a PDF at `dest`, without going through the webview.
+#[tauri::command]
+pub fn export_pdf(
+    vault_path: String,
+    path: String,
+    dest: String,
+    options: Option<PdfExportOptions>,
+) -> Result<PdfExportReport, String> {
+    let options = options.unwrap_or_default();
+    export_note_to_pdf(
+        Path::new(&vault_path),
+        Path::new(&path),
+        Path::new(&dest),
+        &options,
+    )
+    .map_err(|e| {
+        error!("Failed to export {} to PDF: {e}", path);
+        e.to_string()
+    })
+}
 
-    #[test]
-    fn returns_raw_json_when_file_exists() {
-        let dir = tempfile::tempdir().expect("tempdir");
-        let onyx_dir = dir.path().join("onyx");
-        fs::create_dir_all(&onyx_dir).expect("create onyx dir");
-        let theme_path = onyx_dir.join("theme.json");
-        fs::write(&theme_path, r##"{"accent":"#ff0000"}"##).expect("write theme");
-        let result = read_theme_from_dir(dir.path()).unwrap();
-        assert_eq!(result, r##"{"accent":"#ff0000"}"##);
-    }
+/// Converts one note or an ordered set of notes into a Word document at `dest`.
+#[tauri::command]
+pub fn export_docx(
+    vault_path: String,
+    source: ChapterSource,
+    dest: String,
+    options: Option<BookOptions>,
+) -> Result<BookExportReport, String> {
+    let options = options.unwrap_or_default();
+    export_book_to_docx(Path::new(&vault_path), &source, Path::new(&dest), &options).map_err(|e| {
+        error!("Failed to export DOCX to {}: {e}", dest);
+        e.to_string()
+    })
+}
+
+/// Converts one note or an ordered set of notes into an EPUB book at `dest`, one chapter per note.
+#[tauri::command]
+pub fn export_epub(
+    vault_path: String,
+    source: ChapterSource,
+    dest: String,
+    options: Option<BookOptions>,
+) -> Result<BookExportReport, String> {
+    let options = options.unwrap_or_default();
+    export_book_to_epub(Path::new(&vault_path), &source, Path::new(&dest), &options).map_err(|e| {
+        error!("Failed to export EPUB to {}: {e}", dest);
+        e.to_string()
+    })
 }
 
 /// Persists a settings change without clobbering the vault list or other fields.
 #[tauri::command]
-pub fn save_settings(vim_mode: bool) -> Result<(), String> {
+pub fn save_settings(vim_mode: Option<bool>, autosave_on_idle: Option<bool>) -> Result<(), String> {
     let mut config = load_global_config().map_err(|e| e.to_string())?;
-    config.vim_mode = vim_mode;
+    if let Some(vim_mode) = vim_mode {
+        config.vim_mode = vim_mode;
+    }
+    if let Some(autosave_on_idle) = autosave_on_idle {
+        config.autosave_on_idle = autosave_on_idle;
+    }
     save_global_config(&config).map_err(|e| e.to_string())
 }
 
+/// Starts the local API on the configured port, forwarding its writes to the windows as
+/// [`FILE_CHANGED_EVENT`]s.
+pub fn start_api(
+    app: &AppHandle,
+    config: &ApiConfig,
+    vaults: &VaultHandles,
+) -> Result<ApiServer, OnyxError> {
+    let token = config.token.clone().unwrap_or_default();
+    let app = app.clone();
+    let on_change = Arc::new(move |change: &FileChange| {
+        if let Err(e) = app.emit(FILE_CHANGED_EVENT, change.clone()) {
+            warn!("Failed to emit change of {}: {e}", change.path);
+        }
+    });
+    ApiServer::start(config.port, token, Arc::clone(vaults), on_change)
+}
+
+/// Turns the local API on or off and returns its settings, including the token clients must send.
+#[tauri::command]
+pub fn save_api_settings(
+    app: AppHandle,
+    enabled: bool,
+    port: Option<u16>,
+    vaults: State<'_, VaultHandles>,
+    api: State<'_, ApiState>,
+) -> Result<ApiConfig, String> {
+    let mut config = load_global_config().map_err(|e| e.to_string())?;
+    config.api.enabled = enabled;
+    if let Some(port) = port {
+        config.api.port = port;
+    }
+    if enabled {
+        config.api.ensure_token().map_err(|e| e.to_string())?;
+    }
+    save_global_config(&config).map_err(|e| e.to_string())?;
+
+    let mut running = api.lock().map_err(|e| e.to_string())?;
+    // Dropping the old server frees its port before a new one binds.
+    running.take();
+    if enabled {
+        let server = start_api(&app, &config.api, &vaults).map_err(|e| {
+            error!("Failed to start local API on port {}: {e}", config.api.port);
+            e.to_string()
+        })?;
+        *running = Some(server);
+    }
+    Ok(config.api)
+}
+
 /// Renames a file within its current directory, preserving the extension, and returns the new absolute path.
 #[tauri::command]
-pub fn rename_file(old_path: String, new_stem: String) -> Result<String, String> {
+pub fn rename_file(
+    old_path: String,
+    new_stem: String,
+    state: State<'_, VaultHandles>,
+) -> Result<String, String> {
     let source = PathBuf::from(&old_path);
-    let parent = source
-        .parent()
-        .ok_or_else(|| "Path has no parent directory".to_string())?;
-    let extension = source
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("");
-    let new_file_name = if extension.is_empty() {
-        new_stem.clone()
-    } else {
-        format!("{}.{}", new_stem, extension)
-    };
-    let destination = parent.join(&new_file_name);
-    if destination.exists() {
-        return Err(format!("A file named '{}' already exists", new_file_name));
+    let mut handles = state.lock().map_err(|e| e.to_string())?;
+    let destination = match handle_containing(&mut handles, &source) {
+        Some(handle) => handle.rename_file(&source, &new_stem),
+        None => files::rename_file(&source, &new_stem),
     }
-    std::fs::rename(&source, &destination).map_err(|e| e.to_string())?;
+    .map_err(|e| {
+        error!("Failed to rename {}: {e}", old_path);
+        e.to_string()
+    })?;
     Ok(destination.to_string_lossy().to_string())
 }
 
 /// Permanently deletes a file or directory at the given path.
 #[tauri::command]
 pub fn delete_file(path: String) -> Result<(), String> {
-    let target = std::path::Path::new(&path);
-    if target.is_dir() {
-        std::fs::remove_dir_all(target).map_err(|e| e.to_string())
-    } else {
-        std::fs::remove_file(target).map_err(|e| e.to_string())
-    }
+    files::delete_path(Path::new(&path)).map_err(|e| e.to_string())
 }
 
 /// Moves a file or directory to a new parent directory, preserving the original name.
 #[tauri::command]
-pub fn move_file(source_path: String, target_dir: String) -> Result<(), String> {
+pub fn move_file(
+    source_path: String,
+    target_dir: String,
+    state: State<'_, VaultHandles>,
+) -> Result<(), String> {
     let source = PathBuf::from(&source_path);
-    let file_name = source
-        .file_name()
-        .ok_or_else(|| "Invalid source path".to_string())?;
-    let destination = PathBuf::from(&target_dir).join(file_name);
-    std::fs::rename(&source, &destination).map_err(|e| e.to_string())
+    let target_dir = Path::new(&target_dir);
+    let mut handles = state.lock().map_err(|e| e.to_string())?;
+    match handle_containing(&mut handles, &source) {
+        Some(handle) => handle.move_file(&source, target_dir),
+        None => files::move_file(&source, target_dir),
+    }
+    .map_err(|e| {
+        error!("Failed to move {}: {e}", source_path);
+        e.to_string()
+    })?;
+    Ok(())
 }
 
 /// Returns the last active vault, or `None` if no vault has been opened yet or the path is gone.
@@ -351,69 +775,250 @@ pub fn get_known_vaults() -> Result<Vec<VaultEntry>, String> {
     Ok(entries)
 }
 
+/// The open vault whose root contains `path`, if any.
+fn handle_containing<'a>(
+    handles: &'a mut HashMap<PathBuf, VaultHandle>,
+    path: &Path,
+) -> Option<&'a mut VaultHandle> {
+    handles.values_mut().find(|handle| handle.contains(path))
+}
+
+/// Runs `f` against the handle for `vault_path`, opening the vault on first use.
+pub(crate) fn with_vault<T>(
+    state: &VaultHandles,
+    vault_path: &str,
+    f: impl FnOnce(&mut VaultHandle) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut handles = state.lock().map_err(|e| e.to_string())?;
+    let handle = match handles.entry(PathBuf::from(vault_path)) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => {
+            let handle = VaultHandle::open(Path::new(vault_path)).map_err(|e| {
+                error!("Failed to open vault {}: {e}", vault_path);
+                e.to_string()
+            })?;
+            entry.insert(handle)
+        }
+    };
+    f(handle)
+}
+
 /// Scans the vault and builds the in-memory tag index; called once when a vault is opened.
 #[tauri::command]
-pub fn build_tag_index(
+pub fn build_tag_index(vault_path: String, state: State<'_, VaultHandles>) -> Result<(), String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.rebuild_tag_index().map_err(|e| {
+            error!("Failed to build tag index for {}: {e}", vault_path);
+            e.to_string()
+        })
+    })
+}
+
+/// Returns all known tags across the vault, building the tag index on first use.
+#[tauri::command]
+pub fn get_tags(vault_path: String, state: State<'_, VaultHandles>) -> Result<Vec<String>, String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.tags().map_err(|e| {
+            error!("Failed to build tag index for {}: {e}", vault_path);
+            e.to_string()
+        })
+    })
+}
+
+/// Updates the tag and task indexes for a single file after it has been saved.
+#[tauri::command]
+pub fn update_file_index(
     vault_path: String,
-    state: State<'_, Mutex<HashMap<PathBuf, TagIndex>>>,
+    file_path: String,
+    content: String,
+    state: State<'_, VaultHandles>,
 ) -> Result<(), String> {
-    let path = PathBuf::from(&vault_path);
-    let index = TagIndex::build(Path::new(&vault_path)).map_err(|e| {
-        error!("Failed to build tag index for {}: {e}", vault_path);
-        e.to_string()
-    })?;
-    state.lock().map_err(|e| e.to_string())?.insert(path, index);
-    info!("Tag index built for {}", vault_path);
+    let mut handles = state.lock().map_err(|e| e.to_string())?;
+    if let Some(handle) = handles.get_mut(&PathBuf::from(&vault_path)) {
+        handle.update_file(Path::new(&file_path), &content);
+    }
     Ok(())
 }
 
-/// Returns all known tags across the vault; returns an empty list if the index has not been built.
+/// Starts editing a note with the text the editor shows; later edits arrive as change sets.
 #[tauri::command]
-pub fn get_tags(
+pub fn open_buffer(
     vault_path: String,
-    state: State<'_, Mutex<HashMap<PathBuf, TagIndex>>>,
-) -> Result<Vec<String>, String> {
-    let guard = state.lock().map_err(|e| e.to_string())?;
-    let path = PathBuf::from(&vault_path);
-    Ok(guard.get(&path).map_or_else(Vec::new, TagIndex::all_tags))
+    path: String,
+    content: String,
+    state: State<'_, VaultHandles>,
+) -> Result<(), String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.open_buffer(Path::new(&path), &content);
+        Ok(())
+    })
 }
 
-/// Updates the tag index for a single file after it has been saved.
+/// Applies an editor change set to an open note and updates the indexes from the lines it
+/// touched. Fails when the note isn't open or its buffer has drifted from the editor.
 #[tauri::command]
-pub fn update_file_tags(
+pub fn apply_edits(
     vault_path: String,
-    file_path: String,
-    content: String,
-    state: State<'_, Mutex<HashMap<PathBuf, TagIndex>>>,
+    path: String,
+    changes: ChangeSet,
+    state: State<'_, VaultHandles>,
 ) -> Result<(), String> {
-    let mut guard = state.lock().map_err(|e| e.to_string())?;
-    let path = PathBuf::from(&vault_path);
-    if let Some(index) = guard.get_mut(&path) {
-        index.update_file(&file_path, &content);
+    with_vault(&state, &vault_path, |handle| {
+        handle.apply_edits(Path::new(&path), &changes).map_err(|e| {
+            warn!("Failed to apply edits to {}: {e}", path);
+            e.to_string()
+        })
+    })
+}
+
+/// Undoes the last group of edits to an open note; returns the change sets to apply in order.
+#[tauri::command]
+pub fn undo_buffer(
+    vault_path: String,
+    path: String,
+    state: State<'_, VaultHandles>,
+) -> Result<Vec<ChangeSet>, String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.undo_buffer(Path::new(&path)).map_err(|e| {
+            error!("Failed to undo in {}: {e}", path);
+            e.to_string()
+        })
+    })
+}
+
+/// Redoes the last undone group of edits to an open note.
+#[tauri::command]
+pub fn redo_buffer(
+    vault_path: String,
+    path: String,
+    state: State<'_, VaultHandles>,
+) -> Result<Vec<ChangeSet>, String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.redo_buffer(Path::new(&path)).map_err(|e| {
+            error!("Failed to redo in {}: {e}", path);
+            e.to_string()
+        })
+    })
+}
+
+/// Writes an open note's buffer to disk and drops its recovery copy.
+#[tauri::command]
+pub fn save_buffer(
+    vault_path: String,
+    path: String,
+    state: State<'_, VaultHandles>,
+) -> Result<(), String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.save_buffer(Path::new(&path)).map_err(|e| {
+            error!("Failed to save {}: {e}", path);
+            e.to_string()
+        })
+    })?;
+    if let Err(e) = recovery::clear_buffer(Path::new(&vault_path), Path::new(&path)) {
+        warn!("Failed to clear recovery copy of {}: {e}", path);
     }
     Ok(())
 }
 
+/// Stops editing a note, dropping unsaved edits from its buffer.
+#[tauri::command]
+pub fn close_buffer(
+    vault_path: String,
+    path: String,
+    state: State<'_, VaultHandles>,
+) -> Result<(), String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.close_buffer(Path::new(&path));
+        Ok(())
+    })
+}
+
+/// Returns the vault's link graph as nodes and edges, narrowed by `filter`.
+#[tauri::command]
+pub fn get_graph(vault_path: String, filter: Option<GraphFilter>) -> Result<Graph, String> {
+    build_graph(Path::new(&vault_path), &filter.unwrap_or_default()).map_err(|e| {
+        error!("Failed to build graph for {}: {e}", vault_path);
+        e.to_string()
+    })
+}
+
+/// Writes the vault's link graph to `dest` as JSON, DOT or GraphML for external tools.
+#[tauri::command]
+pub fn export_graph(
+    vault_path: String,
+    filter: Option<GraphFilter>,
+    format: GraphFormat,
+    dest: String,
+) -> Result<(), String> {
+    build_graph(Path::new(&vault_path), &filter.unwrap_or_default())
+        .and_then(|graph| render_graph(&graph, format))
+        .and_then(|rendered| Ok(std::fs::write(&dest, rendered)?))
+        .map_err(|e| {
+            error!("Failed to export graph to {}: {e}", dest);
+            e.to_string()
+        })
+}
+
+/// Runs a query such as `TABLE status FROM #project WHERE status != "done"` over the vault's notes.
+#[tauri::command]
+pub fn run_query(vault_path: String, query: String) -> Result<QueryResult, String> {
+    query::run_query(Path::new(&vault_path), &query).map_err(|e| {
+        warn!("Query failed in {}: {e}", vault_path);
+        e.to_string()
+    })
+}
+
+/// Runs every ```` ```query ```` block of a note so the editor can render the results in place.
+#[tauri::command]
+pub fn run_note_queries(vault_path: String, path: String) -> Result<Vec<EmbeddedQuery>, String> {
+    query::run_note_queries(Path::new(&vault_path), Path::new(&path)).map_err(|e| {
+        error!("Failed to run queries in {}: {e}", path);
+        e.to_string()
+    })
+}
+
+/// Returns tasks across the vault matching `filter`, building the task index on first use.
+#[tauri::command]
+pub fn query_tasks(
+    vault_path: String,
+    filter: Option<TaskFilter>,
+    state: State<'_, VaultHandles>,
+) -> Result<Vec<Task>, String> {
+    with_vault(&state, &vault_path, |handle| {
+        handle.tasks(&filter.unwrap_or_default()).map_err(|e| {
+            error!("Failed to build task index for {}: {e}", vault_path);
+            e.to_string()
+        })
+    })
+}
+
+/// Flips the checkbox of the task on 1-based `line` and returns the task as it now reads.
+#[tauri::command]
+pub fn toggle_task(
+    path: String,
+    line: usize,
+    state: State<'_, VaultHandles>,
+) -> Result<Task, String> {
+    let note = PathBuf::from(&path);
+    let mut handles = state.lock().map_err(|e| e.to_string())?;
+    let toggled = match handle_containing(&mut handles, &note) {
+        Some(handle) => handle.toggle_task(&note, line),
+        None => task_index::toggle_task(&note, line).map(|(_, task)| task),
+    };
+    toggled.map_err(|e| {
+        error!("Failed to toggle task on line {line} of {}: {e}", path);
+        e.to_string()
+    })
+}
+
 /// Searches the vault for a `.md` file whose stem matches `link_target` (case-insensitive).
 /// Returns the absolute path of the first match, or `None` if not found.
 #[tauri::command]
 pub fn resolve_wikilink(vault_path: String, link_target: String) -> Result<Option<String>, String> {
-    let root = Path::new(&vault_path);
-    let target_lower = link_target.to_lowercase();
-    for entry in walkdir::WalkDir::new(root)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        let path = entry.path();
-        if path.extension().and_then(|ext| ext.to_str()) != Some("md") {
-            continue;
-        }
-        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
-        if stem.to_lowercase() == target_lower {
-            return Ok(Some(path.to_string_lossy().to_string()));
-        }
-    }
-    Ok(None)
+    Ok(
+        files::find_note_by_stem(Path::new(&vault_path), &link_target)
+            .map(|path| path.to_string_lossy().to_string()),
+    )
 }
 
 /// Resolves a relative asset path to an absolute path for display in the editor.
@@ -431,25 +1036,59 @@ pub fn resolve_asset_path(
 }
 
 fn vault_window_label(path: &str) -> String {
+    hashed_window_label("vault", path)
+}
+
+fn note_window_label(path: &str) -> String {
+    hashed_window_label("note", path)
+}
+
+fn hashed_window_label(prefix: &str, path: &str) -> String {
     let mut hasher = DefaultHasher::new();
     path.hash(&mut hasher);
-    format!("vault-{:x}", hasher.finish())
+    format!("{prefix}-{:x}", hasher.finish())
 }
 
 /// Opens (or focuses) a native window for the given vault path.
 #[tauri::command]
 pub fn open_vault_window(app: AppHandle, path: String) -> Result<(), String> {
-    let label = vault_window_label(&path);
+    show_vault_window(&app, &path, None)
+}
+
+/// Opens (or focuses) the window for a vault and has it show `request`: a running window gets
+/// it as an [`OPEN_REQUEST_EVENT`], a new one through its URL.
+pub fn show_vault_window(
+    app: &AppHandle,
+    path: &str,
+    request: Option<&OpenRequest>,
+) -> Result<(), String> {
+    let label = vault_window_label(path);
 
     if let Some(existing) = app.get_webview_window(&label) {
         existing.set_focus().map_err(|e| e.to_string())?;
+        if let Some(request) = request {
+            app.emit_to(label.as_str(), OPEN_REQUEST_EVENT, request)
+                .map_err(|e| e.to_string())?;
+        }
         return Ok(());
     }
 
-    let encoded = urlencoding::encode(&path);
-    let url = WebviewUrl::App(format!("index.html?vault={encoded}").into());
+    let mut query = format!("vault={}", urlencoding::encode(path));
+    if let Some(request) = request {
+        let params = [
+            ("file", &request.file),
+            ("heading", &request.heading),
+            ("search", &request.search),
+        ];
+        for (key, value) in params {
+            if let Some(value) = value {
+                query.push_str(&format!("&{key}={}", urlencoding::encode(value)));
+            }
+        }
+    }
+    let url = WebviewUrl::App(format!("index.html?{query}").into());
 
-    let window = WebviewWindowBuilder::new(&app, &label, url)
+    let window = WebviewWindowBuilder::new(app, &label, url)
         .title("Onyx")
         .inner_size(1200.0, 800.0)
         .min_inner_size(800.0, 600.0)
@@ -461,6 +1100,28 @@ pub fn open_vault_window(app: AppHandle, path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Opens (or focuses) a bare editor window for a markdown file that belongs to no vault.
+pub fn open_note_window(app: &AppHandle, path: &str) -> Result<(), String> {
+    let label = note_window_label(path);
+
+    if let Some(existing) = app.get_webview_window(&label) {
+        existing.set_focus().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let encoded = urlencoding::encode(path);
+    let url = WebviewUrl::App(format!("index.html?note={encoded}").into());
+
+    WebviewWindowBuilder::new(app, &label, url)
+        .title("Onyx")
+        .inner_size(900.0, 700.0)
+        .min_inner_size(600.0, 400.0)
+        .title_bar_style(TitleBarStyle::Overlay)
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
 /// Opens a welcome window for vault selection.
 #[tauri::command]
 pub fn open_welcome_window(app: AppHandle) -> Result<(), String> {
@@ -0,0 +1,125 @@
+use std::path::{Path, PathBuf};
+
+use log::{error, info};
+use tauri::{AppHandle, Manager};
+
+use onyx_core::deep_link::{is_deep_link, OpenRequest};
+use onyx_core::link_resolver::is_markdown;
+use onyx_core::vault::find_vault_root;
+
+use crate::commands::{open_note_window, show_vault_window};
+
+/// What a path given on the command line opens.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchTarget {
+    /// A vault's root folder, or a folder inside it.
+    Vault(PathBuf),
+    /// A file inside a vault, shown in that vault's window.
+    VaultFile { vault: PathBuf, file: PathBuf },
+    /// A markdown file outside every vault, shown in a window of its own.
+    LooseNote(PathBuf),
+}
+
+/// What the first argument after the program name opens, with relative paths taken from
+/// `cwd`. Flags, `onyx://` URLs (routed by the deep-link plugin) and paths that don't exist
+/// or belong to no vault give `None`.
+pub fn launch_target(args: &[String], cwd: &Path) -> Option<LaunchTarget> {
+    let arg = args.get(1)?;
+    if arg.starts_with('-') || is_deep_link(arg) {
+        return None;
+    }
+    let path = std::fs::canonicalize(cwd.join(arg)).ok()?;
+    let vault = find_vault_root(&path);
+    if path.is_dir() {
+        return vault.map(LaunchTarget::Vault);
+    }
+    match vault {
+        Some(vault) => Some(LaunchTarget::VaultFile { vault, file: path }),
+        None if is_markdown(&path) => Some(LaunchTarget::LooseNote(path)),
+        None => None,
+    }
+}
+
+/// Opens what a launch argument points at, focusing any window that already shows it.
+pub fn open_launch_target(app: &AppHandle, target: &LaunchTarget) -> Result<(), String> {
+    info!("Opening launch target {target:?}");
+    match target {
+        LaunchTarget::Vault(vault) => show_vault_window(app, &vault.to_string_lossy(), None),
+        LaunchTarget::VaultFile { vault, file } => {
+            let request = OpenRequest {
+                file: Some(file.to_string_lossy().to_string()),
+                ..OpenRequest::default()
+            };
+            show_vault_window(app, &vault.to_string_lossy(), Some(&request))
+        }
+        LaunchTarget::LooseNote(file) => open_note_window(app, &file.to_string_lossy()),
+    }
+}
+
+/// Handles the arguments of a second `Onyx` process, which exits after handing them over:
+/// opens its path, or just brings the running app forward.
+pub fn forward_launch(app: &AppHandle, args: Vec<String>, cwd: String) {
+    let Some(target) = launch_target(&args, Path::new(&cwd)) else {
+        if let Some(window) = app.webview_windows().into_values().next() {
+            let _ = window.unminimize();
+            let _ = window.set_focus();
+        }
+        return;
+    };
+    if let Err(e) = open_launch_target(app, &target) {
+        error!("Failed to open forwarded launch {target:?}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn args(arg: &str) -> Vec<String> {
+        vec!["Onyx".to_string(), arg.to_string()]
+    }
+
+    #[test]
+    fn paths_route_to_vaults_files_or_loose_notes() {
+        let dir = TempDir::new().unwrap();
+        let root = std::fs::canonicalize(dir.path()).unwrap();
+        let vault = root.join("Vault");
+        std::fs::create_dir_all(vault.join(".onyx")).unwrap();
+        std::fs::write(vault.join(".onyx/config.toml"), "name = \"Vault\"\n").unwrap();
+        std::fs::create_dir(vault.join("Projects")).unwrap();
+        std::fs::write(vault.join("Projects/Plan.md"), "").unwrap();
+        std::fs::write(root.join("loose.md"), "").unwrap();
+        std::fs::write(root.join("loose.txt"), "").unwrap();
+
+        assert_eq!(
+            launch_target(&args("Vault"), &root),
+            Some(LaunchTarget::Vault(vault.clone()))
+        );
+        assert_eq!(
+            launch_target(&args(vault.join("Projects").to_str().unwrap()), &root),
+            Some(LaunchTarget::Vault(vault.clone()))
+        );
+        assert_eq!(
+            launch_target(&args("Plan.md"), &vault.join("Projects")),
+            Some(LaunchTarget::VaultFile {
+                vault: vault.clone(),
+                file: vault.join("Projects/Plan.md"),
+            })
+        );
+        assert_eq!(
+            launch_target(&args("loose.md"), &root),
+            Some(LaunchTarget::LooseNote(root.join("loose.md")))
+        );
+        for ignored in [
+            "loose.txt",
+            "missing.md",
+            ".",
+            "-psn_0_12345",
+            "onyx://daily",
+        ] {
+            assert_eq!(launch_target(&args(ignored), &root), None, "{ignored}");
+        }
+        assert_eq!(launch_target(&["Onyx".to_string()], &root), None);
+    }
+}
@@ -1,30 +1,46 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod asset_protocol;
+mod cli;
 mod commands;
-mod error;
-mod file_tree;
-mod global_config;
-mod tag_index;
-mod vault;
-mod vault_config;
-
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Mutex;
+mod launch;
+mod url_router;
 
+use asset_protocol::{handle_asset_request, page_origin, AssetRoots, ASSET_SCHEME};
 use commands::{
-    build_tag_index, create_file, create_folder, create_vault, delete_file, get_default_vault_dir,
-    get_file_tree, get_known_vaults, get_last_active_vault, get_settings, get_tags, load_theme,
-    load_vault_session_cmd, maximize_window, move_file, open_vault, open_vault_window,
-    open_welcome_window, read_binary_as_data_url, read_file, rename_file, resolve_asset_path,
-    resolve_wikilink, save_settings, save_vault_session_cmd, update_file_tags, write_file,
+    add_pdf_annotation, apply_edits, build_tag_index, check_vault_health, close_buffer,
+    create_canvas, create_file, create_folder, create_literature_note, create_vault, delete_file,
+    delete_pdf_annotation, discard_buffer, export_docx, export_epub, export_graph, export_html,
+    export_pdf, export_site, get_backlinks, get_default_vault_dir, get_file_info, get_file_tree,
+    get_graph, get_known_vaults, get_last_active_vault, get_pdf_outline, get_settings, get_tags,
+    get_thumbnail, import_notes, import_obsidian_vault, journal_buffer, list_pdf_annotations,
+    list_recoverable_buffers, load_theme, load_vault_session_cmd, maximize_window, move_file,
+    open_buffer, open_vault, open_vault_window, open_welcome_window, query_tasks,
+    read_binary_as_data_url, read_canvas, read_file, redo_buffer, rename_file, resolve_asset_path,
+    resolve_wikilink, restore_buffer, run_note_queries, run_query, save_api_settings,
+    save_attachment, save_buffer, save_settings, save_vault_session_cmd, search_vault, start_api,
+    toggle_task, undo_buffer, update_file_index, write_canvas, write_file, ApiState, VaultHandles,
 };
-use tag_index::TagIndex;
+use onyx_core::global_config::{load_global_config, save_global_config};
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_log::{Target, TargetKind};
 use tauri_plugin_prevent_default::Flags;
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if cli::is_cli_invocation(&args) {
+        std::process::exit(cli::run(&args));
+    }
+    // `Onyx <path>` (as spawned by `onyx open`) opens a vault, or a note in or outside one.
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let launch = launch::launch_target(&args, &cwd);
+
     tauri::Builder::default()
+        // First, so a second launch hands its arguments over and exits before anything starts.
+        .plugin(tauri_plugin_single_instance::init(|app, args, cwd| {
+            launch::forward_launch(app, args, cwd)
+        }))
         .plugin(
             tauri_plugin_prevent_default::Builder::new()
                 .with_flags(Flags::RELOAD | Flags::CONTEXT_MENU)
@@ -41,13 +57,46 @@ fn main() {
         )
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .manage(Mutex::new(HashMap::<PathBuf, TagIndex>::new()))
+        .plugin(tauri_plugin_deep_link::init())
+        .register_asynchronous_uri_scheme_protocol(ASSET_SCHEME, |ctx, request, responder| {
+            // File reads happen off the main thread so large media doesn't stall the UI.
+            let app = ctx.app_handle().clone();
+            let origin = app
+                .get_webview_window(ctx.webview_label())
+                .and_then(|webview| webview.url().ok())
+                .and_then(|url| page_origin(&url));
+            std::thread::spawn(move || {
+                let roots = app.state::<AssetRoots>();
+                responder.respond(handle_asset_request(&request, &roots, origin.as_deref()));
+            });
+        })
+        .setup(move |app| {
+            start_configured_api(app.handle());
+            listen_for_deep_links(app)?;
+            if let Some(target) = &launch {
+                launch::open_launch_target(app.handle(), target)?;
+            }
+            Ok(())
+        })
+        .manage(AssetRoots::default())
+        .manage(VaultHandles::default())
+        .manage(ApiState::default())
         .invoke_handler(tauri::generate_handler![
             create_vault,
             open_vault,
+            import_obsidian_vault,
+            import_notes,
             get_file_tree,
             read_file,
             write_file,
+            journal_buffer,
+            list_recoverable_buffers,
+            restore_buffer,
+            discard_buffer,
+            read_canvas,
+            write_canvas,
+            create_canvas,
+            get_backlinks,
             get_known_vaults,
             maximize_window,
             create_file,
@@ -59,18 +108,93 @@ fn main() {
             rename_file,
             get_settings,
             save_settings,
+            save_api_settings,
             get_last_active_vault,
             build_tag_index,
             get_tags,
-            update_file_tags,
+            update_file_index,
+            open_buffer,
+            apply_edits,
+            undo_buffer,
+            redo_buffer,
+            save_buffer,
+            close_buffer,
+            query_tasks,
+            run_query,
+            get_graph,
+            export_graph,
+            run_note_queries,
+            toggle_task,
             resolve_wikilink,
             resolve_asset_path,
             read_binary_as_data_url,
+            save_attachment,
+            check_vault_health,
+            get_thumbnail,
+            get_file_info,
+            search_vault,
+            get_pdf_outline,
+            list_pdf_annotations,
+            add_pdf_annotation,
+            delete_pdf_annotation,
+            create_literature_note,
             open_vault_window,
             open_welcome_window,
             delete_file,
             load_theme,
+            export_docx,
+            export_epub,
+            export_html,
+            export_pdf,
+            export_site,
         ])
         .run(tauri::generate_context!())
         .expect("error running tauri app");
 }
+
+/// Routes `onyx://` URLs: the one the app was launched with and any opened while it runs.
+fn listen_for_deep_links(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    // Installers register the scheme on macOS and Windows; Linux and dev builds do it here.
+    #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+    app.deep_link().register_all()?;
+    let handle = app.handle().clone();
+    app.deep_link().on_open_url(move |event| {
+        for url in event.urls() {
+            url_router::open_url(&handle, url.as_str());
+        }
+    });
+    for url in app.deep_link().get_current()?.unwrap_or_default() {
+        url_router::open_url(app.handle(), url.as_str());
+    }
+    Ok(())
+}
+
+/// Starts the local API at launch when the user has enabled it.
+fn start_configured_api(app: &tauri::AppHandle) {
+    let mut config = match load_global_config() {
+        Ok(config) if config.api.enabled => config,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("Not starting local API: {e}");
+            return;
+        }
+    };
+    if config.api.token.is_none() {
+        let saved = config
+            .api
+            .ensure_token()
+            .and_then(|_| save_global_config(&config));
+        if let Err(e) = saved {
+            log::error!("Not starting local API without a token: {e}");
+            return;
+        }
+    }
+    match start_api(app, &config.api, &app.state::<VaultHandles>()) {
+        Ok(server) => {
+            if let Ok(mut running) = app.state::<ApiState>().lock() {
+                *running = Some(server);
+            }
+        }
+        Err(e) => log::error!("Failed to start local API on port {}: {e}", config.api.port),
+    }
+}
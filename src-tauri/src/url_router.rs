@@ -0,0 +1,28 @@
+use log::{error, info};
+use tauri::{AppHandle, Manager};
+
+use onyx_core::deep_link::{self, DeepLink};
+use onyx_core::global_config::load_global_config;
+
+use crate::commands::{show_vault_window, with_vault, VaultHandles};
+
+/// Follows an `onyx://` URL handed to the app by the OS, logging anything that goes wrong.
+pub fn open_url(app: &AppHandle, url: &str) {
+    if let Err(e) = route(app, url) {
+        error!("Failed to open {url}: {e}");
+    }
+}
+
+/// Parses `url`, resolves its vault, carries it out there and shows the result in the vault's
+/// window.
+fn route(app: &AppHandle, url: &str) -> Result<(), String> {
+    let link = DeepLink::parse(url).map_err(|e| e.to_string())?;
+    let config = load_global_config().map_err(|e| e.to_string())?;
+    let vault = deep_link::resolve_vault(link.vault(), &config).map_err(|e| e.to_string())?;
+    let vault = vault.to_string_lossy().to_string();
+    let request = with_vault(&app.state::<VaultHandles>(), &vault, |handle| {
+        deep_link::follow(&link, handle).map_err(|e| e.to_string())
+    })?;
+    info!("Following {url} in {vault}");
+    show_vault_window(app, &vault, Some(&request))
+}
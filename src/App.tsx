@@ -2,7 +2,8 @@ import { useCallback, useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import WelcomePage from "./pages/WelcomePage";
-import EditorPage from "./pages/EditorPage";
+import EditorPage, { type OpenRequest } from "./pages/EditorPage";
+import SingleFilePage from "./pages/SingleFilePage";
 
 interface VaultEntry {
   name: string;
@@ -19,6 +20,23 @@ function getVaultFromUrl(): string | null {
   return vault ?? null;
 }
 
+/// A note outside every vault that this window edits on its own.
+function getNoteFromUrl(): string | null {
+  return new URLSearchParams(window.location.search).get("note");
+}
+
+/// What a window opened for an `onyx://` link or a file argument should show, passed along
+/// in its URL.
+function getOpenRequestFromUrl(): OpenRequest | null {
+  const params = new URLSearchParams(window.location.search);
+  const request = {
+    file: params.get("file"),
+    heading: params.get("heading"),
+    search: params.get("search"),
+  };
+  return request.file || request.search ? request : null;
+}
+
 export default function App() {
   const [view, setView] = useState<AppView>({ kind: "welcome" });
   const [knownVaults, setKnownVaults] = useState<VaultEntry[]>([]);
@@ -34,6 +52,7 @@ export default function App() {
   }, [refreshKnownVaults]);
 
   useEffect(() => {
+    if (getNoteFromUrl()) return;
     const vaultFromUrl = getVaultFromUrl();
 
     if (vaultFromUrl) {
@@ -99,6 +118,11 @@ export default function App() {
     }
   }, []);
 
+  const noteFromUrl = getNoteFromUrl();
+  if (noteFromUrl) {
+    return <SingleFilePage filePath={noteFromUrl} />;
+  }
+
   if (view.kind === "editor") {
     return (
       <EditorPage
@@ -106,6 +130,7 @@ export default function App() {
         vaultPath={view.vaultPath}
         vaultName={view.vaultName}
         knownVaults={knownVaults}
+        openRequest={getOpenRequestFromUrl()}
         onClose={handleCloseVault}
         onSwitchVault={handleSwitchVault}
         onOpenWelcome={handleOpenWelcome}
@@ -23,6 +23,7 @@ function flattenTree(entries: FileTreeEntry[]): FlatFile[] {
 
 interface Props {
   files: FileTreeEntry[];
+  initialQuery?: string;
   onOpen: (path: string) => void;
   onCreate: (name: string) => void;
   onClose: () => void;
@@ -30,11 +31,12 @@ interface Props {
 
 export default function FilePicker({
   files,
+  initialQuery = "",
   onOpen,
   onCreate,
   onClose,
 }: Props) {
-  const [query, setQuery] = useState("");
+  const [query, setQuery] = useState(initialQuery);
   const [selectedIndex, setSelectedIndex] = useState(0);
   const listRef = useRef<HTMLUListElement>(null);
 
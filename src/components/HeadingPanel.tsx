@@ -14,7 +14,7 @@ interface Heading {
   position: number;
 }
 
-function parseHeadings(content: string): Heading[] {
+export function parseHeadings(content: string): Heading[] {
   const headings: Heading[] = [];
   const lines = content.split("\n");
   let pos = 0;
@@ -11,6 +11,7 @@ import CodeMirror, {
   EditorView,
   EditorSelection,
   type ReactCodeMirrorRef,
+  type ViewUpdate,
 } from "@uiw/react-codemirror";
 import { markdown } from "@codemirror/lang-markdown";
 import { GFM, Strikethrough, TaskList } from "@lezer/markdown";
@@ -20,6 +21,7 @@ import { marked } from "marked";
 import DOMPurify from "dompurify";
 import { markdownDecorations } from "../extensions/markdownDecorations";
 import { tagAutocomplete } from "../extensions/tagAutocomplete";
+import { afterBufferSync, sendEdits } from "../utils/bufferSync";
 import {
   setWikilinkConfig,
   wikilinkConfigField,
@@ -234,17 +236,16 @@ const MarkdownEditor = forwardRef<MarkdownEditorHandle, Props>(
     );
 
     const handleChange = useCallback(
-      (value: string) => {
+      (value: string, update: ViewUpdate) => {
         onChange(value);
-        if (!filePath) return;
+        if (!filePath || !vaultPath) return;
+        // The backend buffer updates its indexes from each change as it arrives.
+        sendEdits(vaultPath, filePath, update.changes, value).catch((err) =>
+          console.error("Failed to sync edits:", err),
+        );
         if (debounceRef.current) clearTimeout(debounceRef.current);
         debounceRef.current = setTimeout(() => {
-          invoke("update_file_tags", {
-            vaultPath,
-            filePath,
-            content: value,
-          }).catch(() => {});
-          invoke<string[]>("get_tags", { vaultPath })
+          afterBufferSync(() => invoke<string[]>("get_tags", { vaultPath }))
             .then(setTags)
             .catch(() => {});
         }, 800);
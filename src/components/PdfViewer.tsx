@@ -1,5 +1,5 @@
 import { useEffect, useRef, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { convertFileSrc } from "@tauri-apps/api/core";
 import * as pdfjsLib from "pdfjs-dist";
 import type { PDFDocumentProxy } from "pdfjs-dist";
 
@@ -12,7 +12,7 @@ interface Props {
   filePath: string;
 }
 
-/// Renders a local PDF read-only via PDF.js, loaded as a base64 data URL over Tauri IPC.
+/// Renders a local PDF read-only via PDF.js, streamed over the `onyx-asset` protocol.
 export default function PdfViewer({ filePath }: Props) {
   const containerRef = useRef<HTMLDivElement>(null);
   const [error, setError] = useState<string | null>(null);
@@ -24,18 +24,9 @@ export default function PdfViewer({ filePath }: Props) {
     async function render() {
       setError(null);
 
-      let dataUrl: string;
       try {
-        dataUrl = await invoke<string>("read_binary_as_data_url", {
-          path: filePath,
-        });
-      } catch (err) {
-        if (!cancelled) setError(`Failed to load PDF: ${err}`);
-        return;
-      }
-
-      try {
-        pdf = await pdfjsLib.getDocument(dataUrl).promise;
+        pdf = await pdfjsLib.getDocument(convertFileSrc(filePath, "onyx-asset"))
+          .promise;
       } catch (err) {
         if (!cancelled) setError(`Failed to parse PDF: ${err}`);
         return;
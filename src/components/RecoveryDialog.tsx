@@ -0,0 +1,130 @@
+import { useEffect, useState } from "react";
+
+export interface DiffLine {
+  kind: "same" | "added" | "removed";
+  text: string;
+}
+
+/// An unsaved buffer from an earlier session, as returned by `list_recoverable_buffers`.
+export interface RecoverableBuffer {
+  path: string;
+  content: string;
+  saved_secs: number;
+  file_exists: boolean;
+  diff: DiffLine[];
+}
+
+interface Props {
+  buffers: RecoverableBuffer[];
+  vaultPath: string;
+  onRestore: (path: string) => void;
+  onDiscard: (path: string) => void;
+  onClose: () => void;
+}
+
+const LINE_STYLES: Record<DiffLine["kind"], string> = {
+  same: "text-text-secondary",
+  added: "bg-green-500/10 text-green-400",
+  removed: "bg-red-500/10 text-red-400",
+};
+
+const LINE_MARKERS: Record<DiffLine["kind"], string> = {
+  same: " ",
+  added: "+",
+  removed: "-",
+};
+
+export default function RecoveryDialog({
+  buffers,
+  vaultPath,
+  onRestore,
+  onDiscard,
+  onClose,
+}: Props) {
+  const [selectedPath, setSelectedPath] = useState(buffers[0]?.path ?? null);
+  const selected =
+    buffers.find((buffer) => buffer.path === selectedPath) ?? buffers[0];
+
+  useEffect(() => {
+    function onKeyDown(e: KeyboardEvent) {
+      if (e.key === "Escape") onClose();
+    }
+    document.addEventListener("keydown", onKeyDown);
+    return () => document.removeEventListener("keydown", onKeyDown);
+  }, [onClose]);
+
+  if (!selected) return null;
+
+  const relativePath = (path: string) =>
+    path.startsWith(`${vaultPath}/`) ? path.slice(vaultPath.length + 1) : path;
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-start justify-center bg-black/60 pt-24"
+      onMouseDown={onClose}
+    >
+      <div
+        className="flex max-h-[70vh] w-full max-w-2xl flex-col rounded-lg bg-surface shadow-xl ring-1 ring-surface-hover"
+        onMouseDown={(e) => e.stopPropagation()}
+      >
+        <div className="border-b border-surface-hover px-4 py-3">
+          <p className="text-sm text-text-primary">Unsaved changes found</p>
+          <p className="text-xs text-text-secondary">
+            These edits were not saved before Onyx last closed.
+          </p>
+        </div>
+        {buffers.length > 1 && (
+          <ul className="max-h-32 shrink-0 overflow-y-auto border-b border-surface-hover py-1">
+            {buffers.map((buffer) => (
+              <li
+                key={buffer.path}
+                onMouseDown={() => setSelectedPath(buffer.path)}
+                className={`cursor-pointer px-4 py-1.5 text-sm ${
+                  buffer.path === selected.path
+                    ? "bg-surface-hover text-text-primary"
+                    : "text-text-secondary"
+                }`}
+              >
+                {relativePath(buffer.path)}
+              </li>
+            ))}
+          </ul>
+        )}
+        <div className="flex items-center justify-between px-4 py-2 text-xs text-text-secondary">
+          <span className="truncate">{relativePath(selected.path)}</span>
+          <span className="ml-4 shrink-0">
+            {selected.file_exists ? "" : "File no longer exists · "}
+            {new Date(selected.saved_secs * 1000).toLocaleString()}
+          </span>
+        </div>
+        <pre className="min-h-0 flex-1 overflow-auto px-4 pb-2 font-mono text-xs">
+          {selected.diff.map((line, index) => (
+            <div key={index} className={LINE_STYLES[line.kind]}>
+              {LINE_MARKERS[line.kind]} {line.text}
+            </div>
+          ))}
+        </pre>
+        <div className="flex justify-end gap-2 border-t border-surface-hover px-4 py-3">
+          <button
+            onClick={onClose}
+            className="rounded px-3 py-1 text-sm text-text-secondary hover:text-text-primary"
+          >
+            Decide later
+          </button>
+          <button
+            onClick={() => onDiscard(selected.path)}
+            className="rounded px-3 py-1 text-sm text-text-secondary ring-1 ring-surface-hover hover:text-text-primary"
+          >
+            Discard
+          </button>
+          <button
+            onClick={() => onRestore(selected.path)}
+            className="rounded bg-accent px-3 py-1 text-sm font-medium text-background transition-opacity hover:opacity-80"
+          >
+            Restore
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -1,5 +1,6 @@
 import { useCallback, useEffect, useReducer, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import FileTree, {
   type FileTreeEntry,
   type FileTreeHandle,
@@ -9,6 +10,7 @@ import MarkdownEditor, {
   type MarkdownEditorHandle,
 } from "../components/MarkdownEditor";
 import HeadingPanel, {
+  parseHeadings,
   type HeadingPanelHandle,
 } from "../components/HeadingPanel";
 import ImageViewer from "../components/ImageViewer";
@@ -17,12 +19,16 @@ import VaultSwitcher from "../components/VaultSwitcher";
 import AppLayout from "../components/AppLayout";
 import CommandPalette from "../components/CommandPalette";
 import FilePicker from "../components/FilePicker";
+import RecoveryDialog, {
+  type RecoverableBuffer,
+} from "../components/RecoveryDialog";
 import { useKeybindings } from "../hooks/useKeybindings";
 import { useCommandStore } from "../stores/commandStore";
 import { usePanelStore } from "../stores/panelStore";
 import { useCommandPaletteStore } from "../stores/commandPaletteStore";
 import { useFilePickerStore } from "../stores/filePickerStore";
 import { sortFileTree, type FileSortOrder } from "../utils/fileSort";
+import { closeBuffer, openBuffer, saveBuffer } from "../utils/bufferSync";
 
 const IMAGE_EXTENSIONS = new Set([
   "avif",
@@ -44,6 +50,11 @@ function isPdf(path: string): boolean {
   return path.toLowerCase().endsWith(".pdf");
 }
 
+/// Unsaved buffers are copied to the recovery journal at most this often.
+const JOURNAL_INTERVAL_MS = 1000;
+/// With autosave on, dirty buffers are written once typing pauses for this long.
+const AUTOSAVE_IDLE_MS = 2000;
+
 
 interface VaultEntry {
   name: string;
@@ -54,6 +65,7 @@ interface VaultSession {
   open_tabs: string[];
   active_tab: string | null;
   sort_order: string | null;
+  bookmarks: string[];
 }
 
 interface Props {
@@ -63,6 +75,21 @@ interface Props {
   onClose: () => void;
   onSwitchVault: (path: string, name: string) => void;
   onOpenWelcome: () => void;
+  /// A file or search to show once the vault's session is restored.
+  openRequest?: OpenRequest | null;
+}
+
+/// Something the window was asked to show, e.g. by an `onyx://` link. `file` is absolute.
+export interface OpenRequest {
+  file: string | null;
+  heading: string | null;
+  search: string | null;
+}
+
+interface FileChange {
+  vault: string;
+  path: string;
+  kind: "created" | "modified";
 }
 
 interface EditorState {
@@ -78,6 +105,7 @@ type EditorAction =
   | { type: "close_all_tabs" }
   | { type: "activate_tab"; path: string }
   | { type: "update_content"; path: string; content: string }
+  | { type: "reload_file"; path: string; content: string }
   | { type: "mark_saved"; path: string }
   | { type: "rename_file"; oldPath: string; newPath: string; newName: string };
 
@@ -121,6 +149,19 @@ function editorReducer(state: EditorState, action: EditorAction): EditorState {
         dirtyPaths: dirty,
       };
     }
+    case "reload_file": {
+      // Unsaved edits win over a change made outside the editor.
+      if (
+        !(action.path in state.fileContents) ||
+        state.dirtyPaths.has(action.path)
+      ) {
+        return state;
+      }
+      return {
+        ...state,
+        fileContents: { ...state.fileContents, [action.path]: action.content },
+      };
+    }
     case "mark_saved": {
       const dirty = new Set(state.dirtyPaths);
       dirty.delete(action.path);
@@ -166,13 +207,23 @@ export default function EditorPage({
   onClose,
   onSwitchVault,
   onOpenWelcome,
+  openRequest = null,
 }: Props) {
   const [fileTree, setFileTree] = useState<FileTreeEntry[]>([]);
   const [treeError, setTreeError] = useState<string | null>(null);
   const [newNoteName, setNewNoteName] = useState<string | null>(null);
   const [newFolderName, setNewFolderName] = useState<string | null>(null);
   const [sessionLoaded, setSessionLoaded] = useState(false);
+  const [pendingRequest, setPendingRequest] = useState<OpenRequest | null>(
+    openRequest,
+  );
+  const [pendingHeading, setPendingHeading] = useState<{
+    path: string;
+    heading: string;
+  } | null>(null);
   const [vimMode, setVimMode] = useState(false);
+  const [autosaveOnIdle, setAutosaveOnIdle] = useState(false);
+  const [recoverable, setRecoverable] = useState<RecoverableBuffer[]>([]);
   const [selectedFolderPath, setSelectedFolderPath] = useState<string | null>(
     null,
   );
@@ -188,6 +239,10 @@ export default function EditorPage({
     fileContents: {},
     dirtyPaths: new Set<string>(),
   });
+  // Timers read the latest buffers from here rather than the ones they were scheduled with.
+  const stateRef = useRef(state);
+  stateRef.current = state;
+  const journalTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
 
   const { register, unregister } = useCommandStore();
 
@@ -201,6 +256,32 @@ export default function EditorPage({
     fetchFileTree();
   }, [fetchFileTree]);
 
+  // Files written through the local API show up without a manual refresh.
+  useEffect(() => {
+    const unlisten = listen<FileChange>("vault-file-changed", ({ payload }) => {
+      if (payload.vault !== vaultPath) return;
+      fetchFileTree();
+      invoke<string>("read_file", { path: payload.path })
+        .then((content) =>
+          dispatch({ type: "reload_file", path: payload.path, content }),
+        )
+        .catch(() => {});
+    });
+    return () => {
+      unlisten.then((stop) => stop()).catch(() => {});
+    };
+  }, [vaultPath, fetchFileTree]);
+
+  // Links followed while the window is open arrive as events.
+  useEffect(() => {
+    const unlisten = listen<OpenRequest>("open-request", ({ payload }) =>
+      setPendingRequest(payload),
+    );
+    return () => {
+      unlisten.then((stop) => stop()).catch(() => {});
+    };
+  }, []);
+
   // Build the tag index whenever the vault changes so autocomplete is ready immediately.
   useEffect(() => {
     invoke("build_tag_index", { vaultPath }).catch((err) =>
@@ -209,8 +290,11 @@ export default function EditorPage({
   }, [vaultPath]);
 
   useEffect(() => {
-    invoke<{ vim_mode: boolean }>("get_settings")
-      .then((settings) => setVimMode(settings.vim_mode))
+    invoke<{ vim_mode: boolean; autosave_on_idle: boolean }>("get_settings")
+      .then((settings) => {
+        setVimMode(settings.vim_mode);
+        setAutosaveOnIdle(settings.autosave_on_idle);
+      })
       .catch((err) => console.error("Failed to load settings:", err));
   }, []);
 
@@ -268,6 +352,82 @@ export default function EditorPage({
     fileSortOrder,
   ]);
 
+  // Offer to recover edits a crash or closed window left behind, once restored tabs are open.
+  useEffect(() => {
+    if (!sessionLoaded) return;
+    invoke<RecoverableBuffer[]>("list_recoverable_buffers", { vaultPath })
+      .then(setRecoverable)
+      .catch((err) => console.error("Failed to list unsaved changes:", err));
+  }, [vaultPath, sessionLoaded]);
+
+  // Stream dirty buffers to the recovery journal, throttled; saving clears them again.
+  useEffect(() => {
+    if (state.dirtyPaths.size === 0 || journalTimerRef.current) return;
+    journalTimerRef.current = setTimeout(() => {
+      journalTimerRef.current = null;
+      const { dirtyPaths, fileContents } = stateRef.current;
+      for (const path of dirtyPaths) {
+        const content = fileContents[path];
+        if (content === undefined) continue;
+        invoke("journal_buffer", { vaultPath, path, content }).catch((err) =>
+          console.error("Failed to journal unsaved changes:", err),
+        );
+      }
+    }, JOURNAL_INTERVAL_MS);
+  }, [vaultPath, state.dirtyPaths, state.fileContents]);
+
+  useEffect(
+    () => () => {
+      if (journalTimerRef.current) clearTimeout(journalTimerRef.current);
+      journalTimerRef.current = null;
+    },
+    [],
+  );
+
+  useEffect(() => {
+    if (!autosaveOnIdle || state.dirtyPaths.size === 0) return;
+    const timer = setTimeout(() => {
+      const { dirtyPaths, fileContents } = stateRef.current;
+      for (const path of dirtyPaths) {
+        const content = fileContents[path];
+        if (content === undefined || isPdf(path)) continue;
+        saveBuffer(vaultPath, path, content)
+          .then(() => {
+            // Edits made while the write was in flight keep the tab dirty.
+            if (stateRef.current.fileContents[path] === content) {
+              dispatch({ type: "mark_saved", path });
+            }
+          })
+          .catch((err) => console.error("Failed to autosave file:", err));
+      }
+    }, AUTOSAVE_IDLE_MS);
+    return () => clearTimeout(timer);
+  }, [vaultPath, autosaveOnIdle, state.dirtyPaths, state.fileContents]);
+
+  // Opening a tab starts its backend buffer from the text it shows; closing it (or moving or
+  // deleting its file) drops the buffer too.
+  const openPathsRef = useRef<string[]>([]);
+  useEffect(() => {
+    const openPaths = state.tabs.map((tab) => tab.path);
+    for (const path of openPathsRef.current) {
+      if (openPaths.includes(path)) continue;
+      closeBuffer(vaultPath, path).catch((err) =>
+        console.error("Failed to close buffer:", err),
+      );
+    }
+    const { fileContents } = stateRef.current;
+    for (const path of openPaths) {
+      const content = fileContents[path];
+      if (openPathsRef.current.includes(path) || content === undefined) {
+        continue;
+      }
+      openBuffer(vaultPath, path, content).catch((err) =>
+        console.error("Failed to open buffer:", err),
+      );
+    }
+    openPathsRef.current = openPaths;
+  }, [vaultPath, state.tabs]);
+
   const handleNewNoteOpen = useCallback(() => {
     setNewNoteName("Untitled");
   }, []);
@@ -354,6 +514,74 @@ export default function EditorPage({
     [state.tabs],
   );
 
+  const handleRecoveryRestore = useCallback(
+    async (path: string) => {
+      try {
+        const content = await invoke<string>("restore_buffer", {
+          vaultPath,
+          path,
+        });
+        setRecoverable((buffers) => buffers.filter((b) => b.path !== path));
+        dispatch({ type: "reload_file", path, content });
+        fetchFileTree();
+        await handleFileClick(path);
+      } catch (err) {
+        console.error("Failed to restore unsaved changes:", err);
+      }
+    },
+    [vaultPath, fetchFileTree, handleFileClick],
+  );
+
+  const handleRecoveryDiscard = useCallback(
+    async (path: string) => {
+      try {
+        await invoke("discard_buffer", { vaultPath, path });
+        setRecoverable((buffers) => buffers.filter((b) => b.path !== path));
+      } catch (err) {
+        console.error("Failed to discard unsaved changes:", err);
+      }
+    },
+    [vaultPath],
+  );
+
+  const closeRecovery = useCallback(() => setRecoverable([]), []);
+
+  // Requests wait for the session so restored tabs don't take focus from the requested file.
+  useEffect(() => {
+    if (!sessionLoaded || !pendingRequest) return;
+    setPendingRequest(null);
+    if (pendingRequest.file) {
+      fetchFileTree();
+      handleFileClick(pendingRequest.file);
+      if (pendingRequest.heading) {
+        setPendingHeading({
+          path: pendingRequest.file,
+          heading: pendingRequest.heading,
+        });
+      }
+    }
+    if (pendingRequest.search) {
+      useFilePickerStore.getState().open(pendingRequest.search);
+    }
+  }, [sessionLoaded, pendingRequest, fetchFileTree, handleFileClick]);
+
+  useEffect(() => {
+    if (!pendingHeading || state.activeTabPath !== pendingHeading.path) return;
+    const content = state.fileContents[pendingHeading.path];
+    if (content === undefined) return;
+    setPendingHeading(null);
+    const wanted = pendingHeading.heading.toLowerCase();
+    const heading = parseHeadings(content).find(
+      (candidate) => candidate.text.toLowerCase() === wanted,
+    );
+    if (heading) {
+      // Let the editor mount the new tab before moving its cursor.
+      setTimeout(() =>
+        editorHandleRef.current?.jumpToPosition(heading.position),
+      );
+    }
+  }, [pendingHeading, state.activeTabPath, state.fileContents]);
+
   const handleWikilinkCreate = useCallback(
     async (linkTarget: string) => {
       const fileName = `${linkTarget}.md`;
@@ -476,7 +704,7 @@ export default function EditorPage({
         if (isPdf(state.activeTabPath)) return;
         const content = state.fileContents[state.activeTabPath];
         if (content === undefined) return;
-        invoke("write_file", { path: state.activeTabPath, content })
+        saveBuffer(vaultPath, state.activeTabPath, content)
           .then(() =>
             dispatch({ type: "mark_saved", path: state.activeTabPath! }),
           )
@@ -494,6 +722,18 @@ export default function EditorPage({
       },
     });
 
+    register({
+      id: "editor.toggleAutosave",
+      label: "Toggle Autosave on Idle",
+      keywords: ["autosave", "save", "idle", "settings"],
+      execute: () => {
+        const enabled = !autosaveOnIdle;
+        invoke("save_settings", { autosaveOnIdle: enabled })
+          .then(() => setAutosaveOnIdle(enabled))
+          .catch((err) => console.error("Failed to save settings:", err));
+      },
+    });
+
     register({
       id: "tab.closeAll",
       label: "Close All Tabs",
@@ -502,10 +742,18 @@ export default function EditorPage({
 
     return () => {
       unregister("editor.save");
+      unregister("editor.toggleAutosave");
       unregister("tab.close");
       unregister("tab.closeAll");
     };
-  }, [state.activeTabPath, state.fileContents, register, unregister]);
+  }, [
+    vaultPath,
+    state.activeTabPath,
+    state.fileContents,
+    autosaveOnIdle,
+    register,
+    unregister,
+  ]);
 
   useEffect(() => {
     register({
@@ -577,6 +825,7 @@ export default function EditorPage({
   const closePalette = useCommandPaletteStore((s) => s.close);
   const filePickerOpen = useFilePickerStore((s) => s.isOpen);
   const closeFilePicker = useFilePickerStore((s) => s.close);
+  const filePickerQuery = useFilePickerStore((s) => s.initialQuery);
 
   const activeContent =
     state.activeTabPath !== null
@@ -782,11 +1031,21 @@ export default function EditorPage({
       {filePickerOpen && (
         <FilePicker
           files={fileTree}
+          initialQuery={filePickerQuery}
           onOpen={handleFileClick}
           onCreate={handleNewNoteConfirm}
           onClose={closeFilePicker}
         />
       )}
+      {recoverable.length > 0 && (
+        <RecoveryDialog
+          buffers={recoverable}
+          vaultPath={vaultPath}
+          onRestore={handleRecoveryRestore}
+          onDiscard={handleRecoveryDiscard}
+          onClose={closeRecovery}
+        />
+      )}
     </>
   );
 }
@@ -0,0 +1,85 @@
+import { useCallback, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import MarkdownEditor from "../components/MarkdownEditor";
+import { useCommandStore } from "../stores/commandStore";
+import { useKeybindings } from "../hooks/useKeybindings";
+
+interface Props {
+  /// Absolute path of a markdown file that belongs to no vault.
+  filePath: string;
+}
+
+/// A bare editor for one note opened from outside any vault, e.g. `Onyx ~/notes.md`.
+export default function SingleFilePage({ filePath }: Props) {
+  const [content, setContent] = useState<string | null>(null);
+  const [dirty, setDirty] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const [vimMode, setVimMode] = useState(false);
+  const { register, unregister } = useCommandStore();
+  const fileName = filePath.split("/").pop() ?? filePath;
+
+  useEffect(() => {
+    invoke<string>("read_file", { path: filePath })
+      .then(setContent)
+      .catch((err) => setError(String(err)));
+  }, [filePath]);
+
+  useEffect(() => {
+    invoke<{ vim_mode: boolean }>("get_settings")
+      .then((settings) => setVimMode(settings.vim_mode))
+      .catch((err) => console.error("Failed to load settings:", err));
+  }, []);
+
+  const handleChange = useCallback((value: string) => {
+    setContent(value);
+    setDirty(true);
+  }, []);
+
+  useEffect(() => {
+    register({
+      id: "editor.save",
+      label: "Save File",
+      execute: () => {
+        if (content === null) return;
+        invoke("write_file", { path: filePath, content })
+          .then(() => setDirty(false))
+          .catch((err) => console.error("Failed to save file:", err));
+      },
+    });
+    return () => unregister("editor.save");
+  }, [filePath, content, register, unregister]);
+
+  useKeybindings();
+
+  return (
+    <div className="flex h-full flex-col bg-background text-text-primary">
+      <div
+        data-tauri-drag-region
+        className="flex h-10 shrink-0 items-center justify-center border-b border-surface text-sm text-text-secondary"
+      >
+        {fileName}
+        {dirty && " •"}
+      </div>
+      <div className="flex-1 min-h-0 overflow-hidden">
+        {error !== null ? (
+          <div className="flex h-full items-center justify-center text-text-secondary">
+            <p className="text-sm">{error}</p>
+          </div>
+        ) : (
+          content !== null && (
+            <MarkdownEditor
+              content={content}
+              onChange={handleChange}
+              vimMode={vimMode}
+              filePath={filePath}
+              onRename={() => {}}
+              vaultPath={null}
+              onWikilinkOpen={() => {}}
+              onWikilinkCreate={() => {}}
+            />
+          )
+        )}
+      </div>
+    </div>
+  );
+}
@@ -2,12 +2,15 @@ import { create } from "zustand";
 
 interface FilePickerState {
   isOpen: boolean;
-  open: () => void;
+  /// Text the picker starts with, e.g. the query of an `onyx://search` link.
+  initialQuery: string;
+  open: (query?: string) => void;
   close: () => void;
 }
 
 export const useFilePickerStore = create<FilePickerState>()((set) => ({
   isOpen: false,
-  open: () => set({ isOpen: true }),
+  initialQuery: "",
+  open: (query = "") => set({ isOpen: true, initialQuery: query }),
   close: () => set({ isOpen: false }),
 }));
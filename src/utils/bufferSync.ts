@@ -0,0 +1,62 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { ChangeSet } from "@codemirror/state";
+
+// Buffer calls run one at a time in the order they were made, so a save never
+// overtakes the edits typed before it.
+let queue: Promise<unknown> = Promise.resolve();
+
+function enqueue<T>(task: () => Promise<T>): Promise<T> {
+  const result = queue.then(task);
+  queue = result.catch(() => {});
+  return result;
+}
+
+/// Starts the note's backend buffer with the text its tab opened with.
+export function openBuffer(
+  vaultPath: string,
+  path: string,
+  content: string,
+): Promise<void> {
+  return enqueue(() =>
+    invoke<void>("open_buffer", { vaultPath, path, content }),
+  );
+}
+
+/// Sends one editor change to the note's backend buffer. A rejected change means the buffer
+/// has drifted from the editor, so the whole note is sent once to resync it.
+export function sendEdits(
+  vaultPath: string,
+  path: string,
+  changes: ChangeSet,
+  content: string,
+): Promise<void> {
+  const json = changes.toJSON();
+  return enqueue(() =>
+    invoke<void>("apply_edits", { vaultPath, path, changes: json }).catch(() =>
+      invoke<void>("open_buffer", { vaultPath, path, content }),
+    ),
+  );
+}
+
+/// Saves a note from its backend buffer, or writes `content` when it has none.
+export function saveBuffer(
+  vaultPath: string,
+  path: string,
+  content: string,
+): Promise<void> {
+  return enqueue(() =>
+    invoke<void>("save_buffer", { vaultPath, path }).catch(() =>
+      invoke<void>("write_file", { path, content, vaultPath }),
+    ),
+  );
+}
+
+/// Drops the backend buffer of a note whose tab was closed.
+export function closeBuffer(vaultPath: string, path: string): Promise<void> {
+  return enqueue(() => invoke<void>("close_buffer", { vaultPath, path }));
+}
+
+/// Runs `task` once every buffer call made so far has finished.
+export function afterBufferSync<T>(task: () => Promise<T>): Promise<T> {
+  return enqueue(task);
+}